@@ -0,0 +1,239 @@
+//! `#[derive(SObject)]` for `oxidized-force`; use it through the `derive` feature rather than depending on this crate directly.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+  parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, Ident, Lit, Meta, NestedMeta, Result
+};
+
+/// Implements `SObject`, `Serialize` and `Deserialize` with Salesforce style (PascalCase) field names.
+///
+/// Struct attributes:
+///   - `#[sobject(name = "Account")]` sets the object API name (defaults to the struct name)
+///   - `#[sobject(custom)]` appends `__c` to the object name
+///
+/// Field attributes:
+///   - `#[sobject(rename = "Name__c")]` (or `#[serde(rename = "..")]`) sets the field name; if both are given they must agree
+///   - `#[sobject(custom)]` appends `__c` to the field name
+///   - `#[sobject(skip)]` (or `#[serde(skip)]`) leaves the field out of `FIELDS`
+///
+/// Any other `#[serde(..)]` attributes are passed through untouched.
+#[proc_macro_derive(SObject, attributes(sobject, serde))]
+pub fn derive_sobject(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  expand(input).unwrap_or_else(|err| err.to_compile_error()).into()
+}
+
+#[derive(Default)]
+struct Options {
+  rename: Option<String>,
+  custom: bool,
+  skip:   bool
+}
+
+impl Options {
+  fn from_attrs(attrs: &[Attribute]) -> Result<Self> {
+    let mut opts = Options::default();
+
+    for meta in nested(attrs, "sobject")? {
+      match meta {
+        NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.path.is_ident("name") || nv.path.is_ident("rename") => {
+          opts.rename = Some(lit_str(&nv.lit)?);
+        },
+        NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("custom") => opts.custom = true,
+        NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("skip")   => opts.skip = true,
+        other => return Err(Error::new_spanned(other, "unknown sobject attribute"))
+      }
+    }
+
+    // Respect whatever serde was already told, as long as it agrees with `sobject`
+    for meta in nested(attrs, "serde")? {
+      match meta {
+        NestedMeta::Meta(ref rename) if rename.path().is_ident("rename") => {
+          let name = serde_rename(rename)?;
+          match opts.rename {
+            Some(ref current) if *current != name => {
+              return Err(Error::new_spanned(rename, format!("conflicts with the name \"{}\" given elsewhere", current)));
+            },
+            _ => opts.rename = Some(name)
+          }
+        },
+        NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("skip") || path.is_ident("skip_deserializing") => {
+          opts.skip = true;
+        },
+        _ => {}
+      }
+    }
+    Ok(opts)
+  }
+
+  /// Explicit name if there is one, otherwise whatever `default` comes up with.
+  fn api_name<F>(&self, default: F) -> String
+  where F: FnOnce() -> String {
+    let name = self.rename.clone().unwrap_or_else(default);
+    if self.custom && !name.ends_with("__c") {
+      format!("{}__c", name)
+    } else {
+      name
+    }
+  }
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+  if !input.generics.params.is_empty() {
+    return Err(Error::new_spanned(&input.generics, "SObject cannot be derived for generic structs"));
+  }
+
+  let fields = match input.data {
+    Data::Struct(ref data) => match data.fields {
+      Fields::Named(ref fields) => &fields.named,
+      _ => return Err(Error::new_spanned(&input.ident, "SObject can only be derived for structs with named fields"))
+    },
+    _ => return Err(Error::new_spanned(&input.ident, "SObject can only be derived for structs"))
+  };
+
+  let ident   = &input.ident;
+  let vis     = &input.vis;
+  let object  = Options::from_attrs(&input.attrs)?;
+  let api     = object.api_name(|| ident.to_string());
+  let shadow  = Ident::new(&format!("__SObject{}", ident), Span::call_site());
+  let remote  = ident.to_string();
+  let attrs   = serde_attrs(&input.attrs);
+
+  let mut names   = Vec::new();
+  let mut members = Vec::new();
+
+  for field in fields {
+    let opts  = Options::from_attrs(&field.attrs)?;
+    let fname = field.ident.as_ref().unwrap();
+    let name  = opts.api_name(|| pascal_case(&fname.to_string()));
+    let fattr = without_rename(&field.attrs)?;
+    let ty    = &field.ty;
+
+    // The derive owns the serde name, so `FIELDS` and (de)serialization can't drift apart
+    members.push(quote! {
+      #[serde(rename = #name)]
+      #(#fattr)*
+      #fname: #ty
+    });
+
+    if !opts.skip {
+      names.push(name);
+    }
+  }
+
+  Ok(quote! {
+    impl ::oxidized_force::sobject::SObject for #ident {
+      const API_NAME: &'static str = #api;
+      const FIELDS: &'static [&'static str] = &[#(#names),*];
+    }
+
+    #[allow(non_camel_case_types, dead_code)]
+    #[derive(::oxidized_force::__private::Serialize, ::oxidized_force::__private::Deserialize)]
+    #[serde(crate = "::oxidized_force::__private::serde", remote = #remote)]
+    #(#attrs)*
+    #vis struct #shadow {
+      #(#members),*
+    }
+
+    impl ::oxidized_force::__private::Serialize for #ident {
+      fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+      where S: ::oxidized_force::__private::serde::Serializer {
+        #shadow::serialize(self, serializer)
+      }
+    }
+
+    impl<'de> ::oxidized_force::__private::Deserialize<'de> for #ident {
+      fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+      where D: ::oxidized_force::__private::serde::Deserializer<'de> {
+        #shadow::deserialize(deserializer)
+      }
+    }
+  })
+}
+
+/// All the nested items of `#[<name>(..)]` attributes.
+fn nested(attrs: &[Attribute], name: &str) -> Result<Vec<NestedMeta>> {
+  let mut items = Vec::new();
+
+  for attr in attrs.iter().filter(|attr| attr.path.is_ident(name)) {
+    match attr.parse_meta()? {
+      Meta::List(list) => items.extend(list.nested),
+      other => return Err(Error::new_spanned(other, format!("expected #[{}(..)]", name)))
+    }
+  }
+  Ok(items)
+}
+
+fn serde_attrs(attrs: &[Attribute]) -> Vec<&Attribute> {
+  attrs.iter().filter(|attr| attr.path.is_ident("serde")).collect()
+}
+
+/// Field `#[serde(..)]` attributes minus any `rename`, which the derive emits itself.
+fn without_rename(attrs: &[Attribute]) -> Result<Vec<TokenStream2>> {
+  let mut kept = Vec::new();
+
+  for attr in serde_attrs(attrs) {
+    let items = nested(std::slice::from_ref(attr), "serde")?
+      .into_iter()
+      .filter(|meta| !matches!(meta, NestedMeta::Meta(meta) if meta.path().is_ident("rename")))
+      .collect::<Vec<_>>();
+
+    if !items.is_empty() {
+      kept.push(quote!(#[serde(#(#items),*)]));
+    }
+  }
+  Ok(kept)
+}
+
+/// The name out of `rename = ".."` or `rename(serialize = "..", deserialize = "..")`; both directions have to match,
+/// since the same name goes into `FIELDS`.
+fn serde_rename(meta: &Meta) -> Result<String> {
+  match meta {
+    Meta::NameValue(nv) => lit_str(&nv.lit),
+    Meta::List(list) => {
+      let mut name: Option<String> = None;
+
+      for item in &list.nested {
+        let value = match item {
+          NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("serialize") || nv.path.is_ident("deserialize") => lit_str(&nv.lit)?,
+          other => return Err(Error::new_spanned(other, "expected `serialize = \"..\"` or `deserialize = \"..\"`"))
+        };
+        match name {
+          Some(ref current) if *current != value => {
+            return Err(Error::new_spanned(list, "SObject fields need the same name for serialize and deserialize"));
+          },
+          _ => name = Some(value)
+        }
+      }
+      name.ok_or_else(|| Error::new_spanned(list, "expected `serialize` and/or `deserialize`"))
+    },
+    Meta::Path(path) => Err(Error::new_spanned(path, "expected `rename = \"..\"`"))
+  }
+}
+
+fn lit_str(lit: &Lit) -> Result<String> {
+  match lit {
+    Lit::Str(val) => Ok(val.value()),
+    other => Err(Error::new_spanned(other, "expected a string literal"))
+  }
+}
+
+/// `account_number` -> `AccountNumber`
+fn pascal_case(name: &str) -> String {
+  name
+    .trim_start_matches("r#")
+    .split('_')
+    .filter(|word| !word.is_empty())
+    .map(|word| {
+      let mut chars = word.chars();
+      match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+        None        => String::new()
+      }
+    })
+    .collect()
+}
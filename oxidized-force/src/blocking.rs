@@ -0,0 +1,160 @@
+//! A synchronous wrapper around the async [`Client`](crate::client::Client).
+//! Every call is driven to completion on a private single-threaded runtime, so callers never have to touch tokio.
+
+use std::{sync::Mutex, time::Duration};
+
+use serde::de::DeserializeOwned;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::client::{self, AccessToken, ClientBuilder};
+use crate::options::CallOptions;
+use crate::response::*;
+use crate::errors::*;
+
+#[derive(Debug)]
+pub struct Client {
+  inner:   client::Client,
+  runtime: Mutex<Runtime>
+}
+
+impl<'a> ClientBuilder<'a> {
+  /// Consumes the builder & creates a new blocking client.
+  pub fn create_blocking(&self) -> Result<Client> {
+    Client::new(self.create()?)
+  }
+}
+
+impl Client {
+  pub fn builder<'a>() -> ClientBuilder<'a> {
+    ClientBuilder::default()
+  }
+
+  /// Wraps an existing async client.
+  pub fn new(inner: client::Client) -> Result<Self> {
+    let runtime = Builder::new()
+      .basic_scheduler()
+      .enable_all()
+      .build()?;
+
+    Ok(Client { inner, runtime: Mutex::new(runtime) })
+  }
+
+  /// Attempt to login to the Salesforce REST API using the `password` grant type.
+  pub fn login_with_credentials<U, P>(&self, username: U, password: P) -> Result<()>
+  where U: Into<String>, P: Into<String> {
+    self.block_on(self.inner.login_with_credentials(username, password))
+  }
+
+  /// Where to send the user to log in with the OAuth web server flow (see the async client).
+  pub fn authorize_url(&self, redirect_uri: &str, state: &str, code_challenge: Option<&str>) -> Result<String> {
+    self.inner.authorize_url(redirect_uri, state, code_challenge)
+  }
+
+  /// Finishes the web server flow, trading the authorization code for a session.
+  pub fn login_with_authorization_code(&self, code: &str, redirect_uri: &str, code_verifier: Option<&str>) -> Result<()> {
+    self.block_on(self.inner.login_with_authorization_code(code, redirect_uri, code_verifier))
+  }
+
+  /// Starts the device flow.
+  pub fn request_device_code(&self) -> Result<DeviceCodeResponse> {
+    self.block_on(self.inner.request_device_code())
+  }
+
+  /// Waits for the user to enter the device code, then starts the session.
+  pub fn login_with_device_code(&self, device: &DeviceCodeResponse) -> Result<()> {
+    self.block_on(self.inner.login_with_device_code(device))
+  }
+
+  /// Starts a new session from a refresh token.
+  pub fn login_with_refresh_token(&self, refresh_token: &str) -> Result<()> {
+    self.block_on(self.inner.login_with_refresh_token(refresh_token))
+  }
+
+  /// Clones the access token (if one exists)
+  pub fn access_token(&self) -> Result<AccessToken> {
+    self.inner.access_token()
+  }
+
+  /// The refresh token that came with the session, if any.
+  pub fn refresh_token(&self) -> Option<String> {
+    self.inner.refresh_token()
+  }
+
+  /// Perform an SOQL query.
+  pub fn query<'a, Q, T: DeserializeOwned>(&self, query: Q) -> Result<QueryResponse<T>>
+  where Q: Into<&'a str> {
+    self.block_on(self.inner.query(query))
+  }
+
+  /// Perform an SOQL query without knowing the shape of the results up front.
+  pub fn query_raw<'a, Q>(&self, query: Q) -> Result<QueryResponse<serde_json::Value>>
+  where Q: Into<&'a str> {
+    self.block_on(self.inner.query_raw(query))
+  }
+
+  /// Describe an SObject resource.
+  pub fn describe<'a, N>(&self, name: N) -> Result<DescribeResponse>
+  where N: Into<&'a str> {
+    self.block_on(self.inner.describe(name))
+  }
+
+  /// List all of the SObjects available in the org.
+  pub fn describe_global(&self) -> Result<DescribeGlobalResponse> {
+    self.block_on(self.inner.describe_global())
+  }
+
+  /// Forgets the cached describe for an object (or describeGlobal when `None`).
+  pub fn invalidate_describe(&self, name: Option<&str>) -> Result<()> {
+    self.inner.invalidate_describe(name)
+  }
+
+  /// Forgets every cached describe response.
+  pub fn clear_describe_cache(&self) {
+    self.inner.clear_describe_cache()
+  }
+
+  /// Create a bulk query job.
+  pub fn create_query_job<'a, N, F>(&self, from: N, fields: F) -> Result<BulkQueryStatusResponse>
+  where N: Into<&'a str>, F: Into<Vec<&'a str>> {
+    self.block_on(self.inner.create_query_job(from, fields))
+  }
+
+  /// Create a bulk query job for just the records matching `condition`.
+  pub fn create_filtered_query_job<'a, N, F>(&self, from: N, fields: F, condition: Option<&str>) -> Result<BulkQueryStatusResponse>
+  where N: Into<&'a str>, F: Into<Vec<&'a str>> {
+    self.block_on(self.inner.create_filtered_query_job(from, fields, condition))
+  }
+
+  /// Get the status of a previously created bulk query job.
+  pub fn get_query_job_status<'a, N>(&self, job_id: N) -> Result<BulkQueryStatusResponse>
+  where N: Into<&'a str> {
+    self.block_on(self.inner.get_query_job_status(job_id))
+  }
+
+  /// Attempt to abort a previously created bulk query job.
+  pub fn abort_query_job<'a, N>(&self, job_id: N) -> Result<BulkQueryStatusResponse>
+  where N: Into<&'a str> {
+    self.block_on(self.inner.abort_query_job(job_id))
+  }
+
+  /// Polls a bulk query job until it has finished (successfully or not), checking every `poll_interval`.
+  pub fn wait_for_query_job<'a, N>(&self, job_id: N, poll_interval: Duration, options: &CallOptions) -> Result<BulkQueryStatusResponse>
+  where N: Into<&'a str> {
+    self.block_on(self.inner.wait_for_query_job(job_id, poll_interval, options))
+  }
+
+  /// Fetch a single page of CSV results for a completed bulk query job.
+  pub fn get_query_job_results<'a, N>(&self, job_id: N, locator: Option<&str>, max_records: Option<usize>, options: &CallOptions) -> Result<BulkQueryResults>
+  where N: Into<&'a str> {
+    self.block_on(self.inner.get_query_job_results(job_id, locator, max_records, options))
+  }
+
+  /// Gives access to the wrapped async client.
+  pub fn as_async(&self) -> &client::Client {
+    &self.inner
+  }
+
+  fn block_on<F: std::future::Future>(&self, future: F) -> F::Output {
+    self.runtime.lock().expect("blocking runtime poisoned").block_on(future)
+  }
+}
@@ -0,0 +1,105 @@
+//! Caching for describe & describeGlobal responses; they hardly ever change, but pipelines ask for them constantly.
+
+use std::{
+  collections::HashMap,
+  fs,
+  path::PathBuf,
+  sync::RwLock,
+  time::{Duration, Instant, SystemTime}
+};
+
+/// Raw response bodies keyed by org + object, kept in memory & (optionally) on disk so they survive between runs.
+#[derive(Debug)]
+pub struct DescribeCache {
+  ttl:     Duration,
+  dir:     Option<PathBuf>,
+  entries: RwLock<HashMap<String, Entry>>
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+  stored: Instant,
+  body:   Vec<u8>
+}
+
+impl DescribeCache {
+  pub fn new(ttl: Duration) -> Self {
+    DescribeCache { ttl, dir: None, entries: RwLock::new(HashMap::new()) }
+  }
+
+  /// Same as `new`, but also persists entries as JSON files inside `dir` (which should be dedicated to the cache).
+  pub fn with_dir<P>(ttl: Duration, dir: P) -> Self
+  where P: Into<PathBuf> {
+    DescribeCache { dir: Some(dir.into()), ..DescribeCache::new(ttl) }
+  }
+
+  pub fn ttl(&self) -> Duration {
+    self.ttl
+  }
+
+  /// Builds the key for an object in an org; `None` is used for describeGlobal.
+  pub fn key(instance_url: &str, version: &str, object: Option<&str>) -> String {
+    format!("{}/{}/{}", instance_url, version, object.unwrap_or("_global"))
+  }
+
+  /// Gets a cached response body, provided it hasn't expired yet.
+  pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+    if let Some(entry) = self.entries.read().expect("cache lock poisoned").get(key) {
+      if entry.stored.elapsed() < self.ttl {
+        return Some(entry.body.clone());
+      }
+    }
+
+    let (age, body) = self.read_file(key)?;
+    let stored      = Instant::now().checked_sub(age).unwrap_or_else(Instant::now);
+    self.entries.write().expect("cache lock poisoned").insert(key.to_string(), Entry { stored, body: body.clone() });
+    Some(body)
+  }
+
+  pub fn put(&self, key: &str, body: &[u8]) {
+    self.entries.write().expect("cache lock poisoned").insert(key.to_string(), Entry { stored: Instant::now(), body: body.to_vec() });
+
+    // A cache that can't write to disk is still a perfectly good in-memory cache, so errors are ignored
+    if let Some(path) = self.path(key) {
+      let _ = fs::create_dir_all(self.dir.as_ref().unwrap()).and_then(|_| fs::write(path, body));
+    }
+  }
+
+  /// Drops a single entry.
+  pub fn invalidate(&self, key: &str) {
+    self.entries.write().expect("cache lock poisoned").remove(key);
+
+    if let Some(path) = self.path(key) {
+      let _ = fs::remove_file(path);
+    }
+  }
+
+  /// Drops everything, including anything persisted to disk.
+  pub fn clear(&self) {
+    self.entries.write().expect("cache lock poisoned").clear();
+
+    if let Some(ref dir) = self.dir {
+      let files = fs::read_dir(dir).into_iter().flatten().flatten().map(|entry| entry.path());
+      for path in files.filter(|path| path.extension().is_some_and(|ext| ext == "json")) {
+        let _ = fs::remove_file(path);
+      }
+    }
+  }
+
+  fn read_file(&self, key: &str) -> Option<(Duration, Vec<u8>)> {
+    let path = self.path(key)?;
+    let age  = SystemTime::now().duration_since(fs::metadata(&path).ok()?.modified().ok()?).unwrap_or_default();
+
+    if age < self.ttl {
+      Some((age, fs::read(path).ok()?))
+    } else {
+      None
+    }
+  }
+
+  /// `https://acme.my.salesforce.com/v49.0/Account` -> `<dir>/https___acme_my_salesforce_com_v49_0_Account.json`
+  fn path(&self, key: &str) -> Option<PathBuf> {
+    let name: String = key.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' }).collect();
+    self.dir.as_ref().map(|dir| dir.join(format!("{}.json", name)))
+  }
+}
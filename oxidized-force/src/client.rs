@@ -1,10 +1,26 @@
 #![allow(dead_code)]
 
-use std::borrow::Cow;
-
-use reqwest::header::{HeaderMap, AUTHORIZATION, ACCEPT};
+use std::{
+  borrow::Cow,
+  path::PathBuf,
+  sync::{Arc, RwLock},
+  time::{Duration, Instant}
+};
+
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, ACCEPT};
 use serde::{de::DeserializeOwned, Serialize};
-
+use sha2::Sha256;
+use tokio::{
+  sync::{Mutex, Semaphore, SemaphorePermit},
+  time
+};
+
+use crate::cache::DescribeCache;
+use crate::options::CallOptions;
+use crate::streaming::Subscriber;
+use crate::telemetry::metrics;
+use crate::transport::{HttpRequest, HttpResponse, HttpTransport, ReqwestTransport};
 use crate::response::*;
 use crate::errors::*;
 
@@ -12,9 +28,17 @@ use crate::errors::*;
 pub struct AccessToken {
   pub token_type: String,
   pub value:      String,
+  /// Milliseconds since the epoch, as Salesforce sends it
   pub issued_at:  String
 }
 
+#[cfg(feature = "chrono")]
+impl AccessToken {
+  pub fn issued_at_time(&self) -> Option<Timestamp> {
+    crate::serde_sf::epoch_millis::parse(&self.issued_at)
+  }
+}
+
 impl From<TokenResponse> for AccessToken {
   fn from(res: TokenResponse) -> Self {
     AccessToken {
@@ -25,16 +49,49 @@ impl From<TokenResponse> for AccessToken {
   }
 }
 
-#[derive(Debug)]
+/// The PKCE code challenge for a (random, 43 to 128 characters long) code verifier: its SHA-256, base64url encoded.
+pub fn pkce_challenge(verifier: &str) -> String {
+  use sha2::Digest;
+  base64::encode_config(Sha256::digest(verifier.as_bytes()), base64::URL_SAFE_NO_PAD)
+}
+
+/// A Salesforce REST API client.
+/// Cloning is cheap & every clone shares the same session, concurrency limit & transport, so one client can be used from many tasks.
+#[derive(Debug, Clone)]
 pub struct Client {
-  http_client:    reqwest::Client,
+  inner:       Arc<ClientInner>,
+  correlation: Correlation
+}
+
+#[derive(Debug)]
+struct ClientInner {
+  transport:      Arc<dyn HttpTransport>,
   client_id:      String,
   client_secret:  String,
   login_endpoint: String,
   version:        String,
-  base_path:      Option<String>,
   instance_url:   Option<String>,
-  access_token:   Option<AccessToken>
+  limiter:        Option<Semaphore>,
+  verify_sig:     bool,
+  describe_cache: Option<DescribeCache>,
+  session:        RwLock<Option<Session>>,
+  login_lock:     Mutex<()>
+}
+
+/// Everything we get back from a successful login.
+#[derive(Debug, Clone)]
+struct Session {
+  access_token:  AccessToken,
+  refresh_token: Option<String>,
+  instance_url:  String,
+  base_path:     String
+}
+
+/// The (optional) correlation ID attached to every request, along with the header carrying it.
+#[derive(Debug, Clone)]
+struct Correlation {
+  header: HeaderName,
+  id:     Option<HeaderValue>
 }
 
 /// It builds clients - fairly self explanatory I'd hope.
@@ -44,7 +101,14 @@ pub struct ClientBuilder<'a> {
   client_secret:  Option<Cow<'a, str>>,
   login_endpoint: Option<Cow<'a, str>>,
   instance_url:   Option<Cow<'a, str>>,
-  version:        Option<Cow<'a, str>>
+  version:        Option<Cow<'a, str>>,
+  max_concurrent: Option<usize>,
+  correlation_id: Option<Cow<'a, str>>,
+  corr_header:    Cow<'a, str>,
+  transport:      Option<Arc<dyn HttpTransport>>,
+  verify_sig:     bool,
+  cache_ttl:      Option<Duration>,
+  cache_dir:      Option<PathBuf>
 }
 
 impl<'a> Default for ClientBuilder<'a> {
@@ -53,6 +117,13 @@ impl<'a> Default for ClientBuilder<'a> {
       client_id:      None,
       client_secret:  None,
       instance_url:   None,
+      max_concurrent: None,
+      correlation_id: None,
+      corr_header:    Cow::Borrowed("X-Correlation-ID"),
+      transport:      None,
+      verify_sig:     false,
+      cache_ttl:      None,
+      cache_dir:      None,
       version:        Some(Cow::Borrowed("v49.0")),
       login_endpoint: Some(Cow::Borrowed("https://login.salesforce.com"))
     }
@@ -95,43 +166,132 @@ impl<'a> ClientBuilder<'a> {
     self
   }
 
+  /// Limits the number of simultaneous in-flight requests made by the client.
+  /// Salesforce enforces a per-org concurrent request limit, so keeping fan-out under it avoids `REQUEST_LIMIT_EXCEEDED` errors.
+  #[inline]
+  pub fn max_concurrent_requests(&mut self, max: usize) -> &mut Self {
+    self.max_concurrent = Some(max);
+    self
+  }
+
+  /// Attaches a correlation/trace ID to every request, so calls can be traced across systems.
+  #[inline]
+  pub fn correlation_id<S>(&mut self, correlation_id: S) -> &mut Self
+  where S: Into<Cow<'a, str>> {
+    self.correlation_id = Some(correlation_id.into());
+    self
+  }
+
+  /// Overrides the header used to send the correlation ID (defaults to `X-Correlation-ID`).
+  #[inline]
+  pub fn correlation_header<S>(&mut self, header: S) -> &mut Self
+  where S: Into<Cow<'a, str>> {
+    self.corr_header = header.into();
+    self
+  }
+
+  /// Verifies the signature of token responses during login, rejecting tampered (or misrouted) responses.
+  #[inline]
+  pub fn verify_signature(&mut self, verify: bool) -> &mut Self {
+    self.verify_sig = verify;
+    self
+  }
+
+  /// Caches `describe` & `describe_global` responses for `ttl` (off by default).
+  #[inline]
+  pub fn describe_cache(&mut self, ttl: Duration) -> &mut Self {
+    self.cache_ttl = Some(ttl);
+    self
+  }
+
+  /// Also persists cached describe responses to `dir`, so they survive between runs; requires `describe_cache`.
+  #[inline]
+  pub fn describe_cache_dir<P>(&mut self, dir: P) -> &mut Self
+  where P: Into<PathBuf> {
+    self.cache_dir = Some(dir.into());
+    self
+  }
+
+  /// Swaps out the HTTP layer (defaults to `ReqwestTransport`), e.g. for an in-memory fake during tests.
+  #[inline]
+  pub fn transport(&mut self, transport: Arc<dyn HttpTransport>) -> &mut Self {
+    self.transport = Some(transport);
+    self
+  }
+
   /// Consumes the builder & creates a new client.
   pub fn create(&self) -> Result<Client> {
     let client_id = match self.client_id {
-      Some(ref cid) => cid.to_owned().to_string(),
+      Some(ref cid) => cid.to_string(),
       None          => return Err(Error::ClientBuilderError("Must specify `client_id`".to_string()))
     };
 
     let client_secret = match self.client_secret {
-      Some(ref cs) => cs.to_owned().to_string(),
+      Some(ref cs) => cs.to_string(),
       None         => return Err(Error::ClientBuilderError("Must specify `client_secret`".to_string()))
     };
 
     let login_endpoint = match self.login_endpoint {
-      Some(ref ep) => ep.to_owned().to_string(),
+      Some(ref ep) => ep.to_string(),
       None         => return Err(Error::ClientBuilderError("Must specify `login_endpoint`".to_string()))
     };
 
     let version = match self.version {
-      Some(ref vers) => vers.to_owned().to_string(),
+      Some(ref vers) => vers.to_string(),
       None           => return Err(Error::ClientBuilderError("Must specify `version` (This should never happen...)".to_string()))
     };
 
-    let instance_url = match self.instance_url {
-      Some(ref ep) => Some(ep.to_owned().to_string()),
-      None         => None
+    let limiter = match self.max_concurrent {
+      Some(0)   => return Err(Error::ClientBuilderError("`max_concurrent_requests` must be greater than zero".to_string())),
+      Some(max) => Some(Semaphore::new(max)),
+      None      => None
     };
 
-    Ok(Client {
-      http_client:    reqwest::Client::new(),
-      client_id:      client_id,
-      client_secret:  client_secret,
-      login_endpoint: login_endpoint,
-      instance_url:   instance_url,
-      access_token:   None,
-      base_path:      None,
-      version:        version
-    })
+    let describe_cache = match (self.cache_ttl, &self.cache_dir) {
+      (Some(ttl), Some(dir)) => Some(DescribeCache::with_dir(ttl, dir)),
+      (Some(ttl), None)      => Some(DescribeCache::new(ttl)),
+      (None, Some(_))        => return Err(Error::ClientBuilderError("`describe_cache_dir` requires `describe_cache`".to_string())),
+      (None, None)           => None
+    };
+
+    let instance_url = self.instance_url.as_ref().map(|ep| ep.to_string());
+
+    let correlation = Correlation {
+      header: HeaderName::from_bytes(self.corr_header.as_bytes())
+        .map_err(|_| Error::ClientBuilderError(format!("Invalid correlation header `{}`", self.corr_header)))?,
+      id:     self.correlation_id.as_deref().map(Correlation::value).transpose()?
+    };
+
+    let transport = self
+      .transport
+      .clone()
+      .unwrap_or_else(|| Arc::new(ReqwestTransport::default()));
+
+    let inner = ClientInner {
+      session:    RwLock::new(None),
+      login_lock: Mutex::new(()),
+      verify_sig: self.verify_sig,
+      describe_cache,
+      client_id,
+      client_secret,
+      login_endpoint,
+      instance_url,
+      version,
+      limiter,
+      transport
+    };
+
+    Ok(Client { inner: Arc::new(inner), correlation })
+  }
+}
+
+impl Correlation {
+  fn value(id: &str) -> Result<HeaderValue> {
+    HeaderValue::from_str(id).map_err(|_| Error::ClientBuilderError(format!("Invalid correlation id `{}`", id)))
+  }
+
+  fn id(&self) -> Option<&str> {
+    self.id.as_ref().and_then(|id| id.to_str().ok())
   }
 }
 
@@ -141,57 +301,177 @@ impl Client {
   }
 
   /// Attempt to login to the Salesforce REST API using the `password` grant type.
-  pub async fn login_with_credentials<U, P>(&mut self, username: U, password: P) -> Result<()>
+  /// Logins are serialized, so concurrent callers never race each other replacing the session.
+  pub async fn login_with_credentials<U, P>(&self, username: U, password: P) -> Result<()>
   where U: Into<String>, P: Into<String> {
     // https://developer.salesforce.com/docs/atlas.en-us.api_iot.meta/api_iot/qs_auth_access_token.htm
-    let token_url = format!("{}/services/oauth2/token", self.login_endpoint);
     let params = [
       ("grant_type",    "password"),
-      ("client_id",     self.client_id.as_str()),
-      ("client_secret", self.client_secret.as_str()),
+      ("client_id",     self.inner.client_id.as_str()),
+      ("client_secret", self.inner.client_secret.as_str()),
       ("username",      &username.into()),
       ("password",      &password.into()),
     ];
 
-    let res = self
-      .http_client
-      .post(token_url.as_str())
-      .form(&params)
-      .send()
-      .await?;
+    let _login = self.inner.login_lock.lock().await;
+    self.start_session(self.request_token("login", &params).await?);
 
-    if res.status().is_success() {
-      let res: TokenResponse = res.json().await?;
+    // Great success!
+    Ok(())
+  }
 
-      self.access_token = Some(AccessToken {
-        token_type: res.token_type,
-        issued_at:  res.issued_at,
-        value:      res.access_token
-      });
+  /// Where to send the user (in a browser) to log in with the OAuth web server flow; Salesforce redirects back to `redirect_uri`
+  /// with a `code` for `login_with_authorization_code`, along with `state`. Pass a PKCE challenge (see `pkce_challenge`) unless
+  /// the connected app has the secret to go by.
+  pub fn authorize_url(&self, redirect_uri: &str, state: &str, code_challenge: Option<&str>) -> Result<String> {
+    let mut url = url::Url::parse(&format!("{}/services/oauth2/authorize", self.inner.login_endpoint))?;
+    url
+      .query_pairs_mut()
+      .append_pair("response_type", "code")
+      .append_pair("client_id", &self.inner.client_id)
+      .append_pair("redirect_uri", redirect_uri)
+      .append_pair("scope", "api refresh_token")
+      .append_pair("state", state);
+
+    if let Some(challenge) = code_challenge {
+      url.query_pairs_mut().append_pair("code_challenge", challenge).append_pair("code_challenge_method", "S256");
+    }
+    Ok(url.to_string())
+  }
+
+  /// Finishes the web server flow started with `authorize_url`, trading the `code` Salesforce redirected back with for a session.
+  /// The session comes with a refresh token (see `refresh_token`) when the connected app grants the `refresh_token` scope.
+  pub async fn login_with_authorization_code(&self, code: &str, redirect_uri: &str, code_verifier: Option<&str>) -> Result<()> {
+    let mut params = vec![
+      ("grant_type",    "authorization_code"),
+      ("client_id",     self.inner.client_id.as_str()),
+      ("client_secret", self.inner.client_secret.as_str()),
+      ("redirect_uri",  redirect_uri),
+      ("code",          code)
+    ];
+    if let Some(verifier) = code_verifier {
+      params.push(("code_verifier", verifier));
+    }
 
-      self.instance_url = Some(res.instance_url);
+    let _login = self.inner.login_lock.lock().await;
+    self.start_session(self.request_token("login_with_authorization_code", &params).await?);
+    Ok(())
+  }
 
-      // Build a string representing the base path for all further requests
-      self.base_path = Some(
-        format!("{}/services/data/{}",
-        self.instance_url.as_ref().unwrap(),  // Safe to unwrap here since we know this field exists at this point
-        self.version
-      ));
+  /// Starts the device flow, for logging in from somewhere without a browser: show the user `verification_uri` & `user_code`,
+  /// then wait on `login_with_device_code`.
+  pub async fn request_device_code(&self) -> Result<DeviceCodeResponse> {
+    let token_url = format!("{}/services/oauth2/token", self.inner.login_endpoint);
+    let params    = [
+      ("response_type", "device_code"),
+      ("client_id",     self.inner.client_id.as_str()),
+      ("scope",         "api refresh_token")
+    ];
 
-      // Great success!
-      Ok(())
-    } else {
+    let req     = HttpRequest::post(&token_url)?.form(&params);
+    let context = self.context("request_device_code", &req);
+    let res     = self.dispatch(req).await.map_err(|err| err.with_context(context.clone()))?;
+    Self::token_response(res).map_err(|err| err.with_context(context))
+  }
+
+  /// Polls until the user has entered the device code (or it expires), then starts the session.
+  pub async fn login_with_device_code(&self, device: &DeviceCodeResponse) -> Result<()> {
+    let params = [
+      ("grant_type", "device"),
+      ("client_id",  self.inner.client_id.as_str()),
+      ("code",       device.device_code.as_str())
+    ];
+
+    let mut interval = Duration::from_secs(device.interval.max(1));
+    loop {
+      time::delay_for(interval).await;
+
+      let _login = self.inner.login_lock.lock().await;
+      match self.request_token("login_with_device_code", &params).await {
+        Ok(res)  => {
+          self.start_session(res);
+          return Ok(());
+        },
+        Err(err) => match err.inner() {
+          Error::TokenError(res) if res.error == "authorization_pending" => (),
+          Error::TokenError(res) if res.error == "slow_down"             => interval += Duration::from_secs(5),
+          _                                                              => return Err(err)
+        }
+      }
+    }
+  }
+
+  /// Starts a new session from a refresh token (saved from an earlier web server or device flow login).
+  pub async fn login_with_refresh_token(&self, refresh_token: &str) -> Result<()> {
+    let params = [
+      ("grant_type",    "refresh_token"),
+      ("client_id",     self.inner.client_id.as_str()),
+      ("client_secret", self.inner.client_secret.as_str()),
+      ("refresh_token", refresh_token)
+    ];
+
+    let _login  = self.inner.login_lock.lock().await;
+    let mut res = self.request_token("login_with_refresh_token", &params).await?;
+    // Salesforce only hands out a new one when the refresh token rotates
+    res.refresh_token.get_or_insert_with(|| refresh_token.to_string());
+    self.start_session(res);
+    Ok(())
+  }
+
+  /// Asks the token endpoint for a session (callers hold the login lock).
+  async fn request_token(&self, operation: &'static str, params: &[(&str, &str)]) -> Result<TokenResponse> {
+    let token_url = format!("{}/services/oauth2/token", self.inner.login_endpoint);
+
+    let req     = HttpRequest::post(&token_url)?.form(params);
+    let context = self.context(operation, &req);
+    let res     = self.dispatch(req).await.map_err(|err| err.with_context(context.clone()))?;
+
+    if res.status.is_success() && self.inner.verify_sig {
+      self.verify_signature(&res.body)?;
+    }
+    Self::token_response(res).map_err(|err| err.with_context(context))
+  }
+
+  /// Deserializes a response from the token endpoint, or the error it came back with.
+  fn token_response<T: DeserializeOwned>(res: HttpResponse) -> Result<T> {
+    if !res.status.is_success() {
       // Uh-Oh Spaghettios!
-      let token_error = res.json().await?;
-      Err(Error::TokenError(token_error))
+      return Err(match serde_json::from_slice(&res.body) {
+        Ok(token_error) => Error::TokenError(token_error),
+        Err(_)          => Error::http_status(res)
+      });
     }
+    Ok(serde_json::from_slice(&res.body)?)
+  }
+
+  /// The correlation ID currently attached to requests (if any).
+  pub fn correlation_id(&self) -> Option<&str> {
+    self.correlation.id()
+  }
+
+  /// Sets (or clears) the correlation ID attached to all further requests made through this handle.
+  /// Other clones are unaffected, which makes it easy to tag each task with its own ID.
+  pub fn set_correlation_id<S>(&mut self, correlation_id: Option<S>) -> Result<()>
+  where S: AsRef<str> {
+    self.correlation.id = correlation_id.map(|id| Correlation::value(id.as_ref())).transpose()?;
+    Ok(())
   }
 
   /// Clones the access token (if one exists)
   pub fn access_token(&self) -> Result<AccessToken> {
-    match self.access_token.as_ref() {
-      Some(token) => Ok(token.clone()),
-      None        => Err(Error::NotAuthenticatedError)
+    Ok(self.session()?.access_token)
+  }
+
+  /// The refresh token that came with the session, if any (see `login_with_refresh_token`).
+  pub fn refresh_token(&self) -> Option<String> {
+    self.session().ok().and_then(|session| session.refresh_token)
+  }
+
+  /// The instance URL we logged into (or the one the client was configured with).
+  pub fn instance_url(&self) -> Option<String> {
+    match self.session() {
+      Ok(session) => Some(session.instance_url),
+      Err(_)      => self.inner.instance_url.clone()
     }
   }
 
@@ -201,20 +481,60 @@ impl Client {
     let url    = format!("{}/query", self.base_path()?);
     let params = vec![("q", query.into())];
 
-    Ok(self.get(&url, Some(params)).await?)
+    traced!(self.get("query", &url, Some(params)), "query").await
+  }
+
+  /// Perform an SOQL query without knowing the shape of the results up front.
+  /// Records can also be queried as `Row`s, which are a bit friendlier to work with.
+  pub async fn query_raw<'a, Q>(&self, query: Q) -> Result<QueryResponse<serde_json::Value>>
+  where Q: Into<&'a str> {
+    self.query(query).await
   }
 
   /// Describe an SObject resource.
   pub async fn describe<'a, N>(&self, name: N) -> Result<DescribeResponse>
   where N: Into<&'a str> {
-    let url = format!("{}/sobjects/{}/describe", self.base_path()?, name.into());
-    Ok(self.get(&url, None).await?)
+    let name = name.into();
+    let url  = format!("{}/sobjects/{}/describe", self.base_path()?, name);
+    traced!(self.get_cached("describe", &url, Some(name)), "describe", sobject = %name).await
+  }
+
+  /// List all of the SObjects available in the org.
+  pub async fn describe_global(&self) -> Result<DescribeGlobalResponse> {
+    let url = format!("{}/sobjects", self.base_path()?);
+    traced!(self.get_cached("describe_global", &url, None), "describe_global").await
+  }
+
+  /// Forgets the cached describe for an object (or describeGlobal when `None`).
+  pub fn invalidate_describe(&self, name: Option<&str>) -> Result<()> {
+    if let Some(ref cache) = self.inner.describe_cache {
+      cache.invalidate(&self.cache_key(name)?);
+    }
+    Ok(())
+  }
+
+  /// Forgets every cached describe response.
+  pub fn clear_describe_cache(&self) {
+    if let Some(ref cache) = self.inner.describe_cache {
+      cache.clear();
+    }
   }
 
   /// Create a bulk query job.
   pub async fn create_query_job<'a, N, F>(&self, from: N, fields: F) -> Result<BulkQueryStatusResponse>
   where N: Into<&'a str>, F: Into<Vec<&'a str>> {
-    let query = format!("SELECT {} FROM {}", fields.into().join(","), from.into());
+    self.create_filtered_query_job(from, fields, None).await
+  }
+
+  /// Create a bulk query job for just the records matching `condition`, a SOQL `WHERE` clause (without the `WHERE`).
+  pub async fn create_filtered_query_job<'a, N, F>(&self, from: N, fields: F, condition: Option<&str>) -> Result<BulkQueryStatusResponse>
+  where N: Into<&'a str>, F: Into<Vec<&'a str>> {
+    let from      = from.into();
+    let mut query = format!("SELECT {} FROM {}", fields.into().join(","), from);
+    if let Some(condition) = condition {
+      query.push_str(" WHERE ");
+      query.push_str(condition);
+    }
 
     let params = [
       ("operation", "query"),
@@ -222,14 +542,15 @@ impl Client {
     ];
 
     let url = format!("{}/jobs/query", self.base_path()?);
-    Ok(self.post(&url, params).await?)
+    traced!(self.post("create_query_job", &url, params), "create_query_job", sobject = %from).await
   }
 
   /// Get the status of a previously created bulk query job.
   pub async fn get_query_job_status<'a, N>(&self, job_id: N) -> Result<BulkQueryStatusResponse>
   where N: Into<&'a str> {
-    let url = format!("{}/jobs/query/{}", self.base_path()?, job_id.into());
-    Ok(self.get(&url, None).await?)
+    let job_id = job_id.into();
+    let url    = format!("{}/jobs/query/{}", self.base_path()?, job_id);
+    traced!(self.get("get_query_job_status", &url, None), "get_query_job_status", job_id = %job_id).await
   }
 
   /// Attempt to abort a previously created bulk query job.
@@ -238,91 +559,261 @@ impl Client {
   ///   - InProgress
   pub async fn abort_query_job<'a, N>(&self, job_id: N) -> Result<BulkQueryStatusResponse>
   where N: Into<&'a str> {
-    let url = format!("{}/jobs/query/{}", self.base_path()?, job_id.into());
-    Ok(self.patch(&url, [("state", "Aborted")]).await?)
+    let job_id = job_id.into();
+    let url    = format!("{}/jobs/query/{}", self.base_path()?, job_id);
+    traced!(self.patch("abort_query_job", &url, [("state", "Aborted")]), "abort_query_job", job_id = %job_id).await
   }
 
-  /// Helper function to perform a GET request with JSON deserialization.
-  async fn get<T: DeserializeOwned>(&self, url: &str, params: Option<Vec<(&str, &str)>>) -> Result<T> {
-    let res = self
-      .http_client
-      .get(url)
-      .headers(self.default_headers()?)
+  /// Polls a bulk query job until it has finished (successfully or not), checking every `poll_interval`.
+  /// Polling stops early with `Error::Cancelled` / `Error::DeadlineExceeded` depending on `options`; the job itself keeps running.
+  pub async fn wait_for_query_job<'a, N>(&self, job_id: N, poll_interval: Duration, options: &CallOptions) -> Result<BulkQueryStatusResponse>
+  where N: Into<&'a str> {
+    let job_id  = job_id.into();
+    let started = Instant::now();
+
+    loop {
+      let status = options.run(self.get_query_job_status(job_id)).await?;
+      if status.state.is_finished() {
+        metrics::bulk_job(&status.object, &format!("{:?}", status.state), started.elapsed());
+        return Ok(status);
+      }
+
+      options.run(async { time::delay_for(poll_interval).await; Ok(()) }).await?;
+    }
+  }
+
+  /// Fetch a single page of CSV results for a completed bulk query job.
+  /// Pass the `locator` from the previous page to continue where it left off.
+  pub async fn get_query_job_results<'a, N>(&self, job_id: N, locator: Option<&str>, max_records: Option<usize>, options: &CallOptions) -> Result<BulkQueryResults>
+  where N: Into<&'a str> {
+    let job_id      = job_id.into();
+    let url         = format!("{}/jobs/query/{}/results", self.base_path()?, job_id);
+    let max_records = max_records.map(|max| max.to_string());
+
+    let mut params = Vec::new();
+    if let Some(locator) = locator {
+      params.push(("locator", locator));
+    }
+    if let Some(ref max) = max_records {
+      params.push(("maxRecords", max.as_str()));
+    }
+
+    let req = HttpRequest::get(&url)?
       .query(&params)
-      .send()
-      .await?;
+      .header(ACCEPT, HeaderValue::from_static("text/csv"));
 
-    if res.status().is_success() {
-      Ok(res.json::<T>().await?)
-    } else {
-      let error = res.json().await?;
-      Err(Error::ResponseError(error))
+    let res = options.run(traced!(self.send("get_query_job_results", req), "get_query_job_results", job_id = %job_id)).await?;
+
+    Ok(BulkQueryResults {
+      // Salesforce literally sends the string "null" once the final page has been reached
+      locator:           res.header("Sforce-Locator").filter(|locator| *locator != "null").map(String::from),
+      number_of_records: res.header("Sforce-NumberOfRecords").and_then(|num| num.parse().ok()),
+      body:              String::from_utf8_lossy(&res.body).into_owned()
+    })
+  }
+
+  /// Starts a Streaming API subscriber; subscribe it to channels, then wait for events with `Subscriber::next_event`.
+  pub fn subscriber(&self) -> Subscriber {
+    Subscriber::new(self.clone())
+  }
+
+  /// Helper function to perform a GET request with JSON deserialization.
+  async fn get<T: DeserializeOwned>(&self, operation: &'static str, url: &str, params: Option<Vec<(&str, &str)>>) -> Result<T> {
+    self.send_json(operation, HttpRequest::get(url)?.query(params.iter().flatten())).await
+  }
+
+  /// Same as `get`, but goes through the describe cache (when enabled).
+  async fn get_cached<T: DeserializeOwned>(&self, operation: &'static str, url: &str, name: Option<&str>) -> Result<T> {
+    let cache = match self.inner.describe_cache {
+      Some(ref cache) => cache,
+      None            => return self.get(operation, url, None).await
+    };
+
+    let key = self.cache_key(name)?;
+    if let Some(body) = cache.get(&key) {
+      // Anything that no longer deserializes (say after an upgrade) just gets fetched again
+      if let Ok(value) = serde_json::from_slice(&body) {
+        return Ok(value);
+      }
     }
+
+    let req     = HttpRequest::get(url)?;
+    let context = self.context(operation, &req);
+    let res     = self.send(operation, req).await?;
+    let value   = serde_json::from_slice(&res.body).map_err(|err| Error::from(err).with_context(context))?;
+    cache.put(&key, &res.body);
+    Ok(value)
+  }
+
+  fn cache_key(&self, name: Option<&str>) -> Result<String> {
+    Ok(DescribeCache::key(&self.session()?.instance_url, &self.inner.version, name))
   }
 
   /// Helper function to perform a POST request with a JSON payload.
-  async fn post<T, P>(&self, url: &str, params: P) -> Result<T>
+  async fn post<T, P>(&self, operation: &'static str, url: &str, params: P) -> Result<T>
   where T: DeserializeOwned, P: Serialize {
-    let res = self
-    .http_client
-    .post(url)
-    .headers(self.default_headers()?)
-    .json(&params)
-    .send()
-    .await?;
-
-    if res.status().is_success() {
-      Ok(res.json::<T>().await?)
-    } else {
-      let error = res.json().await?;
-      Err(Error::ResponseError(error))
-    }
+    self.send_json(operation, HttpRequest::post(url)?.json(&params)?).await
   }
 
   /// Helper function to perform a POST request with a JSON payload, but without response deserialization.
-  async fn raw_post<P: Serialize>(&self, url: &str, params: P) -> Result<reqwest::Response> {
-    Ok(
-      self
-        .http_client
-        .post(url)
-        .headers(self.default_headers()?)
-        .json(&params)
-        .send()
-        .await?
-    )
+  async fn raw_post<P: Serialize>(&self, operation: &'static str, url: &str, params: P) -> Result<HttpResponse> {
+    let req     = HttpRequest::post(url)?.json(&params)?;
+    let context = self.context(operation, &req);
+    self.dispatch(self.authenticate(req)?).await.map_err(|err| err.with_context(context))
   }
 
   /// Helper function to perform a PATCH request with a JSON payload.
-  async fn patch<T, P>(&self, url: &str, params: P) -> Result<T>
+  async fn patch<T, P>(&self, operation: &'static str, url: &str, params: P) -> Result<T>
   where T: DeserializeOwned, P: Serialize {
-    let res = self
-    .http_client
-    .patch(url)
-    .headers(self.default_headers()?)
-    .json(&params)
-    .send()
-    .await?;
-
-    if res.status().is_success() {
-      Ok(res.json::<T>().await?)
+    self.send_json(operation, HttpRequest::patch(url)?.json(&params)?).await
+  }
+
+  /// Sends an authenticated request & deserializes the JSON response.
+  async fn send_json<T: DeserializeOwned>(&self, operation: &'static str, req: HttpRequest) -> Result<T> {
+    let context = self.context(operation, &req);
+    let res     = self.send(operation, req).await?;
+    serde_json::from_slice(&res.body).map_err(|err| Error::from(err).with_context(context))
+  }
+
+  /// Sends an authenticated request, turning any non-successful response into an error.
+  /// Every error coming out of here carries the request context.
+  pub(crate) async fn send(&self, operation: &'static str, req: HttpRequest) -> Result<HttpResponse> {
+    let context = self.context(operation, &req);
+    let res     = self.dispatch(self.authenticate(req)?).await.map_err(|err| err.with_context(context.clone()))?;
+
+    if res.status.is_success() {
+      Ok(res)
     } else {
-      let error = res.json().await?;
-      Err(Error::ResponseError(error))
+      let err = match ErrorResponse::parse_all(&res.body) {
+        Ok(errors) => Error::ResponseError(errors),
+        Err(_)     => Error::http_status(res)
+      };
+      Err(err.with_context(context))
     }
   }
 
+  fn context(&self, operation: &'static str, req: &HttpRequest) -> RequestContext {
+    RequestContext::new(operation, &req.method, &req.url, self.correlation.id())
+  }
+
+  /// Hands a request off to the transport.
+  /// The concurrency permit (if any) is held until the response body has been fully read.
+  async fn dispatch(&self, mut req: HttpRequest) -> Result<HttpResponse> {
+    if let Some(ref id) = self.correlation.id {
+      req.headers.insert(self.correlation.header.clone(), id.clone());
+    }
+
+    traced!(async move {
+      let _permit = self.acquire_permit().await;
+      let method  = req.method.clone();
+      let sent    = req.body.as_ref().map_or(0, Vec::len);
+      let started = Instant::now();
+      let result  = self.inner.transport.send(req).await;
+      let elapsed = started.elapsed();
+
+      record!("duration_ms", elapsed.as_millis() as u64);
+      match result {
+        Ok(ref res) => {
+          record!("status", res.status.as_u16());
+          metrics::request(&method, Some(res.status.as_u16()), elapsed, sent, res.body.len());
+        },
+        Err(_) => metrics::request(&method, None, elapsed, sent, 0)
+      }
+
+      result
+    },
+      "request",
+      method         = %req.method,
+      endpoint       = %req.url.path(),
+      correlation_id = self.correlation.id().unwrap_or_default(),
+      status         = tracing::field::Empty,
+      duration_ms    = tracing::field::Empty
+    ).await
+  }
+
+  /// Adds the default headers to a request; anything already set on the request wins (such as asking for CSV instead of JSON).
+  fn authenticate(&self, mut req: HttpRequest) -> Result<HttpRequest> {
+    for (name, value) in self.default_headers()? {
+      if let Some(name) = name {
+        req.headers.entry(name).or_insert(value);
+      }
+    }
+    Ok(req)
+  }
+
+  /// Waits for a free request slot when a concurrency limit has been configured.
+  async fn acquire_permit(&self) -> Option<SemaphorePermit<'_>> {
+    match self.inner.limiter {
+      Some(ref limiter) => Some(limiter.acquire().await),
+      None              => None
+    }
+  }
+
+  /// The signature is a base64 encoded HMAC-SHA256 of `id + issued_at`, keyed with the client secret.
+  /// Checked against the raw response, before it's deserialized into whichever response type the flow expects.
+  fn verify_signature(&self, body: &[u8]) -> Result<()> {
+    #[derive(serde::Deserialize)]
+    struct Signed {
+      id:        String,
+      issued_at: String,
+      signature: String
+    }
+
+    let res: Signed = serde_json::from_slice(body)?;
+    let signature   = base64::decode(&res.signature).map_err(|_| Error::InvalidSignature)?;
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(self.inner.client_secret.as_bytes())
+      .expect("HMAC can take a key of any size");
+
+    mac.update(res.id.as_bytes());
+    mac.update(res.issued_at.as_bytes());
+    mac.verify_slice(&signature).map_err(|_| Error::InvalidSignature)
+  }
+
   /// Builds a set of default headers for all authenticated requests.
   fn default_headers(&self) -> Result<HeaderMap> {
     let mut headers = HeaderMap::new();
-    headers.insert(AUTHORIZATION, format!("Bearer {}", self.access_token.as_ref().ok_or(Error::NotAuthenticatedError)?.value).parse()?);
-    headers.insert(ACCEPT, "application/json".parse()?);
+    headers.insert(AUTHORIZATION, format!("Bearer {}", self.session()?.access_token.value).parse()?);
+    headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
 
     Ok(headers)
   }
 
   /// I got tired of typing this over and over; helper function seemed like the next logical step.
-  fn base_path(&self) -> Result<&str> {
-    Ok(self.base_path.as_ref().ok_or(Error::NotAuthenticatedError)?)
+  fn base_path(&self) -> Result<String> {
+    Ok(self.session()?.base_path)
+  }
+
+  /// Snapshots the current session; the lock is never held across an `await`.
+  fn session(&self) -> Result<Session> {
+    self
+      .inner
+      .session
+      .read()
+      .expect("session lock poisoned")
+      .clone()
+      .ok_or(Error::NotAuthenticatedError)
+  }
+
+  fn set_session(&self, session: Session) {
+    *self.inner.session.write().expect("session lock poisoned") = Some(session);
+  }
+
+  /// Replaces the session with the one from a (successful) token response.
+  pub(crate) fn start_session(&self, res: TokenResponse) {
+    // Build a string representing the base path for all further requests
+    let base_path = format!("{}/services/data/{}", res.instance_url, self.inner.version);
+
+    self.set_session(Session {
+      instance_url:  res.instance_url.clone(),
+      refresh_token: res.refresh_token.clone(),
+      access_token:  res.into(),
+      base_path
+    });
+  }
+
+  pub(crate) fn version(&self) -> &str {
+    &self.inner.version
   }
 }
 
@@ -335,7 +826,7 @@ mod tests {
   use serde_json::json;
   use mockito::*;
 
-  #[derive(Deserialize, Serialize)]
+  #[derive(Deserialize, Serialize, Debug)]
   #[serde(rename_all = "PascalCase")]
   struct Case {
     id:          String,
@@ -348,10 +839,10 @@ mod tests {
   async fn login_with_credentials() -> Result<()> {
     let mock = build_mock_server("POST", "/services/oauth2/token", mock_token_response(), 200).expect_at_most(1);
 
-    let mut client = Client::builder()
+    let client = Client::builder()
       .client_id("top_secret_thingy")
       .client_secret("even_more_top_secret_thingy")
-      .login_endpoint(&mockito::server_url())
+      .login_endpoint(mockito::server_url())
       .create()?;
 
     client.login_with_credentials(
@@ -362,11 +853,238 @@ mod tests {
     let token = client.access_token()?;
     assert_eq!("00DR00000008oBT!AQwAQCPqzc_HBE59c80QmEJD4rQKRRc1GRLvYZEq", token.value);
     assert_eq!("1513887500425", token.issued_at);
+    #[cfg(feature = "chrono")]
+    assert_eq!(token.issued_at_time().map(|time| time.timestamp_millis()), Some(1513887500425));
     assert_eq!("Bearer", token.token_type);
     mock.assert();
     Ok(())
   }
 
+  #[tokio::test]
+  async fn login_failure() -> Result<()> {
+    let body = json!({ "error": "invalid_grant", "error_description": "authentication failure" });
+    let mock = build_mock_server("POST", "/services/oauth2/token", body.to_string(), 400).expect_at_most(1);
+
+    let client = Client::builder()
+      .client_id("top_secret_thingy")
+      .client_secret("even_more_top_secret_thingy")
+      .login_endpoint(mockito::server_url())
+      .create()?;
+
+    match client.login_with_credentials("supreme.leader@shibe.com", "hunter2").await.map_err(Error::into_inner) {
+      Err(err @ Error::TokenError(_)) => {
+        assert_eq!(err.to_string(), "token request failed (invalid_grant: authentication failure)");
+        if let Error::TokenError(res) = err {
+          assert_eq!(res.error, "invalid_grant");
+          assert_eq!(res.error_description, "authentication failure");
+        }
+      },
+      other => panic!("expected a token error, got {:?}", other)
+    }
+
+    assert!(client.access_token().is_err());
+    mock.assert();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn login_verifies_signature() -> Result<()> {
+    let secret = "even_more_top_secret_thingy";
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(b"https://login.salesforce.com/id/00DR00000008oBTMAY/005R0000000IUUMIA4");
+    mac.update(b"1513887500425");
+
+    let mut body = serde_json::from_str::<serde_json::Value>(&mock_token_response())?;
+    body["signature"] = json!(base64::encode(mac.finalize().into_bytes()));
+
+    let mock   = build_mock_server("POST", "/services/oauth2/token", body.to_string(), 200).expect_at_most(1);
+    let client = Client::builder()
+      .client_id("top_secret_thingy")
+      .client_secret(secret)
+      .login_endpoint(mockito::server_url())
+      .verify_signature(true)
+      .create()?;
+
+    client.login_with_credentials("supreme.leader@shibe.com", "hunter2").await?;
+    assert!(client.access_token().is_ok());
+    mock.assert();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn login_rejects_invalid_signature() -> Result<()> {
+    let mock   = build_mock_server("POST", "/services/oauth2/token", mock_token_response(), 200).expect_at_most(1);
+    let client = Client::builder()
+      .client_id("top_secret_thingy")
+      .client_secret("definitely_not_the_secret_that_signed_the_response")
+      .login_endpoint(mockito::server_url())
+      .verify_signature(true)
+      .create()?;
+
+    let res = client.login_with_credentials("supreme.leader@shibe.com", "hunter2").await;
+    assert!(matches!(res, Err(Error::InvalidSignature)));
+    assert!(client.access_token().is_err());
+    mock.assert();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn streaming() -> Result<()> {
+    use crate::streaming::ReplayFrom;
+    use crate::test_util::{MockClient, MockResponse};
+    use reqwest::Method;
+
+    let event = |replay_id: i64, name: &str| json!({
+      "channel": "/data/AccountChangeEvent",
+      "data":    { "schema": "IeRuaY6cbI_HsV8Rv1Mc5g", "payload": { "Name": name }, "event": { "replayId": replay_id } }
+    });
+    let handshake = |client_id: &str| MockResponse::json(200, json!([{ "channel": "/meta/handshake", "clientId": client_id, "successful": true }]));
+    let subscribed = MockResponse::json(200, json!([{ "channel": "/meta/subscribe", "subscription": "/data/AccountChangeEvent", "successful": true }]));
+
+    let mock = MockClient::new();
+    let path = "/cometd/49.0";
+    mock.transport().on(Method::POST, path, handshake("3aa1nq").header("set-cookie", "BAYEUX_BROWSER=a5eb; Path=/; Secure"));
+    mock.transport().on(Method::POST, path, subscribed.clone());
+    mock.transport().on(Method::POST, path, MockResponse::json(200, json!([event(5, "Shiba"), event(6, "Akita"), { "channel": "/meta/connect", "successful": true }])));
+    // The client ID expires, so it's back to handshaking
+    mock.transport().on(Method::POST, path, MockResponse::json(200, json!([{
+      "channel": "/meta/connect", "successful": false, "error": "403::Unknown client", "advice": { "reconnect": "handshake" }
+    }])));
+    mock.transport().on(Method::POST, path, handshake("8fx2kl"));
+    mock.transport().on(Method::POST, path, subscribed);
+    mock.transport().on(Method::POST, path, MockResponse::json(200, json!([{ "channel": "/meta/connect", "successful": true }, event(7, "Corgi")])));
+
+    let mut subscriber = mock.subscriber();
+    subscriber.subscribe("/data/AccountChangeEvent", ReplayFrom::After(4));
+    let mut events = Vec::new();
+    for _ in 0..3 {
+      let event = subscriber.next_event().await?;
+      events.push((event.channel, event.replay_id, event.payload["Name"].clone()));
+    }
+    assert_eq!(events, [
+      ("/data/AccountChangeEvent".to_string(), 5, json!("Shiba")),
+      ("/data/AccountChangeEvent".to_string(), 6, json!("Akita")),
+      ("/data/AccountChangeEvent".to_string(), 7, json!("Corgi"))
+    ]);
+
+    let requests = mock.transport().requests();
+    let messages = requests.iter().map(|req| serde_json::from_slice::<serde_json::Value>(req.body.as_deref().unwrap_or_default()).unwrap()[0].clone()).collect::<Vec<_>>();
+    let channels = messages.iter().map(|message| message["channel"].as_str().unwrap_or_default()).collect::<Vec<_>>();
+    assert_eq!(channels, ["/meta/handshake", "/meta/subscribe", "/meta/connect", "/meta/connect", "/meta/handshake", "/meta/subscribe", "/meta/connect"]);
+    assert_eq!(messages[0]["ext"], json!({ "replay": true }));
+    assert_eq!(messages[1]["ext"], json!({ "replay": { "/data/AccountChangeEvent": 4 } }));
+    assert_eq!(messages[2]["clientId"], "3aa1nq");
+    assert_eq!(requests[2].headers.get("cookie").unwrap(), "BAYEUX_BROWSER=a5eb");
+    // Resubscribing picks up after the last event handed out
+    assert_eq!(messages[5]["ext"], json!({ "replay": { "/data/AccountChangeEvent": 6 } }));
+    assert_eq!(messages[6]["clientId"], "8fx2kl");
+
+    // Replay IDs past the retention window get turned down
+    let expired = MockClient::new();
+    expired.transport().on(Method::POST, path, handshake("3aa1nq"));
+    expired.transport().on(Method::POST, path, MockResponse::json(200, json!([{
+      "channel": "/meta/subscribe", "successful": false, "error": "400::The replayId {4} you provided was invalid.  Please provide a valid ID, -2 to replay all events, or -1 to replay only new events."
+    }])));
+    let mut subscriber = expired.subscriber();
+    subscriber.subscribe("/data/AccountChangeEvent", ReplayFrom::After(4));
+    match subscriber.next_event().await.map_err(Error::into_inner) {
+      Err(Error::StreamingError(err)) => assert!(err.starts_with("cannot subscribe to /data/AccountChangeEvent: 400::The replayId {4}"), "{}", err),
+      other                           => panic!("expected a streaming error, got {:?}", other)
+    }
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn oauth_flows() -> Result<()> {
+    use crate::test_util::{fixtures, MockClient, MockResponse};
+    use reqwest::Method;
+
+    let form = |req: &HttpRequest| -> Vec<(String, String)> {
+      url::form_urlencoded::parse(req.body.as_deref().unwrap_or_default()).into_owned().collect()
+    };
+    let param = |req: &HttpRequest, name: &str| form(req).into_iter().find(|(key, _)| key == name).map(|(_, value)| value);
+
+    // Unpadded base64url
+    assert_eq!(pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUwl5ogCJmMGWDxVBUKjw"), "KSF3mRH_NVl8qwc3A3lTdMnt8bJWdDspY25sqP6mSm4");
+
+    let mock = MockClient::new();
+    let url  = url::Url::parse(&mock.authorize_url("http://localhost:1717/OauthRedirect", "xyz", Some("challenge"))?)?;
+    assert_eq!(url.path(), "/services/oauth2/authorize");
+    assert_eq!(url.query_pairs().into_owned().collect::<Vec<_>>(), [
+      ("response_type".to_string(), "code".to_string()),
+      ("client_id".to_string(), "mock-client-id".to_string()),
+      ("redirect_uri".to_string(), "http://localhost:1717/OauthRedirect".to_string()),
+      ("scope".to_string(), "api refresh_token".to_string()),
+      ("state".to_string(), "xyz".to_string()),
+      ("code_challenge".to_string(), "challenge".to_string()),
+      ("code_challenge_method".to_string(), "S256".to_string())
+    ]);
+
+    // The code comes with a refresh token, which is kept when refreshing doesn't hand out a new one
+    let mut token = fixtures::token("https://shibe.my.salesforce.com");
+    token["refresh_token"] = json!("5Aep861TSESvWeug_xvFHRBTTbf_YrTWgEyjBJrqMwQ");
+    mock.transport().on(Method::POST, "/services/oauth2/token", MockResponse::json(200, token));
+    mock.transport().on(Method::POST, "/services/oauth2/token", MockResponse::json(200, fixtures::token("https://shibe.my.salesforce.com")));
+
+    mock.login_with_authorization_code("aPrx4sgoM2Nd1zWeFVlOWveD0HhYmiDiLmlLnXEBgX01tpVOQMWVSUuafFPHu3kCSjzk4CUTZg==", "http://localhost:1717/OauthRedirect", Some("verifier")).await?;
+    assert_eq!(mock.refresh_token().as_deref(), Some("5Aep861TSESvWeug_xvFHRBTTbf_YrTWgEyjBJrqMwQ"));
+
+    mock.login_with_refresh_token("5Aep861TSESvWeug_xvFHRBTTbf_YrTWgEyjBJrqMwQ").await?;
+    assert_eq!(mock.refresh_token().as_deref(), Some("5Aep861TSESvWeug_xvFHRBTTbf_YrTWgEyjBJrqMwQ"));
+
+    let requests = mock.transport().requests();
+    assert_eq!(param(&requests[0], "grant_type").as_deref(), Some("authorization_code"));
+    assert_eq!(param(&requests[0], "code_verifier").as_deref(), Some("verifier"));
+    assert_eq!(param(&requests[1], "grant_type").as_deref(), Some("refresh_token"));
+
+    // Device codes get polled for until the user has entered theirs
+    let device = MockClient::new();
+    device.transport().on(Method::POST, "/services/oauth2/token", MockResponse::json(200, json!({
+      "device_code":      "M0RFVklDRV9DT0RFX0ZPUl9URVNUSU5H",
+      "user_code":        "ABCD1234",
+      "verification_uri": "https://login.salesforce.com/setup/connect",
+      "interval":         0
+    })));
+    device.transport().on(Method::POST, "/services/oauth2/token", MockResponse::json(400, json!({ "error": "authorization_pending", "error_description": "pending" })));
+    device.transport().on(Method::POST, "/services/oauth2/token", MockResponse::json(200, fixtures::token("https://shibe.my.salesforce.com")));
+
+    let code = device.request_device_code().await?;
+    assert_eq!(code.user_code, "ABCD1234");
+    device.login_with_device_code(&code).await?;
+
+    let requests = device.transport().requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(param(&requests[0], "response_type").as_deref(), Some("device_code"));
+    assert_eq!(param(&requests[2], "grant_type").as_deref(), Some("device"));
+    assert_eq!(param(&requests[2], "code").as_deref(), Some("M0RFVklDRV9DT0RFX0ZPUl9URVNUSU5H"));
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn clones_share_the_session() -> Result<()> {
+    let mock = build_mock_server("POST", "/services/oauth2/token", mock_token_response(), 200).expect_at_most(1);
+
+    let client = Client::builder()
+      .client_id("top_secret_thingy")
+      .client_secret("even_more_top_secret_thingy")
+      .login_endpoint(mockito::server_url())
+      .create()?;
+
+    let clone = client.clone();
+    assert!(matches!(clone.access_token(), Err(Error::NotAuthenticatedError)));
+
+    // Login from a spawned task to make sure the client (and its futures) can move across tasks
+    tokio::spawn(async move { client.login_with_credentials("supreme.leader@shibe.com", "hunter2").await })
+      .await
+      .expect("login task panicked")?;
+
+    assert_eq!(clone.access_token()?.value, "00DR00000008oBT!AQwAQCPqzc_HBE59c80QmEJD4rQKRRc1GRLvYZEq");
+    assert_eq!(clone.instance_url().as_deref(), Some("https://MyDomainName.my.salesforce.com"));
+    mock.assert();
+    Ok(())
+  }
+
   #[tokio::test]
   async fn query() -> Result<()> {
     // let _ = env_logger::try_init();
@@ -375,10 +1093,136 @@ mod tests {
     let client = build_test_client();
     let res: QueryResponse<Case> = client.query("SELECT Id, AccountId, ContactId, Description FROM Case").await?;
 
-    assert_eq!(res.done, true);
+    assert!(res.done);
     assert_eq!(res.total_size, 1);
+    assert_eq!(res.len(), 1);
+    assert!(!res.is_empty());
     assert_eq!(res.records[0].id, "0122T000000gkLXQAY");
-    assert_eq!(res.records[0].description, "Halp! Everything is on fire!!");
+    assert_eq!((&res).into_iter().map(|case| case.account_id.as_str()).collect::<Vec<_>>(), vec!["01234000000BnaHAAS"]);
+
+    let cases: Vec<Case> = res.into_iter().collect();
+    assert_eq!(cases[0].description, "Halp! Everything is on fire!!");
+    mock.assert();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn query_with_attributes() -> Result<()> {
+    let body = json!({
+      "totalSize": 1,
+      "done": true,
+      "records": [{
+        "attributes": { "type": "Case", "url": "/services/data/v49.0/sobjects/Case/0122T000000gkLXQAY" },
+        "Id":          "0122T000000gkLXQAY",
+        "AccountId":   "01234000000BnaHAAS",
+        "ContactId":   "01280000000HgqbAAC",
+        "Description": "Halp! Everything is on fire!!"
+      }]
+    }).to_string();
+
+    let mock   = build_mock_server("GET", Matcher::Regex("^/services/data/v49.0/query".to_string()), body, 200).expect_at_most(1);
+    let client = build_test_client();
+    let res: QueryResponse<SObjectRecord<Case>> = client.query("SELECT Id, AccountId, ContactId, Description FROM Case").await?;
+
+    let record = &res.records[0];
+    assert_eq!(record.attributes.sobject_type, "Case");
+    assert_eq!(record.id(), Some("0122T000000gkLXQAY"));
+    assert_eq!(record.description, "Halp! Everything is on fire!!");
+    mock.assert();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn query_derived_sobject() -> Result<()> {
+    use crate::sobject::SObject;
+
+    #[derive(oxidized_force_derive::SObject, Debug)]
+    #[sobject(name = "Case")]
+    struct Ticket {
+      id:          String,
+      account_id:  String,
+      #[serde(rename = "ContactId")]
+      contact:     String,
+      description: String,
+      #[sobject(custom)]
+      #[serde(default)]
+      severity:    Option<String>,
+      #[serde(skip)]
+      notes:       Vec<String>
+    }
+
+    assert_eq!(Ticket::API_NAME, "Case");
+    assert_eq!(Ticket::FIELDS, &["Id", "AccountId", "ContactId", "Description", "Severity__c"]);
+    assert_eq!(Ticket::select(), "SELECT Id, AccountId, ContactId, Description, Severity__c FROM Case");
+
+    let mock   = build_mock_server("GET", Matcher::Regex("^/services/data/v49.0/query".to_string()), mock_query_response(), 200).expect_at_most(1);
+    let client = build_test_client();
+    let res: QueryResponse<Ticket> = client.query(Ticket::select().as_str()).await?;
+
+    let ticket = &res.records[0];
+    assert_eq!(ticket.contact, "01280000000HgqbAAC");
+    assert_eq!(ticket.severity, None);
+    assert!(ticket.notes.is_empty());
+
+    let json = serde_json::to_value(ticket)?;
+    assert_eq!(json["AccountId"], "01234000000BnaHAAS");
+    assert_eq!(json["Severity__c"], serde_json::Value::Null);
+    mock.assert();
+    Ok(())
+  }
+
+  #[test]
+  fn derived_sobject_renames() -> Result<()> {
+    use crate::sobject::SObject;
+
+    #[derive(oxidized_force_derive::SObject, Debug)]
+    #[sobject(name = "Case")]
+    struct Ticket {
+      #[sobject(custom)]
+      #[serde(rename = "Priority", default)]
+      priority: Option<String>,
+      #[serde(rename(deserialize = "Subject"))]
+      title:    String,
+      #[sobject(rename = "Origin")]
+      #[serde(rename = "Origin")]
+      source:   String
+    }
+
+    assert_eq!(Ticket::FIELDS, &["Priority__c", "Subject", "Origin"]);
+
+    let ticket: Ticket = serde_json::from_value(json!({ "Priority__c": "High", "Subject": "Fire", "Origin": "Web" }))?;
+    assert_eq!(ticket.priority.as_deref(), Some("High"));
+    assert_eq!(ticket.title, "Fire");
+    assert_eq!(ticket.source, "Web");
+    assert_eq!(serde_json::to_value(&ticket)?, json!({ "Priority__c": "High", "Subject": "Fire", "Origin": "Web" }));
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn query_raw() -> Result<()> {
+    let body = json!({
+      "totalSize": 1,
+      "done": true,
+      "records": [{
+        "attributes": { "type": "Contact", "url": "/services/data/v49.0/sobjects/Contact/003R00000033I6AIAU" },
+        "Id":         "003R00000033I6AIAU",
+        "Account":    { "attributes": { "type": "Account" }, "Name": "Shibe Inc." }
+      }]
+    }).to_string();
+
+    let mock   = build_mock_server("GET", Matcher::Regex("^/services/data/v49.0/query".to_string()), body, 200).expect(2);
+    let client = build_test_client();
+    let soql   = "SELECT Id, Account.Name FROM Contact";
+
+    let res = client.query_raw(soql).await?;
+    assert_eq!(res.records[0]["Account"]["Name"], "Shibe Inc.");
+
+    let res: QueryResponse<Row> = client.query(soql).await?;
+    let row = &res.records[0];
+    assert_eq!(row.sobject_type(), Some("Contact"));
+    assert_eq!(row.get_str("Account.Name"), Some("Shibe Inc."));
+    assert_eq!(row.get("Account.Owner.Name"), None);
+    assert_eq!(row.fields().count(), 2);
     mock.assert();
     Ok(())
   }
@@ -396,6 +1240,132 @@ mod tests {
     Ok(())
   }
 
+  #[tokio::test]
+  async fn describe_compound_fields() -> Result<()> {
+    let field = |name: &str, tp: &str, compound: Option<&str>| json!({
+      "name": name, "type": tp, "compoundFieldName": compound, "length": 0, "custom": false, "encrypted": false,
+      "precision": 0, "updateable": true, "nillable": true, "unique": false, "relationshipName": null
+    });
+
+    let body = json!({
+      "name": "Account",
+      "fields": [
+        field("Id", "id", None),
+        field("BillingAddress", "address", None),
+        field("BillingCity", "string", Some("BillingAddress")),
+        field("BillingState", "string", Some("BillingAddress")),
+        field("BillingStateCode", "picklist", Some("BillingAddress")),
+        field("HQ__c", "location", None),
+        field("HQ__Latitude__s", "double", Some("HQ__c")),
+        field("HQ__Longitude__s", "double", Some("HQ__c"))
+      ]
+    });
+
+    let mock   = build_mock_server("GET", "/services/data/v49.0/sobjects/Account/describe", body.to_string(), 200).expect_at_most(1);
+    let client = build_test_client();
+    let res    = client.describe("Account").await?;
+
+    assert!(res.is_compound("BillingAddress"));
+    assert!(!res.is_compound("BillingCity"));
+    assert!(res.field("BillingCity").unwrap().is_component());
+    assert_eq!(res.components("HQ__c").len(), 2);
+    assert_eq!(res.component_field_names(), vec!["Id", "BillingCity", "BillingState", "BillingStateCode", "HQ__Latitude__s", "HQ__Longitude__s"]);
+
+    let mut row: Row = serde_json::from_value(json!({
+      "Id":             "0012T00000Zx1bAQAR",
+      "BillingAddress": { "city": "Portland", "state": "Oregon", "stateCode": "OR", "country": "United States" },
+      "HQ__c":          { "latitude": 45.52, "longitude": -122.68 }
+    }))?;
+    row.flatten_compound(&res);
+
+    assert_eq!(row.get("BillingAddress"), None);
+    assert_eq!(row.get_str("BillingCity"), Some("Portland"));
+    assert_eq!(row.get_str("BillingState"), Some("Oregon"));
+    assert_eq!(row.get_str("BillingStateCode"), Some("OR"));
+    assert_eq!(row.get("HQ__Longitude__s"), Some(&json!(-122.68)));
+    mock.assert();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn describe_cache() -> Result<()> {
+    let mock   = build_mock_server("GET", "/services/data/v49.0/sobjects/Case/describe", mock_describe_response(), 200).expect(2);
+    let client = build_test_client_with(test_builder().describe_cache(Duration::from_secs(60)));
+
+    assert_eq!(client.describe("Case").await?.name, "Case");
+    assert_eq!(client.describe("Case").await?.name, "Case");
+
+    client.invalidate_describe(Some("Case"))?;
+    assert_eq!(client.describe("Case").await?.fields.len(), 2);
+    mock.assert();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn describe_cache_on_disk() -> Result<()> {
+    let dir  = std::env::temp_dir().join(format!("oxidized-force-cache-{}", std::process::id()));
+    let mock = build_mock_server("GET", "/services/data/v49.0/sobjects", mock_describe_global_response(), 200).expect(1);
+
+    // Separate clients only share the files on disk
+    for _ in 0..2 {
+      let client = build_test_client_with(test_builder().describe_cache(Duration::from_secs(60)).describe_cache_dir(&dir));
+      assert_eq!(client.describe_global().await?.sobjects.len(), 2);
+    }
+    mock.assert();
+
+    build_test_client_with(test_builder().describe_cache(Duration::from_secs(60)).describe_cache_dir(&dir)).clear_describe_cache();
+    assert_eq!(std::fs::read_dir(&dir)?.count(), 0);
+    std::fs::remove_dir(&dir)?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn describe_unknown_field_type() -> Result<()> {
+    let mut body = serde_json::from_str::<serde_json::Value>(&mock_describe_response())?;
+    body["fields"][1]["type"] = json!("complexvalue");
+
+    let mock   = build_mock_server("GET", "/services/data/v49.0/sobjects/Case/describe", body.to_string(), 200).expect_at_most(1);
+    let client = build_test_client();
+    let res    = client.describe("Case").await?;
+
+    assert_eq!(res.fields[0].field_type, FieldType::Id);
+    assert_eq!(res.fields[1].field_type, FieldType::Unknown);
+    mock.assert();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn describe_default_values() -> Result<()> {
+    let mut body = serde_json::from_str::<serde_json::Value>(&mock_describe_response())?;
+    body["fields"][0]["defaultValue"] = json!(false);
+    body["fields"][1]["defaultValue"] = json!({ "value": "Shiba" });
+
+    let mock   = build_mock_server("GET", "/services/data/v49.0/sobjects/Case/describe", body.to_string(), 200).expect_at_most(1);
+    let client = build_test_client();
+    let res    = client.describe("Case").await?;
+
+    assert_eq!(res.fields[0].default_value, Some(DefaultValue::Boolean(false)));
+    assert_eq!(res.fields[1].default_value, Some(DefaultValue::Text("Shiba".to_string())));
+    mock.assert();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn describe_global() -> Result<()> {
+    let mock   = build_mock_server("GET", "/services/data/v49.0/sobjects", mock_describe_global_response(), 200).expect_at_most(1);
+    let client = build_test_client();
+    let res    = client.describe_global().await?;
+
+    assert_eq!(res.max_batch_size, 200);
+    assert_eq!(res.sobjects.len(), 2);
+    assert_eq!(res.sobjects[0].name, "Account");
+    assert_eq!(res.sobjects[0].key_prefix.as_deref(), Some("001"));
+    assert!(res.sobjects[1].custom);
+    assert!(res.sobjects[1].key_prefix.is_none());
+    mock.assert();
+    Ok(())
+  }
+
   #[tokio::test]
   async fn create_query_job() -> Result<()> {
     let mock   = build_mock_server("POST", "/services/data/v49.0/jobs/query", mock_job_response(), 200).expect_at_most(1);
@@ -404,25 +1374,466 @@ mod tests {
 
     assert_eq!(res.object, "Account");
     assert_eq!(res.id, "750R0000000zlh9IAA");
+    assert_eq!(res.number_records_processed, 0);
+    assert!(res.error_message.is_none());
+
+    #[cfg(feature = "chrono")]
+    assert_eq!(res.created_date_time().map(|time| time.to_rfc3339()).as_deref(), Some("2018-12-10T17:50:19+00:00"));
+    mock.assert();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn get_query_job_status() -> Result<()> {
+    let mut body = serde_json::from_str::<serde_json::Value>(&mock_job_response())?;
+    body["state"]                  = json!("Failed");
+    body["numberRecordsProcessed"] = json!(1200);
+    body["retries"]                = json!(2);
+    body["totalProcessingTime"]    = json!(3487);
+    body["errorMessage"]           = json!("INVALID_FIELD: No such column 'Shibe__c' on entity 'Account'");
+
+    let mock   = build_mock_server("GET", "/services/data/v49.0/jobs/query/750R0000000zlh9IAA", body.to_string(), 200).expect_at_most(1);
+    let client = build_test_client();
+    let res    = client.get_query_job_status("750R0000000zlh9IAA").await?;
+
+    assert_eq!(res.state, BulkState::Failed);
+    assert_eq!(res.number_records_processed, 1200);
+    assert_eq!(res.retries, 2);
+    assert_eq!(res.total_processing_time, 3487);
+    assert!(res.error_message.unwrap().starts_with("INVALID_FIELD"));
+    mock.assert();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn wait_for_query_job() -> Result<()> {
+    let body   = mock_job_response().replace("InProgress", "JobComplete");
+    let mock   = build_mock_server("GET", "/services/data/v49.0/jobs/query/750R0000000zlh9IAA", body, 200).expect(1);
+    let client = build_test_client();
+    let res    = client.wait_for_query_job("750R0000000zlh9IAA", Duration::from_millis(10), &CallOptions::new()).await?;
+
+    assert_eq!(res.state, BulkState::JobComplete);
+    mock.assert();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn wait_for_query_job_deadline() -> Result<()> {
+    let _mock   = build_mock_server("GET", "/services/data/v49.0/jobs/query/750R0000000zlh9IAA", mock_job_response(), 200);
+    let client  = build_test_client();
+    let options = CallOptions::new().timeout(Duration::from_millis(50));
+    let res     = client.wait_for_query_job("750R0000000zlh9IAA", Duration::from_millis(10), &options).await;
+
+    assert!(matches!(res, Err(Error::DeadlineExceeded)));
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn wait_for_query_job_cancelled() -> Result<()> {
+    let _mock   = build_mock_server("GET", "/services/data/v49.0/jobs/query/750R0000000zlh9IAA", mock_job_response(), 200);
+    let client  = build_test_client();
+    let token   = crate::options::CancellationToken::new();
+    let options = CallOptions::new().cancel_token(token.clone());
+
+    let (res, _) = tokio::join!(
+      client.wait_for_query_job("750R0000000zlh9IAA", Duration::from_secs(60), &options),
+      async { time::delay_for(Duration::from_millis(50)).await; token.cancel() }
+    );
+
+    assert!(matches!(res, Err(Error::Cancelled)));
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn get_query_job_results() -> Result<()> {
+    let mock = mock("GET", "/services/data/v49.0/jobs/query/750R0000000zlh9IAA/results?maxRecords=2")
+      .match_header("accept", "text/csv")
+      .with_status(200)
+      .with_header("content-type", "text/csv")
+      .with_header("Sforce-Locator", "MTAwMDA")
+      .with_header("Sforce-NumberOfRecords", "2")
+      .with_body("\"Id\",\"Name\"\n\"001R00000033I6AIAU\",\"Shiba\"\n\"001R00000033I6BIAU\",\"Inu\"\n")
+      .create();
+
+    let client = build_test_client();
+    let res    = client.get_query_job_results("750R0000000zlh9IAA", None, Some(2), &CallOptions::new()).await?;
+
+    assert!(res.has_more());
+    assert_eq!(res.locator.as_deref(), Some("MTAwMDA"));
+    assert_eq!(res.number_of_records, Some(2));
+    assert!(res.body.starts_with("\"Id\",\"Name\""));
+    mock.assert();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn custom_transport() -> Result<()> {
+    let transport = Arc::new(StaticTransport::new(mock_describe_response()));
+
+    let client = build_test_client_with(test_builder().transport(transport.clone()));
+
+    let res = client.describe("Case").await?;
+    assert_eq!(res.name, "Case");
+
+    let requests = transport.requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, reqwest::Method::GET);
+    assert_eq!(requests[0].url.path(), "/services/data/v49.0/sobjects/Case/describe");
+    assert_eq!(requests[0].headers[AUTHORIZATION], "Bearer shiba");
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn mock_client_bulk_lifecycle() -> Result<()> {
+    use crate::test_util::{fixtures, MockClient, MockResponse};
+    use reqwest::Method;
+
+    let mock   = MockClient::new();
+    let job_id = "750R0000000zlh9IAA";
+    let path   = format!("/jobs/query/{}", job_id);
+
+    mock.on(Method::POST, "/jobs/query", MockResponse::json(200, fixtures::bulk_job(job_id, "Case", BulkState::UploadComplete)));
+    for status in fixtures::bulk_job_lifecycle(job_id, "Case") {
+      mock.on(Method::GET, &path, MockResponse::json(200, status));
+    }
+    mock.on(Method::GET, &format!("{}/results", path), MockResponse::csv("Id,Subject\n1,Halp\n2,Fire\n", None));
+    mock.on(Method::GET, "/sobjects/Case/describe", MockResponse::json(200, fixtures::describe("Case").field("Id", FieldType::Id).reference("AccountId").to_json()));
+
+    let job    = mock.create_filtered_query_job("Case", vec!["Id", "Subject"], Some("SystemModstamp > 2021-01-01T00:00:00.000Z")).await?;
+    let body   = String::from_utf8_lossy(&mock.transport().requests()[0].body.clone().unwrap_or_default()).into_owned();
+    assert!(body.contains("SELECT Id,Subject FROM Case WHERE SystemModstamp > 2021-01-01T00:00:00.000Z"));
+
+    let status = mock.wait_for_query_job(job.id.as_str(), Duration::from_millis(1), &CallOptions::new()).await?;
+    assert_eq!(status.state, BulkState::JobComplete);
+    assert_eq!(mock.transport().hits(&mock.api_path(&path)), 3);
+
+    let results = mock.get_query_job_results(job_id, None, None, &CallOptions::new()).await?;
+    assert_eq!(results.number_of_records, Some(2));
+    assert!(!results.has_more());
+
+    let describe = mock.describe("Case").await?;
+    assert_eq!(describe.fields[1].relationship_name.as_deref(), Some("Account"));
+
+    let err = mock.describe("Lead").await.unwrap_err();
+    assert!(matches!(err.inner(), Error::ResponseError(errors) if errors[0].error_code == ErrorCode::NotFound));
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn vcr_record_and_replay() -> Result<()> {
+    use crate::test_util::{fixtures, MockResponse, MockTransport, VcrTransport};
+    use reqwest::Method;
+
+    let cassette = std::env::temp_dir().join(format!("oxidized-force-vcr-{}.json", std::process::id()));
+    let inner    = Arc::new(MockTransport::new());
+
+    inner.on(Method::POST, "/services/oauth2/token", MockResponse::json(200, fixtures::token("https://acme.my.salesforce.com")));
+    inner.on(Method::GET, "/services/data/v49.0/query", MockResponse::json(200, fixtures::query_page(vec![fixtures::record("Case", "500R000001", json!({}))], 2, Some("/services/data/v49.0/query/01gR-2000"))));
+    inner.on(Method::GET, "/services/data/v49.0/query", MockResponse::json(200, fixtures::query_page(vec![fixtures::record("Case", "500R000002", json!({}))], 2, None)));
+
+    let run = |transport: Arc<dyn HttpTransport>| async move {
+      let client = test_builder().login_endpoint("https://login.salesforce.com").transport(transport).create()?;
+      client.login_with_credentials("supreme.leader@shibe.com", "hunter2").await?;
+
+      let first:  QueryResponse<Row> = client.query("SELECT Id FROM Case").await?;
+      let second: QueryResponse<Row> = client.query("SELECT Id FROM Case").await?;
+      Result::<_>::Ok((first.done, second.done, second.records[0].get_str("Id").map(String::from)))
+    };
+
+    let recorder = Arc::new(VcrTransport::record(&cassette, inner));
+    let recorded = run(recorder.clone()).await?;
+    recorder.save()?;
+
+    let raw = std::fs::read_to_string(&cassette)?;
+    assert!(!raw.contains("hunter2"));
+    assert!(!raw.contains("even-more-top-secret"));
+    assert!(!raw.contains("AQwAQCPqzc"));
+    assert!(!raw.contains("acme.my.salesforce.com"));
+
+    let replayer = Arc::new(VcrTransport::replay(&cassette)?);
+    assert_eq!(run(replayer.clone()).await?, recorded);
+    assert_eq!(recorded, (false, true, Some("500R000002".to_string())));
+
+    // Everything recorded has been played back by now
+    assert!(replayer.send(HttpRequest::get("https://login.salesforce.com/services/oauth2/token")?).await.is_err());
+    std::fs::remove_file(&cassette)?;
+    Ok(())
+  }
+
+  #[test]
+  fn generated_describes() -> Result<()> {
+    use crate::test_util::{generate::FIELD_TYPES, DescribeGenerator};
+    use std::collections::HashSet;
+
+    let mut seen = HashSet::new();
+
+    for describe in DescribeGenerator::new(1591).max_fields(60).take(100) {
+      let names: HashSet<_> = describe.fields.iter().map(|f| f.name.as_str()).collect();
+      assert_eq!(names.len(), describe.fields.len(), "duplicate field names in {}", describe.name);
+
+      for field in &describe.fields {
+        seen.insert(format!("{:?}", field.field_type));
+
+        if matches!(field.field_type, FieldType::Reference | FieldType::MasterRecord) {
+          assert!(field.relationship_name.is_some());
+        }
+        if field.field_type == FieldType::Picklist {
+          assert!(!field.picklist_values.is_empty());
+        }
+
+        // Everything generated should survive a trip through the actual wire format
+        let json = serde_json::to_value(field)?;
+        assert_eq!(serde_json::to_value(serde_json::from_value::<Field>(json.clone())?)?, json);
+      }
+    }
+
+    assert_eq!(seen.len(), FIELD_TYPES.len());
+
+    let first  = DescribeGenerator::new(7).describe();
+    let second = DescribeGenerator::new(7).describe();
+    assert_eq!(first.field_names(), second.field_names());
+    Ok(())
+  }
+
+  /// Answers every request after a short delay, keeping track of how many were in flight at once.
+  #[derive(Debug, Default)]
+  struct InFlightTransport {
+    mock:      crate::test_util::MockTransport,
+    in_flight: std::sync::atomic::AtomicUsize,
+    max:       std::sync::atomic::AtomicUsize
+  }
+
+  #[async_trait::async_trait]
+  impl HttpTransport for InFlightTransport {
+    async fn send(&self, req: HttpRequest) -> Result<HttpResponse> {
+      use std::sync::atomic::Ordering::SeqCst;
+
+      let in_flight = self.in_flight.fetch_add(1, SeqCst) + 1;
+      self.max.fetch_max(in_flight, SeqCst);
+      time::delay_for(Duration::from_millis(20)).await;
+      self.in_flight.fetch_sub(1, SeqCst);
+      self.mock.send(req).await
+    }
+  }
+
+  #[tokio::test]
+  async fn concurrency_limiter() -> Result<()> {
+    use crate::test_util::{fixtures, MockResponse, INSTANCE_URL};
+    use std::sync::atomic::Ordering::SeqCst;
+
+    let transport = Arc::new(InFlightTransport::default());
+    transport.mock.on(reqwest::Method::GET, "/services/data/v49.0/sobjects/Case/describe", MockResponse::new(200, mock_describe_response()));
+
+    let client = test_builder().version("v49.0").max_concurrent_requests(2).transport(transport.clone()).create()?;
+    client.start_session(serde_json::from_value(fixtures::token(INSTANCE_URL))?);
+
+    let (a, b, c, d, e) = tokio::join!(
+      client.describe("Case"),
+      client.describe("Case"),
+      client.describe("Case"),
+      client.describe("Case"),
+      client.describe("Case")
+    );
+
+    for res in &[a, b, c, d, e] {
+      assert!(res.is_ok());
+    }
+
+    // Never more than two at once, but the limit was actually reached
+    assert_eq!(transport.max.load(SeqCst), 2);
+    assert_eq!(transport.mock.hits("/services/data/v49.0/sobjects/Case/describe"), 5);
+
+    // All of the permits should have been handed back once the requests completed
+    assert_eq!(client.inner.limiter.as_ref().unwrap().available_permits(), 2);
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn correlation_id() -> Result<()> {
+    let mock = mock("GET", "/services/data/v49.0/sobjects/Case/describe")
+      .match_header("x-request-id", "sync-42")
+      .with_status(404)
+      .with_header("content-type", "application/json")
+      .with_body(json!({ "message": "The requested resource does not exist", "errorCode": "NOT_FOUND" }).to_string())
+      .create();
+
+    let mut client = build_test_client_with(test_builder().correlation_header("X-Request-Id"));
+    client.set_correlation_id(Some("sync-42"))?;
+
+    match client.describe("Case").await {
+      Err(Error::Request { context, source }) => {
+        assert_eq!(context.correlation_id.as_deref(), Some("sync-42"));
+        assert!(matches!(*source, Error::ResponseError(_)));
+      },
+      other => panic!("expected a request error, got {:?}", other)
+    }
+
     mock.assert();
     Ok(())
   }
 
+  #[tokio::test]
+  async fn error_array_response() -> Result<()> {
+    let body = json!([
+      { "message": "No such column 'Foo' on entity 'Case'", "errorCode": "INVALID_FIELD" },
+      { "message": "Required fields are missing: [Subject]", "errorCode": "REQUIRED_FIELD_MISSING", "fields": ["Subject"] },
+      { "message": "Shibes only", "errorCode": "NOT_ENOUGH_SHIBES" }
+    ]);
+
+    let mock   = build_mock_server("GET", Matcher::Regex("^/services/data/v49.0/query".to_string()), body.to_string(), 400).expect_at_most(1);
+    let client = build_test_client();
+
+    match client.query::<_, Case>("SELECT Foo FROM Case").await.map_err(Error::into_inner) {
+      Err(err @ Error::ResponseError(_)) => {
+        assert_eq!(err.to_string(), "request failed (No such column 'Foo' on entity 'Case'; Required fields are missing: [Subject]; Shibes only)");
+        if let Error::ResponseError(errors) = err {
+          assert_eq!(errors.len(), 3);
+          assert_eq!(errors[2].error_code, ErrorCode::Other("NOT_ENOUGH_SHIBES".to_string()));
+          assert_eq!(errors[0].error_code, ErrorCode::InvalidField);
+          assert_eq!(errors[1].error_code, ErrorCode::RequiredFieldMissing);
+          assert_eq!(errors[1].error_code.as_str(), "REQUIRED_FIELD_MISSING");
+          assert_eq!(errors[1].fields, Some(vec!["Subject".to_string()]));
+        }
+      },
+      other => panic!("expected a response error, got {:?}", other)
+    }
+
+    mock.assert();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn non_json_error_response() -> Result<()> {
+    let mock = mock("GET", "/services/data/v49.0/sobjects/Case/describe")
+      .with_status(503)
+      .with_header("content-type", "text/html")
+      .with_header("retry-after", "120")
+      .with_header("set-cookie", "BrowserId=shiba")
+      .with_body("<html><body>Down for maintenance</body></html>")
+      .create();
+
+    let client = build_test_client();
+
+    match client.describe("Case").await.map_err(Error::into_inner) {
+      Err(Error::HttpStatus { status, headers, body }) => {
+        assert_eq!(status, 503);
+        assert_eq!(headers.get("retry-after").unwrap(), "120");
+        assert!(headers.get("set-cookie").is_none());
+        assert!(body.contains("Down for maintenance"));
+      },
+      other => panic!("expected an http status error, got {:?}", other)
+    }
+
+    mock.assert();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn request_context() -> Result<()> {
+    let body = json!([{ "message": "unexpected token: FORM", "errorCode": "MALFORMED_QUERY" }]);
+    let mock = build_mock_server("GET", Matcher::Regex("^/services/data/v49.0/query".to_string()), body.to_string(), 400).expect_at_most(1);
+
+    let mut client = build_test_client();
+    client.set_correlation_id(Some("sync-7"))?;
+
+    let err     = client.query::<_, Case>("SELECT Id FORM Case").await.unwrap_err();
+    let context = err.context().expect("missing request context");
+
+    assert_eq!(context.operation, "query");
+    assert_eq!(context.method, "GET");
+    assert_eq!(context.path, "/services/data/v49.0/query?q=SELECT Id FORM Case");
+    assert!(matches!(err.inner(), Error::ResponseError(_)));
+    assert_eq!(err.to_string(), "request failed (unexpected token: FORM) (query: GET /services/data/v49.0/query?q=SELECT Id FORM Case, correlation id `sync-7`)");
+
+    let url = url::Url::parse("https://example.com/services/oauth2/token?code=shh&state=42")?;
+    assert_eq!(RequestContext::new("login", &reqwest::Method::POST, &url, None).path, "/services/oauth2/token?code=REDACTED&state=42");
+    mock.assert();
+    Ok(())
+  }
+
+  #[test]
+  fn zero_concurrency_limit_is_rejected() {
+    let res = Client::builder()
+      .client_id("top_secret_thingy")
+      .client_secret("even_more_top_secret_thingy")
+      .max_concurrent_requests(0)
+      .create();
+
+    assert!(matches!(res, Err(Error::ClientBuilderError(_))));
+  }
+
+  #[cfg(feature = "blocking")]
+  #[test]
+  fn blocking_describe() -> Result<()> {
+    let mock   = build_mock_server("GET", "/services/data/v49.0/sobjects/Case/describe", mock_describe_response(), 200).expect_at_most(1);
+    let client = crate::blocking::Client::new(build_test_client())?;
+    let res    = client.describe("Case")?;
+
+    assert_eq!(res.name, "Case");
+    assert_eq!(res.fields.len(), 2);
+    mock.assert();
+    Ok(())
+  }
+
+  /// An in-memory transport that always replies with the same body & remembers every request it saw.
+  #[derive(Debug)]
+  struct StaticTransport {
+    body:     String,
+    requests: std::sync::Mutex<Vec<HttpRequest>>
+  }
+
+  impl StaticTransport {
+    fn new(body: String) -> Self {
+      StaticTransport { body, requests: Default::default() }
+    }
+  }
+
+  #[async_trait::async_trait]
+  impl HttpTransport for StaticTransport {
+    async fn send(&self, req: HttpRequest) -> Result<HttpResponse> {
+      self.requests.lock().unwrap().push(req);
+
+      Ok(HttpResponse {
+        status:  reqwest::StatusCode::OK,
+        headers: HeaderMap::new(),
+        body:    self.body.clone().into_bytes()
+      })
+    }
+  }
+
   /// Does exactly what it says it does...
   fn build_test_client() -> Client {
+    build_test_client_with(&mut test_builder())
+  }
+
+  /// Builds an (already authenticated) client from a builder, for the tests that need to tweak configuration.
+  fn build_test_client_with(builder: &mut ClientBuilder) -> Client {
     let api_version = "v49.0".to_string();
     let base_path   = format!("{}/services/data/{}", &mockito::server_url(), api_version);
 
-    Client {
-      http_client:    reqwest::Client::new(),
-      client_id:      "top-secret".to_string(),
-      client_secret:  "even-more-top-secret".to_string(),
-      login_endpoint: "https://example.com".to_string(),
-      version:        api_version,
-      base_path:      Some(base_path),
-      instance_url:   Some(mockito::server_url()),
-      access_token:   Some(AccessToken { value: "shiba".to_string(), token_type: "Bearer".to_string(), issued_at: "1513887500425".to_string() })
-    }
+    let client = builder
+      .version(api_version)
+      .create()
+      .expect("failed to build test client");
+
+    client.set_session(Session {
+      instance_url:  mockito::server_url(),
+      access_token:  AccessToken { value: "shiba".to_string(), token_type: "Bearer".to_string(), issued_at: "1513887500425".to_string() },
+      refresh_token: None,
+      base_path
+    });
+
+    client
+  }
+
+  fn test_builder<'a>() -> ClientBuilder<'a> {
+    let mut builder = Client::builder();
+    builder
+      .client_id("top-secret")
+      .client_secret("even-more-top-secret")
+      .login_endpoint("https://example.com");
+
+    builder
   }
 
   /// This also does exactly what it says it does...
@@ -482,14 +1893,33 @@ mod tests {
     }).to_string()
   }
 
+  fn mock_describe_global_response() -> String {
+    json!({
+      "encoding": "UTF-8",
+      "maxBatchSize": 200,
+      "sobjects": [
+        {
+          "name": "Account", "label": "Account", "labelPlural": "Accounts", "keyPrefix": "001",
+          "custom": false, "queryable": true, "replicateable": true,
+          "urls": { "sobject": "/services/data/v49.0/sobjects/Account", "describe": "/services/data/v49.0/sobjects/Account/describe" }
+        },
+        {
+          "name": "Shibe__c", "label": "Shibe", "labelPlural": "Shibes", "keyPrefix": null,
+          "custom": true, "queryable": true, "replicateable": false,
+          "urls": { "sobject": "/services/data/v49.0/sobjects/Shibe__c" }
+        }
+      ]
+    }).to_string()
+  }
+
   fn mock_describe_response() -> String {
     use crate::response::*;
 
     json!({
       "name": "Case",
       "fields": vec![
-        Field { name: "Id".to_string(),        length: 42, custom: false, encrypted: false, precision: 0, updateable: false, nillable: false, unique: true,  relationship_name: None, field_type: FieldType::Id },
-        Field { name: "AccountId".to_string(), length: 42, custom: false, encrypted: false, precision: 0, updateable: false, nillable: false, unique: false, relationship_name: None, field_type: FieldType::Id }
+        Field { name: "Id".to_string(),        label: "Record ID".to_string(),  length: 42, custom: false, encrypted: false, precision: 0, scale: 0, digits: 0, updateable: false, nillable: false, unique: true,  relationship_name: None, reference_to: vec![], cascade_delete: false, restricted_delete: false, compound_field_name: None, default_value: None, picklist_values: vec![], inline_help_text: None, calculated: false, calculated_formula: None, field_type: FieldType::Id },
+        Field { name: "AccountId".to_string(), label: "Account ID".to_string(), length: 42, custom: false, encrypted: false, precision: 0, scale: 0, digits: 0, updateable: false, nillable: false, unique: false, relationship_name: None, reference_to: vec![], cascade_delete: false, restricted_delete: false, compound_field_name: None, default_value: None, picklist_values: vec![], inline_help_text: None, calculated: false, calculated_formula: None, field_type: FieldType::Id }
       ]
     }).to_string()
   }
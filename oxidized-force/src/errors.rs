@@ -1,4 +1,10 @@
+use std::fmt;
+
+use reqwest::{header::{HeaderMap, HeaderName}, Method, StatusCode};
+use url::Url;
+
 use crate::response::{ErrorResponse, TokenErrorResponse};
+use crate::transport::HttpResponse;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -11,17 +17,158 @@ pub enum Error {
   #[error("must login first")]
   NotAuthenticatedError,
 
-  #[error("token request failed")]
+  #[error("token request failed ({}: {})", .0.error, .0.error_description)]
   TokenError(TokenErrorResponse),
 
-  #[error("request failed ({})", .0.message)]
-  ResponseError(ErrorResponse),
+  #[error("token response signature is invalid")]
+  InvalidSignature,
+
+  #[error("request failed ({})", messages(.0))]
+  ResponseError(Vec<ErrorResponse>),
+
+  /// The response wasn't a Salesforce error at all (maintenance pages, proxies & the like).
+  #[error("request failed with status {status}")]
+  HttpStatus {
+    status:  StatusCode,
+    headers: HeaderMap,
+    body:    String
+  },
+
+  /// The Streaming API turned down a handshake or subscription (or ended the connection).
+  #[error("streaming failed ({0})")]
+  StreamingError(String),
+
+  #[error("operation was cancelled")]
+  Cancelled,
+
+  #[error("operation deadline exceeded")]
+  DeadlineExceeded,
+
+  /// Wraps failed requests with enough context to tell which of them failed.
+  #[error("{source} ({context})")]
+  Request {
+    context: RequestContext,
+    source:  Box<Error>
+  },
 
   #[error("request failed")]
   HttpError(#[from] reqwest::Error),
 
+  #[error("invalid request url")]
+  InvalidUrl(#[from] url::ParseError),
+
+  #[error("i/o error")]
+  IoError(#[from] std::io::Error),
+
   #[error("invalid request header")]
   InvalidRequestHeader(#[from] reqwest::header::InvalidHeaderValue)
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Which request failed: the operation (`query`, `describe` etc), method & path, along with the correlation ID (if any).
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+  pub operation:      &'static str,
+  pub method:         Method,
+  pub path:           String,
+  pub correlation_id: Option<String>
+}
+
+/// Query parameters that never end up in error messages.
+const SENSITIVE_PARAMS: [&str; 6] = ["access_token", "client_secret", "code", "password", "refresh_token", "token"];
+
+impl RequestContext {
+  pub(crate) fn new(operation: &'static str, method: &Method, url: &Url, correlation_id: Option<&str>) -> Self {
+    let mut path = url.path().to_string();
+
+    let query: Vec<String> = url
+      .query_pairs()
+      .map(|(key, value)| {
+        if SENSITIVE_PARAMS.contains(&key.as_ref()) {
+          format!("{}=REDACTED", key)
+        } else {
+          format!("{}={}", key, value)
+        }
+      })
+      .collect();
+
+    if !query.is_empty() {
+      path.push('?');
+      path.push_str(&query.join("&"));
+    }
+
+    RequestContext {
+      method:         method.clone(),
+      correlation_id: correlation_id.map(String::from),
+      operation,
+      path
+    }
+  }
+}
+
+impl fmt::Display for RequestContext {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}: {} {}", self.operation, self.method, self.path)?;
+    if let Some(ref id) = self.correlation_id {
+      write!(f, ", correlation id `{}`", id)?;
+    }
+    Ok(())
+  }
+}
+
+/// Headers worth keeping around when a response can't be made sense of.
+const INTERESTING_HEADERS: [&str; 5] = ["content-type", "location", "retry-after", "sforce-limit-info", "www-authenticate"];
+
+impl Error {
+  /// The underlying error, without any request context.
+  pub fn inner(&self) -> &Error {
+    match self {
+      Error::Request { source, .. } => source.inner(),
+      other                         => other
+    }
+  }
+
+  pub fn into_inner(self) -> Error {
+    match self {
+      Error::Request { source, .. } => source.into_inner(),
+      other                         => other
+    }
+  }
+
+  /// The request that failed (when the error came from a request).
+  pub fn context(&self) -> Option<&RequestContext> {
+    match self {
+      Error::Request { context, .. } => Some(context),
+      _                              => None
+    }
+  }
+
+  /// Attaches request context; errors that already have some are left alone.
+  pub(crate) fn with_context(self, context: RequestContext) -> Error {
+    match self {
+      err @ Error::Request { .. } => err,
+      err                         => Error::Request { context, source: Box::new(err) }
+    }
+  }
+
+  /// Builds an `HttpStatus` error from an unsuccessful response nobody could parse.
+  pub(crate) fn http_status(res: HttpResponse) -> Error {
+    let mut headers = HeaderMap::new();
+    for name in INTERESTING_HEADERS.iter() {
+      if let Some(value) = res.headers.get(*name) {
+        headers.insert(HeaderName::from_static(name), value.clone());
+      }
+    }
+
+    Error::HttpStatus {
+      status: res.status,
+      body:   String::from_utf8_lossy(&res.body).into_owned(),
+      headers
+    }
+  }
+}
+
+fn messages(errors: &[ErrorResponse]) -> String {
+  errors.iter().map(|err| err.message.as_str()).collect::<Vec<_>>().join("; ")
+}
@@ -1,8 +1,40 @@
+// Lets the derive macros refer to `::oxidized_force` from inside this crate as well
+extern crate self as oxidized_force;
+
+#[macro_use]
+mod telemetry;
+
+pub mod cache;
 pub mod errors;
 pub mod client;
+pub mod options;
 pub mod response;
+pub mod serde_sf;
+pub mod sobject;
+pub mod streaming;
+pub mod transport;
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+
+#[cfg(feature = "derive")]
+pub use oxidized_force_derive::SObject;
+
+#[doc(hidden)]
+pub mod __private {
+  pub use serde;
+  pub use serde::{Deserialize, Serialize};
+}
 
 pub mod prelude {
   pub use crate::errors::Error;
   pub use crate::client::Client;
+  pub use crate::options::{CallOptions, CancellationToken};
+  pub use crate::sobject::SObject;
+
+  #[cfg(feature = "derive")]
+  pub use oxidized_force_derive::SObject;
 }
@@ -0,0 +1,110 @@
+use std::{
+  future::{pending, Future},
+  sync::Arc,
+  time::{Duration, Instant}
+};
+
+use tokio::{sync::watch, time};
+
+use crate::errors::*;
+
+/// Lets an orchestrator abort operations from the outside; cloned tokens all share the same state.
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+  sender:   Arc<watch::Sender<bool>>,
+  receiver: watch::Receiver<bool>
+}
+
+impl Default for CancellationToken {
+  fn default() -> Self {
+    let (sender, receiver) = watch::channel(false);
+    CancellationToken { sender: Arc::new(sender), receiver }
+  }
+}
+
+impl CancellationToken {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Cancels every operation currently (or later) using this token.
+  pub fn cancel(&self) {
+    // Sending only fails when there are no receivers, and we always hold one
+    let _ = self.sender.broadcast(true);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    *self.receiver.borrow()
+  }
+
+  /// Resolves once the token has been cancelled.
+  pub async fn cancelled(&self) {
+    let mut receiver = self.receiver.clone();
+
+    while !*receiver.borrow() {
+      if receiver.recv().await.is_none() {
+        return pending().await;
+      }
+    }
+  }
+}
+
+/// Per-call controls for (potentially) long running operations such as bulk job polling & result downloads.
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+  deadline: Option<Instant>,
+  cancel:   Option<CancellationToken>
+}
+
+impl CallOptions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Give up with `Error::DeadlineExceeded` once this instant has passed.
+  pub fn deadline(self, deadline: Instant) -> Self {
+    Self { deadline: Some(deadline), ..self }
+  }
+
+  /// Same as `deadline`, just relative to right now.
+  pub fn timeout(self, timeout: Duration) -> Self {
+    self.deadline(Instant::now() + timeout)
+  }
+
+  /// Give up with `Error::Cancelled` as soon as the token is cancelled.
+  pub fn cancel_token(self, token: CancellationToken) -> Self {
+    Self { cancel: Some(token), ..self }
+  }
+
+  /// Drives any client operation to completion, unless it is cancelled or the deadline passes first.
+  pub async fn run<F, T>(&self, future: F) -> Result<T>
+  where F: Future<Output = Result<T>> {
+    if self.cancel.as_ref().is_some_and(|token| token.is_cancelled()) {
+      return Err(Error::Cancelled);
+    }
+
+    if self.deadline.is_some_and(|deadline| deadline <= Instant::now()) {
+      return Err(Error::DeadlineExceeded);
+    }
+
+    let cancelled = async {
+      match self.cancel {
+        Some(ref token) => token.cancelled().await,
+        None            => pending().await
+      }
+    };
+
+    let expired = async {
+      match self.deadline {
+        Some(deadline) => time::delay_until(deadline.into()).await,
+        None           => pending().await
+      }
+    };
+
+    tokio::select! {
+      res = future    => res,
+      _   = cancelled => Err(Error::Cancelled),
+      _   = expired   => Err(Error::DeadlineExceeded)
+    }
+  }
+}
@@ -1,4 +1,13 @@
-use serde::{Serialize, Deserialize};
+use std::{
+  collections::HashMap,
+  ops::{Deref, DerefMut}
+};
+
+use serde::{Serialize, Deserialize, Deserializer};
+use serde_json::{Map, Value};
+
+#[cfg(feature = "chrono")]
+pub use crate::serde_sf::Timestamp;
 
 /// Represents a successful query response.
 #[derive(Deserialize, Debug, Clone)]
@@ -9,6 +18,146 @@ pub struct QueryResponse<T> {
   pub records:    Vec<T>
 }
 
+impl<T> QueryResponse<T> {
+  /// Number of records in this response (which, for paged results, can be less than `total_size`).
+  pub fn len(&self) -> usize {
+    self.records.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.records.is_empty()
+  }
+
+  pub fn iter(&self) -> std::slice::Iter<'_, T> {
+    self.records.iter()
+  }
+}
+
+impl<T> IntoIterator for QueryResponse<T> {
+  type Item     = T;
+  type IntoIter = std::vec::IntoIter<T>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.records.into_iter()
+  }
+}
+
+impl<'a, T> IntoIterator for &'a QueryResponse<T> {
+  type Item     = &'a T;
+  type IntoIter = std::slice::Iter<'a, T>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.records.iter()
+  }
+}
+
+/// Represents the `attributes` object Salesforce includes with every queried record.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordAttributes {
+  #[serde(rename = "type")]
+  pub sobject_type: String,
+
+  // Aggregate query results don't have a URL
+  pub url: Option<String>
+}
+
+/// Wraps a queried record, capturing its `attributes` alongside the user defined struct.
+/// Handy for polymorphic queries (where the type isn't known up front) & for building follow-up record URLs.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SObjectRecord<T> {
+  pub attributes: RecordAttributes,
+
+  #[serde(flatten)]
+  pub record: T
+}
+
+impl<T> SObjectRecord<T> {
+  /// The record URL relative to the instance (e.g. `/services/data/v49.0/sobjects/Case/500...`).
+  pub fn url(&self) -> Option<&str> {
+    self.attributes.url.as_deref()
+  }
+
+  /// The record ID, as taken from the tail end of the record URL.
+  pub fn id(&self) -> Option<&str> {
+    self.url().and_then(|url| url.rsplit('/').next())
+  }
+
+  pub fn into_inner(self) -> T {
+    self.record
+  }
+}
+
+impl<T> Deref for SObjectRecord<T> {
+  type Target = T;
+
+  fn deref(&self) -> &T {
+    &self.record
+  }
+}
+
+impl<T> DerefMut for SObjectRecord<T> {
+  fn deref_mut(&mut self) -> &mut T {
+    &mut self.record
+  }
+}
+
+/// A dynamically typed record, for tooling that can't know the shape of an object at compile time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(transparent)]
+pub struct Row(pub Map<String, Value>);
+
+impl Row {
+  /// Looks up a field, following relationships for dotted paths (e.g. `Account.Owner.Name`).
+  pub fn get(&self, path: &str) -> Option<&Value> {
+    let mut parts = path.split('.');
+    let first     = self.0.get(parts.next()?)?;
+
+    parts.try_fold(first, |value, part| value.as_object()?.get(part))
+  }
+
+  pub fn get_str(&self, path: &str) -> Option<&str> {
+    self.get(path).and_then(Value::as_str)
+  }
+
+  /// The SObject type this row came from (taken from the record attributes).
+  pub fn sobject_type(&self) -> Option<&str> {
+    self.get_str("attributes.type")
+  }
+
+  /// Iterates over the fields of the row, skipping the record `attributes`.
+  pub fn fields(&self) -> impl Iterator<Item = (&String, &Value)> {
+    self.0.iter().filter(|(name, _)| name.as_str() != "attributes")
+  }
+
+  pub fn into_map(self) -> Map<String, Value> {
+    self.0
+  }
+
+  /// Replaces compound values (addresses & geolocations come back as nested objects) with their component fields,
+  /// so `BillingAddress: { city: .. }` ends up as `BillingCity: ..`.
+  pub fn flatten_compound(&mut self, describe: &DescribeResponse) {
+    for field in describe.fields.iter().filter(|f| describe.is_compound(&f.name)) {
+      let values = match self.0.get(&field.name) {
+        Some(Value::Object(values)) => values.clone(),
+        _ => continue
+      };
+      self.0.remove(&field.name);
+
+      for component in describe.components(&field.name) {
+        // `BillingPostalCode` -> `postalCode`, `Location__Latitude__s` -> `latitude`
+        let name  = component.name.trim_end_matches("__s").to_lowercase();
+        let value = values
+          .iter()
+          .filter(|(key, _)| name.ends_with(&key.to_lowercase()))
+          .max_by_key(|(key, _)| key.len())
+          .map(|(_, value)| value.clone());
+
+        self.0.insert(component.name.clone(), value.unwrap_or(Value::Null));
+      }
+    }
+  }
+}
+
 /// Represents a successful token request response.
 #[derive(Deserialize, Debug, Clone)]
 pub struct TokenResponse {
@@ -17,14 +166,46 @@ pub struct TokenResponse {
   pub access_token: String,
   pub instance_url: String,
   pub signature:    String,
-  pub token_type:   String
+  pub token_type:   String,
+  /// Only handed out by the web server & device flows (and only when the `refresh_token` scope was granted)
+  #[serde(default)]
+  pub refresh_token: Option<String>
+}
+
+#[cfg(feature = "chrono")]
+impl TokenResponse {
+  /// `issued_at` as a timestamp (it comes as milliseconds since the epoch).
+  pub fn issued_at_time(&self) -> Option<Timestamp> {
+    crate::serde_sf::epoch_millis::parse(&self.issued_at)
+  }
+}
+
+/// What the device flow hands out to start with: the code for the user to enter, and the one to poll with.
+/// See https://help.salesforce.com/articleView?id=remoteaccess_oauth_device_flow.htm
+#[derive(Deserialize, Debug, Clone)]
+pub struct DeviceCodeResponse {
+  pub device_code:      String,
+  pub user_code:        String,
+  pub verification_uri: String,
+  /// Seconds to wait between polls
+  #[serde(default = "default_device_interval")]
+  pub interval:         u64
+}
+
+fn default_device_interval() -> u64 {
+  5
 }
 
 /// Represents a failed token request response.
+/// See https://help.salesforce.com/articleView?id=remoteaccess_oauth_flow_errors.htm
 #[derive(Deserialize, Debug, Clone)]
 pub struct TokenErrorResponse {
-  error_description: String,
-  error: String
+  /// Short error code, e.g. `invalid_grant` or `invalid_client_id`.
+  pub error: String,
+
+  /// Human readable reason, e.g. `authentication failure` or `ip restricted`.
+  #[serde(default)]
+  pub error_description: String
 }
 
 /// Represents the response from creating query jobs & fetching their statuses.
@@ -34,17 +215,60 @@ pub struct BulkQueryStatusResponse {
   pub id:               String,
   pub operation:        String,
   pub object:           String,
+  pub created_by_id:    String,
   pub created_date:     String,
+  pub system_modstamp:  String,
   pub state:            BulkState,
   pub concurrency_mode: String,
   pub content_type:     String,
   pub api_version:      f32,
   pub line_ending:      String,
-  pub column_delimiter: String
+  pub column_delimiter: String,
+
+  // Only included when fetching the status of a job (not when creating one)
+  #[serde(default)]
+  pub number_records_processed: i64,
+
+  #[serde(default)]
+  pub retries: i32,
+
+  /// Milliseconds spent processing the job.
+  #[serde(default)]
+  pub total_processing_time: i64,
+
+  /// Explains why a job ended up `Failed`.
+  #[serde(default)]
+  pub error_message: Option<String>
+}
+
+#[cfg(feature = "chrono")]
+impl BulkQueryStatusResponse {
+  pub fn created_date_time(&self) -> Option<Timestamp> {
+    crate::serde_sf::datetime::parse(&self.created_date)
+  }
+
+  pub fn system_modstamp_time(&self) -> Option<Timestamp> {
+    crate::serde_sf::datetime::parse(&self.system_modstamp)
+  }
+}
+
+/// Represents a single page of (CSV) bulk query results.
+#[derive(Debug, Clone)]
+pub struct BulkQueryResults {
+  pub body:              String,
+  pub locator:           Option<String>,
+  pub number_of_records: Option<usize>
+}
+
+impl BulkQueryResults {
+  /// Whether or not there are more pages to fetch (using `locator`).
+  pub fn has_more(&self) -> bool {
+    self.locator.is_some()
+  }
 }
 
 /// Represents the possible bulk query states.
-#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum BulkState {
   UploadComplete,
   InProgress,
@@ -53,12 +277,47 @@ pub enum BulkState {
   Failed
 }
 
+impl BulkState {
+  /// Whether or not the job has stopped processing (either successfully or otherwise).
+  pub fn is_finished(&self) -> bool {
+    matches!(self, BulkState::JobComplete | BulkState::Failed | BulkState::Aborted)
+  }
+}
+
+/// Represents a successful describe global (list all SObjects) request response.
+/// See https://developer.salesforce.com/docs/atlas.en-us.api_rest.meta/api_rest/resources_describeGlobal.htm
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DescribeGlobalResponse {
+  pub encoding:       String,
+  pub max_batch_size: i32,
+  pub sobjects:       Vec<SObjectSummary>
+}
+
+/// Represents a single (summarized) SObject contained inside of a describe global response.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SObjectSummary {
+  pub name:          String,
+  pub label:         String,
+  pub label_plural:  String,
+  pub custom:        bool,
+  pub queryable:     bool,
+  pub replicateable: bool,
+  pub key_prefix:    Option<String>,
+
+  #[serde(default)]
+  pub urls: HashMap<String, String>
+}
+
 /// Represents a successful describe request response.
 /// See https://developer.salesforce.com/docs/atlas.en-us.uiapi.meta/uiapi/ui_api_responses_object_info.htm
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct DescribeResponse {
   pub name:   String,
+  #[serde(default)]
+  pub label:  String,
   pub fields: Vec<Field>
 }
 
@@ -66,33 +325,185 @@ pub struct DescribeResponse {
 #[serde(rename_all = "camelCase")]
 pub struct Field {
   pub name:       String,
+  #[serde(default)]
+  pub label:      String,
   pub length:     i32,
   pub custom:     bool,
   pub encrypted:  bool,
   pub precision:  u8,
+  #[serde(default)]
+  pub scale:      u8,
+  /// Max number of digits for integer fields (zero for everything else).
+  #[serde(default)]
+  pub digits:     u8,
   pub updateable: bool,
   pub nillable:   bool,
   pub unique:     bool,
 
   pub relationship_name: Option<String>,
 
-  // TODO: Make this work...
-  //#[serde(skip_serializing_if = "Option::is_none", deserialize_with = "deserialize_default_value")]
-  //pub default_value: Option<String>,
+  /// Objects a reference can point at; more than one makes it polymorphic, like a task's `WhoId`.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub reference_to: Vec<String>,
+
+  /// Deleting the parent deletes this record too (master-detail relationships).
+  #[serde(default)]
+  pub cascade_delete: bool,
+
+  /// Parent records can't be deleted while this one points at them.
+  #[serde(default)]
+  pub restricted_delete: bool,
+
+  /// Set on the components of compound fields, e.g. `BillingCity` -> `BillingAddress` or `FirstName` -> `Name`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub compound_field_name: Option<String>,
+
+  #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "deserialize_default_value")]
+  pub default_value: Option<DefaultValue>,
+
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub picklist_values: Vec<PicklistValue>,
+
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub inline_help_text: Option<String>,
+
+  /// Formula and roll-up summary fields, whose values Salesforce works out itself.
+  #[serde(default)]
+  pub calculated: bool,
+
+  /// Source of formula fields, e.g. `Amount * Probability / 100`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub calculated_formula: Option<String>,
 
   #[serde(rename = "type")]
   pub field_type: FieldType
 }
 
+/// One of the allowed values of a (multi) picklist field.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PicklistValue {
+  pub value:         String,
+  pub label:         Option<String>,
+  pub active:        bool,
+  #[serde(default)]
+  pub default_value: bool
+}
+
+/// Represents a field default value; depending on the field type this can be pretty much anything.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum DefaultValue {
+  Boolean(bool),
+  Number(f64),
+  Text(String),
+  Object(Value)
+}
+
 /// Represents a generic error response.
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ErrorResponse {
   pub message: String,
-  pub error_code: String,
+  pub error_code: ErrorCode,
   pub fields: Option<Vec<String>>
 }
 
+impl ErrorResponse {
+  /// Salesforce usually sends errors as an array, but a handful of endpoints send a single object instead.
+  pub fn parse_all(body: &[u8]) -> serde_json::Result<Vec<ErrorResponse>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+      One(ErrorResponse),
+      Many(Vec<ErrorResponse>)
+    }
+
+    Ok(match serde_json::from_slice(body)? {
+      OneOrMany::One(error)   => vec![error],
+      OneOrMany::Many(errors) => errors
+    })
+  }
+}
+
+/// The `errorCode` of an error response; anything not listed here ends up in `Other`.
+/// See https://developer.salesforce.com/docs/atlas.en-us.api_rest.meta/api_rest/errorcodes.htm
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(from = "String")]
+pub enum ErrorCode {
+  InvalidSessionId,
+  RequestLimitExceeded,
+  MalformedQuery,
+  InvalidField,
+  InvalidType,
+  EntityIsDeleted,
+  NotFound,
+  RequiredFieldMissing,
+  DuplicateValue,
+  InvalidQueryLocator,
+  QueryTimeout,
+  InsufficientAccess,
+  ApiDisabledForOrg,
+  ServerUnavailable,
+  Other(String)
+}
+
+impl ErrorCode {
+  pub fn as_str(&self) -> &str {
+    match self {
+      ErrorCode::InvalidSessionId     => "INVALID_SESSION_ID",
+      ErrorCode::RequestLimitExceeded => "REQUEST_LIMIT_EXCEEDED",
+      ErrorCode::MalformedQuery       => "MALFORMED_QUERY",
+      ErrorCode::InvalidField         => "INVALID_FIELD",
+      ErrorCode::InvalidType          => "INVALID_TYPE",
+      ErrorCode::EntityIsDeleted      => "ENTITY_IS_DELETED",
+      ErrorCode::NotFound             => "NOT_FOUND",
+      ErrorCode::RequiredFieldMissing => "REQUIRED_FIELD_MISSING",
+      ErrorCode::DuplicateValue       => "DUPLICATE_VALUE",
+      ErrorCode::InvalidQueryLocator  => "INVALID_QUERY_LOCATOR",
+      ErrorCode::QueryTimeout         => "QUERY_TIMEOUT",
+      ErrorCode::InsufficientAccess   => "INSUFFICIENT_ACCESS_OR_READONLY",
+      ErrorCode::ApiDisabledForOrg    => "API_DISABLED_FOR_ORG",
+      ErrorCode::ServerUnavailable    => "SERVER_UNAVAILABLE",
+      ErrorCode::Other(code)          => code
+    }
+  }
+}
+
+impl From<String> for ErrorCode {
+  fn from(code: String) -> Self {
+    match code.as_str() {
+      "INVALID_SESSION_ID"              => ErrorCode::InvalidSessionId,
+      "REQUEST_LIMIT_EXCEEDED"          => ErrorCode::RequestLimitExceeded,
+      "MALFORMED_QUERY"                 => ErrorCode::MalformedQuery,
+      "INVALID_FIELD"                   => ErrorCode::InvalidField,
+      "INVALID_TYPE"                    => ErrorCode::InvalidType,
+      "ENTITY_IS_DELETED"               => ErrorCode::EntityIsDeleted,
+      "NOT_FOUND"                       => ErrorCode::NotFound,
+      "REQUIRED_FIELD_MISSING"          => ErrorCode::RequiredFieldMissing,
+      "DUPLICATE_VALUE"                 => ErrorCode::DuplicateValue,
+      "INVALID_QUERY_LOCATOR"           => ErrorCode::InvalidQueryLocator,
+      "QUERY_TIMEOUT"                   => ErrorCode::QueryTimeout,
+      "INSUFFICIENT_ACCESS_OR_READONLY" => ErrorCode::InsufficientAccess,
+      "API_DISABLED_FOR_ORG"            => ErrorCode::ApiDisabledForOrg,
+      "SERVER_UNAVAILABLE"              => ErrorCode::ServerUnavailable,
+      _                                 => ErrorCode::Other(code)
+    }
+  }
+}
+
+impl PartialEq<str> for ErrorCode {
+  fn eq(&self, other: &str) -> bool {
+    self.as_str() == other
+  }
+}
+
+impl std::fmt::Display for ErrorCode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
 /// Represents all of the possible field types contained inside various responses.
 /// See https://developer.salesforce.com/docs/atlas.en-us.object_reference.meta/object_reference/primitive_data_types.htm
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -139,7 +550,11 @@ pub enum FieldType {
   MasterRecord,
 
   #[serde(rename = "multipicklist")]
-  MultiPicklist
+  MultiPicklist,
+
+  /// Salesforce adds new types every so often (and managed packages bring their own); anything we don't recognize ends up here.
+  #[serde(other)]
+  Unknown
 }
 
 impl DescribeResponse {
@@ -151,14 +566,60 @@ impl DescribeResponse {
       .map(|f| f.name.clone())
       .collect()
   }
+
+  pub fn field(&self, name: &str) -> Option<&Field> {
+    self.fields.iter().find(|f| f.name == name)
+  }
+
+  /// Whether other fields on this object are components of `name` (e.g. `BillingAddress`).
+  pub fn is_compound(&self, name: &str) -> bool {
+    self.fields.iter().any(|f| f.compound_field_name.as_deref() == Some(name))
+  }
+
+  /// Gets the components of a compound field (`BillingStreet`, `BillingCity`, ... for `BillingAddress`).
+  pub fn components(&self, compound: &str) -> Vec<&Field> {
+    self
+      .fields
+      .iter()
+      .filter(|f| f.compound_field_name.as_deref() == Some(compound))
+      .collect()
+  }
+
+  /// Same as `field_names`, but with compound fields swapped out for their components.
+  /// Bulk queries can't select compound fields at all, so use this one for those.
+  pub fn component_field_names(&self) -> Vec<String> {
+    self
+      .fields
+      .iter()
+      .filter(|f| !self.is_compound(&f.name))
+      .map(|f| f.name.clone())
+      .collect()
+  }
 }
 
-// fn deserialize_default_value<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
-// where D: Deserializer<'de> {
-//   #[derive(Deserialize)]
-//   struct DefaultValue {
-//     value: String
-//   }
+impl Field {
+  /// Whether this field is part of a compound field.
+  pub fn is_component(&self) -> bool {
+    self.compound_field_name.is_some()
+  }
+}
+
+/// Default values show up as scalars, booleans or (occasionally) objects wrapping the actual value.
+fn deserialize_default_value<'de, D>(deserializer: D) -> Result<Option<DefaultValue>, D::Error>
+where D: Deserializer<'de> {
+  fn convert(value: Value) -> Option<DefaultValue> {
+    match value {
+      Value::Null        => None,
+      Value::Bool(val)   => Some(DefaultValue::Boolean(val)),
+      Value::String(val) => Some(DefaultValue::Text(val)),
+      Value::Number(val) => match val.as_f64() {
+        Some(num) => Some(DefaultValue::Number(num)),
+        None      => Some(DefaultValue::Text(val.to_string()))
+      },
+      Value::Object(mut obj) if obj.len() == 1 && obj.contains_key("value") => convert(obj.remove("value").unwrap()),
+      other => Some(DefaultValue::Object(other))
+    }
+  }
 
-//   DefaultValue::deserialize(deserializer).map(|d| Some(d.value))
-// }
+  Ok(convert(Value::deserialize(deserializer)?))
+}
@@ -0,0 +1,420 @@
+//! Serde helpers for the (somewhat peculiar) formats Salesforce uses.
+//! Meant to be used with `#[serde(with = "...")]` on your own query structs; the datetime & date ones come with the `chrono` feature:
+//!
+//! ```ignore
+//! #[derive(Deserialize)]
+//! #[serde(rename_all = "PascalCase")]
+//! struct Account {
+//!   #[serde(with = "oxidized_force::serde_sf::datetime")]
+//!   created_date: Timestamp,
+//!
+//!   #[serde(with = "oxidized_force::serde_sf::empty_string_as_none")]
+//!   account_number: Option<String>
+//! }
+//! ```
+
+/// Salesforce datetimes, with the `chrono` feature (without it, datetime fields are plain `String`s).
+#[cfg(feature = "chrono")]
+pub type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// Salesforce dates, with the `chrono` feature (without it, date fields are plain `String`s).
+#[cfg(feature = "chrono")]
+pub type Date = chrono::NaiveDate;
+
+/// Exact decimals for Currency, Percent & (high precision) Double fields, with the `decimal` feature.
+#[cfg(feature = "decimal")]
+pub type Decimal = rust_decimal::Decimal;
+
+/// Salesforce datetimes, e.g. `2018-12-10T17:50:19.000+0000`.
+#[cfg(feature = "chrono")]
+pub mod datetime {
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+  use super::Timestamp;
+
+  pub fn serialize<S>(value: &Timestamp, serializer: S) -> Result<S::Ok, S::Error>
+  where S: Serializer {
+    value.format("%Y-%m-%dT%H:%M:%S%.3f%z").to_string().serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D>(deserializer: D) -> Result<Timestamp, D::Error>
+  where D: Deserializer<'de> {
+    use serde::de::Error;
+
+    let raw = String::deserialize(deserializer)?;
+    parse(&raw).ok_or_else(|| D::Error::custom(format!("invalid datetime `{}`", raw)))
+  }
+
+  /// Salesforce uses `+0000` style offsets, which RFC 3339 parsing won't accept.
+  pub(crate) fn parse(raw: &str) -> Option<Timestamp> {
+    use chrono::{DateTime, Utc};
+
+    DateTime::parse_from_str(raw, "%Y-%m-%dT%H:%M:%S%.f%z")
+      .or_else(|_| DateTime::parse_from_rfc3339(raw))
+      .map(|date| date.with_timezone(&Utc))
+      .ok()
+  }
+}
+
+/// Same as `datetime`, for nullable fields (empty strings are treated as null too).
+#[cfg(feature = "chrono")]
+pub mod datetime_opt {
+  use serde::{Deserializer, Serializer};
+
+  use super::{empty_string_as_none, Timestamp};
+
+  pub fn serialize<S>(value: &Option<Timestamp>, serializer: S) -> Result<S::Ok, S::Error>
+  where S: Serializer {
+    match value {
+      Some(value) => super::datetime::serialize(value, serializer),
+      None        => serializer.serialize_none()
+    }
+  }
+
+  pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Timestamp>, D::Error>
+  where D: Deserializer<'de> {
+    use serde::de::{Error, IntoDeserializer};
+
+    let raw: Option<String> = empty_string_as_none::deserialize(deserializer)?;
+
+    raw
+      .map(|raw| super::datetime::deserialize(raw.into_deserializer()))
+      .transpose()
+      .map_err(|err: serde::de::value::Error| D::Error::custom(err))
+  }
+}
+
+/// Salesforce dates, e.g. `2018-12-10`.
+#[cfg(feature = "chrono")]
+pub mod date {
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+  use super::Date;
+
+  pub fn serialize<S>(value: &Date, serializer: S) -> Result<S::Ok, S::Error>
+  where S: Serializer {
+    value.format("%Y-%m-%d").to_string().serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D>(deserializer: D) -> Result<Date, D::Error>
+  where D: Deserializer<'de> {
+    use serde::de::Error;
+
+    let raw = String::deserialize(deserializer)?;
+    Date::parse_from_str(&raw, "%Y-%m-%d").map_err(|err| D::Error::custom(format!("invalid date `{}` ({})", raw, err)))
+  }
+}
+
+/// Same as `date`, for nullable fields (empty strings are treated as null too).
+#[cfg(feature = "chrono")]
+pub mod date_opt {
+  use serde::{Deserializer, Serializer};
+
+  use super::{empty_string_as_none, Date};
+
+  pub fn serialize<S>(value: &Option<Date>, serializer: S) -> Result<S::Ok, S::Error>
+  where S: Serializer {
+    match value {
+      Some(value) => super::date::serialize(value, serializer),
+      None        => serializer.serialize_none()
+    }
+  }
+
+  pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Date>, D::Error>
+  where D: Deserializer<'de> {
+    use serde::de::{Error, IntoDeserializer};
+
+    let raw: Option<String> = empty_string_as_none::deserialize(deserializer)?;
+
+    raw
+      .map(|raw| super::date::deserialize(raw.into_deserializer()))
+      .transpose()
+      .map_err(|err: serde::de::value::Error| D::Error::custom(err))
+  }
+}
+
+/// Milliseconds since the epoch sent as a string, e.g. the `issued_at` of token responses.
+#[cfg(feature = "chrono")]
+pub mod epoch_millis {
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+  use super::Timestamp;
+
+  pub fn serialize<S>(value: &Timestamp, serializer: S) -> Result<S::Ok, S::Error>
+  where S: Serializer {
+    value.timestamp_millis().to_string().serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D>(deserializer: D) -> Result<Timestamp, D::Error>
+  where D: Deserializer<'de> {
+    use serde::de::Error;
+
+    let raw = String::deserialize(deserializer)?;
+    parse(&raw).ok_or_else(|| D::Error::custom(format!("invalid epoch timestamp `{}`", raw)))
+  }
+
+  pub(crate) fn parse(raw: &str) -> Option<Timestamp> {
+    use chrono::{TimeZone, Utc};
+
+    raw.parse::<i64>().ok().and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+  }
+}
+
+/// Numeric strings (as found in bulk results) into exact decimals; JSON numbers go by their shortest round-tripping form,
+/// so `12.5` stays `12.5` rather than picking up f64 noise. Send values as strings when every digit matters.
+#[cfg(feature = "decimal")]
+pub mod decimal {
+  use std::{fmt, str::FromStr};
+
+  use serde::{de::{self, Visitor}, Deserializer, Serializer};
+
+  use super::Decimal;
+
+  /// Serialized as a string, so nothing downstream gets a chance to round it either.
+  pub fn serialize<S>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error>
+  where S: Serializer {
+    serializer.collect_str(value)
+  }
+
+  pub fn deserialize<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+  where D: Deserializer<'de> {
+    use serde::de::Error;
+
+    deserializer.deserialize_any(DecimalVisitor)?.ok_or_else(|| D::Error::custom("expected a decimal, got nothing"))
+  }
+
+  /// Handles scientific notation too, since that's how large/tiny Doubles come back.
+  pub(crate) fn parse(raw: &str) -> Result<Decimal, String> {
+    Decimal::from_str(raw)
+      .or_else(|_| Decimal::from_scientific(raw))
+      .map_err(|err| format!("invalid decimal `{}` ({})", raw, err))
+  }
+
+  /// Takes whichever token comes along rather than going through `serde_json::Value`, which works behind
+  /// `#[serde(flatten)]` (as in `SObjectRecord`) & untagged enums too. Null & empty strings come out as `None`.
+  pub(crate) struct DecimalVisitor;
+
+  impl<'de> Visitor<'de> for DecimalVisitor {
+    type Value = Option<Decimal>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      f.write_str("a decimal number or numeric string")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+      Ok(Some(Decimal::from(value)))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+      Ok(Some(Decimal::from(value)))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+      parse(&value.to_string()).map(Some).map_err(E::custom)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+      match value.trim() {
+        ""  => Ok(None),
+        raw => parse(raw).map(Some).map_err(E::custom)
+      }
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+      Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+      Ok(None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where D: Deserializer<'de> {
+      deserializer.deserialize_any(self)
+    }
+  }
+}
+
+/// Same as `decimal`, for nullable fields (empty strings are treated as null too).
+#[cfg(feature = "decimal")]
+pub mod decimal_opt {
+  use serde::{Deserializer, Serializer};
+
+  use super::Decimal;
+
+  pub fn serialize<S>(value: &Option<Decimal>, serializer: S) -> Result<S::Ok, S::Error>
+  where S: Serializer {
+    match value {
+      Some(value) => super::decimal::serialize(value, serializer),
+      None        => serializer.serialize_none()
+    }
+  }
+
+  pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+  where D: Deserializer<'de> {
+    deserializer.deserialize_any(super::decimal::DecimalVisitor)
+  }
+}
+
+/// Base64 encoded blobs (`FieldType::Base64`) into raw bytes.
+pub mod base64 {
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+  pub fn serialize<S>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+  where S: Serializer {
+    ::base64::encode(value).serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+  where D: Deserializer<'de> {
+    use serde::de::Error;
+
+    let raw = String::deserialize(deserializer)?;
+    ::base64::decode(raw.trim()).map_err(|err| D::Error::custom(format!("invalid base64 ({})", err)))
+  }
+}
+
+/// Treats empty strings the same as null, which is how Salesforce sends blank values in a bunch of places (bulk results in particular).
+pub mod empty_string_as_none {
+  use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+  use serde_json::Value;
+
+  pub fn serialize<S, T>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+  where S: Serializer, T: Serialize {
+    value.serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+  where D: Deserializer<'de>, T: DeserializeOwned {
+    use serde::de::Error;
+
+    match Value::deserialize(deserializer)? {
+      Value::Null                              => Ok(None),
+      Value::String(ref val) if val.is_empty() => Ok(None),
+      other                                    => T::deserialize(other).map(Some).map_err(D::Error::custom)
+    }
+  }
+}
+
+#[cfg(all(test, any(feature = "chrono", feature = "decimal")))]
+mod tests {
+  use serde::{Deserialize, Serialize};
+  use serde_json::json;
+
+  use super::*;
+
+  #[cfg(feature = "chrono")]
+  #[derive(Serialize, Deserialize, Debug, PartialEq)]
+  #[serde(rename_all = "PascalCase")]
+  struct Attachment {
+    #[serde(with = "datetime")]
+    created_date: Timestamp,
+
+    #[serde(default, with = "datetime_opt")]
+    last_viewed_date: Option<Timestamp>,
+
+    #[serde(with = "date")]
+    due_date: Date,
+
+    #[serde(default, with = "date_opt")]
+    reminder_date: Option<Date>,
+
+    #[serde(with = "base64")]
+    body: Vec<u8>,
+
+    #[serde(default, with = "empty_string_as_none")]
+    description: Option<String>,
+
+    #[serde(default, with = "empty_string_as_none")]
+    body_length: Option<i64>
+  }
+
+  #[cfg(feature = "chrono")]
+  #[test]
+  fn round_trip() {
+    let raw = json!({
+      "CreatedDate":    "2018-12-10T17:50:19.000+0000",
+      "LastViewedDate": "",
+      "DueDate":        "2018-12-24",
+      "ReminderDate":   "2018-12-20",
+      "Body":           "U2hpYmEgSW51",
+      "Description":    "",
+      "BodyLength":     9
+    });
+
+    let attachment: Attachment = serde_json::from_value(raw).unwrap();
+    assert_eq!(attachment.body, b"Shiba Inu");
+    assert_eq!(attachment.last_viewed_date, None);
+    assert!(attachment.reminder_date.is_some());
+    assert_eq!(attachment.description, None);
+    assert_eq!(attachment.body_length, Some(9));
+
+    let json = serde_json::to_value(&attachment).unwrap();
+    assert_eq!(json["CreatedDate"], "2018-12-10T17:50:19.000+0000");
+    assert_eq!(json["DueDate"], "2018-12-24");
+    assert_eq!(json["ReminderDate"], "2018-12-20");
+    assert_eq!(json["Body"], "U2hpYmEgSW51");
+    assert_eq!(serde_json::from_value::<Attachment>(json).unwrap(), attachment);
+  }
+
+  #[cfg(feature = "decimal")]
+  #[test]
+  fn decimals() {
+    #[derive(Serialize, Deserialize)]
+    struct Opportunity {
+      #[serde(with = "decimal")]
+      amount: Decimal,
+
+      #[serde(default, with = "decimal_opt")]
+      probability: Option<Decimal>,
+
+      #[serde(default, with = "decimal_opt")]
+      discount: Option<Decimal>
+    }
+
+    let opp: Opportunity = serde_json::from_str(r#"{ "amount": "1234567890.123456789", "probability": 12.5, "discount": "" }"#).unwrap();
+    assert_eq!(opp.amount.to_string(), "1234567890.123456789");
+    assert_eq!(opp.probability.unwrap().to_string(), "12.5");
+    assert_eq!(opp.discount, None);
+    assert_eq!(serde_json::from_str::<Opportunity>(r#"{ "amount": 0.1, "probability": null }"#).unwrap().amount.to_string(), "0.1");
+    assert_eq!(serde_json::from_str::<Opportunity>(r#"{ "amount": 42 }"#).unwrap().amount.to_string(), "42");
+    assert!(serde_json::from_str::<Opportunity>(r#"{ "amount": "" }"#).is_err());
+    assert_eq!(decimal::parse("1.5E-3").unwrap().to_string(), "0.0015");
+    assert_eq!(serde_json::to_value(&opp).unwrap()["amount"], "1234567890.123456789");
+  }
+
+  /// Flattened fields get buffered by serde before they reach the field's own deserializer.
+  #[cfg(feature = "decimal")]
+  #[test]
+  fn decimals_in_records() {
+    use crate::response::SObjectRecord;
+
+    #[derive(Deserialize, Debug)]
+    #[serde(rename_all = "PascalCase")]
+    struct Opportunity {
+      #[serde(with = "decimal")]
+      amount: Decimal,
+
+      #[serde(default, with = "decimal_opt")]
+      probability: Option<Decimal>,
+
+      expected_revenue: f64
+    }
+
+    let raw = r#"{ "attributes": { "type": "Opportunity", "url": "/services/data/v49.0/sobjects/Opportunity/006A" }, "Amount": 12.5, "Probability": null, "ExpectedRevenue": 1.25 }"#;
+    let opp = serde_json::from_str::<SObjectRecord<Opportunity>>(raw).unwrap();
+    assert_eq!(opp.amount.to_string(), "12.5");
+    assert_eq!(opp.probability, None);
+    assert_eq!(opp.expected_revenue, 1.25);
+    assert_eq!(opp.id(), Some("006A"));
+  }
+
+  #[cfg(feature = "chrono")]
+  #[test]
+  fn datetime_offsets() {
+    let utc = datetime::parse("2018-12-10T17:50:19.000+0000").unwrap();
+    assert_eq!(datetime::parse("2018-12-10T12:50:19.000-0500"), Some(utc));
+    assert_eq!(datetime::parse("2018-12-10T17:50:19Z"), Some(utc));
+    assert_eq!(datetime::parse("yesterday-ish"), None);
+  }
+}
@@ -0,0 +1,13 @@
+/// Ties a Rust struct to a Salesforce object; usually via `#[derive(SObject)]` (behind the `derive` feature).
+pub trait SObject {
+  /// Object API name, e.g. `Account` or `Invoice__c`.
+  const API_NAME: &'static str;
+
+  /// Salesforce names of every (non skipped) field on the struct.
+  const FIELDS: &'static [&'static str];
+
+  /// `SELECT <FIELDS> FROM <API_NAME>`, ready for a `WHERE` clause to be tacked on.
+  fn select() -> String {
+    format!("SELECT {} FROM {}", Self::FIELDS.join(", "), Self::API_NAME)
+  }
+}
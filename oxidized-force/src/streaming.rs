@@ -0,0 +1,221 @@
+//! The Streaming API: CometD (Bayeux) long polling for Change Data Capture events, platform events & PushTopics.
+//! Every event comes with a replay ID; subscribing after one picks up right where it left off, as long as the events
+//! are still retained (three days for change events).
+
+use std::{
+  collections::{BTreeMap, VecDeque},
+  time::Duration
+};
+
+use reqwest::header::{HeaderValue, COOKIE, SET_COOKIE};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::time;
+
+use crate::client::Client;
+use crate::errors::*;
+use crate::transport::HttpRequest;
+
+/// Where a subscription starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayFrom {
+  /// Only events from now on
+  Tip,
+  /// Every event that's still retained
+  Earliest,
+  /// The events after this replay ID
+  After(i64)
+}
+
+impl ReplayFrom {
+  fn id(self) -> i64 {
+    match self {
+      ReplayFrom::Tip       => -1,
+      ReplayFrom::Earliest  => -2,
+      ReplayFrom::After(id) => id
+    }
+  }
+}
+
+/// An event that came in on a channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamingEvent {
+  pub channel:   String,
+  pub replay_id: i64,
+  /// The `payload` of change & platform events, the `sobject` of PushTopic events
+  pub payload:   Value
+}
+
+/// Just the parts of a Bayeux message we care about.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Message {
+  channel:    String,
+  successful: Option<bool>,
+  client_id:  Option<String>,
+  error:      Option<String>,
+  advice:     Option<Advice>,
+  data:       Option<Value>
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Advice {
+  reconnect: Option<String>,
+  interval:  Option<u64>
+}
+
+/// Subscribes to channels & hands out their events one at a time, see `Client::subscriber`.
+/// Handshakes (again) as needed, resubscribing after the last event handed out on each channel.
+#[derive(Debug)]
+pub struct Subscriber {
+  client:    Client,
+  channels:  BTreeMap<String, ReplayFrom>,
+  client_id: Option<String>,
+  /// Salesforce pins CometD clients to a server with cookies
+  cookies:   BTreeMap<String, String>,
+  events:    VecDeque<StreamingEvent>,
+  next_id:   u64
+}
+
+impl Subscriber {
+  pub(crate) fn new(client: Client) -> Self {
+    Subscriber {
+      client,
+      channels:  BTreeMap::new(),
+      client_id: None,
+      cookies:   BTreeMap::new(),
+      events:    VecDeque::new(),
+      next_id:   0
+    }
+  }
+
+  /// Subscribes to a channel (e.g. `/data/AccountChangeEvent` or `/event/Order_Placed__e`) once connected.
+  pub fn subscribe<C>(&mut self, channel: C, from: ReplayFrom) -> &mut Self
+  where C: Into<String> {
+    self.channels.insert(channel.into(), from);
+    self
+  }
+
+  /// Waits for the next event on any of the channels, connecting first if need be.
+  pub async fn next_event(&mut self) -> Result<StreamingEvent> {
+    loop {
+      if let Some(event) = self.events.pop_front() {
+        if let Some(from) = self.channels.get_mut(&event.channel) {
+          *from = ReplayFrom::After(event.replay_id);
+        }
+        return Ok(event);
+      }
+
+      if self.client_id.is_none() {
+        self.handshake().await?;
+      }
+      self.connect().await?;
+    }
+  }
+
+  /// Says goodbye to the server; events that were already received are still handed out.
+  pub async fn disconnect(&mut self) -> Result<()> {
+    if self.client_id.is_some() {
+      self.exchange("streaming_disconnect", json!({ "channel": "/meta/disconnect" })).await?;
+      self.client_id = None;
+    }
+    Ok(())
+  }
+
+  async fn handshake(&mut self) -> Result<()> {
+    self.cookies.clear();
+    let reply = self
+      .exchange("streaming_handshake", json!({
+        "channel":                  "/meta/handshake",
+        "version":                  "1.0",
+        "minimumVersion":           "1.0",
+        "supportedConnectionTypes": ["long-polling"],
+        "ext":                      { "replay": true }
+      }))
+      .await?;
+
+    self.client_id = match reply.client_id {
+      Some(client_id) if reply.successful == Some(true) => Some(client_id),
+      _                                                  => return Err(streaming_error("handshake failed", &reply))
+    };
+
+    for (channel, from) in self.channels.clone() {
+      let reply = self
+        .exchange("streaming_subscribe", json!({
+          "channel":      "/meta/subscribe",
+          "subscription": channel,
+          "ext":          { "replay": { channel.as_str(): from.id() } }
+        }))
+        .await?;
+
+      if reply.successful != Some(true) {
+        self.client_id = None;
+        return Err(streaming_error(&format!("cannot subscribe to {}", channel), &reply));
+      }
+    }
+    Ok(())
+  }
+
+  /// Long polls for events; the server answers once there are some, or after a while without any.
+  async fn connect(&mut self) -> Result<()> {
+    let reply = self.exchange("streaming_connect", json!({ "channel": "/meta/connect", "connectionType": "long-polling" })).await?;
+    if reply.successful == Some(true) {
+      return Ok(());
+    }
+
+    let advice = reply.advice.as_ref();
+    match advice.and_then(|advice| advice.reconnect.as_deref()) {
+      // The client ID expired (or the server forgot about it), so start over
+      Some("handshake") => self.client_id = None,
+      Some("none")      => return Err(streaming_error("the server ended the connection", &reply)),
+      _                 => {
+        let interval = advice.and_then(|advice| advice.interval).unwrap_or(0).max(1000);
+        time::delay_for(Duration::from_millis(interval)).await;
+      }
+    }
+    Ok(())
+  }
+
+  /// Sends a message, queueing up any events that came back with the reply.
+  async fn exchange(&mut self, operation: &'static str, mut message: Value) -> Result<Message> {
+    let channel = message["channel"].as_str().unwrap_or_default().to_string();
+    self.next_id += 1;
+    message["id"] = json!(self.next_id.to_string());
+    if let Some(ref client_id) = self.client_id {
+      message["clientId"] = json!(client_id);
+    }
+
+    let instance_url = self.client.instance_url().ok_or(Error::NotAuthenticatedError)?;
+    let url          = format!("{}/cometd/{}", instance_url, self.client.version().trim_start_matches('v'));
+    let mut req      = HttpRequest::post(&url)?.json(&json!([message]))?;
+    if !self.cookies.is_empty() {
+      let cookies = self.cookies.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<_>>();
+      req = req.header(COOKIE, HeaderValue::from_str(&cookies.join("; "))?);
+    }
+
+    let res = self.client.send(operation, req).await?;
+    for cookie in res.headers.get_all(SET_COOKIE).iter().filter_map(|cookie| cookie.to_str().ok()) {
+      if let Some((name, value)) = cookie.split(';').next().and_then(|pair| pair.split_once('=')) {
+        self.cookies.insert(name.trim().to_string(), value.trim().to_string());
+      }
+    }
+
+    let mut reply = None;
+    for message in serde_json::from_slice::<Vec<Message>>(&res.body)? {
+      match message.data {
+        Some(data) if !message.channel.starts_with("/meta/") => self.events.push_back(StreamingEvent {
+          replay_id: data["event"]["replayId"].as_i64().unwrap_or_default(),
+          payload:   data.get("payload").or_else(|| data.get("sobject")).cloned().unwrap_or(data),
+          channel:   message.channel
+        }),
+        _ if message.channel == channel => reply = Some(message),
+        _                               => ()
+      }
+    }
+    reply.ok_or_else(|| Error::StreamingError(format!("no reply to {}", channel)))
+  }
+}
+
+fn streaming_error(what: &str, reply: &Message) -> Error {
+  Error::StreamingError(format!("{}: {}", what, reply.error.as_deref().unwrap_or("no reason given")))
+}
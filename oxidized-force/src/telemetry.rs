@@ -0,0 +1,69 @@
+//! Thin wrappers around the optional `tracing` & `metrics` instrumentation.
+//! Keeps the client free of `cfg` attributes; everything below compiles away when the feature is disabled.
+
+/// Runs a future inside of a new span, e.g. `traced!(fut, "describe", sobject = %name)`.
+#[cfg(feature = "tracing")]
+macro_rules! traced {
+  ($fut:expr, $($span:tt)+) => {{
+    let span = tracing::info_span!($($span)+);
+    tracing::Instrument::instrument($fut, span)
+  }};
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! traced {
+  ($fut:expr, $($span:tt)+) => { $fut };
+}
+
+/// Records a value on a field of the current span (which must have been declared as `Empty`).
+#[cfg(feature = "tracing")]
+macro_rules! record {
+  ($field:literal, $value:expr) => {
+    tracing::Span::current().record($field, &$value);
+  };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! record {
+  ($field:literal, $value:expr) => {
+    let _ = $value;
+  };
+}
+
+/// Metrics emitted through the `metrics` facade; these are no-ops when the feature is disabled.
+#[cfg(feature = "metrics")]
+pub(crate) mod metrics {
+  use std::time::Duration;
+
+  use reqwest::Method;
+
+  /// Records a finished request (`status` is `None` when no response was received at all).
+  pub(crate) fn request(method: &Method, status: Option<u16>, duration: Duration, sent: usize, received: usize) {
+    let method = method.to_string();
+    let status = status.map_or_else(|| "error".to_string(), |status| status.to_string());
+
+    metrics::counter!("sf_requests_total", "method" => method.clone(), "status" => status).increment(1);
+    metrics::histogram!("sf_request_duration_seconds", "method" => method).record(duration.as_secs_f64());
+    metrics::counter!("sf_request_bytes_sent_total").increment(sent as u64);
+    metrics::counter!("sf_response_bytes_received_total").increment(received as u64);
+  }
+
+  /// Records how long we waited on a bulk job to finish.
+  pub(crate) fn bulk_job(object: &str, state: &str, duration: Duration) {
+    metrics::histogram!(
+      "sf_bulk_job_duration_seconds",
+      "object" => object.to_string(),
+      "state"  => state.to_string()
+    ).record(duration.as_secs_f64());
+  }
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) mod metrics {
+  use std::time::Duration;
+
+  use reqwest::Method;
+
+  pub(crate) fn request(_: &Method, _: Option<u16>, _: Duration, _: usize, _: usize) {}
+  pub(crate) fn bulk_job(_: &str, _: &str, _: Duration) {}
+}
@@ -0,0 +1,367 @@
+//! Helpers for testing code built on top of the client (behind the `test-util` feature), no mock server required.
+//!
+//! ```ignore
+//! let mock = MockClient::new();
+//! mock.on(Method::GET, "/sobjects/Case/describe", MockResponse::json(200, fixtures::describe("Case").field("Id", FieldType::Id).to_json()));
+//!
+//! let describe = mock.describe("Case").await?;
+//! ```
+
+use std::{
+  collections::VecDeque,
+  ops::Deref,
+  sync::{Arc, Mutex}
+};
+
+use async_trait::async_trait;
+use reqwest::{
+  header::{HeaderMap, HeaderName, HeaderValue},
+  StatusCode
+};
+use serde_json::Value;
+
+pub mod generate;
+pub mod vcr;
+
+pub use generate::DescribeGenerator;
+pub use vcr::VcrTransport;
+
+/// So routes can be registered without depending on reqwest directly.
+pub use reqwest::Method;
+
+use crate::client::{Client, ClientBuilder};
+use crate::transport::{HttpRequest, HttpResponse, HttpTransport};
+use crate::errors::*;
+
+/// Instance URL every `MockClient` pretends to be logged into.
+pub const INSTANCE_URL: &str = "https://shibe.my.salesforce.com";
+
+/// A canned response handed out by `MockTransport`.
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+  status:  StatusCode,
+  headers: HeaderMap,
+  body:    Vec<u8>
+}
+
+impl MockResponse {
+  pub fn new<B>(status: u16, body: B) -> Self
+  where B: Into<Vec<u8>> {
+    MockResponse {
+      status:  StatusCode::from_u16(status).expect("invalid status code"),
+      headers: HeaderMap::new(),
+      body:    body.into()
+    }
+  }
+
+  pub fn json(status: u16, body: Value) -> Self {
+    MockResponse::new(status, body.to_string()).header("content-type", "application/json")
+  }
+
+  /// A page of bulk query results; `locator` is `None` for the last page.
+  pub fn csv(body: &str, locator: Option<&str>) -> Self {
+    let records = body.lines().count().saturating_sub(1).to_string();
+
+    MockResponse::new(200, body)
+      .header("content-type", "text/csv")
+      .header("sforce-locator", locator.unwrap_or("null"))
+      .header("sforce-numberofrecords", &records)
+  }
+
+  pub fn header(mut self, name: &str, value: &str) -> Self {
+    let name  = HeaderName::from_bytes(name.as_bytes()).expect("invalid header name");
+    let value = HeaderValue::from_str(value).expect("invalid header value");
+    self.headers.insert(name, value);
+    self
+  }
+
+  fn into_response(self) -> HttpResponse {
+    HttpResponse { status: self.status, headers: self.headers, body: self.body }
+  }
+}
+
+#[derive(Debug)]
+struct Route {
+  method:    Method,
+  path:      String,
+  responses: VecDeque<MockResponse>
+}
+
+/// An in-memory transport answering requests from registered routes.
+/// Responses for a route are handed out in order, with the last one repeating forever (handy for polling).
+/// Anything unmatched gets a 404 `NOT_FOUND` error response.
+#[derive(Debug, Default)]
+pub struct MockTransport {
+  routes:   Mutex<Vec<Route>>,
+  requests: Mutex<Vec<HttpRequest>>
+}
+
+impl MockTransport {
+  pub fn new() -> Self {
+    MockTransport::default()
+  }
+
+  /// Registers a response for `method` + `path`; paths containing a `?` must match the query string too.
+  pub fn on(&self, method: Method, path: &str, response: MockResponse) -> &Self {
+    let mut routes = self.routes.lock().expect("mock lock poisoned");
+
+    match routes.iter_mut().find(|route| route.method == method && route.path == path) {
+      Some(route) => route.responses.push_back(response),
+      None        => routes.push(Route { method, path: path.to_string(), responses: vec![response].into() })
+    }
+    self
+  }
+
+  /// Every request sent so far, in order.
+  pub fn requests(&self) -> Vec<HttpRequest> {
+    self.requests.lock().expect("mock lock poisoned").clone()
+  }
+
+  /// How many requests were sent to `path` (query strings are ignored).
+  pub fn hits(&self, path: &str) -> usize {
+    self.requests.lock().expect("mock lock poisoned").iter().filter(|req| req.url.path() == path).count()
+  }
+
+  fn respond(&self, req: &HttpRequest) -> MockResponse {
+    let full_path = match req.url.query() {
+      Some(query) => format!("{}?{}", req.url.path(), query),
+      None        => req.url.path().to_string()
+    };
+
+    let mut routes = self.routes.lock().expect("mock lock poisoned");
+    let route      = routes.iter_mut().find(|route| {
+      route.method == req.method && (route.path == req.url.path() || route.path == full_path)
+    });
+
+    match route {
+      Some(route) if route.responses.len() > 1 => route.responses.pop_front().unwrap(),
+      Some(route)                              => route.responses[0].clone(),
+      None => MockResponse::json(404, fixtures::error("NOT_FOUND", &format!("no mock registered for {} {}", req.method, full_path)))
+    }
+  }
+}
+
+#[async_trait]
+impl HttpTransport for MockTransport {
+  async fn send(&self, req: HttpRequest) -> Result<HttpResponse> {
+    let res = self.respond(&req);
+    self.requests.lock().expect("mock lock poisoned").push(req);
+    Ok(res.into_response())
+  }
+}
+
+/// An (already logged in) client wired up to a `MockTransport`; derefs to the client itself.
+#[derive(Debug, Clone)]
+pub struct MockClient {
+  client:    Client,
+  transport: Arc<MockTransport>
+}
+
+impl MockClient {
+  pub fn new() -> Self {
+    MockClient::with_builder(&mut Client::builder())
+  }
+
+  /// Builds the client from an existing builder; credentials & the transport are filled in.
+  pub fn with_builder(builder: &mut ClientBuilder) -> Self {
+    let transport = Arc::new(MockTransport::new());
+
+    let client = builder
+      .client_id("mock-client-id")
+      .client_secret("mock-client-secret")
+      .transport(transport.clone())
+      .create()
+      .expect("failed to build mock client");
+
+    let token = serde_json::from_value(fixtures::token(INSTANCE_URL)).expect("invalid token fixture");
+    client.start_session(token);
+
+    MockClient { client, transport }
+  }
+
+  /// Registers a response for a path relative to the REST API base, e.g. `/sobjects/Case/describe`.
+  pub fn on(&self, method: Method, path: &str, response: MockResponse) -> &Self {
+    self.transport.on(method, &self.api_path(path), response);
+    self
+  }
+
+  /// Full request path for a path relative to the REST API base.
+  pub fn api_path(&self, path: &str) -> String {
+    format!("/services/data/{}{}", self.client.version(), path)
+  }
+
+  pub fn transport(&self) -> &Arc<MockTransport> {
+    &self.transport
+  }
+
+  pub fn client(&self) -> &Client {
+    &self.client
+  }
+}
+
+impl Default for MockClient {
+  fn default() -> Self {
+    MockClient::new()
+  }
+}
+
+impl Deref for MockClient {
+  type Target = Client;
+
+  fn deref(&self) -> &Client {
+    &self.client
+  }
+}
+
+/// JSON payloads shaped like the real thing.
+pub mod fixtures {
+  use serde_json::{json, Value};
+
+  use crate::response::{BulkState, Field, FieldType};
+
+  /// A successful token response.
+  pub fn token(instance_url: &str) -> Value {
+    json!({
+      "access_token": "00DR00000008oBT!AQwAQCPqzc_HBE59c80QmEJD4rQKRRc1GRLvYZEq",
+      "instance_url": instance_url,
+      "id":           "https://login.salesforce.com/id/00DR00000008oBTMAY/005R0000000IUUMIA4",
+      "token_type":   "Bearer",
+      "issued_at":    "1513887500425",
+      "signature":    "3PiFUIioqKkHpHxUiCCDzpvSiM2F6//w2/CslNTuf+o="
+    })
+  }
+
+  /// A (single element) error response.
+  pub fn error(code: &str, message: &str) -> Value {
+    json!([{ "message": message, "errorCode": code }])
+  }
+
+  /// A page of query results; pass `next` for anything but the last page.
+  pub fn query_page(records: Vec<Value>, total_size: usize, next: Option<&str>) -> Value {
+    let mut page = json!({ "totalSize": total_size, "done": next.is_none(), "records": records });
+    if let Some(next) = next {
+      page["nextRecordsUrl"] = json!(next);
+    }
+    page
+  }
+
+  /// A record, `attributes` included.
+  pub fn record(sobject: &str, id: &str, fields: Value) -> Value {
+    let mut record = json!({
+      "attributes": { "type": sobject, "url": format!("/services/data/v49.0/sobjects/{}/{}", sobject, id) },
+      "Id":         id
+    });
+
+    if let (Some(record), Value::Object(fields)) = (record.as_object_mut(), fields) {
+      record.extend(fields);
+    }
+    record
+  }
+
+  /// Status of a bulk query job in the given state.
+  pub fn bulk_job(id: &str, object: &str, state: BulkState) -> Value {
+    json!({
+      "id":                     id,
+      "operation":              "query",
+      "object":                 object,
+      "createdById":            "005R0000000GiwjIAC",
+      "createdDate":            "2018-12-10T17:50:19.000+0000",
+      "systemModstamp":         "2018-12-10T17:50:19.000+0000",
+      "state":                  state,
+      "concurrencyMode":        "Parallel",
+      "contentType":            "CSV",
+      "apiVersion":             49.0,
+      "lineEnding":             "LF",
+      "columnDelimiter":        "COMMA",
+      "numberRecordsProcessed": if state == BulkState::JobComplete { 2 } else { 0 }
+    })
+  }
+
+  /// Job statuses as seen when polling a job from creation to completion.
+  pub fn bulk_job_lifecycle(id: &str, object: &str) -> Vec<Value> {
+    vec![BulkState::UploadComplete, BulkState::InProgress, BulkState::JobComplete]
+      .into_iter()
+      .map(|state| bulk_job(id, object, state))
+      .collect()
+  }
+
+  /// Starts building a describe payload.
+  pub fn describe(name: &str) -> DescribeBuilder {
+    DescribeBuilder { name: name.to_string(), fields: Vec::new() }
+  }
+
+  #[derive(Debug, Clone)]
+  pub struct DescribeBuilder {
+    name:   String,
+    fields: Vec<Field>
+  }
+
+  impl DescribeBuilder {
+    pub fn field(self, name: &str, field_type: FieldType) -> Self {
+      self.with_field(field(name, field_type))
+    }
+
+    /// A lookup field; the relationship and its object are named after the field
+    /// (`AccountId` -> `Account` to `Account`, `Owner__c` -> `Owner__r` to `Owner__c`).
+    pub fn reference(self, name: &str) -> Self {
+      let (relationship, object) = match name.strip_suffix("__c") {
+        Some(custom) => (format!("{}__r", custom), name.to_string()),
+        None         => (name.trim_end_matches("Id").to_string(), name.trim_end_matches("Id").to_string())
+      };
+
+      let mut lookup = field(name, FieldType::Reference);
+      lookup.relationship_name = Some(relationship);
+      lookup.reference_to      = vec![object];
+      self.with_field(lookup)
+    }
+
+    /// Adds a fully customized field.
+    pub fn with_field(mut self, field: Field) -> Self {
+      self.fields.push(field);
+      self
+    }
+
+    pub fn to_json(&self) -> Value {
+      json!({ "name": self.name, "fields": self.fields })
+    }
+
+    pub fn build(&self) -> crate::response::DescribeResponse {
+      serde_json::from_value(self.to_json()).expect("invalid describe fixture")
+    }
+  }
+
+  /// A field with reasonable defaults for its type.
+  pub fn field(name: &str, field_type: FieldType) -> Field {
+    let length = match field_type {
+      FieldType::Id | FieldType::Reference => 18,
+      FieldType::String                    => 255,
+      FieldType::TextArea                  => 32768,
+      _                                    => 0
+    };
+
+    Field {
+      name:                name.to_string(),
+      label:               name.trim_end_matches("__c").to_string(),
+      custom:              name.ends_with("__c"),
+      encrypted:           field_type == FieldType::EncryptedString,
+      precision:           0,
+      scale:               0,
+      digits:              0,
+      updateable:          field_type != FieldType::Id,
+      nillable:            field_type != FieldType::Id,
+      unique:              field_type == FieldType::Id,
+      relationship_name:   None,
+      reference_to:        Vec::new(),
+      cascade_delete:      false,
+      restricted_delete:   false,
+      compound_field_name: None,
+      default_value:       None,
+      picklist_values:     Vec::new(),
+      inline_help_text:    None,
+      calculated:          false,
+      calculated_formula:  None,
+      length,
+      field_type
+    }
+  }
+}
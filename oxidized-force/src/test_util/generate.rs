@@ -0,0 +1,186 @@
+//! Randomized (but valid) describe responses, for property testing anything that maps Salesforce metadata.
+//! Generators are seeded, so a failing case can always be reproduced from its seed.
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::response::{DefaultValue, DescribeResponse, Field, FieldType, PicklistValue};
+
+/// Every known field type (`Unknown` is only included when asked for).
+pub const FIELD_TYPES: [FieldType; 28] = [
+  FieldType::Id, FieldType::Base64, FieldType::Boolean, FieldType::Byte, FieldType::Date, FieldType::Double,
+  FieldType::Int, FieldType::Long, FieldType::String, FieldType::Time, FieldType::Address, FieldType::AnyType,
+  FieldType::Calculated, FieldType::Currency, FieldType::Email, FieldType::JunctionIdList, FieldType::Location,
+  FieldType::Percent, FieldType::Phone, FieldType::Picklist, FieldType::Reference, FieldType::Url,
+  FieldType::TextArea, FieldType::DateTime, FieldType::ComboBox, FieldType::EncryptedString, FieldType::MasterRecord,
+  FieldType::MultiPicklist
+];
+
+const WORDS: [&str; 12] = ["Shibe", "Account", "Order", "Bark", "Treat", "Region", "Owner", "Status", "Total", "Wag", "Leash", "Bone"];
+
+#[derive(Debug)]
+pub struct DescribeGenerator {
+  rng:             StdRng,
+  max_fields:      usize,
+  include_unknown: bool
+}
+
+impl DescribeGenerator {
+  pub fn new(seed: u64) -> Self {
+    DescribeGenerator { rng: StdRng::seed_from_u64(seed), max_fields: 40, include_unknown: false }
+  }
+
+  /// Upper bound on the number of fields (besides `Id`) per object.
+  pub fn max_fields(mut self, max: usize) -> Self {
+    self.max_fields = max;
+    self
+  }
+
+  /// Also generate `FieldType::Unknown` fields.
+  pub fn include_unknown(mut self, include: bool) -> Self {
+    self.include_unknown = include;
+    self
+  }
+
+  /// An object with a primary key & a bunch of randomized (uniquely named) fields.
+  pub fn describe(&mut self) -> DescribeResponse {
+    let custom = self.rng.gen_bool(0.5);
+    let name   = format!("{}{}", self.word(), if custom { "__c" } else { "" });
+    let count  = self.rng.gen_range(0..=self.max_fields);
+
+    let mut fields = vec![self.field_of("Id", FieldType::Id)];
+    for idx in 0..count {
+      let mut field = self.field();
+      field.name    = format!("{}{}{}", field.name.trim_end_matches("__c"), idx, if field.custom { "__c" } else { "" });
+
+      if let Some(ref mut rel) = field.relationship_name {
+        *rel = format!("{}{}{}", rel.trim_end_matches("__r"), idx, if field.custom { "__r" } else { "" });
+      }
+      fields.push(field);
+    }
+
+    let label = name.trim_end_matches("__c").to_string();
+    DescribeResponse { name, label, fields }
+  }
+
+  /// A single field of a random type (other than `Id`).
+  pub fn field(&mut self) -> Field {
+    let field_type = if self.include_unknown && self.rng.gen_ratio(1, 30) {
+      FieldType::Unknown
+    } else {
+      // Objects only ever have the one `Id` field
+      FIELD_TYPES[1..].choose(&mut self.rng).cloned().unwrap()
+    };
+
+    let custom = self.rng.gen_bool(0.3);
+    let name   = format!("{}{}", self.word(), if custom { "__c" } else { "" });
+    self.field_of(&name, field_type)
+  }
+
+  /// A field of the given type, with values that make sense for it.
+  pub fn field_of(&mut self, name: &str, field_type: FieldType) -> Field {
+    let is_id = field_type == FieldType::Id;
+
+    let length = match field_type {
+      FieldType::Id | FieldType::Reference | FieldType::MasterRecord => 18,
+      FieldType::TextArea                                            => self.rng.gen_range(256..=131_072),
+      _ if is_text(&field_type)                                      => self.rng.gen_range(1..=255),
+      _                                                              => 0
+    };
+
+    let precision = match field_type {
+      FieldType::Double | FieldType::Currency | FieldType::Percent | FieldType::Calculated => self.rng.gen_range(1..=18),
+      _                                                                                      => 0
+    };
+
+    let scale = match precision {
+      0 => 0,
+      _ => self.rng.gen_range(0..=precision)
+    };
+
+    let digits = match field_type {
+      FieldType::Int => self.rng.gen_range(1..=9),
+      _              => 0
+    };
+
+    let relationship_name = match field_type {
+      FieldType::Reference | FieldType::MasterRecord => Some(match name.strip_suffix("__c") {
+        Some(custom) => format!("{}__r", custom),
+        None         => format!("{}Rel", name)
+      }),
+      _ => None
+    };
+
+    let reference_to = match field_type {
+      FieldType::Reference | FieldType::MasterRecord => vec![format!("{}Object", name.trim_end_matches("__c"))],
+      _                                              => Vec::new()
+    };
+
+    let picklist_values = match field_type {
+      FieldType::Picklist | FieldType::MultiPicklist | FieldType::ComboBox => self.picklist_values(),
+      _ => Vec::new()
+    };
+
+    let default_value = match field_type {
+      FieldType::Boolean                                            => Some(DefaultValue::Boolean(self.rng.gen())),
+      FieldType::Int | FieldType::Double if self.rng.gen_bool(0.2) => Some(DefaultValue::Number(self.rng.gen_range(0..1000) as f64)),
+      _                                                             => None
+    };
+
+    Field {
+      name:                name.to_string(),
+      label:               name.trim_end_matches("__c").to_string(),
+      custom:              name.ends_with("__c"),
+      encrypted:           field_type == FieldType::EncryptedString,
+      updateable:          !is_id && self.rng.gen_bool(0.8),
+      nillable:            !is_id && field_type != FieldType::Boolean && self.rng.gen_bool(0.7),
+      unique:              is_id || (length > 0 && self.rng.gen_ratio(1, 20)),
+      cascade_delete:      false,
+      restricted_delete:   false,
+      compound_field_name: None,
+      inline_help_text:    None,
+      calculated:          false,
+      calculated_formula:  None,
+      length,
+      precision,
+      scale,
+      digits,
+      relationship_name,
+      reference_to,
+      default_value,
+      picklist_values,
+      field_type
+    }
+  }
+
+  fn picklist_values(&mut self) -> Vec<PicklistValue> {
+    let count   = self.rng.gen_range(1..=8);
+    let default = self.rng.gen_range(0..count + 1);
+
+    (0..count)
+      .map(|idx| {
+        let value = format!("{} {}", self.word(), idx);
+        PicklistValue { label: Some(value.clone()), active: self.rng.gen_bool(0.9), default_value: idx == default, value }
+      })
+      .collect()
+  }
+
+  fn word(&mut self) -> &'static str {
+    WORDS.choose(&mut self.rng).unwrap()
+  }
+}
+
+fn is_text(field_type: &FieldType) -> bool {
+  matches!(
+    field_type,
+    FieldType::String | FieldType::ComboBox | FieldType::Email | FieldType::Phone | FieldType::Url |
+    FieldType::Picklist | FieldType::MultiPicklist | FieldType::EncryptedString
+  )
+}
+
+impl Iterator for DescribeGenerator {
+  type Item = DescribeResponse;
+
+  fn next(&mut self) -> Option<DescribeResponse> {
+    Some(self.describe())
+  }
+}
@@ -0,0 +1,232 @@
+//! Record/replay ("VCR") transport: record real API interactions to a cassette once, then replay them in tests.
+//! Cassettes are sanitized while recording, so they can be committed: credentials & tokens are replaced with `REDACTED`
+//! and the instance host is swapped out for `INSTANCE_URL`.
+
+use std::{
+  collections::{BTreeMap, HashMap, VecDeque},
+  fs,
+  io::{Error as IoError, ErrorKind},
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex}
+};
+
+use async_trait::async_trait;
+use reqwest::{
+  header::{HeaderMap, HeaderName, HeaderValue},
+  StatusCode
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::INSTANCE_URL;
+use crate::transport::{HttpRequest, HttpResponse, HttpTransport};
+use crate::errors::*;
+
+/// JSON & form fields that never make it into a cassette.
+const SECRETS: [&str; 9] = [
+  "access_token", "client_id", "client_secret", "code", "id_token", "password", "refresh_token", "signature", "username"
+];
+
+/// Response headers that never make it into a cassette.
+const SKIPPED_HEADERS: [&str; 3] = ["authorization", "set-cookie", "strict-transport-security"];
+
+/// A single recorded request/response pair.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Interaction {
+  pub method:  String,
+  /// Path & query string (the host is not part of the match).
+  pub path:    String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub request: Option<String>,
+  pub status:  u16,
+  #[serde(default)]
+  pub headers: BTreeMap<String, String>,
+  pub body:    String
+}
+
+#[derive(Debug)]
+enum Mode {
+  Record(Arc<dyn HttpTransport>),
+  Replay(Mutex<HashMap<(String, String), VecDeque<Interaction>>>)
+}
+
+/// Wraps another transport while recording, or stands in for one entirely while replaying.
+/// Replayed interactions are matched on method, path & query string, in recorded order (so paging & polling replay faithfully).
+#[derive(Debug)]
+pub struct VcrTransport {
+  path:     PathBuf,
+  mode:     Mode,
+  recorded: Mutex<Vec<Interaction>>
+}
+
+impl VcrTransport {
+  /// Records everything sent through `inner`; call `save` once done.
+  pub fn record<P>(path: P, inner: Arc<dyn HttpTransport>) -> Self
+  where P: Into<PathBuf> {
+    VcrTransport { path: path.into(), mode: Mode::Record(inner), recorded: Mutex::new(Vec::new()) }
+  }
+
+  /// Replays a previously recorded cassette.
+  pub fn replay<P>(path: P) -> Result<Self>
+  where P: Into<PathBuf> {
+    let path     = path.into();
+    let cassette = serde_json::from_slice::<Vec<Interaction>>(&fs::read(&path)?)?;
+
+    let mut interactions: HashMap<_, VecDeque<_>> = HashMap::new();
+    for interaction in cassette {
+      interactions.entry((interaction.method.clone(), interaction.path.clone())).or_default().push_back(interaction);
+    }
+
+    Ok(VcrTransport { path, mode: Mode::Replay(Mutex::new(interactions)), recorded: Mutex::new(Vec::new()) })
+  }
+
+  /// Replays the cassette if it exists (and `SF_VCR_RECORD` isn't set), otherwise records a fresh one through `inner`.
+  pub fn auto<P>(path: P, inner: Arc<dyn HttpTransport>) -> Result<Self>
+  where P: Into<PathBuf> {
+    let path = path.into();
+
+    if path.exists() && std::env::var_os("SF_VCR_RECORD").is_none() {
+      VcrTransport::replay(path)
+    } else {
+      Ok(VcrTransport::record(path, inner))
+    }
+  }
+
+  pub fn is_recording(&self) -> bool {
+    matches!(self.mode, Mode::Record(_))
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// Writes the recorded interactions to the cassette (does nothing while replaying).
+  pub fn save(&self) -> Result<()> {
+    if !self.is_recording() {
+      return Ok(());
+    }
+
+    if let Some(dir) = self.path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+
+    let recorded = self.recorded.lock().expect("vcr lock poisoned");
+    fs::write(&self.path, serde_json::to_vec_pretty(&*recorded)?)?;
+    Ok(())
+  }
+
+  fn replay_one(&self, req: &HttpRequest) -> Result<HttpResponse> {
+    let key = (req.method.to_string(), path_and_query(req));
+
+    let interaction = match self.mode {
+      Mode::Replay(ref interactions) => {
+        let mut interactions = interactions.lock().expect("vcr lock poisoned");
+        interactions.get_mut(&key).and_then(VecDeque::pop_front)
+      },
+      Mode::Record(_) => None
+    };
+
+    let interaction = interaction.ok_or_else(|| {
+      let msg = format!("no recorded interaction left for {} {} in {}", key.0, key.1, self.path.display());
+      Error::IoError(IoError::new(ErrorKind::NotFound, msg))
+    })?;
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in &interaction.headers {
+      if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+        headers.insert(name, value);
+      }
+    }
+
+    Ok(HttpResponse {
+      status: StatusCode::from_u16(interaction.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+      body:   interaction.body.into_bytes(),
+      headers
+    })
+  }
+}
+
+#[async_trait]
+impl HttpTransport for VcrTransport {
+  async fn send(&self, req: HttpRequest) -> Result<HttpResponse> {
+    let inner = match self.mode {
+      Mode::Record(ref inner) => inner,
+      Mode::Replay(_)         => return self.replay_one(&req)
+    };
+
+    let host    = format!("{}://{}", req.url.scheme(), req.url.host_str().unwrap_or_default());
+    let method  = req.method.to_string();
+    let path    = path_and_query(&req);
+    let request = req.body.as_deref().map(|body| sanitize(body, &host));
+    let res     = inner.send(req).await?;
+
+    let headers = res
+      .headers
+      .iter()
+      .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.as_str()))
+      .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+      .collect();
+
+    self.recorded.lock().expect("vcr lock poisoned").push(Interaction {
+      status: res.status.as_u16(),
+      body:   sanitize(&res.body, &host),
+      method,
+      path,
+      request,
+      headers
+    });
+
+    Ok(res)
+  }
+}
+
+fn path_and_query(req: &HttpRequest) -> String {
+  match req.url.query() {
+    Some(query) => format!("{}?{}", req.url.path(), query),
+    None        => req.url.path().to_string()
+  }
+}
+
+/// Redacts secrets from JSON & form bodies, and replaces the host with `INSTANCE_URL`.
+fn sanitize(body: &[u8], host: &str) -> String {
+  let text = String::from_utf8_lossy(body);
+
+  let text = match serde_json::from_str::<Value>(&text) {
+    Ok(mut json) => {
+      redact(&mut json);
+      json.to_string()
+    },
+    Err(_) if !text.contains('\n') && text.contains('=') => {
+      text
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+          Some((key, _)) if SECRETS.contains(&key) => format!("{}=REDACTED", key),
+          _                                        => pair.to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+    },
+    Err(_) => text.into_owned()
+  };
+
+  text.replace(host, INSTANCE_URL)
+}
+
+fn redact(value: &mut Value) {
+  match value {
+    Value::Object(map) => {
+      for (key, value) in map.iter_mut() {
+        if SECRETS.contains(&key.as_str()) {
+          *value = Value::String("REDACTED".to_string());
+        } else if key == "instance_url" {
+          // Token responses point at the instance, which the rest of the requests only do by host
+          *value = Value::String(INSTANCE_URL.to_string());
+        } else {
+          redact(value);
+        }
+      }
+    },
+    Value::Array(values) => values.iter_mut().for_each(redact),
+    _ => {}
+  }
+}
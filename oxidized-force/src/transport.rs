@@ -0,0 +1,133 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use reqwest::{header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE}, Method, StatusCode, Url};
+use serde::Serialize;
+
+use crate::errors::*;
+
+/// A fully buffered HTTP request, as handed over to an `HttpTransport`.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+  pub method:  Method,
+  pub url:     Url,
+  pub headers: HeaderMap,
+  pub body:    Option<Vec<u8>>
+}
+
+/// A fully buffered HTTP response, as returned from an `HttpTransport`.
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+  pub status:  StatusCode,
+  pub headers: HeaderMap,
+  pub body:    Vec<u8>
+}
+
+/// The raw HTTP layer used by the client; implement this to plug in another HTTP stack or an in-memory fake.
+#[async_trait]
+pub trait HttpTransport: Debug + Send + Sync {
+  async fn send(&self, req: HttpRequest) -> Result<HttpResponse>;
+}
+
+/// The default transport, backed by `reqwest`.
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+  client: reqwest::Client
+}
+
+impl ReqwestTransport {
+  pub fn new(client: reqwest::Client) -> Self {
+    ReqwestTransport { client }
+  }
+}
+
+#[async_trait]
+impl HttpTransport for ReqwestTransport {
+  async fn send(&self, req: HttpRequest) -> Result<HttpResponse> {
+    let mut builder = self
+      .client
+      .request(req.method, req.url)
+      .headers(req.headers);
+
+    if let Some(body) = req.body {
+      builder = builder.body(body);
+    }
+
+    let res     = builder.send().await?;
+    let status  = res.status();
+    let headers = res.headers().clone();
+    let body    = res.bytes().await?.to_vec();
+
+    Ok(HttpResponse { status, headers, body })
+  }
+}
+
+impl HttpRequest {
+  pub fn new(method: Method, url: &str) -> Result<Self> {
+    Ok(HttpRequest {
+      method,
+      url:     Url::parse(url)?,
+      headers: HeaderMap::new(),
+      body:    None
+    })
+  }
+
+  pub fn get(url: &str) -> Result<Self> {
+    Self::new(Method::GET, url)
+  }
+
+  pub fn post(url: &str) -> Result<Self> {
+    Self::new(Method::POST, url)
+  }
+
+  pub fn patch(url: &str) -> Result<Self> {
+    Self::new(Method::PATCH, url)
+  }
+
+  /// Appends query string parameters to the URL.
+  pub fn query<'a, I>(mut self, params: I) -> Self
+  where I: IntoIterator<Item = &'a (&'a str, &'a str)> {
+    {
+      let mut pairs = self.url.query_pairs_mut();
+      for (key, val) in params {
+        pairs.append_pair(key, val);
+      }
+    }
+
+    // An empty query string would otherwise leave a dangling `?` behind
+    if self.url.query() == Some("") {
+      self.url.set_query(None);
+    }
+    self
+  }
+
+  /// Sets (replacing any existing values) a header.
+  pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+    self.headers.insert(name, value);
+    self
+  }
+
+  /// Serializes the payload as the JSON request body.
+  pub fn json<P: Serialize>(mut self, payload: &P) -> Result<Self> {
+    self.body = Some(serde_json::to_vec(payload)?);
+    Ok(self.header(CONTENT_TYPE, HeaderValue::from_static("application/json")))
+  }
+
+  /// Encodes the pairs as a `application/x-www-form-urlencoded` request body.
+  pub fn form<'a, I>(mut self, params: I) -> Self
+  where I: IntoIterator<Item = &'a (&'a str, &'a str)> {
+    let body = url::form_urlencoded::Serializer::new(String::new())
+      .extend_pairs(params)
+      .finish();
+
+    self.body = Some(body.into_bytes());
+    self.header(CONTENT_TYPE, HeaderValue::from_static("application/x-www-form-urlencoded"))
+  }
+}
+
+impl HttpResponse {
+  /// Grabs a header value as a string slice (if it exists & is valid UTF-8).
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self.headers.get(name).and_then(|val| val.to_str().ok())
+  }
+}
@@ -0,0 +1,111 @@
+use oxidized_force::response::DescribeResponse;
+
+use crate::describe::Mapping;
+use crate::sql::{Identifiers, Quoting, Table};
+
+/// `sources.yml` declaring every table (in the schema they were created in), with their labels as descriptions.
+pub fn sources(source: &str, tables: &[(&Table, &DescribeResponse)], mapping: &Mapping) -> String {
+  let mut out = format!("version: 2\n\nsources:\n  - name: {}\n", yaml_string(source));
+  if let Some(ref schema) = mapping.schema {
+    out.push_str(&format!("    schema: {}\n", yaml_string(&mapping.identifiers.fold(schema))));
+  }
+  out.push_str("    tables:\n");
+
+  for (table, desc) in tables {
+    let name = mapping.identifiers.fold(&table.name());
+    out.push_str(&format!("      - name: {}\n", yaml_string(&name)));
+    if !desc.label.is_empty() {
+      out.push_str(&format!("        description: {}\n", yaml_string(&desc.label)));
+    }
+    // dbt would leave the name bare otherwise, which only works when the SQL did that too
+    if mapping.identifiers.render_for(mapping.dialect, &name) != name {
+      out.push_str("        quoting:\n          identifier: true\n");
+    }
+
+    out.push_str("        columns:\n");
+    for (field, column) in table.field_columns() {
+      out.push_str(&format!("          - name: {}\n", yaml_string(&mapping.identifiers.fold(column))));
+      if let Some(label) = desc.field(field).map(|field| field.label.as_str()).filter(|label| !label.is_empty()) {
+        out.push_str(&format!("            description: {}\n", yaml_string(label)));
+      }
+    }
+  }
+  out
+}
+
+/// Name of the staging model for a table, e.g. `stg_salesforce__account`.
+pub fn model_name(source: &str, table: &Table) -> String {
+  format!("stg_{}__{}", source, super::snake_case(&table.name()))
+}
+
+/// Staging model selecting every column of the source table, renamed to snake case.
+pub fn staging_model(source: &str, table: &Table, mapping: &Mapping) -> String {
+  let aliases = Identifiers::default().quoting(Quoting::WhenNeeded);
+
+  let columns = table.field_columns()
+    .iter()
+    .map(|(field, column)| {
+      let column = mapping.identifiers.render_for(mapping.dialect, column);
+      let alias  = aliases.render_for(mapping.dialect, &super::snake_case(field));
+      match column == alias {
+        true  => format!("        {}", column),
+        false => format!("        {} as {}", column, alias)
+      }
+    })
+    .collect::<Vec<_>>();
+
+  format!(
+    "with source as (\n\n    select * from {{{{ source('{}', '{}') }}}}\n\n),\n\nrenamed as (\n\n    select\n{}\n    from source\n\n)\n\nselect * from renamed\n",
+    source,
+    mapping.identifiers.fold(&table.name()),
+    columns.join(",\n")
+  )
+}
+
+/// Double quoted, so labels with colons and the like don't trip up YAML.
+fn yaml_string(value: &str) -> String {
+  format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use oxidized_force::response::FieldType;
+  use oxidized_force::test_util::fixtures;
+
+  use crate::describe::table_from_describe;
+
+  #[test]
+  fn dbt_codegen() {
+    let mut name = fixtures::field("Name", FieldType::String);
+    name.label = "Account \"Name\"".to_string();
+
+    let desc = fixtures::describe("Account")
+      .field("Id", FieldType::Id)
+      .with_field(name)
+      .field("Order__c", FieldType::Double)
+      .build();
+
+    let mapping = Mapping { schema: Some("sfdc".to_string()), ..Default::default() };
+    let account = table_from_describe(mapping.table_name("Account"), &desc, &mapping);
+
+    let yaml = sources("salesforce", &[(&account, &desc)], &mapping);
+    assert_eq!(yaml, concat!(
+      "version: 2\n\nsources:\n  - name: \"salesforce\"\n    schema: \"sfdc\"\n    tables:\n",
+      "      - name: \"Account\"\n        quoting:\n          identifier: true\n        columns:\n",
+      "          - name: \"Id\"\n            description: \"Id\"\n",
+      "          - name: \"Name\"\n            description: \"Account \\\"Name\\\"\"\n",
+      "          - name: \"Order__c\"\n            description: \"Order\"\n"
+    ));
+
+    assert_eq!(model_name("salesforce", &account), "stg_salesforce__account");
+    let model = staging_model("salesforce", &account, &mapping);
+    assert!(model.starts_with("with source as (\n\n    select * from {{ source('salesforce', 'Account') }}\n\n),\n"));
+    assert!(model.contains("    select\n        \"Id\" as id,\n        \"Name\" as name,\n        \"Order__c\" as order_c\n    from source\n"));
+
+    // Bare names stay bare, and so does dbt
+    let mapping = Mapping { lowercase_tables: true, identifiers: Identifiers::default().quoting(Quoting::WhenNeeded), ..Default::default() };
+    let account = table_from_describe(mapping.table_name("Account"), &desc, &mapping);
+    assert!(!sources("salesforce", &[(&account, &desc)], &mapping).contains("quoting"));
+  }
+}
@@ -0,0 +1,33 @@
+//! Generates code other than SQL from describes, e.g. Rust structs for the records.
+
+pub mod dbt;
+pub mod rust;
+pub mod schema;
+
+use crate::sql::{Naming, NamingCase};
+
+/// `BillingCity` -> `billing_city`, `SLA_Expiration__c` -> `sla_expiration_c`.
+pub(crate) fn snake_case(name: &str) -> String {
+  Naming::default()
+    .replace("_+", "_")
+    .expect("valid pattern")
+    .case(NamingCase::Snake)
+    .apply(name)
+}
+
+/// `Account` -> `Account`, `Invoice_Line__c` -> `InvoiceLine`, `ns__Thing__c` -> `NsThing`.
+pub(crate) fn pascal_case(object: &str) -> String {
+  object
+    .strip_suffix("__c")
+    .unwrap_or(object)
+    .split('_')
+    .filter(|word| !word.is_empty())
+    .map(|word| {
+      let mut chars = word.chars();
+      match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+        None        => String::new()
+      }
+    })
+    .collect()
+}
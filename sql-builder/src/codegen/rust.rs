@@ -0,0 +1,197 @@
+use oxidized_force::response::{DescribeResponse, Field, FieldType};
+
+use crate::filter::FieldFilter;
+
+/// Knobs for `generate`.
+#[derive(Debug, Clone, Default)]
+pub struct RustOptions {
+  decimal: bool,
+  sobject: bool,
+  filter:  FieldFilter
+}
+
+impl RustOptions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Currency and percent fields become `Decimal`s (which needs oxidized-force's `decimal` feature) instead of `f64`s.
+  pub fn decimal(self, decimal: bool) -> Self {
+    Self { decimal, ..self }
+  }
+
+  /// Derives `SObject` (oxidized-force's `derive` feature) rather than plain `Serialize`/`Deserialize`, so there's a `select()` too.
+  pub fn sobject(self, sobject: bool) -> Self {
+    Self { sobject, ..self }
+  }
+
+  /// Which fields get a struct field.
+  pub fn filter(self, filter: FieldFilter) -> Self {
+    Self { filter, ..self }
+  }
+}
+
+/// A struct per object that records can be deserialized into straight out of `Client::query`,
+/// with the Salesforce names as serde renames and nillable fields as `Option`s. Dates & datetimes need oxidized-force's `chrono` feature.
+pub fn generate(descs: &[DescribeResponse], options: &RustOptions) -> String {
+  let used = descs
+    .iter()
+    .flat_map(|desc| desc.fields.iter().filter(|field| options.filter.allows(field)))
+    .map(|field| rust_type(field, options).0)
+    .collect::<Vec<_>>();
+  let types = ["Date", "Decimal", "Timestamp"].iter().filter(|tp| used.contains(tp)).copied().collect::<Vec<_>>();
+
+  let mut out = String::from("// Generated from the Salesforce describes, changes will be lost when it's regenerated.\n\n");
+  match options.sobject {
+    true  => out.push_str("use oxidized_force::SObject;\n"),
+    false => out.push_str("use serde::{Deserialize, Serialize};\n")
+  }
+  match types.len() {
+    0 => (),
+    1 => out.push_str(&format!("use oxidized_force::serde_sf::{};\n", types[0])),
+    _ => out.push_str(&format!("use oxidized_force::serde_sf::{{{}}};\n", types.join(", ")))
+  }
+
+  for desc in descs {
+    out.push('\n');
+    out.push_str(&generate_struct(desc, options));
+  }
+  out
+}
+
+fn generate_struct(desc: &DescribeResponse, options: &RustOptions) -> String {
+  let mut out = String::new();
+  if !desc.label.is_empty() {
+    out.push_str(&format!("/// {}\n", desc.label));
+  }
+  match options.sobject {
+    true  => out.push_str(&format!("#[derive(Debug, Clone, SObject)]\n#[sobject(name = \"{}\")]\n", desc.name)),
+    false => out.push_str("#[derive(Debug, Clone, Serialize, Deserialize)]\n")
+  }
+  out.push_str(&format!("pub struct {} {{\n", super::pascal_case(&desc.name)));
+
+  let fields = desc.fields
+    .iter()
+    .filter(|field| options.filter.allows(field))
+    .map(|field| generate_field(field, options))
+    .collect::<Vec<_>>();
+  out.push_str(&fields.join(",\n\n"));
+  out.push_str("\n}\n");
+  out
+}
+
+fn generate_field(field: &Field, options: &RustOptions) -> String {
+  let (tp, with) = rust_type(field, options);
+
+  let mut serde = vec![format!("rename = \"{}\"", field.name)];
+  if field.nillable {
+    serde.push("default".to_string());
+  }
+  if let Some(with) = with {
+    let module = match field.nillable {
+      true  => format!("{}_opt", with),
+      false => with.to_string()
+    };
+    serde.push(format!("with = \"oxidized_force::serde_sf::{}\"", module));
+  }
+
+  let mut out = String::new();
+  if !field.label.is_empty() {
+    out.push_str(&format!("    /// {}\n", field.label));
+  }
+  out.push_str(&format!("    #[serde({})]\n", serde.join(", ")));
+  match field.nillable {
+    true  => out.push_str(&format!("    pub {}: Option<{}>", field_ident(&field.name), tp)),
+    false => out.push_str(&format!("    pub {}: {}", field_ident(&field.name), tp))
+  }
+  out
+}
+
+/// The type of a field, along with the `serde_sf` module it goes through (if any).
+fn rust_type(field: &Field, options: &RustOptions) -> (&'static str, Option<&'static str>) {
+  let tp = rust_type_of(&field.field_type, options);
+  let with = match tp {
+    "Date"      => Some("date"),
+    "Timestamp" => Some("datetime"),
+    "Decimal"   => Some("decimal"),
+    _           => None
+  };
+  (tp, with)
+}
+
+fn rust_type_of(field_type: &FieldType, options: &RustOptions) -> &'static str {
+  use FieldType::*;
+
+  match field_type {
+    Boolean                                => "bool",
+    Int                                    => "i32",
+    Long                                   => "i64",
+    Double                                 => "f64",
+    Currency | Percent if options.decimal  => "Decimal",
+    Currency | Percent                     => "f64",
+    Date                                   => "Date",
+    DateTime                               => "Timestamp",
+    JunctionIdList                         => "Vec<String>",
+    AnyType | Address | Location | Unknown => "serde_json::Value",
+    // Multi-select picklists come back as `a;b;c`
+    _                                      => "String"
+  }
+}
+
+/// `BillingCity` -> `billing_city`, `SLA_Expiration__c` -> `sla_expiration_c`, `Type` -> `r#type`.
+fn field_ident(name: &str) -> String {
+  let ident = super::snake_case(name);
+
+  match ident.as_str() {
+    "self" | "super" | "crate" => format!("{}_", ident),
+    ident if KEYWORDS.contains(&ident) => format!("r#{}", ident),
+    _ => ident
+  }
+}
+
+/// Keywords that can be used as raw identifiers (`self`, `super` and `crate` can't).
+const KEYWORDS: &[&str] = &[
+  "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn", "else", "enum",
+  "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut",
+  "override", "priv", "pub", "ref", "return", "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe",
+  "unsized", "use", "virtual", "where", "while", "yield"
+];
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use oxidized_force::test_util::fixtures;
+
+  #[test]
+  fn rust_codegen() {
+    let mut name = fixtures::field("Name", FieldType::String);
+    name.nillable = false;
+
+    let desc = fixtures::describe("Invoice_Line__c")
+      .field("Id", FieldType::Id)
+      .with_field(name)
+      .field("Type", FieldType::Picklist)
+      .field("Amount__c", FieldType::Currency)
+      .field("Due_Date__c", FieldType::Date)
+      .field("CreatedDate", FieldType::DateTime)
+      .field("Paid__c", FieldType::Boolean)
+      .build();
+
+    let code = generate(std::slice::from_ref(&desc), &RustOptions::new());
+    assert!(code.contains("use serde::{Deserialize, Serialize};\nuse oxidized_force::serde_sf::{Date, Timestamp};\n"));
+    assert!(code.contains("#[derive(Debug, Clone, Serialize, Deserialize)]\npub struct InvoiceLine {\n"));
+    assert!(code.contains("    #[serde(rename = \"Id\")]\n    pub id: String,\n"));
+    assert!(code.contains("    #[serde(rename = \"Name\")]\n    pub name: String,\n"));
+    assert!(code.contains("    #[serde(rename = \"Type\", default)]\n    pub r#type: Option<String>,\n"));
+    assert!(code.contains("    #[serde(rename = \"Amount__c\", default)]\n    pub amount_c: Option<f64>,\n"));
+    assert!(code.contains("    #[serde(rename = \"Due_Date__c\", default, with = \"oxidized_force::serde_sf::date_opt\")]\n    pub due_date_c: Option<Date>,\n"));
+    assert!(code.contains("    pub created_date: Option<Timestamp>,\n"));
+    assert!(code.ends_with("    /// Paid\n    #[serde(rename = \"Paid__c\", default)]\n    pub paid_c: Option<bool>\n}\n"));
+
+    let code = generate(&[desc], &RustOptions::new().decimal(true).sobject(true).filter(FieldFilter::new().include("Amount__c")));
+    assert!(code.contains("use oxidized_force::SObject;\nuse oxidized_force::serde_sf::Decimal;\n"));
+    assert!(code.contains("#[derive(Debug, Clone, SObject)]\n#[sobject(name = \"Invoice_Line__c\")]\n"));
+    assert!(code.contains("with = \"oxidized_force::serde_sf::decimal_opt\")]\n    pub amount_c: Option<Decimal>\n"));
+    assert!(!code.contains("Timestamp"));
+  }
+}
@@ -0,0 +1,178 @@
+use oxidized_force::response::{DescribeResponse, Field, FieldType};
+use serde_json::{json, Map, Value};
+
+use crate::error::ParseError;
+use crate::filter::FieldFilter;
+
+/// Schema flavors `generate` can produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaFormat {
+  Avro,
+  JsonSchema
+}
+
+impl std::str::FromStr for SchemaFormat {
+  type Err = ParseError;
+
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    match name.to_ascii_lowercase().as_str() {
+      "avro"                       => Ok(SchemaFormat::Avro),
+      "jsonschema" | "json-schema" => Ok(SchemaFormat::JsonSchema),
+      _ => Err(ParseError::unknown("schema format", name, "avro or jsonschema"))
+    }
+  }
+}
+
+/// A schema for the records of each object; several objects make an Avro union (an array of records),
+/// or a JSON Schema with one definition per object.
+pub fn generate(descs: &[DescribeResponse], format: SchemaFormat, filter: &FieldFilter) -> Value {
+  let mut schemas = descs
+    .iter()
+    .map(|desc| match format {
+      SchemaFormat::Avro       => avro(desc, filter),
+      SchemaFormat::JsonSchema => json_schema(desc, filter)
+    })
+    .collect::<Vec<_>>();
+
+  match (format, schemas.len()) {
+    (_, 1)                        => schemas.remove(0),
+    (SchemaFormat::Avro, _)       => Value::Array(schemas),
+    (SchemaFormat::JsonSchema, _) => {
+      let defs = descs.iter().map(|desc| desc.name.clone()).zip(schemas).collect::<Map<_, _>>();
+      json!({ "$schema": JSON_SCHEMA, "$defs": defs })
+    }
+  }
+}
+
+const JSON_SCHEMA: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Avro record schema; nillable fields are unions with `null` (defaulting to it).
+pub fn avro(desc: &DescribeResponse, filter: &FieldFilter) -> Value {
+  let fields = desc.fields
+    .iter()
+    .filter(|field| filter.allows(field))
+    .map(|field| {
+      let mut schema = json!({ "name": field.name });
+      match field.nillable {
+        true  => {
+          schema["type"]    = json!(["null", avro_type(field)]);
+          schema["default"] = Value::Null;
+        },
+        false => schema["type"] = avro_type(field)
+      }
+      if !field.label.is_empty() {
+        schema["doc"] = json!(field.label);
+      }
+      schema
+    })
+    .collect::<Vec<_>>();
+
+  let mut schema = json!({ "type": "record", "name": desc.name, "fields": fields });
+  if !desc.label.is_empty() {
+    schema["doc"] = json!(desc.label);
+  }
+  schema
+}
+
+fn avro_type(field: &Field) -> Value {
+  use FieldType::*;
+
+  match field.field_type {
+    Boolean                     => json!("boolean"),
+    Int                         => json!("int"),
+    Long                        => json!("long"),
+    Double | Currency | Percent => json!("double"),
+    Date                        => json!({ "type": "int", "logicalType": "date" }),
+    DateTime                    => json!({ "type": "long", "logicalType": "timestamp-millis" }),
+    Time                        => json!({ "type": "int", "logicalType": "time-millis" }),
+    JunctionIdList              => json!({ "type": "array", "items": "string" }),
+    // Compound and free form values go in as JSON text
+    _                           => json!("string")
+  }
+}
+
+/// JSON Schema for records as they come out of the API (or a JSONL export); fields that aren't nillable are required.
+pub fn json_schema(desc: &DescribeResponse, filter: &FieldFilter) -> Value {
+  let fields = desc.fields.iter().filter(|field| filter.allows(field)).collect::<Vec<_>>();
+
+  let properties = fields
+    .iter()
+    .map(|field| {
+      let mut schema = json_schema_type(field);
+      if field.nillable {
+        if let Some(tp) = schema.get("type").cloned() {
+          schema["type"] = json!([tp, "null"]);
+        }
+      }
+      if !field.label.is_empty() {
+        schema["description"] = json!(field.label);
+      }
+      (field.name.clone(), schema)
+    })
+    .collect::<Map<_, _>>();
+  let required = fields.iter().filter(|field| !field.nillable).map(|field| field.name.as_str()).collect::<Vec<_>>();
+
+  let mut schema = json!({ "$schema": JSON_SCHEMA, "title": desc.name, "type": "object", "properties": properties, "required": required });
+  if !desc.label.is_empty() {
+    schema["description"] = json!(desc.label);
+  }
+  schema
+}
+
+fn json_schema_type(field: &Field) -> Value {
+  use FieldType::*;
+
+  match field.field_type {
+    Boolean                                => json!({ "type": "boolean" }),
+    Int | Long                             => json!({ "type": "integer" }),
+    Double | Currency | Percent            => json!({ "type": "number" }),
+    Date                                   => json!({ "type": "string", "format": "date" }),
+    DateTime                               => json!({ "type": "string", "format": "date-time" }),
+    Time                                   => json!({ "type": "string", "format": "time" }),
+    Email                                  => json!({ "type": "string", "format": "email" }),
+    Url                                    => json!({ "type": "string", "format": "uri" }),
+    JunctionIdList                         => json!({ "type": "array", "items": { "type": "string" } }),
+    // Anything goes for these
+    AnyType | Address | Location | Unknown => json!({}),
+    _ if field.length > 0                  => json!({ "type": "string", "maxLength": field.length }),
+    _                                      => json!({ "type": "string" })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use oxidized_force::test_util::fixtures;
+
+  #[test]
+  fn schema_codegen() {
+    let desc = fixtures::describe("Account")
+      .field("Id", FieldType::Id)
+      .field("Name", FieldType::String)
+      .field("CreatedDate", FieldType::DateTime)
+      .field("Website", FieldType::Url)
+      .build();
+    let filter = FieldFilter::new();
+
+    let avro = avro(&desc, &filter);
+    assert_eq!(avro["type"], "record");
+    assert_eq!(avro["name"], "Account");
+    assert_eq!(avro["fields"][0], json!({ "name": "Id", "type": "string", "doc": "Id" }));
+    assert_eq!(avro["fields"][2]["type"], json!(["null", { "type": "long", "logicalType": "timestamp-millis" }]));
+    assert_eq!(avro["fields"][2]["default"], json!(null));
+
+    let json_schema = json_schema(&desc, &FieldFilter::new().exclude("Website"));
+    assert_eq!(json_schema["required"], json!(["Id"]));
+    assert_eq!(json_schema["properties"]["Id"], json!({ "type": "string", "maxLength": 18, "description": "Id" }));
+    assert_eq!(json_schema["properties"]["CreatedDate"]["type"], json!(["string", "null"]));
+    assert_eq!(json_schema["properties"]["CreatedDate"]["format"], "date-time");
+    assert!(json_schema["properties"].get("Website").is_none());
+
+    let contact = fixtures::describe("Contact").field("Id", FieldType::Id).build();
+    let both    = [desc, contact];
+    assert_eq!(generate(&both, SchemaFormat::Avro, &filter).as_array().map(Vec::len), Some(2));
+    assert_eq!(generate(&both, SchemaFormat::JsonSchema, &filter)["$defs"]["Contact"]["title"], "Contact");
+    assert_eq!(generate(&both[..1], SchemaFormat::Avro, &filter)["name"], "Account");
+    assert_eq!("json-schema".parse::<SchemaFormat>().unwrap(), SchemaFormat::JsonSchema);
+  }
+}
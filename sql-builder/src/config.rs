@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use sql_builder::filter::FieldFilter;
+
+/// Looked for in the working directory when there's no `--config`.
+pub const DEFAULT_PATH: &str = "sf-etl.toml";
+
+/// Settings read from a TOML file, so they don't have to be passed on every run; command line flags win over all of it.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+  pub credentials:    Credentials,
+  pub objects:        Vec<String>,
+  pub dialect:        Option<String>,
+  pub output:         Option<PathBuf>,
+  pub mapping_output: Option<PathBuf>,
+  pub upsert_output:  Option<PathBuf>,
+  pub type_overrides: Option<PathBuf>,
+  pub state_file:     Option<PathBuf>,
+  pub token_store:    Option<PathBuf>,
+  pub fields:         Fields,
+  /// When `daemon` syncs each object, e.g. `Account = "*/15 * * * *"`
+  pub schedules:      BTreeMap<String, String>
+}
+
+/// Everything but the login endpoint can be a reference to a secret instead, e.g. `client_secret = "secret://aws/sf-prod/client_secret"`
+/// (see `secrets::SecretRef`).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Credentials {
+  pub client_id:      Option<String>,
+  pub client_secret:  Option<String>,
+  pub login_endpoint: Option<String>,
+  pub username:       Option<String>,
+  pub password:       Option<String>,
+  pub refresh_token:  Option<String>
+}
+
+/// Same as the `FieldFilter` knobs.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Fields {
+  pub include:         Vec<String>,
+  pub exclude:         Vec<String>,
+  pub skip_custom:     bool,
+  pub skip_calculated: bool,
+  pub skip_encrypted:  bool,
+  pub custom_only:     bool
+}
+
+impl Config {
+  /// Reads `path`, or `sf-etl.toml` if there is one; no file at all is the same as an empty one.
+  pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+    let path = match path {
+      Some(path)                                => path,
+      None if Path::new(DEFAULT_PATH).is_file() => Path::new(DEFAULT_PATH),
+      None                                      => return Ok(Config::default())
+    };
+
+    let contents = std::fs::read_to_string(path).map_err(|err| anyhow::anyhow!("cannot read {}: {}", path.display(), err))?;
+    toml::from_str(&contents).map_err(|err| anyhow::anyhow!("invalid config {}: {}", path.display(), err))
+  }
+}
+
+impl Fields {
+  pub fn filter(&self) -> FieldFilter {
+    let filter = self.include.iter().fold(FieldFilter::new(), |filter, pattern| filter.include(pattern));
+    self.exclude
+      .iter()
+      .fold(filter, |filter, pattern| filter.exclude(pattern))
+      .skip_custom(self.skip_custom)
+      .skip_calculated(self.skip_calculated)
+      .skip_encrypted(self.skip_encrypted)
+      .custom_only(self.custom_only)
+  }
+}
@@ -0,0 +1,1252 @@
+//! Turns Salesforce describes into tables, the same way `sf-sql` does.
+
+use oxidized_force::response::{DescribeResponse, Field, FieldType};
+
+use crate::filter::FieldFilter;
+use crate::formula;
+use crate::overrides::TypeOverrides;
+use crate::sql::*;
+
+/// Knobs for turning a describe into a table.
+#[derive(Debug, Default)]
+pub struct Mapping {
+  pub uuid_key:                 Option<String>,
+  pub serial_key:               Option<String>,
+  pub naive_timestamps:         bool,
+  pub picklist_checks:          bool,
+  pub postgis:                  bool,
+  pub citext:                   bool,
+  pub foreign_key_actions:      bool,
+  pub deferrable:               bool,
+  pub collation:                Option<String>,
+  pub computed_formulas:        bool,
+  pub comments:                 bool,
+  /// Multi-select picklists get a table of their own (see `junction_tables`) instead of an array column
+  pub normalize_multipicklists: bool,
+  pub dialect:                  Dialect,
+  pub identifiers:              Identifiers,
+  pub naming:                   Naming,
+  pub filter:                   FieldFilter,
+  /// Applied on top of the default types, before per column overrides like `--type`
+  pub type_overrides:           TypeOverrides,
+  /// Schema every table goes in
+  pub schema:                   Option<String>,
+  pub table_prefix:             String,
+  pub table_suffix:             String,
+  pub lowercase_tables:         bool
+}
+
+impl Mapping {
+  /// Table name for an object (`Account` -> `account_raw`), which foreign keys to it point at.
+  /// `table_from_describe` takes its name as-is, so callers should run it through here.
+  pub fn table_name(&self, object: &str) -> String {
+    let name = format!("{}{}{}", self.table_prefix, object, self.table_suffix);
+    match self.lowercase_tables {
+      true  => name.to_lowercase(),
+      false => name
+    }
+  }
+
+  fn new_table(&self, name: String) -> Table {
+    let mut table = match self.schema.as_ref() {
+      Some(schema) => Table::new_in(schema.as_str(), name),
+      None         => Table::new(name)
+    };
+    table.identifiers(self.identifiers.clone()).naming(self.naming.clone());
+    table
+  }
+
+  /// Renders the column for a field, for use in check and generated column expressions.
+  pub fn ident(&self, field: &str) -> String {
+    self.identifiers.render_for(self.dialect, &self.naming.apply(field))
+  }
+}
+
+pub fn table_from_describe<N>(name: N, desc: &DescribeResponse, mapping: &Mapping) -> Table
+where N: Into<String> {
+  let mut table = mapping.new_table(name.into());
+
+  if mapping.comments && !desc.label.is_empty() {
+    table.comment(desc.label.as_str());
+  }
+
+  // Create columns for all of the object fields (that make it past the filter)
+  for field in desc.fields.iter().filter(|field| mapping.filter.allows(field)) {
+    if mapping.normalize_multipicklists && field.field_type == FieldType::MultiPicklist {
+      continue;
+    }
+
+    let mut column = column_from_field(field, mapping)
+      .nullable(field.nillable)
+      .unique(field.unique);
+
+    // A surrogate key takes over as primary key; the Salesforce Id just stays unique
+    if (mapping.uuid_key.is_some() || mapping.serial_key.is_some()) && field.field_type == FieldType::Id {
+      column = column.primary(false).unique(true);
+    }
+
+    if mapping.picklist_checks && field.field_type == FieldType::Picklist {
+      if let Some(expr) = picklist_check(field, &field.name, mapping) {
+        column = column.check(expr);
+      }
+    }
+
+    // Bulk results only carry the latitude/longitude components, so the point gets computed from those
+    if mapping.postgis && field.field_type == FieldType::Location {
+      if let Some(expr) = location_point(field, desc, mapping) {
+        column = column.generated(expr).nullable(true);
+      }
+    }
+
+    if mapping.computed_formulas {
+      if let Some(expr) = computed_formula(field, desc, mapping) {
+        column = column.generated(expr).nullable(true);
+      }
+    }
+
+    // Case-insensitive columns come with a collation of their own
+    if let Some(ref collation) = mapping.collation {
+      if column.is_text() && !column.is_citext() {
+        column = column.collate(collation.as_str());
+      }
+    }
+
+    if mapping.comments && !field.label.is_empty() {
+      column = column.comment(field.label.as_str());
+    }
+
+    if let Some(sql) = mapping.type_overrides.sql_type(&desc.name, field) {
+      column = column.sql_type(sql);
+    }
+
+    table.add_column(&field.name, column);
+  }
+
+  if let Some(ref key) = mapping.uuid_key {
+    let column = uuid().primary(true);
+    table.add_column(key, match mapping.dialect.uuid_default() {
+      Some(sql) => column.default(WrappedDefault::Custom(sql)),
+      None      => column
+    });
+  }
+
+  if let Some(ref key) = mapping.serial_key {
+    table.add_column(key, bigint().increments(true).primary(true));
+  }
+  table
+}
+
+/// One `{table}_{field}_values` table per multi-select picklist (with `normalize_multipicklists`), holding a row per
+/// selected value; rows get deleted along with their parent.
+pub fn junction_tables<N>(name: N, desc: &DescribeResponse, mapping: &Mapping) -> Vec<Table>
+where N: Into<String> {
+  if !mapping.normalize_multipicklists {
+    return Vec::new();
+  }
+
+  let parent = name.into();
+  desc.fields
+    .iter()
+    .filter(|field| field.field_type == FieldType::MultiPicklist && mapping.filter.allows(field))
+    .map(|field| {
+      let mut table = mapping.new_table(format!("{}_{}_values", parent, field.name));
+
+      let mut value = varchar(Some(255));
+      if mapping.picklist_checks {
+        if let Some(expr) = picklist_check(field, "Value", mapping) {
+          value = value.check(expr);
+        }
+      }
+
+      table
+        .add_column("ParentId", foreign(parent.as_str(), vec!["Id"]).on_delete(ReferentialAction::Cascade).deferrable(mapping.deferrable))
+        .add_column("Value", value)
+        .primary_key(&["ParentId", "Value"]);
+      table
+    })
+    .collect()
+}
+
+/// Companion view named `{table}_view`, with field labels for column names and formulas worked out on the fly
+/// (where they're simple enough, see `formula`) rather than read from the copied values.
+/// Labels that come up more than once (ignoring case) fall back to the column name.
+pub fn view_from_describe(table: &Table, desc: &DescribeResponse, mapping: &Mapping) -> View {
+  let mut view    = table.view(format!("{}_view", table.name()));
+  let mut aliases = Vec::new();
+
+  for (field, column) in table.field_columns() {
+    let field = desc.field(field);
+    let alias = match field.map(|f| f.label.as_str()) {
+      Some(label) if !label.is_empty() && !aliases.contains(&label.to_lowercase()) => label.to_string(),
+      _                                                                            => column.clone()
+    };
+    aliases.push(alias.to_lowercase());
+
+    // Generated columns already hold the computed value
+    match field.filter(|_| !mapping.computed_formulas).and_then(|f| computed_formula(f, desc, mapping)) {
+      Some(expr) => view.expression(expr, alias),
+      None       => view.column(column.as_str(), alias)
+    };
+  }
+  view
+}
+
+/// Builds an `IN (...)` restriction on `column` from the active picklist values, if there are any.
+fn picklist_check(field: &Field, column: &str, mapping: &Mapping) -> Option<String> {
+  let values = field.picklist_values
+    .iter()
+    .filter(|value| value.active)
+    .map(|value| format!("'{}'", value.value.replace('\'', "''")))
+    .collect::<Vec<_>>();
+
+  match values.is_empty() {
+    true  => None,
+    false => Some(format!("{} IN ({})", mapping.ident(column), values.join(", ")))
+  }
+}
+
+/// Builds a point expression out of the latitude/longitude components of a location field.
+fn location_point(field: &Field, desc: &DescribeResponse, mapping: &Mapping) -> Option<String> {
+  let components = desc.components(&field.name);
+  let component  = |suffix: &str| {
+    components.iter().find(|f| f.name.ends_with(suffix) && mapping.filter.allows(f)).map(|f| f.name.clone())
+  };
+
+  let latitude  = component("Latitude__s").or_else(|| component("Latitude"))?;
+  let longitude = component("Longitude__s").or_else(|| component("Longitude"))?;
+  Some(format!("ST_SetSRID(ST_MakePoint({}, {}), 4326)::geography", mapping.ident(&longitude), mapping.ident(&latitude)))
+}
+
+/// Picks the `ON DELETE` action matching what Salesforce does when the parent record goes away.
+fn delete_action(field: &Field) -> ReferentialAction {
+  match (field.cascade_delete, field.restricted_delete, field.nillable) {
+    (true, _, _)  => ReferentialAction::Cascade,
+    (_, true, _)  => ReferentialAction::Restrict,
+    (_, _, true)  => ReferentialAction::SetNull,
+    (_, _, false) => ReferentialAction::NoAction
+  }
+}
+
+/// Generated column expression for a formula field, as long as it only does simple math on other (plain) fields.
+fn computed_formula(field: &Field, desc: &DescribeResponse, mapping: &Mapping) -> Option<String> {
+  let source = field.calculated_formula.as_ref()?;
+
+  // Generated columns can't reference each other, so only plain fields are fair game
+  formula::to_sql(source, mapping.dialect, |name| {
+    desc.field(name)
+      .filter(|f| f.calculated_formula.is_none() && !desc.is_compound(&f.name) && mapping.filter.allows(f))
+      .map(|f| mapping.ident(&f.name))
+  })
+}
+
+fn column_from_field(field: &Field, mapping: &Mapping) -> Type {
+  use FieldType::*;
+
+  match &field.field_type {
+    MultiPicklist => array(&varchar(None)),
+    // Polymorphic lookups (and ones that don't say what they point at) can't be foreign keys
+    Reference     => match field.reference_to.as_slice() {
+      [object] => {
+        let column = foreign(mapping.table_name(object), vec!["Id"]).deferrable(mapping.deferrable);
+        match mapping.foreign_key_actions {
+          true  => column.on_delete(delete_action(field)),
+          false => column
+        }
+      },
+      _ => varchar(Some(field.length as usize))
+    },
+    Id            => varchar(None).primary(true),
+    AnyType       => jsonb(),
+    Location      => match mapping.postgis {
+      true  => point(),
+      false => varchar(Some(field.length as usize))
+    },
+    Base64        => binary(),
+    Boolean       => boolean(),
+    Time          => time(),
+    Date          => date(),
+    DateTime      => match mapping.naive_timestamps {
+      true  => datetime(),
+      false => datetime_tz()
+    },
+    Currency      => numeric(field.precision, field.scale),
+    Percent       => numeric(field.precision, field.scale),
+    Double        => match field.precision {
+      0 => double(),
+      _ => numeric(field.precision, field.scale)
+    },
+    // Anything up to 4 digits fits in 2 bytes
+    Int           => match field.digits {
+      1..=4 => smallint(),
+      _     => integer()
+    },
+    Long          => bigint(),
+    Email | Picklist | String if mapping.citext => citext(),
+    _             => varchar(Some(field.length as usize))
+  }
+}
+
+/// A table with all the default mappings, named after the object.
+impl From<&DescribeResponse> for Table {
+  fn from(desc: &DescribeResponse) -> Self {
+    table_from_describe(desc.name.as_str(), desc, &Mapping::default())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use oxidized_force::response::PicklistValue;
+  use oxidized_force::test_util::{fixtures, DescribeGenerator};
+
+  #[test]
+  fn numeric_columns() {
+    let mut amount = fixtures::field("Amount", FieldType::Currency);
+    amount.precision = 18;
+    amount.scale     = 2;
+
+    let mut ratio = fixtures::field("Ratio__c", FieldType::Double);
+    ratio.precision = 10;
+    ratio.scale     = 4;
+
+    let desc = fixtures::describe("Opportunity")
+      .with_field(amount)
+      .with_field(ratio)
+      .field("Legacy__c", FieldType::Double)
+      .field("Discount__c", FieldType::Percent)
+      .build();
+
+    let sql = table_from_describe("Opportunity", &desc, &Mapping::default()).generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Opportunity\" (\n\
+      \"Amount\" NUMERIC(18, 2),\n\
+      \"Ratio__c\" NUMERIC(10, 4),\n\
+      \"Legacy__c\" DOUBLE PRECISION,\n\
+      \"Discount__c\" NUMERIC\n\
+      )");
+  }
+
+  #[test]
+  fn integer_columns() {
+    let mut rank = fixtures::field("Rank__c", FieldType::Int);
+    rank.digits = 3;
+
+    let mut visits = fixtures::field("Visits__c", FieldType::Int);
+    visits.digits = 9;
+
+    let desc = fixtures::describe("Account")
+      .with_field(rank)
+      .with_field(visits)
+      .field("Legacy__c", FieldType::Int)
+      .build();
+
+    let sql = table_from_describe("Account", &desc, &Mapping::default()).generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Account\" (\n\
+      \"Rank__c\" SMALLINT,\n\
+      \"Visits__c\" INTEGER,\n\
+      \"Legacy__c\" INTEGER\n\
+      )");
+  }
+
+  #[test]
+  fn uuid_surrogate_key() {
+    let desc    = fixtures::describe("Account").field("Id", FieldType::Id).build();
+    let mapping = Mapping { uuid_key: Some("uuid".to_string()), ..Default::default() };
+    let sql     = table_from_describe("Account", &desc, &mapping).generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Account\" (\n\
+      \"Id\" VARCHAR NOT NULL UNIQUE,\n\
+      \"uuid\" UUID PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL\n\
+      )");
+
+    let mapping = Mapping { uuid_key: Some("uuid".to_string()), dialect: Dialect::MySql, ..Default::default() };
+    let sql     = table_from_describe("Account", &desc, &mapping).generate::<MySql>();
+    assert_eq!(sql, "\
+      CREATE TABLE `Account` (\n\
+      `Id` VARCHAR(255) NOT NULL UNIQUE,\n\
+      `uuid` CHAR(36) NOT NULL DEFAULT (UUID()) PRIMARY KEY\n\
+      ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4");
+    assert_eq!(Dialect::Mssql.uuid_default(), Some("NEWID()"));
+    assert_eq!(Dialect::Sqlite.uuid_default(), None);
+    assert_eq!(Dialect::Redshift.uuid_default(), None);
+  }
+
+  #[test]
+  fn serial_surrogate_key() {
+    let desc    = fixtures::describe("Account").field("Id", FieldType::Id).build();
+    let mapping = Mapping { serial_key: Some("row_id".to_string()), ..Default::default() };
+    let sql     = table_from_describe("Account", &desc, &mapping).generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Account\" (\n\
+      \"Id\" VARCHAR NOT NULL UNIQUE,\n\
+      \"row_id\" BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY NOT NULL\n\
+      )");
+
+    let mut table = Table::new("Widget");
+    table.add_column("code", varchar(Some(10)).increments(true));
+    table.add_column("seq", integer().increments(true).default(WrappedDefault::Integer(1)));
+    let sql = table.generate::<Pg>();
+    assert_eq!(sql, "CREATE TABLE \"Widget\" (\n\"code\" VARCHAR(10) NOT NULL,\n\"seq\" INTEGER DEFAULT 1 NOT NULL\n)");
+  }
+
+  #[test]
+  fn timestamp_columns() {
+    let desc  = fixtures::describe("Case").field("ClosedDate", FieldType::DateTime).build();
+    let aware = table_from_describe("Case", &desc, &Mapping::default()).generate::<Pg>();
+    assert_eq!(aware, "CREATE TABLE \"Case\" (\n\"ClosedDate\" TIMESTAMPTZ\n)");
+    let naive = table_from_describe("Case", &desc, &Mapping { naive_timestamps: true, ..Default::default() }).generate::<Pg>();
+    assert_eq!(naive, "CREATE TABLE \"Case\" (\n\"ClosedDate\" TIMESTAMP\n)");
+  }
+
+  #[test]
+  fn binary_columns() {
+    let desc = fixtures::describe("Attachment").field("Body", FieldType::Base64).build();
+    let sql  = table_from_describe("Attachment", &desc, &Mapping::default()).generate::<Pg>();
+    assert_eq!(sql, "CREATE TABLE \"Attachment\" (\n\"Body\" BYTEA\n)");
+  }
+
+  #[test]
+  fn picklist_checks() {
+    let value = |value: &str, active| PicklistValue { value: value.to_string(), label: None, active, default_value: false };
+
+    let mut status = fixtures::field("Status", FieldType::Picklist);
+    status.length          = 40;
+    status.picklist_values = vec![value("New", true), value("Won't Fix", true), value("Legacy", false)];
+
+    let desc    = fixtures::describe("Case").with_field(status).build();
+    let mapping = Mapping { picklist_checks: true, ..Default::default() };
+
+    let checked = table_from_describe("Case", &desc, &mapping).generate::<Pg>();
+    assert_eq!(checked, "\
+      CREATE TABLE \"Case\" (\n\
+      \"Status\" VARCHAR(40) CHECK (\"Status\" IN ('New', 'Won''t Fix'))\n\
+      )");
+
+    let plain = table_from_describe("Case", &desc, &Mapping::default()).generate::<Pg>();
+    assert_eq!(plain, "CREATE TABLE \"Case\" (\n\"Status\" VARCHAR(40)\n)");
+
+    let mut table = Table::new("Case");
+    table.add_column("Status", varchar(Some(40)).nullable(true)).check("char_length(\"Status\") > 0");
+    let sql = table.generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Case\" (\n\
+      \"Status\" VARCHAR(40),\n\
+      CONSTRAINT \"Case_check1\" CHECK (char_length(\"Status\") > 0)\n\
+      )");
+  }
+
+  #[test]
+  fn composite_primary_key() {
+    let desc = fixtures::describe("AccountShare")
+      .field("Id", FieldType::Id)
+      .reference("AccountId")
+      .field("UserOrGroupId", FieldType::Id)
+      .build();
+
+    let mut table = table_from_describe("AccountShare", &desc, &Mapping::default());
+    let sql = table.primary_key(&["AccountId", "UserOrGroupId"]).generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"AccountShare\" (\n\
+      \"Id\" VARCHAR NOT NULL UNIQUE,\n\
+      \"AccountId\" VARCHAR REFERENCES \"Account\" (\"Id\"),\n\
+      \"UserOrGroupId\" VARCHAR NOT NULL UNIQUE,\n\
+      CONSTRAINT \"AccountShare_pkey\" PRIMARY KEY (\"AccountId\", \"UserOrGroupId\")\n\
+      )");
+  }
+
+  #[test]
+  fn composite_unique_keys() {
+    let desc = fixtures::describe("Contact")
+      .field("Id", FieldType::Id)
+      .reference("AccountId")
+      .field("ExternalKey__c", FieldType::String)
+      .build();
+
+    let mut table = table_from_describe("Contact", &desc, &Mapping::default());
+    table.unique(&["AccountId", "ExternalKey__c"]);
+    assert!(table.has_column("ExternalKey__c") && !table.has_column("Email"));
+
+    // Neither column is unique by itself
+    let sql = table.generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Contact\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL UNIQUE,\n\
+      \"AccountId\" VARCHAR REFERENCES \"Account\" (\"Id\"),\n\
+      \"ExternalKey__c\" VARCHAR(255),\n\
+      CONSTRAINT \"Contact_AccountId_ExternalKey__c_key\" UNIQUE (\"AccountId\", \"ExternalKey__c\")\n\
+      )");
+    assert_eq!(table.generate::<MySql>(), "\
+      CREATE TABLE `Contact` (\n\
+      `Id` VARCHAR(255) NOT NULL PRIMARY KEY UNIQUE,\n\
+      `AccountId` VARCHAR(255) NULL,\n\
+      `ExternalKey__c` VARCHAR(255) NULL,\n\
+      CONSTRAINT `Contact_AccountId_fkey` FOREIGN KEY (`AccountId`) REFERENCES `Account` (`Id`),\n\
+      CONSTRAINT `Contact_AccountId_ExternalKey__c_key` UNIQUE (`AccountId`, `ExternalKey__c`)\n\
+      ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4");
+    assert_eq!(table.generate::<Mssql>(), "\
+      CREATE TABLE [Contact] (\n\
+      [Id] NVARCHAR(255) NOT NULL PRIMARY KEY UNIQUE,\n\
+      [AccountId] NVARCHAR(255) NULL REFERENCES [Account] ([Id]),\n\
+      [ExternalKey__c] NVARCHAR(255) NULL,\n\
+      CONSTRAINT [Contact_AccountId_ExternalKey__c_key] UNIQUE ([AccountId], [ExternalKey__c])\n\
+      )");
+  }
+
+  #[test]
+  fn postgis_locations() {
+    let component = |name: &str| {
+      let mut field = fixtures::field(name, FieldType::Double);
+      field.compound_field_name = Some("Site__c".to_string());
+      field
+    };
+
+    let desc = fixtures::describe("Store__c")
+      .field("Site__c", FieldType::Location)
+      .with_field(component("Site__Latitude__s"))
+      .with_field(component("Site__Longitude__s"))
+      .build();
+
+    let mapping = Mapping { postgis: true, ..Default::default() };
+    let sql     = table_from_describe("Store__c", &desc, &mapping).generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Store__c\" (\n\
+      \"Site__c\" GEOGRAPHY(POINT, 4326) GENERATED ALWAYS AS (ST_SetSRID(ST_MakePoint(\"Site__Longitude__s\", \"Site__Latitude__s\"), 4326)::geography) STORED,\n\
+      \"Site__Latitude__s\" DOUBLE PRECISION,\n\
+      \"Site__Longitude__s\" DOUBLE PRECISION\n\
+      )");
+
+    let plain = table_from_describe("Store__c", &desc, &Mapping::default()).generate::<Pg>();
+    assert_eq!(plain, "\
+      CREATE TABLE \"Store__c\" (\n\
+      \"Site__c\" VARCHAR,\n\
+      \"Site__Latitude__s\" DOUBLE PRECISION,\n\
+      \"Site__Longitude__s\" DOUBLE PRECISION\n\
+      )");
+  }
+
+  #[test]
+  fn citext_columns() {
+    let desc = fixtures::describe("Contact")
+      .field("Email", FieldType::Email)
+      .field("LeadSource", FieldType::Picklist)
+      .field("Title", FieldType::String)
+      .field("Phone", FieldType::Phone)
+      .build();
+
+    let mapping = Mapping { citext: true, ..Default::default() };
+    let sql     = table_from_describe("Contact", &desc, &mapping).generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Contact\" (\n\
+      \"Email\" CITEXT,\n\
+      \"LeadSource\" CITEXT,\n\
+      \"Title\" CITEXT,\n\
+      \"Phone\" VARCHAR\n\
+      )");
+
+    // Other dialects get a case-insensitive collation instead, which `--collation` leaves alone
+    let mapping = Mapping { citext: true, collation: Some("utf8mb4_bin".to_string()), ..Default::default() };
+    let table   = table_from_describe("Contact", &desc, &mapping);
+    assert_eq!(table.generate::<MySql>(), "\
+      CREATE TABLE `Contact` (\n\
+      `Email` VARCHAR(255) COLLATE utf8mb4_0900_ai_ci NULL,\n\
+      `LeadSource` VARCHAR(255) COLLATE utf8mb4_0900_ai_ci NULL,\n\
+      `Title` VARCHAR(255) COLLATE utf8mb4_0900_ai_ci NULL,\n\
+      `Phone` VARCHAR(255) COLLATE utf8mb4_bin NULL\n\
+      ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4");
+    assert_eq!(table.generate::<Snowflake>(), "\
+      CREATE TABLE \"Contact\" (\n\
+      \"Email\" VARCHAR COLLATE 'en-ci',\n\
+      \"LeadSource\" VARCHAR COLLATE 'en-ci',\n\
+      \"Title\" VARCHAR COLLATE 'en-ci',\n\
+      \"Phone\" VARCHAR COLLATE 'utf8mb4_bin'\n\
+      )");
+  }
+
+  #[test]
+  fn foreign_key_actions() {
+    let mut master = fixtures::field("Order__c", FieldType::Reference);
+    master.relationship_name = Some("Order__r".to_string());
+    master.reference_to      = vec!["Order__c".to_string()];
+    master.cascade_delete    = true;
+
+    // Keys point at the object, not the relationship
+    let mut owner = fixtures::field("OwnerId", FieldType::Reference);
+    owner.relationship_name = Some("Owner".to_string());
+    owner.reference_to      = vec!["User".to_string()];
+    let mut who = fixtures::field("WhoId", FieldType::Reference);
+    who.relationship_name = Some("Who".to_string());
+    who.reference_to      = vec!["Contact".to_string(), "Lead".to_string()];
+
+    let desc = fixtures::describe("OrderLine__c")
+      .with_field(master)
+      .reference("Product__c")
+      .with_field(owner)
+      .with_field(who)
+      .build();
+
+    let mapping = Mapping { foreign_key_actions: true, ..Default::default() };
+    let sql     = table_from_describe("OrderLine__c", &desc, &mapping).generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"OrderLine__c\" (\n\
+      \"Order__c\" VARCHAR REFERENCES \"Order__c\" (\"Id\") ON DELETE CASCADE,\n\
+      \"Product__c\" VARCHAR REFERENCES \"Product__c\" (\"Id\") ON DELETE SET NULL,\n\
+      \"OwnerId\" VARCHAR REFERENCES \"User\" (\"Id\") ON DELETE SET NULL,\n\
+      \"WhoId\" VARCHAR(18)\n\
+      )");
+
+    let mut table = Table::new("Contact");
+    table
+      .add_column("AccountId", varchar(None))
+      .foreign_key(&["AccountId"], "Account", &["Id"])
+      .on_delete(ReferentialAction::Cascade)
+      .on_update(ReferentialAction::Restrict);
+
+    let sql = table.generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Contact\" (\n\
+      \"AccountId\" VARCHAR NOT NULL,\n\
+      CONSTRAINT \"Contact_AccountId_fkey\" FOREIGN KEY (\"AccountId\") REFERENCES \"Account\" (\"Id\") ON DELETE CASCADE ON UPDATE RESTRICT\n\
+      )");
+  }
+
+  #[test]
+  fn deferrable_constraints() {
+    let desc    = fixtures::describe("Contact").reference("AccountId").build();
+    let mapping = Mapping { deferrable: true, ..Default::default() };
+    let sql     = table_from_describe("Contact", &desc, &mapping).generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Contact\" (\n\
+      \"AccountId\" VARCHAR REFERENCES \"Account\" (\"Id\") DEFERRABLE INITIALLY DEFERRED\n\
+      )");
+
+    let mut table = Table::new("Contact");
+    table
+      .add_column("Email", varchar(None).unique(true).deferrable(true))
+      .unique(&["Email"])
+      .deferrable()
+      .check("\"Email\" <> ''")
+      .deferrable();
+
+    let sql = table.generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Contact\" (\n\
+      \"Email\" VARCHAR NOT NULL UNIQUE DEFERRABLE INITIALLY DEFERRED,\n\
+      CONSTRAINT \"Contact_Email_key\" UNIQUE (\"Email\") DEFERRABLE INITIALLY DEFERRED,\n\
+      CONSTRAINT \"Contact_check1\" CHECK (\"Email\" <> '')\n\
+      )");
+  }
+
+  #[test]
+  fn type_overrides() {
+    let desc      = fixtures::describe("Opportunity").field("Amount", FieldType::Currency).build();
+    let mut table = table_from_describe("Opportunity", &desc, &Mapping::default());
+
+    assert!(table.override_type("Amount", "MONEY"));
+    assert!(!table.override_type("Missing__c", "MONEY"));
+    assert_eq!(table.generate::<Pg>(), "CREATE TABLE \"Opportunity\" (\n\"Amount\" MONEY\n)");
+  }
+
+  #[test]
+  fn collations() {
+    let desc = fixtures::describe("Account")
+      .field("Name", FieldType::String)
+      .field("NumberOfEmployees", FieldType::Int)
+      .build();
+
+    let mapping = Mapping { collation: Some("en-US-x-icu".to_string()), ..Default::default() };
+    let sql     = table_from_describe("Account", &desc, &mapping).generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Account\" (\n\
+      \"Name\" VARCHAR(255) COLLATE \"en-US-x-icu\",\n\
+      \"NumberOfEmployees\" INTEGER\n\
+      )");
+  }
+
+  #[test]
+  fn computed_formulas() {
+    let formula = |name: &str, field_type, source: &str| {
+      let mut field = fixtures::field(name, field_type);
+      field.calculated_formula = Some(source.to_string());
+      field
+    };
+
+    let desc = fixtures::describe("Opportunity")
+      .field("Amount", FieldType::Currency)
+      .field("Probability", FieldType::Percent)
+      .with_field(formula("Expected__c", FieldType::Currency, "Amount * Probability / 100"))
+      .with_field(formula("Rounded__c", FieldType::Currency, "ROUND(Expected__c, 0)"))
+      .build();
+
+    let mapping = Mapping { computed_formulas: true, ..Default::default() };
+    let sql     = table_from_describe("Opportunity", &desc, &mapping).generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Opportunity\" (\n\
+      \"Amount\" NUMERIC,\n\
+      \"Probability\" NUMERIC,\n\
+      \"Expected__c\" NUMERIC GENERATED ALWAYS AS (\"Amount\" * \"Probability\" / 100) STORED,\n\
+      \"Rounded__c\" NUMERIC\n\
+      )");
+  }
+
+  #[test]
+  fn mysql_tables() {
+    let desc = fixtures::describe("Contact")
+      .field("Id", FieldType::Id)
+      .reference("AccountId")
+      .field("Tags__c", FieldType::MultiPicklist)
+      .field("Active__c", FieldType::Boolean)
+      .build();
+
+    let mapping   = Mapping { serial_key: Some("row_id".to_string()), foreign_key_actions: true, ..Default::default() };
+    let mut table = table_from_describe("Contact", &desc, &mapping);
+    let sql       = table.option("ENGINE", "Aria").generate::<MySql>();
+    assert_eq!(sql, "\
+      CREATE TABLE `Contact` (\n\
+      `Id` VARCHAR(255) NOT NULL UNIQUE,\n\
+      `AccountId` VARCHAR(255) NULL,\n\
+      `Tags__c` JSON NULL,\n\
+      `Active__c` BOOLEAN NULL,\n\
+      `row_id` BIGINT NOT NULL AUTO_INCREMENT PRIMARY KEY,\n\
+      CONSTRAINT `Contact_AccountId_fkey` FOREIGN KEY (`AccountId`) REFERENCES `Account` (`Id`) ON DELETE SET NULL\n\
+      ) ENGINE=Aria DEFAULT CHARSET=utf8mb4");
+  }
+
+  #[test]
+  fn sqlite_tables() {
+    let desc = fixtures::describe("Contact")
+      .field("Id", FieldType::Id)
+      .reference("AccountId")
+      .field("Active__c", FieldType::Boolean)
+      .field("LastModifiedDate", FieldType::DateTime)
+      .field("Email", FieldType::Email)
+      .build();
+
+    let mapping   = Mapping { serial_key: Some("row_id".to_string()), citext: true, ..Default::default() };
+    let mut table = table_from_describe("Contact", &desc, &mapping);
+    table.add_column("Score__c", double().default(1.5)).add_column("Flag__c", boolean().default(true));
+
+    let sql = table.generate::<Sqlite>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Contact\" (\n\
+      \"Id\" TEXT NOT NULL UNIQUE,\n\
+      \"AccountId\" TEXT REFERENCES \"Account\" (\"Id\"),\n\
+      \"Active__c\" INTEGER,\n\
+      \"LastModifiedDate\" TEXT,\n\
+      \"Email\" TEXT COLLATE NOCASE,\n\
+      \"row_id\" INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,\n\
+      \"Score__c\" REAL DEFAULT 1.5 NOT NULL,\n\
+      \"Flag__c\" INTEGER DEFAULT 1 NOT NULL\n\
+      )");
+  }
+
+  #[test]
+  fn mssql_tables() {
+    let mut master = fixtures::field("AccountId", FieldType::Reference);
+    master.relationship_name = Some("Account".to_string());
+    master.reference_to      = vec!["Account".to_string()];
+    master.restricted_delete = true;
+
+    let mut formula = fixtures::field("Total__c", FieldType::Currency);
+    formula.calculated_formula = Some("Amount + 1".to_string());
+
+    let desc = fixtures::describe("Opportunity")
+      .field("Id", FieldType::Id)
+      .with_field(master)
+      .with_field(formula)
+      .field("Amount", FieldType::Currency)
+      .field("IsWon", FieldType::Boolean)
+      .field("Description", FieldType::TextArea)
+      .field("CloseDate", FieldType::DateTime)
+      .build();
+
+    let mapping = Mapping {
+      serial_key:          Some("row_id".to_string()),
+      foreign_key_actions: true,
+      computed_formulas:   true,
+      ..Default::default()
+    };
+
+    let sql = table_from_describe("Opportunity", &desc, &mapping).generate::<Mssql>();
+    assert_eq!(sql, "\
+      CREATE TABLE [Opportunity] (\n\
+      [Id] NVARCHAR(255) NOT NULL UNIQUE,\n\
+      [AccountId] NVARCHAR(255) NULL REFERENCES [Account] ([Id]) ON DELETE NO ACTION,\n\
+      [Total__c] AS (\"Amount\" + 1) PERSISTED,\n\
+      [Amount] DECIMAL(38, 18) NULL,\n\
+      [IsWon] BIT NULL,\n\
+      [Description] NVARCHAR(MAX) NULL,\n\
+      [CloseDate] DATETIMEOFFSET NULL,\n\
+      [row_id] BIGINT IDENTITY(1,1) NOT NULL PRIMARY KEY\n\
+      )");
+  }
+
+  #[test]
+  fn snowflake_tables() {
+    let mut amount = fixtures::field("Amount", FieldType::Currency);
+    amount.precision = 18;
+    amount.scale     = 2;
+
+    let mut stage = fixtures::field("StageName", FieldType::Picklist);
+    stage.picklist_values = vec![PicklistValue { value: "Won".to_string(), label: None, active: true, default_value: false }];
+
+    let desc = fixtures::describe("Opportunity")
+      .field("Id", FieldType::Id)
+      .reference("AccountId")
+      .with_field(amount)
+      .with_field(stage)
+      .field("Payload__c", FieldType::AnyType)
+      .field("CloseDate", FieldType::DateTime)
+      .build();
+
+    let mapping   = Mapping { picklist_checks: true, ..Default::default() };
+    let mut table = table_from_describe("Opportunity", &desc, &mapping);
+    let sql       = table.check("\"Amount\" > 0").generate::<Snowflake>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Opportunity\" (\n\
+      \"Id\" VARCHAR NOT NULL PRIMARY KEY UNIQUE,\n\
+      \"AccountId\" VARCHAR REFERENCES \"Account\" (\"Id\"),\n\
+      \"Amount\" NUMBER(18, 2),\n\
+      \"StageName\" VARCHAR,\n\
+      \"Payload__c\" VARIANT,\n\
+      \"CloseDate\" TIMESTAMP_TZ\n\
+      )");
+  }
+
+  #[test]
+  fn redshift_tables() {
+    let mut name = fixtures::field("Name", FieldType::String);
+    name.length = 80;
+
+    let desc = fixtures::describe("Account")
+      .field("Id", FieldType::Id)
+      .with_field(name)
+      .field("Description", FieldType::TextArea)
+      .field("Tags__c", FieldType::MultiPicklist)
+      .field("Payload__c", FieldType::AnyType)
+      .field("CreatedDate", FieldType::DateTime)
+      .build();
+
+    let mut table = table_from_describe("Account", &desc, &Mapping::default());
+    let sql = table
+      .option("DISTSTYLE", "KEY")
+      .option("DISTKEY", "Id")
+      .option("SORTKEY", "CreatedDate, Id")
+      .generate::<Redshift>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Account\" (\n\
+      \"Id\" VARCHAR(256) NOT NULL PRIMARY KEY UNIQUE,\n\
+      \"Name\" VARCHAR(320),\n\
+      \"Description\" VARCHAR(65535),\n\
+      \"Tags__c\" SUPER,\n\
+      \"Payload__c\" SUPER,\n\
+      \"CreatedDate\" TIMESTAMPTZ\n\
+      ) DISTSTYLE KEY DISTKEY(\"Id\") SORTKEY(\"CreatedDate\", \"Id\")");
+  }
+
+  #[test]
+  fn duckdb_tables() {
+    let desc = fixtures::describe("Contact")
+      .field("Id", FieldType::Id)
+      .reference("AccountId")
+      .field("Tags__c", FieldType::MultiPicklist)
+      .field("Photo__c", FieldType::Base64)
+      .field("Payload__c", FieldType::AnyType)
+      .build();
+
+    let mut table = table_from_describe("Contact", &desc, &Mapping::default());
+    table.add_column("Flags__c", array(&integer()).default(vec![1i64, 2]));
+
+    let sql = table.generate::<DuckDb>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Contact\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL UNIQUE,\n\
+      \"AccountId\" VARCHAR REFERENCES \"Account\" (\"Id\"),\n\
+      \"Tags__c\" VARCHAR[],\n\
+      \"Photo__c\" BLOB,\n\
+      \"Payload__c\" JSON,\n\
+      \"Flags__c\" INTEGER[] DEFAULT [1, 2] NOT NULL\n\
+      )");
+  }
+
+  #[test]
+  fn clickhouse_tables() {
+    let desc = fixtures::describe("Event")
+      .field("Id", FieldType::Id)
+      .reference("WhoId")
+      .field("Subject", FieldType::String)
+      .field("Tags__c", FieldType::MultiPicklist)
+      .field("ActivityDateTime", FieldType::DateTime)
+      .build();
+
+    let sql = table_from_describe("Event", &desc, &Mapping::default()).generate::<ClickHouse>();
+    assert_eq!(sql, "\
+      CREATE TABLE `Event` (\n\
+      `Id` String,\n\
+      `WhoId` Nullable(String),\n\
+      `Subject` Nullable(String),\n\
+      `Tags__c` Array(String),\n\
+      `ActivityDateTime` Nullable(DateTime64(3, 'UTC'))\n\
+      )\n\
+      ENGINE = MergeTree()\n\
+      ORDER BY (`Id`)");
+
+    let mut table = table_from_describe("Event", &desc, &Mapping::default());
+    let sql = table
+      .option("ENGINE", "ReplacingMergeTree(SystemModstamp)")
+      .option("PARTITION BY", "toYYYYMM(ActivityDateTime)")
+      .generate::<ClickHouse>();
+    assert_eq!(sql, "\
+      CREATE TABLE `Event` (\n\
+      `Id` String,\n\
+      `WhoId` Nullable(String),\n\
+      `Subject` Nullable(String),\n\
+      `Tags__c` Array(String),\n\
+      `ActivityDateTime` Nullable(DateTime64(3, 'UTC'))\n\
+      )\n\
+      ENGINE = ReplacingMergeTree(SystemModstamp)\n\
+      ORDER BY (`Id`)\n\
+      PARTITION BY toYYYYMM(ActivityDateTime)");
+  }
+
+  #[test]
+  fn table_naming() {
+    let mapping = Mapping {
+      schema:                   Some("sfdc".to_string()),
+      table_suffix:             "_raw".to_string(),
+      lowercase_tables:         true,
+      normalize_multipicklists: true,
+      ..Default::default()
+    };
+    assert_eq!(mapping.table_name("Account"), "account_raw");
+    assert_eq!(Mapping { table_prefix: "sf_".to_string(), ..Default::default() }.table_name("Invoice__c"), "sf_Invoice__c");
+
+    let desc = fixtures::describe("Contact")
+      .field("Id", FieldType::Id)
+      .reference("AccountId")
+      .field("Tags__c", FieldType::MultiPicklist)
+      .build();
+    let table = table_from_describe(mapping.table_name("Contact"), &desc, &mapping);
+
+    // Columns keep their names, references follow the tables
+    let sql = table.generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"sfdc\".\"contact_raw\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL UNIQUE,\n\
+      \"AccountId\" VARCHAR REFERENCES \"sfdc\".\"account_raw\" (\"Id\")\n\
+      )");
+
+    let junctions = junction_tables(table.name(), &desc, &mapping);
+    assert_eq!(junctions[0].name(), "contact_raw_Tags__c_values");
+    assert_eq!(junctions[0].generate::<Pg>(), "\
+      CREATE TABLE \"sfdc\".\"contact_raw_Tags__c_values\" (\n\
+      \"ParentId\" VARCHAR REFERENCES \"sfdc\".\"contact_raw\" (\"Id\") ON DELETE CASCADE NOT NULL,\n\
+      \"Value\" VARCHAR(255) NOT NULL,\n\
+      CONSTRAINT \"contact_raw_Tags__c_values_pkey\" PRIMARY KEY (\"ParentId\", \"Value\")\n\
+      )");
+  }
+
+  #[test]
+  fn comments_and_grants() {
+    let mut desc = fixtures::describe("Account")
+      .field("Id", FieldType::Id)
+      .field("Owner_s_Name__c", FieldType::String)
+      .build();
+    desc.label           = "Account".to_string();
+    desc.fields[1].label = "Owner's Name".to_string();
+
+    let mut table = table_from_describe("Account", &desc, &Mapping { comments: true, ..Default::default() });
+    table.grant("SELECT", "analyst").owner("etl");
+
+    let sql = table.generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Account\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL UNIQUE,\n\
+      \"Owner_s_Name__c\" VARCHAR(255)\n\
+      );\n\
+      \n\
+      COMMENT ON TABLE \"Account\" IS 'Account';\n\
+      COMMENT ON COLUMN \"Account\".\"Id\" IS 'Id';\n\
+      COMMENT ON COLUMN \"Account\".\"Owner_s_Name__c\" IS 'Owner''s Name';\n\
+      GRANT SELECT ON TABLE \"Account\" TO analyst;\n\
+      ALTER TABLE \"Account\" OWNER TO etl");
+
+    // MySQL keeps column comments inline
+    let sql = table.generate::<MySql>();
+    assert_eq!(sql, "\
+      CREATE TABLE `Account` (\n\
+      `Id` VARCHAR(255) NOT NULL PRIMARY KEY UNIQUE COMMENT 'Id',\n\
+      `Owner_s_Name__c` VARCHAR(255) NULL COMMENT 'Owner''s Name'\n\
+      ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;\n\
+      \n\
+      ALTER TABLE `Account` COMMENT = 'Account';\n\
+      GRANT SELECT ON TABLE `Account` TO analyst");
+
+    assert_eq!(table.generate::<Snowflake>(), "\
+      CREATE TABLE \"Account\" (\n\
+      \"Id\" VARCHAR NOT NULL PRIMARY KEY UNIQUE,\n\
+      \"Owner_s_Name__c\" VARCHAR(255)\n\
+      );\n\
+      \n\
+      COMMENT ON TABLE \"Account\" IS 'Account';\n\
+      COMMENT ON COLUMN \"Account\".\"Id\" IS 'Id';\n\
+      COMMENT ON COLUMN \"Account\".\"Owner_s_Name__c\" IS 'Owner''s Name';\n\
+      GRANT SELECT ON TABLE \"Account\" TO ROLE analyst;\n\
+      GRANT OWNERSHIP ON TABLE \"Account\" TO ROLE etl COPY CURRENT GRANTS");
+    assert_eq!(table.generate::<Sqlite>(), "\
+      CREATE TABLE \"Account\" (\n\
+      \"Id\" TEXT PRIMARY KEY NOT NULL UNIQUE,\n\
+      \"Owner_s_Name__c\" TEXT\n\
+      )");
+
+    // Nothing gets added without asking
+    let plain = table_from_describe("Account", &desc, &Mapping::default()).generate::<Pg>();
+    assert_eq!(plain, "\
+      CREATE TABLE \"Account\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL UNIQUE,\n\
+      \"Owner_s_Name__c\" VARCHAR(255)\n\
+      )");
+  }
+
+  #[test]
+  fn tables_from_describes() {
+    let desc  = fixtures::describe("Contact").field("Id", FieldType::Id).reference("AccountId").build();
+    let table = Table::from(&desc);
+
+    assert_eq!(table.name(), "Contact");
+    assert_eq!(table.generate::<Pg>(), table_from_describe("Contact", &desc, &Mapping::default()).generate::<Pg>());
+
+    // References that don't say what they point at end up as plain text
+    let desc = fixtures::describe("Note").field("Id", FieldType::Id).field("ParentId", FieldType::Reference).build();
+    assert_eq!(Table::from(&desc).generate::<Pg>(), "\
+      CREATE TABLE \"Note\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL UNIQUE,\n\
+      \"ParentId\" VARCHAR(18)\n\
+      )");
+  }
+
+  #[test]
+  fn views() {
+    let mut expected = fixtures::field("Expected__c", FieldType::Currency);
+    expected.label              = "Expected Revenue".to_string();
+    expected.calculated_formula = Some("Amount * Probability / 100".to_string());
+    let mut rounded = fixtures::field("Rounded__c", FieldType::Currency);
+    rounded.calculated_formula = Some("ROUND(Amount, 0)".to_string());
+
+    let mut desc = fixtures::describe("Opportunity")
+      .field("Id", FieldType::Id)
+      .field("Amount", FieldType::Currency)
+      .field("Probability", FieldType::Percent)
+      .field("Amount__c", FieldType::Currency)
+      .with_field(expected)
+      .with_field(rounded)
+      .build();
+    desc.fields[0].label = "Opportunity ID".to_string();
+    desc.fields[3].label = "amount".to_string();
+
+    let table = table_from_describe("Opportunity", &desc, &Mapping::default());
+    let view  = view_from_describe(&table, &desc, &Mapping::default());
+    assert_eq!(view.generate::<Pg>(), "\
+      CREATE OR REPLACE VIEW \"Opportunity_view\" AS\n\
+      SELECT\n\
+      \"Id\" AS \"Opportunity ID\",\n\
+      \"Amount\" AS \"Amount\",\n\
+      \"Probability\" AS \"Probability\",\n\
+      \"Amount__c\" AS \"Amount__c\",\n\
+      (\"Amount\" * \"Probability\" / 100) AS \"Expected Revenue\",\n\
+      \"Rounded__c\" AS \"Rounded\"\n\
+      FROM \"Opportunity\"");
+
+    assert_eq!(view.generate::<Mssql>(), "\
+      CREATE OR ALTER VIEW [Opportunity_view] AS\n\
+      SELECT\n\
+      [Id] AS [Opportunity ID],\n\
+      [Amount] AS [Amount],\n\
+      [Probability] AS [Probability],\n\
+      [Amount__c] AS [Amount__c],\n\
+      (\"Amount\" * \"Probability\" / 100) AS [Expected Revenue],\n\
+      [Rounded__c] AS [Rounded]\n\
+      FROM [Opportunity]");
+    assert_eq!(view.generate::<Sqlite>(), "\
+      DROP VIEW IF EXISTS \"Opportunity_view\";\n\
+      \n\
+      CREATE VIEW \"Opportunity_view\" AS\n\
+      SELECT\n\
+      \"Id\" AS \"Opportunity ID\",\n\
+      \"Amount\" AS \"Amount\",\n\
+      \"Probability\" AS \"Probability\",\n\
+      \"Amount__c\" AS \"Amount__c\",\n\
+      (\"Amount\" * \"Probability\" / 100) AS \"Expected Revenue\",\n\
+      \"Rounded__c\" AS \"Rounded\"\n\
+      FROM \"Opportunity\"");
+
+    // Views follow the table into its schema, and generated columns get used as they are
+    let mapping   = Mapping { computed_formulas: true, ..Default::default() };
+    let mut table = Table::new_in("sales", "Opportunity");
+    table.add_column("Expected__c", numeric(18, 2).generated("\"Amount\" * 2"));
+    let sql = view_from_describe(&table, &desc, &mapping).generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE OR REPLACE VIEW \"sales\".\"Opportunity_view\" AS\n\
+      SELECT\n\
+      \"Expected__c\" AS \"Expected Revenue\"\n\
+      FROM \"sales\".\"Opportunity\"");
+  }
+
+  #[test]
+  fn multipicklist_junctions() {
+    let mut industries = fixtures::field("Industries__c", FieldType::MultiPicklist);
+    industries.picklist_values = vec![
+      PicklistValue { value: "Retail".to_string(), label: None, active: true, default_value: false },
+      PicklistValue { value: "Energy".to_string(), label: None, active: true, default_value: false }
+    ];
+
+    let desc = fixtures::describe("Account")
+      .field("Id", FieldType::Id)
+      .with_field(industries)
+      .build();
+
+    // Nothing changes unless asked
+    assert!(junction_tables("Account", &desc, &Mapping::default()).is_empty());
+
+    let mapping = Mapping { normalize_multipicklists: true, picklist_checks: true, ..Default::default() };
+    let account = table_from_describe("Account", &desc, &mapping);
+    assert!(!account.has_column("Industries__c"));
+
+    let junctions = junction_tables("Account", &desc, &mapping);
+    assert_eq!(junctions.len(), 1);
+    assert_eq!(junctions[0].generate::<Pg>(), "\
+      CREATE TABLE \"Account_Industries__c_values\" (\n\
+      \"ParentId\" VARCHAR REFERENCES \"Account\" (\"Id\") ON DELETE CASCADE NOT NULL,\n\
+      \"Value\" VARCHAR(255) NOT NULL CHECK (\"Value\" IN ('Retail', 'Energy')),\n\
+      CONSTRAINT \"Account_Industries__c_values_pkey\" PRIMARY KEY (\"ParentId\", \"Value\")\n\
+      )");
+
+    // Added in any order, the parent still comes first
+    let mut schema = Schema::new();
+    schema.add_table(junctions[0].clone()).add_table(account);
+    assert_eq!(schema.order(), vec!["Account", "Account_Industries__c_values"]);
+    assert_eq!(schema.generate::<Sqlite>(), "\
+      CREATE TABLE \"Account\" (\n\
+      \"Id\" TEXT PRIMARY KEY NOT NULL UNIQUE\n\
+      );\n\
+      \n\
+      CREATE TABLE \"Account_Industries__c_values\" (\n\
+      \"ParentId\" TEXT NOT NULL REFERENCES \"Account\" (\"Id\") ON DELETE CASCADE,\n\
+      \"Value\" TEXT NOT NULL CHECK (\"Value\" IN ('Retail', 'Energy')),\n\
+      CONSTRAINT \"Account_Industries__c_values_pkey\" PRIMARY KEY (\"ParentId\", \"Value\")\n\
+      )");
+  }
+
+  #[test]
+  fn column_naming() {
+    let naming = Naming::default().case(NamingCase::Snake);
+    for (field, column) in &[
+      ("Id", "id"), ("BillingCity", "billing_city"), ("SLAExpirationDate__c", "sla_expiration_date__c"),
+      ("Is_Active__c", "is_active__c"), ("Address2Line", "address2_line"), ("URL", "url")
+    ] {
+      assert_eq!(naming.apply(field), *column);
+    }
+
+    let naming = naming
+      .replace("__c$", "").unwrap()
+      .rename("Name", "account_name");
+    let desc = fixtures::describe("Account")
+      .field("Id", FieldType::Id)
+      .field("Name", FieldType::String)
+      .field("Rating__c", FieldType::Picklist)
+      .reference("ParentId")
+      .build();
+
+    let mapping   = Mapping { naming, ..Default::default() };
+    let mut table = table_from_describe("Account", &desc, &mapping);
+    assert!(table.override_type("Name", "TEXT") && table.has_column("Rating__c"));
+    table.unique(&["Name", "Rating__c"]);
+
+    let sql = table.generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Account\" (\n\
+      \"id\" VARCHAR PRIMARY KEY NOT NULL UNIQUE,\n\
+      \"account_name\" TEXT,\n\
+      \"rating\" VARCHAR,\n\
+      \"parent_id\" VARCHAR REFERENCES \"Parent\" (\"id\"),\n\
+      CONSTRAINT \"Account_account_name_rating_key\" UNIQUE (\"account_name\", \"rating\")\n\
+      )");
+    assert_eq!(mapping.ident("Rating__c"), "\"rating\"");
+
+    assert_eq!(table.field_columns(), &[
+      ("Id".to_string(), "id".to_string()),
+      ("Name".to_string(), "account_name".to_string()),
+      ("Rating__c".to_string(), "rating".to_string()),
+      ("ParentId".to_string(), "parent_id".to_string())
+    ]);
+
+    assert!("snake".parse::<NamingCase>().is_ok() && "kebab".parse::<NamingCase>().is_err());
+    assert!(Naming::default().replace("(", "").is_err());
+  }
+
+  #[test]
+  fn column_order() {
+    let desc = fixtures::describe("Account")
+      .field("Name", FieldType::String)
+      .field("Id", FieldType::Id)
+      .field("AnnualRevenue", FieldType::Currency)
+      .reference("ParentId")
+      .build();
+
+    // Columns come out in describe order, every time
+    let mapping = Mapping { serial_key: Some("row_id".to_string()), ..Default::default() };
+    let sql     = table_from_describe("Account", &desc, &mapping).generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Account\" (\n\
+      \"Name\" VARCHAR(255),\n\
+      \"Id\" VARCHAR NOT NULL UNIQUE,\n\
+      \"AnnualRevenue\" NUMERIC,\n\
+      \"ParentId\" VARCHAR REFERENCES \"Parent\" (\"Id\"),\n\
+      \"row_id\" BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY NOT NULL\n\
+      )");
+    let names   = sql.lines().skip(1).filter_map(|line| line.split('"').nth(1)).collect::<Vec<_>>();
+    assert_eq!(names, vec!["Name", "Id", "AnnualRevenue", "ParentId", "row_id"]);
+
+    for _ in 0..10 {
+      assert_eq!(table_from_describe("Account", &desc, &mapping).generate::<Pg>(), sql);
+    }
+  }
+
+  #[test]
+  fn generated_describes() {
+    for desc in DescribeGenerator::new(1606).include_unknown(true).take(250) {
+      let sql = table_from_describe(desc.name.as_str(), &desc, &Mapping::default()).generate::<Pg>();
+
+      assert!(sql.starts_with(&format!("CREATE TABLE \"{}\" (", desc.name)));
+      for field in &desc.fields {
+        assert!(sql.contains(&format!("\n\"{}\" ", field.name)), "missing column {} in {}", field.name, sql);
+      }
+      assert_eq!(sql.matches("PRIMARY KEY").count(), 1);
+    }
+  }
+
+  #[test]
+  fn quoted_checks() {
+    let mut group = fixtures::field("Group", FieldType::Picklist);
+    group.picklist_values = vec![PicklistValue { value: "A".to_string(), label: None, active: true, default_value: false }];
+    let desc = fixtures::describe("Order").field("Id", FieldType::Id).field("status", FieldType::String).with_field(group).build();
+
+    // Checks quote the same way the columns do, backticks included
+    let identifiers = Identifiers::default().quoting(Quoting::WhenNeeded);
+    let mapping     = Mapping { picklist_checks: true, identifiers, ..Default::default() };
+    assert_eq!(table_from_describe("Order", &desc, &mapping).generate::<Pg>(), "\
+      CREATE TABLE \"Order\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL UNIQUE,\n\
+      status VARCHAR(255),\n\
+      \"Group\" VARCHAR CHECK (\"Group\" IN ('A'))\n\
+      )");
+
+    let mapping = Mapping { picklist_checks: true, dialect: Dialect::MySql, ..Default::default() };
+    assert_eq!(table_from_describe("Order", &desc, &mapping).generate::<MySql>(), "\
+      CREATE TABLE `Order` (\n\
+      `Id` VARCHAR(255) NOT NULL PRIMARY KEY UNIQUE,\n\
+      `status` VARCHAR(255) NULL,\n\
+      `Group` VARCHAR(255) NULL CHECK (`Group` IN ('A'))\n\
+      ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4");
+  }
+}
@@ -0,0 +1,22 @@
+//! Errors out of the `FromStr` impls (dialects, schedules, secret references...).
+
+/// Why a name or spec didn't parse.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+  /// Not one of the names there are
+  #[error("unknown {what} `{value}`, expected {expected}")]
+  Unknown { what: &'static str, value: String, expected: String },
+  /// The right kind of thing, but malformed
+  #[error("invalid {what} `{value}`, {reason}")]
+  Invalid { what: &'static str, value: String, reason: String }
+}
+
+impl ParseError {
+  pub(crate) fn unknown(what: &'static str, value: &str, expected: impl Into<String>) -> Self {
+    ParseError::Unknown { what, value: value.to_string(), expected: expected.into() }
+  }
+
+  pub(crate) fn invalid(what: &'static str, value: &str, reason: impl Into<String>) -> Self {
+    ParseError::Invalid { what, value: value.to_string(), reason: reason.into() }
+  }
+}
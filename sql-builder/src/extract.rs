@@ -0,0 +1,645 @@
+//! Pulls records out of Salesforce with bulk queries, the same way `sf-sql extract` does.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use oxidized_force::client::Client;
+use oxidized_force::options::CallOptions;
+use oxidized_force::response::{BulkQueryResults, BulkState, DescribeResponse, FieldType};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::ParseError;
+use crate::filter::FieldFilter;
+
+/// Formats `extract` can write records in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtractFormat {
+  Csv,
+  JsonLines
+}
+
+impl std::str::FromStr for ExtractFormat {
+  type Err = ParseError;
+
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    match name.to_ascii_lowercase().as_str() {
+      "csv"                  => Ok(ExtractFormat::Csv),
+      "jsonl" | "json-lines" => Ok(ExtractFormat::JsonLines),
+      _ => Err(ParseError::unknown("extract format", name, "csv or jsonl"))
+    }
+  }
+}
+
+/// Somewhere pages of bulk query results end up.
+#[async_trait]
+pub trait Sink: Send {
+  async fn write_page(&mut self, page: &BulkQueryResults) -> anyhow::Result<()>;
+
+  /// Records written so far.
+  fn records(&self) -> usize;
+
+  /// Makes sure every page written so far sticks, before a checkpoint says it did (see `extract_resumable`).
+  async fn commit(&mut self) -> anyhow::Result<()> {
+    Ok(())
+  }
+
+  /// Called once the last page is written, e.g. to flush.
+  async fn finish(&mut self) -> anyhow::Result<()> {
+    Ok(())
+  }
+}
+
+/// How far a bulk query got, so an interrupted extraction can pick up with the same job instead of starting over.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+  pub job_id:    String,
+  pub object:    String,
+  pub fields:    Vec<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub condition: Option<String>,
+  /// Where the next page of results starts; `None` with pages already written means they all were
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub locator:   Option<String>,
+  #[serde(default)]
+  pub pages:     usize,
+  #[serde(default)]
+  pub records:   usize
+}
+
+impl Checkpoint {
+  /// Whether this is a checkpoint for the very same query.
+  pub fn matches(&self, object: &str, fields: &[String], condition: Option<&str>) -> bool {
+    self.object == object && self.fields == fields && self.condition.as_deref() == condition
+  }
+
+  /// Loads a checkpoint file, if there is one.
+  pub fn load<P>(path: P) -> anyhow::Result<Option<Self>>
+  where P: AsRef<std::path::Path> {
+    let path = path.as_ref();
+    match std::fs::read(path) {
+      Ok(contents)                                            => Ok(Some(serde_json::from_slice(&contents).map_err(|err| anyhow::anyhow!("invalid checkpoint {}: {}", path.display(), err))?)),
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+      Err(err)                                                => Err(anyhow::anyhow!("cannot read {}: {}", path.display(), err))
+    }
+  }
+
+  pub fn save<P>(&self, path: P) -> anyhow::Result<()>
+  where P: AsRef<std::path::Path> {
+    Ok(std::fs::write(path, serde_json::to_vec(self)?)?)
+  }
+
+  /// Whether the job's results can still be downloaded; they don't stick around forever.
+  pub async fn resumable(&self, client: &Client) -> bool {
+    let why = match client.get_query_job_status(self.job_id.as_str()).await {
+      Ok(status) if !matches!(status.state, BulkState::Aborted | BulkState::Failed) => return true,
+      Ok(status)                                                                    => format!("it ended up {:?}", status.state),
+      Err(err)                                                                      => err.to_string()
+    };
+    log::warn!("Cannot resume bulk query {} for {}, starting over: {}", self.job_id, self.object, why);
+    false
+  }
+}
+
+/// Fields of an object a bulk query can select (and that make it past the filter).
+/// Bulk queries reject compound fields, so those get their components instead; base64 fields aren't supported at all.
+pub fn bulk_fields(desc: &DescribeResponse, filter: &FieldFilter) -> Vec<String> {
+  desc.fields
+    .iter()
+    .filter(|field| field.field_type != FieldType::Base64 && !desc.is_compound(&field.name) && filter.allows(field))
+    .map(|field| field.name.clone())
+    .collect()
+}
+
+/// Stitches pages of bulk query results into one CSV file; every page comes with its own header, only the first one is kept.
+#[derive(Debug)]
+pub struct CsvSink<W> {
+  w:       W,
+  header:  bool,
+  records: usize
+}
+
+impl<W> CsvSink<W>
+where W: Write {
+  pub fn new(w: W) -> Self {
+    CsvSink { w, header: false, records: 0 }
+  }
+
+  /// Carries on with a file that already has the header (and some records), see `Checkpoint`.
+  pub fn resume(w: W) -> Self {
+    CsvSink { w, header: true, records: 0 }
+  }
+
+  pub fn into_inner(self) -> W {
+    self.w
+  }
+}
+
+#[async_trait]
+impl<W> Sink for CsvSink<W>
+where W: Write + Send {
+  async fn write_page(&mut self, page: &BulkQueryResults) -> anyhow::Result<()> {
+    let body = match self.header {
+      // Field names never have line breaks in them, so the header is just the first line
+      true  => page.body.split_once('\n').map_or("", |(_, rest)| rest),
+      false => page.body.as_str()
+    };
+    self.header   = self.header || !page.body.is_empty();
+    self.records += page.number_of_records.unwrap_or(0);
+    Ok(self.w.write_all(body.as_bytes())?)
+  }
+
+  fn records(&self) -> usize {
+    self.records
+  }
+
+  async fn commit(&mut self) -> anyhow::Result<()> {
+    Ok(self.w.flush()?)
+  }
+
+  async fn finish(&mut self) -> anyhow::Result<()> {
+    Ok(self.w.flush()?)
+  }
+}
+
+/// Writes every record as a JSON object on its own line, keyed by field name (or column name, see `columns`).
+/// Empty values are `null`; numbers and booleans stay strings unless the sink knows the field types (see `typed`).
+#[derive(Debug)]
+pub struct JsonLinesSink<W> {
+  w:       W,
+  names:   HashMap<String, String>,
+  types:   HashMap<String, FieldType>,
+  records: usize
+}
+
+impl<W> JsonLinesSink<W>
+where W: Write {
+  pub fn new(w: W) -> Self {
+    JsonLinesSink { w, names: HashMap::new(), types: HashMap::new(), records: 0 }
+  }
+
+  /// Keys records by these (field, column) names instead, e.g. `Table::field_columns`.
+  pub fn columns(mut self, field_columns: &[(String, String)]) -> Self {
+    self.names = field_columns.iter().cloned().collect();
+    self
+  }
+
+  /// Turns numbers and booleans into JSON ones, going by the describe.
+  pub fn typed(mut self, desc: &DescribeResponse) -> Self {
+    self.types = desc.fields.iter().map(|field| (field.name.clone(), field.field_type.clone())).collect();
+    self
+  }
+
+  pub fn into_inner(self) -> W {
+    self.w
+  }
+
+  fn value(&self, field: &str, raw: String) -> Value {
+    json_value(self.types.get(field), raw)
+  }
+}
+
+/// A bulk query value as JSON: `null` if it's empty, a number or boolean if the field is one (and it parses), a string otherwise.
+pub fn json_value(field_type: Option<&FieldType>, raw: String) -> Value {
+  if raw.is_empty() {
+    return Value::Null;
+  }
+
+  match field_type {
+    Some(FieldType::Boolean) => raw.parse::<bool>().map_or(Value::String(raw), Value::from),
+    Some(FieldType::Int | FieldType::Long) => raw.parse::<i64>().map_or(Value::String(raw), Value::from),
+    Some(FieldType::Double | FieldType::Currency | FieldType::Percent) => match raw.parse::<f64>() {
+      Ok(number) if number.is_finite() => Value::from(number),
+      _                                => Value::String(raw)
+    },
+    _ => Value::String(raw)
+  }
+}
+
+#[async_trait]
+impl<W> Sink for JsonLinesSink<W>
+where W: Write + Send {
+  async fn write_page(&mut self, page: &BulkQueryResults) -> anyhow::Result<()> {
+    let mut rows = parse_csv(&page.body).into_iter();
+    let header   = match rows.next() {
+      Some(header) => header,
+      None         => return Ok(())
+    };
+
+    // Written out by hand, a `Map` would sort the keys rather than keep them in query order
+    for row in rows {
+      let mut line = String::from("{");
+      for (i, (field, raw)) in header.iter().zip(row).enumerate() {
+        if i > 0 {
+          line.push(',');
+        }
+        line.push_str(&Value::from(self.names.get(field).unwrap_or(field).as_str()).to_string());
+        line.push(':');
+        line.push_str(&self.value(field, raw).to_string());
+      }
+      line.push_str("}\n");
+
+      self.w.write_all(line.as_bytes())?;
+      self.records += 1;
+    }
+    Ok(())
+  }
+
+  fn records(&self) -> usize {
+    self.records
+  }
+
+  async fn commit(&mut self) -> anyhow::Result<()> {
+    Ok(self.w.flush()?)
+  }
+
+  async fn finish(&mut self) -> anyhow::Result<()> {
+    Ok(self.w.flush()?)
+  }
+}
+
+/// Passes pages on to another sink, keeping track of the latest value of a field (like `SystemModstamp`) along the way.
+/// Values are compared as text, which works for the timestamps Salesforce hands out since they're all in UTC.
+#[derive(Debug)]
+pub struct Watermark<S> {
+  inner:  S,
+  field:  String,
+  latest: Option<String>
+}
+
+impl<S> Watermark<S>
+where S: Sink {
+  pub fn new<F>(inner: S, field: F) -> Self
+  where F: Into<String> {
+    Watermark { inner, field: field.into(), latest: None }
+  }
+
+  /// Starts from the latest value of an earlier (interrupted) run.
+  pub fn since(mut self, latest: Option<String>) -> Self {
+    self.latest = latest;
+    self
+  }
+
+  /// The latest value seen so far, if there were any records (with a value).
+  pub fn latest(&self) -> Option<&str> {
+    self.latest.as_deref()
+  }
+
+  pub fn into_inner(self) -> S {
+    self.inner
+  }
+}
+
+#[async_trait]
+impl<S> Sink for Watermark<S>
+where S: Sink {
+  async fn write_page(&mut self, page: &BulkQueryResults) -> anyhow::Result<()> {
+    let mut rows = parse_csv(&page.body).into_iter();
+    if let Some(index) = rows.next().and_then(|header| header.iter().position(|field| *field == self.field)) {
+      let latest = rows.filter_map(|mut row| (index < row.len()).then(|| row.swap_remove(index))).filter(|value| !value.is_empty()).max();
+      if latest > self.latest {
+        self.latest = latest;
+      }
+    }
+    self.inner.write_page(page).await
+  }
+
+  fn records(&self) -> usize {
+    self.inner.records()
+  }
+
+  async fn commit(&mut self) -> anyhow::Result<()> {
+    self.inner.commit().await
+  }
+
+  async fn finish(&mut self) -> anyhow::Result<()> {
+    self.inner.finish().await
+  }
+}
+
+/// Passes pages on to another sink, showing each one to `on_page` first (e.g. to report progress).
+pub struct Inspect<S, F> {
+  inner:   S,
+  on_page: F
+}
+
+impl<S, F> Inspect<S, F>
+where S: Sink, F: FnMut(&BulkQueryResults) + Send {
+  pub fn new(inner: S, on_page: F) -> Self {
+    Inspect { inner, on_page }
+  }
+
+  pub fn into_inner(self) -> S {
+    self.inner
+  }
+}
+
+impl<S, F> std::fmt::Debug for Inspect<S, F>
+where S: std::fmt::Debug {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Inspect").field("inner", &self.inner).finish()
+  }
+}
+
+#[async_trait]
+impl<S, F> Sink for Inspect<S, F>
+where S: Sink, F: FnMut(&BulkQueryResults) + Send {
+  async fn write_page(&mut self, page: &BulkQueryResults) -> anyhow::Result<()> {
+    (self.on_page)(page);
+    self.inner.write_page(page).await
+  }
+
+  fn records(&self) -> usize {
+    self.inner.records()
+  }
+
+  async fn commit(&mut self) -> anyhow::Result<()> {
+    self.inner.commit().await
+  }
+
+  async fn finish(&mut self) -> anyhow::Result<()> {
+    self.inner.finish().await
+  }
+}
+
+/// Splits bulk query CSV into rows of values; quoted values can have commas, line breaks and (doubled) quotes in them.
+pub fn parse_csv(body: &str) -> Vec<Vec<String>> {
+  let mut rows   = Vec::new();
+  let mut row    = Vec::new();
+  let mut value  = String::new();
+  let mut quoted = false;
+  let mut chars  = body.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    match (c, quoted) {
+      ('"', true) if chars.peek() == Some(&'"') => {
+        chars.next();
+        value.push('"');
+      },
+      ('"', _)      => quoted = !quoted,
+      (',', false)  => row.push(std::mem::take(&mut value)),
+      ('\r', false) => (),
+      ('\n', false) => {
+        row.push(std::mem::take(&mut value));
+        rows.push(std::mem::take(&mut row));
+      },
+      (c, _)        => value.push(c)
+    }
+  }
+  // The last line doesn't always end with a line break
+  if !value.is_empty() || !row.is_empty() {
+    row.push(value);
+    rows.push(row);
+  }
+  rows
+}
+
+/// Runs a bulk query for `fields` of `object`, polling every `poll_interval` until it's done, and writes every page
+/// of results to `sink` as it comes in (finishing it after the last one). Returns the number of records.
+pub async fn extract<S>(client: &Client, object: &str, fields: &[String], poll_interval: Duration, sink: &mut S) -> anyhow::Result<usize>
+where S: Sink + ?Sized {
+  extract_where(client, object, fields, None, poll_interval, sink).await
+}
+
+/// Same as `extract`, for just the records matching `condition` (a SOQL `WHERE` clause).
+pub async fn extract_where<S>(client: &Client, object: &str, fields: &[String], condition: Option<&str>, poll_interval: Duration, sink: &mut S) -> anyhow::Result<usize>
+where S: Sink + ?Sized {
+  extract_resumable(client, object, fields, condition, poll_interval, sink, None, |_, _| Ok(())).await
+}
+
+/// Same as `extract_where`, picking up where `checkpoint` left off if it's for the same query (see `Checkpoint::resumable`).
+/// `on_checkpoint` gets called once the job is created, and after every page once the sink has committed it.
+/// Returns the number of records, counting the ones from before the checkpoint.
+#[allow(clippy::too_many_arguments)]
+pub async fn extract_resumable<S, F>(
+  client:        &Client,
+  object:        &str,
+  fields:        &[String],
+  condition:     Option<&str>,
+  poll_interval: Duration,
+  sink:          &mut S,
+  checkpoint:    Option<Checkpoint>,
+  mut on_checkpoint: F
+) -> anyhow::Result<usize>
+where S: Sink + ?Sized, F: FnMut(&Checkpoint, &S) -> anyhow::Result<()> {
+  let options = CallOptions::new();
+
+  let mut checkpoint = match checkpoint.filter(|checkpoint| checkpoint.matches(object, fields, condition)) {
+    Some(checkpoint) => checkpoint,
+    None             => {
+      let job        = client.create_filtered_query_job(object, fields.iter().map(String::as_str).collect::<Vec<_>>(), condition).await?;
+      let checkpoint = Checkpoint {
+        job_id:    job.id,
+        object:    object.to_string(),
+        fields:    fields.to_vec(),
+        condition: condition.map(String::from),
+        locator:   None,
+        pages:     0,
+        records:   0
+      };
+      on_checkpoint(&checkpoint, sink)?;
+      checkpoint
+    }
+  };
+
+  let status = client.wait_for_query_job(checkpoint.job_id.as_str(), poll_interval, &options).await?;
+  if status.state != BulkState::JobComplete {
+    anyhow::bail!(
+      "bulk query for {} ended up {:?}: {}",
+      object,
+      status.state,
+      status.error_message.as_deref().unwrap_or("no reason given")
+    );
+  }
+
+  while checkpoint.pages == 0 || checkpoint.locator.is_some() {
+    let page = client.get_query_job_results(checkpoint.job_id.as_str(), checkpoint.locator.as_deref(), None, &options).await?;
+    sink.write_page(&page).await?;
+    sink.commit().await?;
+
+    checkpoint.locator  = page.locator.clone();
+    checkpoint.pages   += 1;
+    checkpoint.records += page.number_of_records.unwrap_or(0);
+    on_checkpoint(&checkpoint, sink)?;
+  }
+
+  sink.finish().await?;
+  Ok(checkpoint.records)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use oxidized_force::test_util::{fixtures, Method, MockClient, MockResponse};
+
+  use crate::describe::{table_from_describe, Mapping};
+  use crate::sql::{Naming, NamingCase};
+
+  #[tokio::test]
+  async fn extracts() -> anyhow::Result<()> {
+    let mut street = fixtures::field("BillingStreet", FieldType::TextArea);
+    street.compound_field_name = Some("BillingAddress".to_string());
+
+    let desc = fixtures::describe("Account")
+      .field("Id", FieldType::Id)
+      .field("Name", FieldType::String)
+      .field("BillingAddress", FieldType::Address)
+      .with_field(street)
+      .field("Logo__c", FieldType::Base64)
+      .build();
+    assert_eq!(bulk_fields(&desc, &FieldFilter::new()), ["Id", "Name", "BillingStreet"]);
+    assert_eq!(bulk_fields(&desc, &FieldFilter::new().exclude("Name")), ["Id", "BillingStreet"]);
+
+    let mock   = MockClient::new();
+    let job_id = "750R0000000zlh9IAA";
+    let path   = format!("/jobs/query/{}", job_id);
+    mock.on(Method::POST, "/jobs/query", MockResponse::json(200, fixtures::bulk_job(job_id, "Account", BulkState::UploadComplete)));
+    mock.on(Method::GET, &path, MockResponse::json(200, fixtures::bulk_job(job_id, "Account", BulkState::JobComplete)));
+    // Routes match the first one registered, so the page with the locator goes first
+    mock.on(Method::GET, &format!("{}/results?locator=page2", path), MockResponse::csv("\"Id\",\"Name\"\n\"2\",\"Inu, \"\"Jr\"\"\"\n", None));
+    mock.on(Method::GET, &format!("{}/results", path), MockResponse::csv("\"Id\",\"Name\"\n\"1\",\"Shiba\"\n", Some("page2")));
+
+    let fields   = ["Id".to_string(), "Name".to_string()];
+    let mut sink = CsvSink::new(Vec::new());
+    let records  = extract(&mock, "Account", &fields, std::time::Duration::from_millis(1), &mut sink).await?;
+    assert_eq!(records, 2);
+    assert_eq!(String::from_utf8(sink.into_inner())?, "\"Id\",\"Name\"\n\"1\",\"Shiba\"\n\"2\",\"Inu, \"\"Jr\"\"\"\n");
+
+    // Failed jobs say why
+    let failed = MockClient::new();
+    let mut status = fixtures::bulk_job(job_id, "Account", BulkState::Failed);
+    status["errorMessage"] = "INVALID_FIELD: No such column 'Nope'".into();
+    failed.on(Method::POST, "/jobs/query", MockResponse::json(200, fixtures::bulk_job(job_id, "Account", BulkState::UploadComplete)));
+    failed.on(Method::GET, &path, MockResponse::json(200, status));
+    let err = extract(&failed, "Account", &fields, std::time::Duration::from_millis(1), &mut CsvSink::new(Vec::new())).await.unwrap_err();
+    assert!(err.to_string().contains("No such column 'Nope'"));
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn resumed_extracts() -> anyhow::Result<()> {
+    let mock   = MockClient::new();
+    let job_id = "750R0000000zlh9IAA";
+    let path   = format!("/jobs/query/{}", job_id);
+    mock.on(Method::POST, "/jobs/query", MockResponse::json(200, fixtures::bulk_job(job_id, "Account", BulkState::UploadComplete)));
+    mock.on(Method::GET, &path, MockResponse::json(200, fixtures::bulk_job(job_id, "Account", BulkState::JobComplete)));
+    mock.on(Method::GET, &format!("{}/results?locator=page3", path), MockResponse::csv("\"Id\"\n\"3\"\n", None));
+    mock.on(Method::GET, &format!("{}/results?locator=page2", path), MockResponse::csv("\"Id\"\n\"2\"\n", Some("page3")));
+    mock.on(Method::GET, &format!("{}/results", path), MockResponse::csv("\"Id\"\n\"1\"\n", Some("page2")));
+
+    // Checkpoints come once the job is there, then after every page
+    let fields      = ["Id".to_string()];
+    let mut saved   = Vec::new();
+    let mut sink    = CsvSink::new(Vec::new());
+    let records     = extract_resumable(&mock, "Account", &fields, None, std::time::Duration::from_millis(1), &mut sink, None, |checkpoint, sink| {
+      saved.push((checkpoint.clone(), sink.records()));
+      Ok(())
+    })
+    .await?;
+    assert_eq!(records, 3);
+    assert_eq!(saved.iter().map(|(checkpoint, records)| (checkpoint.locator.as_deref(), checkpoint.pages, *records)).collect::<Vec<_>>(), [
+      (None, 0, 0),
+      (Some("page2"), 1, 1),
+      (Some("page3"), 2, 2),
+      (None, 3, 3)
+    ]);
+
+    // Picking up after the first page reuses the job and skips the page that's already there
+    let resumed  = saved[1].0.clone();
+    assert!(resumed.matches("Account", &fields, None) && !resumed.matches("Account", &fields, Some("SystemModstamp > 2020-01-01T00:00:00Z")));
+    assert!(resumed.resumable(&mock).await);
+    let mut sink = CsvSink::resume(Vec::new());
+    let records  = extract_resumable(&mock, "Account", &fields, None, std::time::Duration::from_millis(1), &mut sink, Some(resumed), |_, _| Ok(())).await?;
+    assert_eq!(records, 3);
+    assert_eq!(String::from_utf8(sink.into_inner())?, "\"2\"\n\"3\"\n");
+    assert_eq!(mock.transport().hits(&mock.api_path("/jobs/query")), 1);
+
+    // Nothing left to do after the last page
+    let mut sink = CsvSink::resume(Vec::new());
+    let records  = extract_resumable(&mock, "Account", &fields, None, std::time::Duration::from_millis(1), &mut sink, Some(saved[3].0.clone()), |_, _| Ok(())).await?;
+    assert_eq!((records, sink.records()), (3, 0));
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn json_lines_extracts() -> anyhow::Result<()> {
+    assert_eq!(
+      parse_csv("\"Id\",\"Name\"\r\n\"1\",\"Shiba, \"\"Inu\"\"\nJr\"\n\"2\","),
+      vec![vec!["Id", "Name"], vec!["1", "Shiba, \"Inu\"\nJr"], vec!["2", ""]]
+    );
+
+    let desc = fixtures::describe("Account")
+      .field("Id", FieldType::Id)
+      .field("Name", FieldType::String)
+      .field("IsActive__c", FieldType::Boolean)
+      .field("NumberOfEmployees", FieldType::Int)
+      .field("AnnualRevenue", FieldType::Currency)
+      .build();
+    let page = |body: &str| BulkQueryResults { body: body.to_string(), locator: None, number_of_records: None };
+
+    let mut sink = JsonLinesSink::new(Vec::new()).typed(&desc);
+    sink.write_page(&page("\"Id\",\"Name\",\"IsActive__c\",\"NumberOfEmployees\",\"AnnualRevenue\"\n\"1\",\"Shiba\",\"true\",\"12\",\"1.5\"\n")).await?;
+    sink.write_page(&page("\"Id\",\"Name\",\"IsActive__c\",\"NumberOfEmployees\",\"AnnualRevenue\"\n\"2\",\"\",\"false\",\"\",\"\"\n")).await?;
+    assert_eq!(sink.records(), 2);
+    assert_eq!(
+      String::from_utf8(sink.into_inner())?,
+      concat!(
+        "{\"Id\":\"1\",\"Name\":\"Shiba\",\"IsActive__c\":true,\"NumberOfEmployees\":12,\"AnnualRevenue\":1.5}\n",
+        "{\"Id\":\"2\",\"Name\":null,\"IsActive__c\":false,\"NumberOfEmployees\":null,\"AnnualRevenue\":null}\n"
+      )
+    );
+
+    // Column names come from the table, so naming settings apply
+    let mapping  = Mapping { naming: Naming::default().case(NamingCase::Snake), ..Mapping::default() };
+    let table    = table_from_describe("Account", &desc, &mapping);
+    let mut sink = JsonLinesSink::new(Vec::new()).columns(table.field_columns());
+    sink.write_page(&page("\"Id\",\"IsActive__c\"\n\"1\",\"true\"\n")).await?;
+    assert_eq!(String::from_utf8(sink.into_inner())?, "{\"id\":\"1\",\"is_active__c\":\"true\"}\n");
+
+    assert_eq!("jsonl".parse::<ExtractFormat>()?, ExtractFormat::JsonLines);
+    assert!("parquet".parse::<ExtractFormat>().is_err());
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn inspected_pages() -> anyhow::Result<()> {
+    let page = |body: &str, records| BulkQueryResults { body: body.to_string(), locator: None, number_of_records: Some(records) };
+    let mut seen = Vec::new();
+    let mut sink = Inspect::new(CsvSink::new(Vec::new()), |page: &BulkQueryResults| seen.push((page.number_of_records, page.body.len())));
+    sink.write_page(&page("\"Id\"\n\"1\"\n\"2\"\n", 2)).await?;
+    sink.write_page(&page("\"Id\"\n\"3\"\n", 1)).await?;
+    sink.finish().await?;
+    assert_eq!(sink.records(), 3);
+    assert_eq!(String::from_utf8(sink.into_inner().into_inner())?, "\"Id\"\n\"1\"\n\"2\"\n\"3\"\n");
+    assert_eq!(seen, [(Some(2), 13), (Some(1), 9)]);
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn watermarks() -> anyhow::Result<()> {
+    // Only changed records get queried, and the latest change is what the next sync starts from
+    let mock   = MockClient::new();
+    let job_id = "750R0000000zlh9IAA";
+    let path   = format!("/jobs/query/{}", job_id);
+    mock.on(Method::POST, "/jobs/query", MockResponse::json(200, fixtures::bulk_job(job_id, "Account", BulkState::UploadComplete)));
+    mock.on(Method::GET, &path, MockResponse::json(200, fixtures::bulk_job(job_id, "Account", BulkState::JobComplete)));
+    mock.on(Method::GET, &format!("{}/results", path), MockResponse::csv(
+      "\"Id\",\"SystemModstamp\"\n\"1\",\"2021-01-03T10:00:00.000Z\"\n\"2\",\"2021-01-05T08:30:00.000Z\"\n\"3\",\"2021-01-04T23:59:59.000Z\"\n",
+      None
+    ));
+
+    let fields   = ["Id".to_string(), "SystemModstamp".to_string()];
+    let mut sink = Watermark::new(CsvSink::new(Vec::new()), "SystemModstamp");
+    let records  = extract_where(&mock, "Account", &fields, Some("SystemModstamp > 2021-01-02T00:00:00.000Z"), Duration::from_millis(1), &mut sink).await?;
+    assert_eq!(records, 3);
+    assert_eq!(sink.latest(), Some("2021-01-05T08:30:00.000Z"));
+
+    let body = String::from_utf8(mock.transport().requests()[0].body.clone().unwrap_or_default())?;
+    assert!(body.contains("SELECT Id,SystemModstamp FROM Account WHERE SystemModstamp > 2021-01-02T00:00:00.000Z"));
+    Ok(())
+  }
+}
@@ -0,0 +1,127 @@
+use oxidized_force::response::{Field, FieldType};
+use regex::Regex;
+
+/// Decides which fields of an object get a column.
+/// The record Id always does, since everything else keys off of it.
+#[derive(Debug, Clone, Default)]
+pub struct FieldFilter {
+  include:     Vec<Regex>,
+  exclude:     Vec<Regex>,
+  custom:      bool,
+  custom_only: bool,
+  calculated:  bool,
+  encrypted:   bool
+}
+
+impl FieldFilter {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Only keeps fields matching one of the included patterns (once there are any).
+  /// Patterns match whole (case insensitive) field names, where `*` is anything and `?` any one character, e.g. `Billing*`.
+  pub fn include(mut self, pattern: &str) -> Self {
+    self.include.push(glob(pattern));
+    self
+  }
+
+  /// Drops fields matching the pattern, even when they're included.
+  pub fn exclude(mut self, pattern: &str) -> Self {
+    self.exclude.push(glob(pattern));
+    self
+  }
+
+  pub fn skip_custom(self, custom: bool) -> Self {
+    Self { custom, ..self }
+  }
+
+  /// Leaves out every standard field (other than the Id).
+  pub fn custom_only(self, custom_only: bool) -> Self {
+    Self { custom_only, ..self }
+  }
+
+  /// Formula and roll-up summary fields.
+  pub fn skip_calculated(self, calculated: bool) -> Self {
+    Self { calculated, ..self }
+  }
+
+  /// Shield encrypted fields (and classic encrypted text).
+  pub fn skip_encrypted(self, encrypted: bool) -> Self {
+    Self { encrypted, ..self }
+  }
+
+  pub fn allows(&self, field: &Field) -> bool {
+    if field.field_type == FieldType::Id {
+      return true;
+    }
+
+    let calculated = field.calculated || field.calculated_formula.is_some();
+    let encrypted  = field.encrypted || field.field_type == FieldType::EncryptedString;
+
+    let flagged  = (self.custom && field.custom) || (self.custom_only && !field.custom)
+      || (self.calculated && calculated) || (self.encrypted && encrypted);
+    let included = self.include.is_empty() || self.include.iter().any(|pattern| pattern.is_match(&field.name));
+    let excluded = self.exclude.iter().any(|pattern| pattern.is_match(&field.name));
+
+    !flagged && included && !excluded
+  }
+}
+
+fn glob(pattern: &str) -> Regex {
+  let expr = regex::escape(pattern).replace(r"\*", ".*").replace(r"\?", ".");
+  Regex::new(&format!("(?i)^{}$", expr)).expect("escaped patterns are always valid")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use oxidized_force::test_util::fixtures;
+
+  use crate::describe::{table_from_describe, Mapping};
+  use crate::sql::Pg;
+
+  #[test]
+  fn field_filters() {
+    let mut formula = fixtures::field("Total__c", FieldType::Currency);
+    formula.calculated_formula = Some("Amount__c * 2".to_string());
+    let mut rollup = fixtures::field("Children__c", FieldType::Double);
+    rollup.calculated = true;
+
+    let desc = fixtures::describe("Account")
+      .field("Id", FieldType::Id)
+      .field("Name", FieldType::String)
+      .field("BillingCity", FieldType::String)
+      .field("BillingState", FieldType::String)
+      .field("Amount__c", FieldType::Currency)
+      .field("SSN__c", FieldType::EncryptedString)
+      .with_field(formula)
+      .with_field(rollup)
+      .build();
+
+    let columns = |filter: FieldFilter| {
+      let mapping = Mapping { filter, ..Default::default() };
+      table_from_describe("Account", &desc, &mapping).field_columns().iter().map(|(field, _)| field.clone()).collect::<Vec<_>>()
+    };
+
+    assert_eq!(columns(FieldFilter::new()).len(), 8);
+    assert_eq!(columns(FieldFilter::new().include("billing*")), vec!["Id", "BillingCity", "BillingState"]);
+    assert_eq!(columns(FieldFilter::new().include("Billing*").exclude("*State")), vec!["Id", "BillingCity"]);
+    assert_eq!(columns(FieldFilter::new().exclude("Id")).len(), 8);
+    assert_eq!(columns(FieldFilter::new().skip_calculated(true)), vec!["Id", "Name", "BillingCity", "BillingState", "Amount__c", "SSN__c"]);
+    assert_eq!(columns(FieldFilter::new().skip_encrypted(true).skip_custom(true)), vec!["Id", "Name", "BillingCity", "BillingState"]);
+    assert_eq!(columns(FieldFilter::new().custom_only(true)), vec!["Id", "Amount__c", "SSN__c", "Total__c", "Children__c"]);
+
+    // Formulas can't be computed from fields that were left out
+    let mapping = Mapping { computed_formulas: true, filter: FieldFilter::new().exclude("Amount__c"), ..Default::default() };
+    assert_eq!(table_from_describe("Account", &desc, &mapping).generate::<Pg>(), "\
+      CREATE TABLE \"Account\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL UNIQUE,\n\
+      \"Name\" VARCHAR(255),\n\
+      \"BillingCity\" VARCHAR(255),\n\
+      \"BillingState\" VARCHAR(255),\n\
+      \"SSN__c\" VARCHAR,\n\
+      \"Total__c\" NUMERIC,\n\
+      \"Children__c\" DOUBLE PRECISION\n\
+      )");
+  }
+}
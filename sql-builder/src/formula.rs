@@ -0,0 +1,192 @@
+//! Translates (simple) Salesforce formulas into SQL expressions for generated columns.
+//!
+//! Only plain arithmetic/concatenation over fields of the same object is supported; anything
+//! with functions, cross-object references or other formula fields gets copied as a plain column instead.
+
+use crate::sql::Dialect;
+
+enum Token {
+  Field(String),
+  Number(String),
+  Text(String),
+  Symbol(&'static str)
+}
+
+/// Converts `formula` into SQL, or `None` when it uses something we can't express as a generated column.
+/// `column` gets asked about every field the formula references, and renders it (or returns `None` when it can't be used).
+pub fn to_sql<F>(formula: &str, dialect: Dialect, column: F) -> Option<String>
+where F: Fn(&str) -> Option<String> {
+  let tokens = tokenize(formula)?;
+  if tokens.is_empty() {
+    return None;
+  }
+
+  // Split on `&` as we go, since concatenation renders differently depending on the dialect
+  let mut operands = vec![Operand::default()];
+  let mut depth    = 0_usize;
+  for token in tokens {
+    let top     = depth == 0;
+    let operand = operands.last_mut().unwrap();
+    let sql     = match token {
+      Token::Symbol("&") if top => {
+        operands.push(Operand::default());
+        continue;
+      },
+      // Concatenating inside parentheses would take an actual parser to get the precedence right
+      Token::Symbol("&")        => return None,
+      Token::Symbol("(")        => {
+        depth += 1;
+        "(".to_string()
+      },
+      Token::Symbol(")")        => {
+        depth = depth.checked_sub(1)?;
+        ")".to_string()
+      },
+      Token::Symbol(sym)        => sym.to_string(),
+      Token::Field(name)        => match name.to_ascii_uppercase().as_str() {
+        "TRUE" | "FALSE" | "NULL" => name.to_ascii_uppercase(),
+        _                         => column(&name)?
+      },
+      Token::Number(num)        => num,
+      Token::Text(text)         => {
+        operand.literal = true;
+        format!("'{}'", text.replace('\'', "''"))
+      }
+    };
+    if top && sql != ")" {
+      operand.units += 1;
+    }
+    operand.sql.push(sql);
+  }
+
+  if operands.len() == 1 {
+    return Some(operands.remove(0).sql.join(" "));
+  }
+  // Each side has to be a single value (or parenthesized), `A & B + C` depends on precedence again
+  if operands.iter().any(|operand| operand.units != 1) {
+    return None;
+  }
+
+  // Salesforce treats blanks as empty text when concatenating, where SQL's NULL would swallow the whole thing
+  let operands = operands
+    .into_iter()
+    .map(|operand| match operand.literal && operand.sql.len() == 1 {
+      true  => operand.sql.join(" "),
+      false => format!("COALESCE({}, '')", operand.sql.join(" "))
+    })
+    .collect::<Vec<_>>();
+  match dialect {
+    // `||` is a logical OR in MySQL, and doesn't exist in SQL Server
+    Dialect::MySql | Dialect::Mssql => Some(format!("CONCAT({})", operands.join(", "))),
+    _                               => Some(operands.join(" || "))
+  }
+}
+
+/// One side of a concatenation (or the whole formula, when there's none).
+#[derive(Default)]
+struct Operand {
+  sql:     Vec<String>,
+  /// Values (or parenthesized groups) outside any parentheses
+  units:   usize,
+  literal: bool
+}
+
+fn tokenize(formula: &str) -> Option<Vec<Token>> {
+  let mut tokens = Vec::new();
+  let mut chars  = formula.chars().peekable();
+
+  while let Some(&ch) = chars.peek() {
+    match ch {
+      _ if ch.is_whitespace() => {
+        chars.next();
+      },
+      '0'..='9' | '.' => {
+        let mut num = String::new();
+        while let Some(&ch) = chars.peek().filter(|ch| ch.is_ascii_digit() || **ch == '.') {
+          num.push(ch);
+          chars.next();
+        }
+        tokens.push(Token::Number(num));
+      },
+      '\'' | '"' => {
+        let quote    = ch;
+        let mut text = String::new();
+        chars.next();
+
+        loop {
+          match chars.next()? {
+            '\\' => text.push(chars.next()?),
+            ch if ch == quote => break,
+            ch => text.push(ch)
+          }
+        }
+        tokens.push(Token::Text(text));
+      },
+      _ if ch.is_ascii_alphabetic() || ch == '_' => {
+        let mut name = String::new();
+        while let Some(&ch) = chars.peek().filter(|ch| ch.is_ascii_alphanumeric() || **ch == '_') {
+          name.push(ch);
+          chars.next();
+        }
+
+        // Function calls and cross-object (`Account.Name`) references are out
+        if matches!(chars.peek(), Some('(') | Some('.')) {
+          return None;
+        }
+        tokens.push(Token::Field(name));
+      },
+      _ => {
+        let symbol = match ch {
+          '+' => "+",
+          '-' => "-",
+          '*' => "*",
+          '/' => "/",
+          '(' => "(",
+          ')' => ")",
+          '&' => "&",
+          _   => return None
+        };
+        tokens.push(Token::Symbol(symbol));
+        chars.next();
+      }
+    }
+  }
+  Some(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn translates_simple_formulas() {
+    let column = |name: &str| ["Amount", "Probability", "FirstName", "LastName"].contains(&name).then(|| format!("\"{}\"", name));
+
+    assert_eq!(to_sql("Amount * Probability / 100", Dialect::Pg, column).unwrap(), "\"Amount\" * \"Probability\" / 100");
+    assert_eq!(to_sql("(Amount - 1.5)", Dialect::Pg, column).unwrap(), "( \"Amount\" - 1.5 )");
+  }
+
+  #[test]
+  fn concatenates_per_dialect() {
+    let column = |name: &str| ["FirstName", "LastName"].contains(&name).then(|| format!("\"{}\"", name));
+
+    assert_eq!(to_sql("FirstName & ' ' & LastName", Dialect::Pg, column).unwrap(), "COALESCE(\"FirstName\", '') || ' ' || COALESCE(\"LastName\", '')");
+    assert_eq!(to_sql("FirstName & ' ' & LastName", Dialect::MySql, column).unwrap(), "CONCAT(COALESCE(\"FirstName\", ''), ' ', COALESCE(\"LastName\", ''))");
+    assert_eq!(to_sql("FirstName & ' ' & LastName", Dialect::Mssql, column).unwrap(), "CONCAT(COALESCE(\"FirstName\", ''), ' ', COALESCE(\"LastName\", ''))");
+    assert!(to_sql("'(' & (FirstName & LastName) & ')'", Dialect::Pg, column).is_none());
+    assert!(to_sql("FirstName & LastName + 1", Dialect::Pg, column).is_none());
+    assert!(to_sql("& LastName", Dialect::Pg, column).is_none());
+  }
+
+  #[test]
+  fn rejects_everything_else() {
+    let column = |name: &str| (name == "Amount").then(|| format!("\"{}\"", name));
+
+    assert!(to_sql("ROUND(Amount, 2)", Dialect::Pg, column).is_none());
+    assert!(to_sql("Account.AnnualRevenue", Dialect::Pg, column).is_none());
+    assert!(to_sql("Missing__c + 1", Dialect::Pg, column).is_none());
+    assert!(to_sql("Amount > 1", Dialect::Pg, column).is_none());
+    assert!(to_sql("'unterminated", Dialect::Pg, column).is_none());
+    assert!(to_sql("", Dialect::Pg, column).is_none());
+  }
+}
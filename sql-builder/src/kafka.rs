@@ -0,0 +1,305 @@
+//! Publishing records to Kafka, a topic per object keyed by record Id, through a Kafka REST Proxy (the v2 API Confluent's proxy speaks).
+//! The proxy takes care of talking to the brokers, and of registering Avro schemas with the schema registry.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveTime, Timelike};
+use oxidized_force::response::{BulkQueryResults, DescribeResponse, FieldType};
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::codegen::schema;
+use crate::error::ParseError;
+use crate::extract::{self, parse_csv, Sink};
+use crate::filter::FieldFilter;
+
+/// Records sent to the proxy per request.
+pub const BATCH_SIZE: usize = 500;
+
+/// How message values are serialized; keys are plain strings either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Serialization {
+  Json,
+  Avro
+}
+
+impl std::str::FromStr for Serialization {
+  type Err = ParseError;
+
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    match name.to_ascii_lowercase().as_str() {
+      "json" => Ok(Serialization::Json),
+      "avro" => Ok(Serialization::Avro),
+      _      => Err(ParseError::unknown("serialization", name, "json or avro"))
+    }
+  }
+}
+
+impl Serialization {
+  fn content_type(self) -> &'static str {
+    match self {
+      Serialization::Json => "application/vnd.kafka.json.v2+json",
+      Serialization::Avro => "application/vnd.kafka.avro.v2+json"
+    }
+  }
+}
+
+/// Publishes every record to a topic, keyed by the record Id (or whichever field `key` says).
+/// JSON values are typed like `JsonLinesSink` types them; Avro ones follow `schema::avro`, for just the fields queried.
+#[derive(Debug)]
+pub struct KafkaSink {
+  http:          reqwest::Client,
+  url:           String,
+  topic:         String,
+  key:           String,
+  serialization: Serialization,
+  types:         HashMap<String, FieldType>,
+  value_schema:  Value,
+  avro_types:    HashMap<String, Value>,
+  records:       usize
+}
+
+/// What the proxy says about the records it took.
+#[derive(Debug, Deserialize)]
+struct ProduceResponse {
+  #[serde(default)]
+  offsets: Vec<Offset>
+}
+
+#[derive(Debug, Deserialize)]
+struct Offset {
+  error: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+struct ProxyError {
+  message: String
+}
+
+impl KafkaSink {
+  /// Publishes `fields` of `desc` (e.g. `extract::bulk_fields`) to `topic` through the proxy at `proxy_url`, e.g. `http://localhost:8082`.
+  pub fn new(proxy_url: &str, topic: &str, desc: &DescribeResponse, fields: &[String], serialization: Serialization) -> Self {
+    let mut value_schema = schema::avro(desc, &FieldFilter::default());
+    if let Some(Value::Array(schema_fields)) = value_schema.get_mut("fields") {
+      schema_fields.retain(|field| field["name"].as_str().is_some_and(|name| fields.iter().any(|queried| queried == name)));
+    }
+    let avro_types = match value_schema["fields"] {
+      Value::Array(ref schema_fields) => schema_fields.iter().map(|field| (field["name"].as_str().unwrap_or_default().to_string(), field["type"].clone())).collect(),
+      _                               => HashMap::new()
+    };
+
+    KafkaSink {
+      http: reqwest::Client::new(),
+      url: format!("{}/topics/{}", proxy_url.trim_end_matches('/'), topic),
+      topic: topic.to_string(),
+      key: "Id".to_string(),
+      serialization,
+      types: desc.fields.iter().map(|field| (field.name.clone(), field.field_type.clone())).collect(),
+      value_schema,
+      avro_types,
+      records: 0
+    }
+  }
+
+  /// Keys messages by this field rather than the record Id.
+  pub fn key(mut self, field: &str) -> Self {
+    self.key = field.to_string();
+    self
+  }
+
+  pub fn topic(&self) -> &str {
+    &self.topic
+  }
+
+  /// The request bodies publishing a page of bulk query results, `BATCH_SIZE` records apiece.
+  pub fn requests(&self, page: &BulkQueryResults) -> anyhow::Result<Vec<Value>> {
+    let mut rows = parse_csv(&page.body).into_iter();
+    let header   = match rows.next() {
+      Some(header) => header,
+      None         => return Ok(Vec::new())
+    };
+    let key = header
+      .iter()
+      .position(|field| *field == self.key)
+      .ok_or_else(|| anyhow::anyhow!("cannot key messages for {} by {}, it isn't one of the fields", self.topic, self.key))?;
+
+    let records = rows
+      .map(|row| {
+        let mut value = Map::new();
+        let key       = row.get(key).cloned().unwrap_or_default();
+        for (field, raw) in header.iter().zip(row) {
+          let field_value = match self.serialization {
+            Serialization::Json => extract::json_value(self.types.get(field), raw),
+            Serialization::Avro => avro_value(field, self.avro_types.get(field).unwrap_or(&Value::Null), raw)?
+          };
+          value.insert(field.clone(), field_value);
+        }
+        Ok(json!({ "key": key, "value": value }))
+      })
+      .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let bodies = records
+      .chunks(BATCH_SIZE)
+      .map(|batch| match self.serialization {
+        Serialization::Json => json!({ "records": batch }),
+        // The proxy registers the schemas (or finds them already registered) and caches them, so sending them every time is cheap
+        Serialization::Avro => json!({ "key_schema": json!("string").to_string(), "value_schema": self.value_schema.to_string(), "records": batch })
+      })
+      .collect();
+    Ok(bodies)
+  }
+
+  /// Publishes JSON values as they are, e.g. change events, keyed by whatever comes with them.
+  pub async fn publish(&mut self, messages: Vec<(String, Value)>) -> anyhow::Result<()> {
+    if self.serialization != Serialization::Json {
+      anyhow::bail!("cannot publish to {} as {:?}, only as JSON", self.topic, self.serialization);
+    }
+
+    let records = messages.into_iter().map(|(key, value)| json!({ "key": key, "value": value })).collect::<Vec<_>>();
+    for batch in records.chunks(BATCH_SIZE) {
+      self.send(&json!({ "records": batch })).await?;
+      self.records += batch.len();
+    }
+    Ok(())
+  }
+
+  async fn send(&self, body: &Value) -> anyhow::Result<()> {
+    let res = self
+      .http
+      .post(&self.url)
+      .header(CONTENT_TYPE, self.serialization.content_type())
+      .header(ACCEPT, "application/vnd.kafka.v2+json")
+      .body(body.to_string())
+      .send()
+      .await
+      .map_err(|err| anyhow::anyhow!("cannot publish to {}: {}", self.topic, err))?;
+
+    let status = res.status();
+    let text   = res.text().await?;
+    if !status.is_success() {
+      let message = serde_json::from_str::<ProxyError>(&text).map_or(text, |err| err.message);
+      anyhow::bail!("cannot publish to {}: {} ({})", self.topic, message, status);
+    }
+
+    let produced = serde_json::from_str::<ProduceResponse>(&text).map_err(|err| anyhow::anyhow!("unexpected response publishing to {}: {}", self.topic, err))?;
+    match produced.offsets.into_iter().find_map(|offset| offset.error) {
+      Some(err) => anyhow::bail!("cannot publish to {}: {}", self.topic, err),
+      None      => Ok(())
+    }
+  }
+}
+
+#[async_trait]
+impl Sink for KafkaSink {
+  async fn write_page(&mut self, page: &BulkQueryResults) -> anyhow::Result<()> {
+    for body in self.requests(page)? {
+      self.send(&body).await?;
+      self.records += body["records"].as_array().map_or(0, Vec::len);
+    }
+    Ok(())
+  }
+
+  fn records(&self) -> usize {
+    self.records
+  }
+}
+
+/// A bulk query value in Avro's JSON encoding, for a field of type `schema`; nillable fields are unions,
+/// which wrap values in an object keyed by the branch's type (`{"long": 1610734874000}`).
+fn avro_value(field: &str, schema: &Value, raw: String) -> anyhow::Result<Value> {
+  let (nillable, field_type) = match schema {
+    Value::Array(branches) => (true, branches.last().unwrap_or(&Value::Null)),
+    field_type             => (false, field_type)
+  };
+  if nillable && raw.is_empty() {
+    return Ok(Value::Null);
+  }
+
+  let (branch, logical) = match field_type {
+    Value::String(name)   => (name.as_str(), None),
+    Value::Object(object) => (object["type"].as_str().unwrap_or("string"), object.get("logicalType").and_then(Value::as_str)),
+    _                     => ("string", None)
+  };
+  let invalid = |raw: &str| anyhow::anyhow!("invalid {} `{}` for {}", logical.unwrap_or(branch), raw, field);
+
+  let value = match (branch, logical) {
+    ("boolean", _)                     => Value::from(raw.parse::<bool>().map_err(|_| invalid(&raw))?),
+    ("int", Some("date"))              => {
+      let date = NaiveDate::parse_from_str(&raw, "%Y-%m-%d").map_err(|_| invalid(&raw))?;
+      Value::from(date.signed_duration_since(NaiveDate::from_ymd(1970, 1, 1)).num_days())
+    },
+    ("long", Some("timestamp-millis")) => Value::from(chrono::DateTime::parse_from_rfc3339(&raw).map_err(|_| invalid(&raw))?.timestamp_millis()),
+    ("int", Some("time-millis"))       => {
+      let time = NaiveTime::parse_from_str(raw.trim_end_matches('Z'), "%H:%M:%S%.f").map_err(|_| invalid(&raw))?;
+      Value::from(time.num_seconds_from_midnight() * 1000 + time.nanosecond() / 1_000_000)
+    },
+    ("int" | "long", _)                => Value::from(raw.parse::<i64>().map_err(|_| invalid(&raw))?),
+    ("double", _)                      => match raw.parse::<f64>() {
+      Ok(number) if number.is_finite() => Value::from(number),
+      _                                => return Err(invalid(&raw))
+    },
+    // Ids never have separators in them, whichever one the list came with
+    ("array", _)                       => Value::from(raw.split(&[',', ';'][..]).filter(|id| !id.is_empty()).collect::<Vec<_>>()),
+    _                                  => Value::String(raw)
+  };
+
+  match nillable {
+    true  => Ok(json!({ branch: value })),
+    false => Ok(value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use oxidized_force::test_util::fixtures;
+
+  #[test]
+  fn kafka_messages() -> anyhow::Result<()> {
+    let desc = fixtures::describe("Account")
+      .field("Id", FieldType::Id)
+      .field("Name", FieldType::String)
+      .field("IsActive__c", FieldType::Boolean)
+      .field("NumberOfEmployees", FieldType::Int)
+      .field("CreatedDate", FieldType::DateTime)
+      .field("Founded__c", FieldType::Date)
+      .field("Logo__c", FieldType::Base64)
+      .build();
+    let fields = extract::bulk_fields(&desc, &FieldFilter::default());
+    let page   = BulkQueryResults {
+      body:              concat!(
+        "\"Id\",\"Name\",\"IsActive__c\",\"NumberOfEmployees\",\"CreatedDate\",\"Founded__c\"\n",
+        "\"001A\",\"Shiba\",\"true\",\"12\",\"2021-01-15T18:21:14.000Z\",\"1970-01-11\"\n",
+        "\"001B\",\"\",\"false\",\"\",\"\",\"\"\n"
+      )
+      .to_string(),
+      locator:           None,
+      number_of_records: Some(2)
+    };
+
+    let sink = KafkaSink::new("http://localhost:8082/", "salesforce.Account", &desc, &fields, Serialization::Json);
+    assert_eq!(sink.requests(&page)?, [json!({ "records": [
+      { "key": "001A", "value": { "Id": "001A", "Name": "Shiba", "IsActive__c": true, "NumberOfEmployees": 12, "CreatedDate": "2021-01-15T18:21:14.000Z", "Founded__c": "1970-01-11" } },
+      { "key": "001B", "value": { "Id": "001B", "Name": null, "IsActive__c": false, "NumberOfEmployees": null, "CreatedDate": null, "Founded__c": null } }
+    ] })]);
+
+    // Avro's JSON encoding wraps values of nillable fields in their type; the schema only has the fields that were queried
+    let sink     = KafkaSink::new("http://localhost:8082", "salesforce.Account", &desc, &fields, Serialization::Avro).key("Name");
+    let requests = sink.requests(&page)?;
+    assert_eq!(requests[0]["records"], json!([
+      { "key": "Shiba", "value": { "Id": "001A", "Name": { "string": "Shiba" }, "IsActive__c": { "boolean": true }, "NumberOfEmployees": { "int": 12 }, "CreatedDate": { "long": 1610734874000_i64 }, "Founded__c": { "int": 10 } } },
+      { "key": "", "value": { "Id": "001B", "Name": null, "IsActive__c": { "boolean": false }, "NumberOfEmployees": null, "CreatedDate": null, "Founded__c": null } }
+    ]));
+    let schema = serde_json::from_str::<serde_json::Value>(requests[0]["value_schema"].as_str().unwrap_or_default())?;
+    assert_eq!(schema["fields"].as_array().map(|fields| fields.iter().map(|field| field["name"].clone()).collect::<Vec<_>>()), Some(fields.iter().map(|field| json!(field)).collect()));
+    assert_eq!(requests[0]["key_schema"], "\"string\"");
+
+    let bad = BulkQueryResults { body: "\"Id\",\"Name\",\"NumberOfEmployees\"\n\"001A\",\"Shiba\",\"lots\"\n".to_string(), locator: None, number_of_records: None };
+    assert!(sink.requests(&bad).is_err());
+    assert!(KafkaSink::new("http://localhost:8082", "salesforce.Account", &desc, &fields, Serialization::Json).key("Email").requests(&page).is_err());
+    assert!("protobuf".parse::<Serialization>().is_err());
+    Ok(())
+  }
+}
@@ -0,0 +1,26 @@
+#[cfg(feature = "describe")]
+pub mod codegen;
+#[cfg(feature = "describe")]
+pub mod describe;
+pub mod error;
+#[cfg(feature = "describe")]
+pub mod extract;
+#[cfg(feature = "describe")]
+pub mod filter;
+pub mod formula;
+#[cfg(feature = "describe")]
+pub mod kafka;
+#[cfg(feature = "describe")]
+pub mod listen;
+#[cfg(feature = "describe")]
+pub mod load;
+#[cfg(feature = "describe")]
+pub mod login;
+#[cfg(feature = "describe")]
+pub mod overrides;
+pub mod schedule;
+pub mod secrets;
+pub mod sql;
+#[cfg(feature = "describe")]
+pub mod state;
+pub mod storage;
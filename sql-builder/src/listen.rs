@@ -0,0 +1,278 @@
+//! `listen`: Change Data Capture events, applied to Postgres tables (or published to Kafka) as they come in.
+//! Change events only carry the fields that changed, and never formula fields; a `sync` now and then brings those up to date.
+
+use oxidized_force::client::Client;
+use oxidized_force::streaming::StreamingEvent;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::sql::{Rows, Table};
+
+/// Records fetched again per query after a gap, comfortably under the SOQL length limit.
+const REFETCH_BATCH: usize = 200;
+
+/// Records per upsert statement, for the occasional change touching a lot of records.
+const UPSERT_BATCH: usize = 500;
+
+/// The change event channel of an object, e.g. `/data/AccountChangeEvent`, or `/data/Invoice__ChangeEvent` for `Invoice__c`.
+pub fn channel(object: &str) -> String {
+  match object.strip_suffix("__c") {
+    Some(custom) => format!("/data/{}__ChangeEvent", custom),
+    None         => format!("/data/{}ChangeEvent", object)
+  }
+}
+
+/// What happened to the records; gaps are changes Salesforce couldn't turn into a regular event (too big, or not
+/// made through the API), so the records have to be fetched again. An overflow is too many changes at once to say which.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ChangeType {
+  Create,
+  Update,
+  Delete,
+  Undelete,
+  GapCreate,
+  GapUpdate,
+  GapDelete,
+  GapUndelete,
+  GapOverflow
+}
+
+/// A change to one or more records of an object, out of a change event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+  pub object:      String,
+  pub change_type: ChangeType,
+  pub record_ids:  Vec<String>,
+  /// New values by field name, compound fields like `BillingAddress` split into their components (`BillingCity`...)
+  pub fields:      Map<String, Value>,
+  /// Fields an update changed; the ones missing from `fields` were emptied
+  pub changed:     Vec<String>,
+  pub replay_id:   i64
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangeEventHeader {
+  entity_name:    String,
+  change_type:    ChangeType,
+  #[serde(default)]
+  record_ids:     Vec<String>,
+  #[serde(default)]
+  changed_fields: Vec<String>,
+  #[serde(default)]
+  nulled_fields:  Vec<String>
+}
+
+impl Change {
+  pub fn from_event(event: &StreamingEvent) -> anyhow::Result<Self> {
+    let mut payload = match event.payload {
+      Value::Object(ref payload) => payload.clone(),
+      _                          => anyhow::bail!("change event {} on {} has no payload", event.replay_id, event.channel)
+    };
+    let header = payload
+      .remove("ChangeEventHeader")
+      .ok_or_else(|| anyhow::anyhow!("change event {} on {} has no ChangeEventHeader", event.replay_id, event.channel))?;
+    let header = serde_json::from_value::<ChangeEventHeader>(header)
+      .map_err(|err| anyhow::anyhow!("invalid ChangeEventHeader in change event {} on {}: {}", event.replay_id, event.channel, err))?;
+
+    let mut fields = Map::new();
+    for (field, value) in payload {
+      match value {
+        Value::Object(components) => fields.extend(components.into_iter().map(|(component, value)| (component_field(&field, &component), value))),
+        value                     => {
+          fields.insert(field, value);
+        }
+      }
+    }
+
+    // Changes to compound fields are listed as `BillingAddress.City`
+    let mut changed = Vec::new();
+    for field in header.changed_fields.iter().chain(&header.nulled_fields) {
+      let field = match field.split_once('.') {
+        Some((compound, component)) => component_field(compound, component),
+        None                        => field.clone()
+      };
+      if !changed.contains(&field) {
+        changed.push(field);
+      }
+    }
+
+    Ok(Change {
+      object:      header.entity_name,
+      change_type: header.change_type,
+      record_ids:  header.record_ids,
+      replay_id:   event.replay_id,
+      fields,
+      changed
+    })
+  }
+
+  /// Whether the records have to be fetched again (see `refetch`) to apply the change.
+  pub fn needs_refetch(&self) -> bool {
+    matches!(self.change_type, ChangeType::GapCreate | ChangeType::GapUpdate | ChangeType::GapUndelete)
+  }
+
+  /// Postgres statements applying the change to `table`: created records get upserted, updates only touch the fields that
+  /// changed (of the records that are there; the rest are left for a sync), deleted records go away. Gaps take the records
+  /// as they are now, out of `refetched`; overflows can't be applied at all.
+  pub fn statements(&self, table: &Table, refetched: &[Map<String, Value>]) -> anyhow::Result<Vec<String>> {
+    let key = match table.field_columns().iter().find(|(field, _)| field == "Id") {
+      Some((_, column)) => column.clone(),
+      None              => anyhow::bail!("cannot apply changes to {} without an Id column", table.name())
+    };
+
+    let with_ids = |rows: &mut Rows| {
+      for id in &self.record_ids {
+        let mut record = self.fields.clone();
+        record.insert("Id".to_string(), Value::from(id.as_str()));
+        rows.push(&record);
+      }
+    };
+
+    let statements = match self.change_type {
+      ChangeType::Create | ChangeType::Undelete                               => {
+        let mut rows = table.rows();
+        with_ids(&mut rows);
+        rows.generate_upserts(&[key], UPSERT_BATCH)
+      },
+      ChangeType::Update                                                      => {
+        let mut fields = self.changed.clone();
+        fields.push("Id".to_string());
+        // Nothing the table has a column for changed makes for no statement
+        let mut rows = table.rows().only(&fields);
+        with_ids(&mut rows);
+        rows.generate_update(&key).into_iter().collect()
+      },
+      ChangeType::Delete | ChangeType::GapDelete                              => {
+        let mut rows = table.rows().only(&["Id".to_string()]);
+        with_ids(&mut rows);
+        rows.generate_delete(&key).into_iter().collect()
+      },
+      ChangeType::GapCreate | ChangeType::GapUpdate | ChangeType::GapUndelete => {
+        let mut rows = table.rows();
+        for record in refetched {
+          rows.push(record);
+        }
+        match rows.is_empty() {
+          true  => Vec::new(),
+          false => rows.generate_upserts(&[key], UPSERT_BATCH)
+        }
+      },
+      ChangeType::GapOverflow                                                 => anyhow::bail!("too many changes to {} at once for change events, a sync has to catch up", self.object)
+    };
+    Ok(statements)
+  }
+
+  /// The change event for each of the records, to publish.
+  pub fn messages(&self, event: &StreamingEvent) -> Vec<(String, Value)> {
+    self.record_ids.iter().map(|id| (id.clone(), event.payload.clone())).collect()
+  }
+}
+
+/// Fetches the records as they are now (well, `fields` of them), for applying gaps.
+pub async fn refetch(client: &Client, object: &str, fields: &[String], ids: &[String]) -> anyhow::Result<Vec<Map<String, Value>>> {
+  let mut records = Vec::new();
+  for batch in ids.chunks(REFETCH_BATCH) {
+    let ids   = batch.iter().map(|id| format!("'{}'", id.replace('\\', "\\\\").replace('\'', "\\'"))).collect::<Vec<_>>();
+    let query = format!("SELECT {} FROM {} WHERE Id IN ({})", fields.join(","), object, ids.join(","));
+    let res   = client.query_raw(query.as_str()).await?;
+    records.extend(res.records.into_iter().filter_map(|record| match record {
+      Value::Object(record) => Some(record),
+      _                     => None
+    }));
+  }
+  Ok(records)
+}
+
+/// `BillingAddress` + `City` -> `BillingCity`, `Name` + `FirstName` -> `FirstName`, `Location__c` + `Latitude` -> `Location__Latitude__s`.
+fn component_field(compound: &str, component: &str) -> String {
+  if let Some(custom) = compound.strip_suffix("__c") {
+    return format!("{}__{}__s", custom, component);
+  }
+  match compound.strip_suffix("Address") {
+    Some(prefix) => format!("{}{}", prefix, component),
+    None         => component.to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use oxidized_force::response::FieldType;
+  use oxidized_force::test_util::fixtures;
+  use serde_json::json;
+
+  use crate::describe::{table_from_describe, Mapping};
+  use crate::sql::Pg;
+
+  #[test]
+  fn change_events() -> anyhow::Result<()> {
+    assert_eq!(channel("Account"), "/data/AccountChangeEvent");
+    assert_eq!(channel("Invoice__c"), "/data/Invoice__ChangeEvent");
+
+    let mut name = fixtures::field("Name", FieldType::String);
+    name.nillable = false;
+    let desc = fixtures::describe("Account")
+      .field("Id", FieldType::Id)
+      .with_field(name)
+      .field("BillingCity", FieldType::String)
+      .field("BillingStreet", FieldType::String)
+      .build();
+    let table = table_from_describe("account", &desc, &Mapping::default());
+    assert_eq!(table.generate::<Pg>(), "\
+      CREATE TABLE \"account\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL UNIQUE,\n\
+      \"Name\" VARCHAR(255) NOT NULL,\n\
+      \"BillingCity\" VARCHAR(255),\n\
+      \"BillingStreet\" VARCHAR(255)\n\
+      )");
+
+    let event = |change_type: &str, fields: serde_json::Value| {
+      let mut payload = json!({ "ChangeEventHeader": { "entityName": "Account", "changeType": change_type, "recordIds": ["001A", "001B"] } });
+      payload["ChangeEventHeader"]["changedFields"] = json!(["Name", "BillingAddress.City"]);
+      payload["ChangeEventHeader"]["nulledFields"] = json!(["BillingAddress.Street"]);
+      if let (Some(payload), serde_json::Value::Object(fields)) = (payload.as_object_mut(), fields) {
+        payload.extend(fields);
+      }
+      StreamingEvent { channel: "/data/AccountChangeEvent".to_string(), replay_id: 42, payload }
+    };
+
+    // Compound fields come apart into the columns they're stored in
+    let update = Change::from_event(&event("UPDATE", json!({ "Name": "Shiba", "BillingAddress": { "City": "Tokyo" } })))?;
+    assert_eq!(update.change_type, ChangeType::Update);
+    assert_eq!(update.record_ids, ["001A", "001B"]);
+    assert_eq!(update.changed, ["Name", "BillingCity", "BillingStreet"]);
+    assert_eq!(update.fields, *json!({ "Name": "Shiba", "BillingCity": "Tokyo" }).as_object().unwrap());
+    assert_eq!(update.replay_id, 42);
+    assert_eq!(update.messages(&event("UPDATE", json!({}))).len(), 2);
+
+    // Updates leave the fields that didn't change alone, and empty the ones that were nulled; records that aren't there
+    // yet don't get a row, which would be missing whatever didn't change (NOT NULL columns included)
+    assert_eq!(update.statements(&table, &[])?, [
+      "UPDATE \"account\" SET \"Name\" = 'Shiba', \"BillingCity\" = 'Tokyo', \"BillingStreet\" = NULL WHERE \"Id\" IN ('001A', '001B')"
+    ]);
+    let mut city = Change::from_event(&event("UPDATE", json!({ "BillingAddress": { "City": "Kyoto" } })))?;
+    city.changed = vec!["BillingCity".to_string()];
+    assert_eq!(city.statements(&table, &[])?, ["UPDATE \"account\" SET \"BillingCity\" = 'Kyoto' WHERE \"Id\" IN ('001A', '001B')"]);
+    let mut unmapped = Change::from_event(&event("UPDATE", json!({})))?;
+    unmapped.changed = vec!["Description".to_string()];
+    assert!(unmapped.statements(&table, &[])?.is_empty());
+
+    let delete = Change::from_event(&event("DELETE", json!({})))?;
+    assert_eq!(delete.statements(&table, &[])?, ["DELETE FROM \"account\" WHERE \"Id\" IN ('001A', '001B')"]);
+
+    // Gaps go by the records as they are now
+    let gap       = Change::from_event(&event("GAP_UPDATE", json!({})))?;
+    let refetched = [json!({ "Id": "001A", "Name": "Akita", "BillingCity": null, "BillingStreet": "1 Dog Lane" }).as_object().unwrap().clone()];
+    assert!(gap.needs_refetch());
+    assert_eq!(gap.statements(&table, &refetched)?, ["\
+      INSERT INTO \"account\" (\"Id\", \"Name\", \"BillingCity\", \"BillingStreet\") VALUES\n\
+      ('001A', 'Akita', NULL, '1 Dog Lane')\n\
+      ON CONFLICT (\"Id\") DO UPDATE SET \"Name\" = EXCLUDED.\"Name\", \"BillingCity\" = EXCLUDED.\"BillingCity\", \"BillingStreet\" = EXCLUDED.\"BillingStreet\""
+    ]);
+    assert!(Change::from_event(&event("GAP_OVERFLOW", json!({})))?.statements(&table, &[]).is_err());
+    assert!(Change::from_event(&StreamingEvent { channel: "/data/AccountChangeEvent".to_string(), replay_id: 1, payload: json!({ "Name": "Shiba" }) }).is_err());
+    Ok(())
+  }
+}
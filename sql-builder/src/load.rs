@@ -0,0 +1,175 @@
+//! Loads bulk query results straight into Postgres with `COPY`, no intermediate file required.
+
+use std::pin::Pin;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::SinkExt;
+use oxidized_force::response::BulkQueryResults;
+use serde_json::{Map, Value};
+use tokio_postgres::{Client, CopyInSink, NoTls};
+
+use crate::extract::{parse_csv, Sink};
+use crate::sql::{CopyFormat, Pg, Table};
+
+/// Connects to a database URL, e.g. `postgres://etl@localhost/warehouse`.
+/// The connection itself runs in the background until the client is dropped.
+pub async fn connect(url: &str) -> anyhow::Result<Client> {
+  let (client, connection) = tokio_postgres::connect(url, NoTls).await?;
+  tokio::spawn(async move {
+    if let Err(err) = connection.await {
+      log::error!("Postgres connection failed: {}", err);
+    }
+  });
+  Ok(client)
+}
+
+/// Creates `table` unless it's already there, see `create_statement`.
+pub async fn create_table(client: &Client, table: &Table) -> anyhow::Result<()> {
+  client.batch_execute(&create_statement(table)).await?;
+  Ok(())
+}
+
+/// `CREATE TABLE IF NOT EXISTS` for loading records into, minus the foreign keys: the parents usually aren't loaded
+/// (or aren't loaded first), and keys that can't be satisfied would only turn away records.
+pub fn create_statement(table: &Table) -> String {
+  let mut table = table.clone();
+  table.drop_foreign_keys::<Pg>(&table.references());
+  table.if_not_exists();
+  table.generate::<Pg>()
+}
+
+/// Copies pages of results into a table with `COPY`; nothing shows up in the table until they're committed (or finished).
+pub struct PostgresSink<'a> {
+  client:    &'a Client,
+  table:     Table,
+  /// Creates the staging table when upserting
+  stage:     Option<String>,
+  statement: String,
+  /// Moves the staged rows over when upserting
+  merge:     Option<String>,
+  copy:      Option<Pin<Box<CopyInSink<Bytes>>>>,
+  records:   usize
+}
+
+impl<'a> PostgresSink<'a> {
+  /// Appends the records to the table.
+  pub async fn start(client: &'a Client, table: &Table) -> anyhow::Result<Self> {
+    let statement = table.rows().copy_statement(CopyFormat::Csv);
+    Ok(PostgresSink { client, table: table.clone(), stage: None, statement, merge: None, copy: None, records: 0 })
+  }
+
+  /// Inserts the records, or updates the rows with the same record Id.
+  pub async fn upsert(client: &'a Client, table: &Table) -> anyhow::Result<Self> {
+    let key = match table.field_columns().iter().find(|(field, _)| field == "Id") {
+      Some((_, column)) => vec![column.clone()],
+      None              => anyhow::bail!("cannot upsert into {} without an Id column", table.name())
+    };
+    let staged = table.rows().staged_upsert(&format!("{}_stage", table.name()), &key, CopyFormat::Csv);
+    Ok(PostgresSink {
+      client,
+      table: table.clone(),
+      stage: Some(staged.create),
+      statement: staged.copy,
+      merge: Some(staged.merge),
+      copy: None,
+      records: 0
+    })
+  }
+
+  async fn copy(&mut self) -> anyhow::Result<&mut Pin<Box<CopyInSink<Bytes>>>> {
+    if self.copy.is_none() {
+      if let Some(ref stage) = self.stage {
+        self.client.batch_execute(stage).await?;
+      }
+      self.copy = Some(Box::pin(self.client.copy_in(self.statement.as_str()).await?));
+    }
+    Ok(self.copy.as_mut().unwrap())
+  }
+}
+
+impl std::fmt::Debug for PostgresSink<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("PostgresSink").field("table", &self.table.name()).field("records", &self.records).finish()
+  }
+}
+
+#[async_trait]
+impl Sink for PostgresSink<'_> {
+  async fn write_page(&mut self, page: &BulkQueryResults) -> anyhow::Result<()> {
+    let mut csv = parse_csv(&page.body).into_iter();
+    let header  = match csv.next() {
+      Some(header) => header,
+      None         => return Ok(())
+    };
+
+    // Going through `Rows` gets multi-select picklists into arrays and so on; bulk queries can't tell empty from NULL
+    let mut rows = self.table.rows();
+    for row in csv {
+      let record = header
+        .iter()
+        .cloned()
+        .zip(row)
+        .map(|(field, raw)| (field, if raw.is_empty() { Value::Null } else { Value::String(raw) }))
+        .collect::<Map<_, _>>();
+      rows.push(&record);
+    }
+
+    let mut data = Vec::new();
+    rows.write_copy(CopyFormat::Csv, &mut data)?;
+    self.copy().await?.send(Bytes::from(data)).await?;
+    self.records += rows.len();
+    Ok(())
+  }
+
+  fn records(&self) -> usize {
+    self.records
+  }
+
+  /// Ends the `COPY` so far (merging the staged rows when upserting); the next page starts another one.
+  async fn commit(&mut self) -> anyhow::Result<()> {
+    if let Some(mut copy) = self.copy.take() {
+      copy.as_mut().finish().await?;
+      if let Some(ref merge) = self.merge {
+        self.client.batch_execute(merge).await?;
+      }
+    }
+    Ok(())
+  }
+
+  async fn finish(&mut self) -> anyhow::Result<()> {
+    self.commit().await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use oxidized_force::response::FieldType;
+  use oxidized_force::test_util::fixtures;
+
+  use crate::describe::{table_from_describe, Mapping};
+
+  #[test]
+  fn load_tables() {
+    let mut owner = fixtures::field("OwnerId", FieldType::Reference);
+    owner.reference_to = vec!["User".to_string()];
+    let desc  = fixtures::describe("Contact").field("Id", FieldType::Id).reference("AccountId").with_field(owner).build();
+    let table = table_from_describe("Contact", &desc, &Mapping::default());
+
+    // Parents don't get loaded along with their children, so there's nothing for keys to point at
+    let sql = create_statement(&table);
+    assert_eq!(sql, "\
+      CREATE TABLE IF NOT EXISTS \"Contact\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL UNIQUE,\n\
+      \"AccountId\" VARCHAR,\n\
+      \"OwnerId\" VARCHAR\n\
+      )");
+    assert_eq!(table.generate::<Pg>(), "\
+      CREATE TABLE \"Contact\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL UNIQUE,\n\
+      \"AccountId\" VARCHAR REFERENCES \"Account\" (\"Id\"),\n\
+      \"OwnerId\" VARCHAR REFERENCES \"User\" (\"Id\")\n\
+      )");
+  }
+}
@@ -0,0 +1,248 @@
+//! `login`: the OAuth web server (or device) flow, ending up with a refresh token in the token store so nobody has to pass passwords around.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use oxidized_force::client::{pkce_challenge, Client};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Where Salesforce sends the browser back to; the connected app needs `http://localhost:<port>/OauthRedirect` as a callback URL.
+pub const CALLBACK_PATH: &str = "/OauthRedirect";
+
+/// Refresh tokens from `login`, one per login endpoint, kept in a JSON file only the user can read.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenStore {
+  pub tokens: BTreeMap<String, StoredToken>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredToken {
+  pub refresh_token: String,
+  pub instance_url:  String,
+  /// When `login` saved it (RFC 3339)
+  pub saved_at:      String
+}
+
+impl TokenStore {
+  /// `$XDG_CONFIG_HOME/sf-etl/tokens.json`, or `~/.config/sf-etl/tokens.json`.
+  pub fn default_path() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+      .map(PathBuf::from)
+      .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("sf-etl").join("tokens.json"))
+  }
+
+  /// Reads the token store; a missing file means nobody logged in yet.
+  pub fn load<P>(path: P) -> anyhow::Result<Self>
+  where P: AsRef<Path> {
+    let path = path.as_ref();
+    match std::fs::read(path) {
+      Ok(contents)                                            => serde_json::from_slice(&contents).map_err(|err| anyhow::anyhow!("invalid token store {}: {}", path.display(), err)),
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(TokenStore::default()),
+      Err(err)                                                => Err(anyhow::anyhow!("cannot read {}: {}", path.display(), err))
+    }
+  }
+
+  /// Writes the token store (creating its directory), readable by the user alone since refresh tokens don't expire on their own.
+  pub fn save<P>(&self, path: P) -> anyhow::Result<()>
+  where P: AsRef<Path> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+      std::fs::create_dir_all(dir)?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(path).map_err(|err| anyhow::anyhow!("cannot write {}: {}", path.display(), err))?;
+    // The mode only applies to new files, and a store that's already there may have been left readable
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    std::io::Write::write_all(&mut file, &serde_json::to_vec_pretty(self)?)?;
+    Ok(())
+  }
+
+  pub fn get(&self, login_endpoint: &str) -> Option<&StoredToken> {
+    self.tokens.get(login_endpoint.trim_end_matches('/'))
+  }
+
+  /// Keeps the client's refresh token for `login_endpoint`, replacing whatever was there.
+  pub fn insert(&mut self, login_endpoint: &str, client: &Client) -> anyhow::Result<&StoredToken> {
+    let refresh_token = client
+      .refresh_token()
+      .ok_or_else(|| anyhow::anyhow!("no refresh token came back, the connected app needs the `refresh_token` (perform requests at any time) scope"))?;
+
+    let token = StoredToken {
+      instance_url: client.instance_url().unwrap_or_default(),
+      saved_at:     chrono::Utc::now().to_rfc3339(),
+      refresh_token
+    };
+    let key = login_endpoint.trim_end_matches('/').to_string();
+    self.tokens.insert(key.clone(), token);
+    Ok(&self.tokens[&key])
+  }
+}
+
+/// Logs in through the browser: opens the authorize page (printing it too, in case there's no browser to open)
+/// and waits on `port` for Salesforce to send the browser back with a code.
+pub async fn browser_login(client: &Client, port: u16) -> anyhow::Result<()> {
+  // `localhost` resolves to either one, depending on the browser; IPv6 might not be around at all though
+  let mut ipv4 = TcpListener::bind(("127.0.0.1", port)).await.map_err(|err| anyhow::anyhow!("cannot listen on port {}: {}", port, err))?;
+  let mut ipv6 = TcpListener::bind(("::1", port)).await.ok();
+
+  let redirect_uri = format!("http://localhost:{}{}", port, CALLBACK_PATH);
+  let verifier     = random_string(64);
+  let state        = random_string(24);
+  let url          = client.authorize_url(&redirect_uri, &state, Some(&pkce_challenge(&verifier)))?;
+
+  println!("Log in at {}", url);
+  if let Err(err) = open_browser(&url) {
+    log::warn!("Cannot open a browser ({}), open the link above instead", err);
+  }
+
+  let code = loop {
+    let (mut socket, _) = match ipv6.as_mut() {
+      Some(ipv6) => tokio::select! {
+        accepted = ipv4.accept() => accepted?,
+        accepted = ipv6.accept() => accepted?
+      },
+      None => ipv4.accept().await?
+    };
+    let mut request     = vec![0; 8192];
+    let read            = socket.read(&mut request).await?;
+
+    let (status, page, outcome) = match callback(&String::from_utf8_lossy(&request[..read]), &state) {
+      Callback::Code(code)  => ("200 OK", "Logged in, this tab can be closed.".to_string(), Some(Ok(code))),
+      Callback::Failed(err) => ("400 Bad Request", format!("Login failed: {}", err), Some(Err(anyhow::anyhow!("login failed: {}", err)))),
+      Callback::Forged      => ("400 Bad Request", "The state doesn't match this login.".to_string(), None),
+      // Favicons and the like
+      Callback::Other       => ("404 Not Found", "Not found".to_string(), None)
+    };
+    let body = format!("<!DOCTYPE html><html><body><p>{}</p></body></html>", html_escape(&page));
+    let res  = format!("HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body);
+    socket.write_all(res.as_bytes()).await?;
+
+    if let Some(outcome) = outcome {
+      break outcome?;
+    }
+  };
+
+  client.login_with_authorization_code(&code, &redirect_uri, Some(&verifier)).await?;
+  Ok(())
+}
+
+/// Logs in with the device flow, for terminals without a browser around: the user enters a code on another device.
+pub async fn device_login(client: &Client) -> anyhow::Result<()> {
+  let device = client.request_device_code().await?;
+  println!("Go to {} and enter {}", device.verification_uri, device.user_code);
+  client.login_with_device_code(&device).await?;
+  Ok(())
+}
+
+/// What came in on the callback port.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Callback {
+  Code(String),
+  Failed(String),
+  /// A redirect with some other state, which could be forged; the real one may still be on its way
+  Forged,
+  Other
+}
+
+/// Makes sense of the HTTP request Salesforce redirected the browser with, e.g. `GET /OauthRedirect?code=...&state=... HTTP/1.1`.
+pub fn callback(request: &str, state: &str) -> Callback {
+  let target = match request.lines().next().map(|line| line.split_whitespace().collect::<Vec<_>>()) {
+    Some(line) if line.len() == 3 && line[0] == "GET" => line[1].to_string(),
+    _                                                 => return Callback::Other
+  };
+  let url = match url::Url::parse(&format!("http://localhost{}", target)) {
+    Ok(url) if url.path() == CALLBACK_PATH => url,
+    _                                      => return Callback::Other
+  };
+
+  let params = url.query_pairs().into_owned().collect::<BTreeMap<_, _>>();
+  // Errors included, or anyone could cut the login short
+  if params.get("state").map(String::as_str) != Some(state) {
+    return Callback::Forged;
+  }
+  if let Some(error) = params.get("error") {
+    return Callback::Failed(params.get("error_description").map_or_else(|| error.clone(), |description| format!("{} ({})", description, error)));
+  }
+  match params.get("code") {
+    Some(code) => Callback::Code(code.clone()),
+    None       => Callback::Failed("no authorization code came back".to_string())
+  }
+}
+
+fn random_string(len: usize) -> String {
+  rand::thread_rng().sample_iter(&Alphanumeric).take(len).map(char::from).collect()
+}
+
+fn html_escape(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn open_browser(url: &str) -> std::io::Result<()> {
+  #[cfg(target_os = "macos")]
+  let mut command = std::process::Command::new("open");
+  // Not `cmd /C start`, which would cut the URL off at the first `&`
+  #[cfg(target_os = "windows")]
+  let mut command = std::process::Command::new("explorer");
+  #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+  let mut command = std::process::Command::new("xdg-open");
+
+  command.arg(url).stdout(std::process::Stdio::null()).stderr(std::process::Stdio::null()).spawn().map(drop)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use oxidized_force::test_util::{fixtures, Method, MockClient, MockResponse};
+
+  #[tokio::test]
+  async fn logins() -> anyhow::Result<()> {
+    let request = |target: &str| format!("GET {} HTTP/1.1\r\nHost: localhost:1717\r\n\r\n", target);
+    assert_eq!(callback(&request("/OauthRedirect?code=aPrx%3D%3D&state=xyz"), "xyz"), Callback::Code("aPrx==".to_string()));
+    assert_eq!(callback(&request("/favicon.ico"), "xyz"), Callback::Other);
+    assert_eq!(callback("POST /OauthRedirect HTTP/1.1\r\n\r\n", "xyz"), Callback::Other);
+    assert_eq!(callback(&request("/OauthRedirect?code=aPrx&state=abc"), "xyz"), Callback::Forged);
+    assert_eq!(callback(&request("/OauthRedirect?error=access_denied"), "xyz"), Callback::Forged);
+    assert_eq!(
+      callback(&request("/OauthRedirect?error=access_denied&error_description=end-user+denied+authorization&state=xyz"), "xyz"),
+      Callback::Failed("end-user denied authorization (access_denied)".to_string())
+    );
+
+    // Refresh tokens are kept by login endpoint, for the user's eyes only
+    let mock = MockClient::new();
+    let dir  = std::env::temp_dir().join(format!("sf-etl-tokens-{}", std::process::id()));
+    let path = dir.join("sf-etl").join("tokens.json");
+    let mut store = TokenStore::load(&path)?;
+    assert!(store.insert("https://login.salesforce.com", &mock).unwrap_err().to_string().contains("refresh_token"));
+
+    let mut token = fixtures::token("https://shibe.my.salesforce.com");
+    token["refresh_token"] = "5Aep861TSESvWeug_xvFHRBTTbf_YrTWgEyjBJrqMwQ".into();
+    mock.transport().on(Method::POST, "/services/oauth2/token", MockResponse::json(200, token));
+    mock.login_with_authorization_code("aPrx==", "http://localhost:1717/OauthRedirect", None).await?;
+    store.insert("https://login.salesforce.com/", &mock)?;
+    store.save(&path)?;
+    // Saving over a store that was left readable tightens it back up
+    #[cfg(unix)]
+    std::fs::set_permissions(&path, std::os::unix::fs::PermissionsExt::from_mode(0o644))?;
+    store.save(&path)?;
+
+    let stored = TokenStore::load(&path)?.get("https://login.salesforce.com").cloned().unwrap();
+    assert_eq!((stored.refresh_token.as_str(), stored.instance_url.as_str()), ("5Aep861TSESvWeug_xvFHRBTTbf_YrTWgEyjBJrqMwQ", "https://shibe.my.salesforce.com"));
+    assert!(TokenStore::load(&path)?.get("https://test.salesforce.com").is_none());
+    #[cfg(unix)]
+    assert_eq!(std::os::unix::fs::PermissionsExt::mode(&std::fs::metadata(&path)?.permissions()) & 0o777, 0o600);
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+  }
+}
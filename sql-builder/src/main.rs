@@ -4,98 +4,1820 @@
 #[macro_use]
 extern crate log;
 
-use std::path::PathBuf;
-use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::io::{BufWriter, Write};
 use std::fs::File;
 
+use futures::stream::{self, StreamExt};
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressStyle};
 use structopt::StructOpt;
 
 use oxidized_force::prelude::*;
 
-mod sql;
-use sql::*;
+mod config;
+
+use config::Config;
+
+use oxidized_force::response::DescribeResponse;
+use oxidized_force::streaming::{ReplayFrom, StreamingEvent};
+
+use sql_builder::codegen::{self, rust::RustOptions, schema::SchemaFormat};
+use sql_builder::describe::*;
+use sql_builder::extract::{self, ExtractFormat, Sink};
+use sql_builder::filter::FieldFilter;
+use sql_builder::kafka;
+use sql_builder::listen;
+use sql_builder::load;
+use sql_builder::login::{self, TokenStore};
+use sql_builder::schedule;
+use sql_builder::secrets;
+use sql_builder::state::{self, SyncState};
+use sql_builder::storage;
+use sql_builder::overrides::TypeOverrides;
+use sql_builder::sql::*;
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "sf-sql", about = "Builds SQL for Salesforce objects")]
 struct Opts {
+  /// TOML file with defaults for credentials (or `secret://` references to them), objects, field filters, dialect and outputs (`sf-etl.toml` if there is one)
+  #[structopt(long, global = true)]
+  config: Option<PathBuf>,
+
   /// Client Id
-  #[structopt(long, short = "c", env = "SF_CLIENT_ID", hide_env_values = true)]
-  client_id: String,
+  #[structopt(long, short = "c", env = "SF_CLIENT_ID", hide_env_values = true, global = true)]
+  client_id: Option<String>,
 
-  /// Keep it secret, keep it safe
-  #[structopt(long, short = "s", env = "SF_CLIENT_SECRET", hide_env_values = true)]
-  client_secret: String,
+  /// Keep it secret, keep it safe (set `SF_CLIENT_SECRET` rather than passing it, arguments show up in process listings)
+  #[structopt(long, short = "s", env = "SF_CLIENT_SECRET", hide_env_values = true, global = true)]
+  client_secret: Option<String>,
 
-  /// Login endpoint
-  #[structopt(long, short = "e", default_value = "https://login.salesforce.com", env = "SF_LOGIN_ENDPOINT")]
-  login_endpoint: String,
+  /// Login endpoint (https://login.salesforce.com by default); `SF_LOGIN_ENDPOINT` still works too
+  #[structopt(long, short = "e", env = "SF_LOGIN_URL", global = true)]
+  login_endpoint: Option<String>,
 
   /// Username
-  #[structopt(long, short = "u", env = "SF_USERNAME", hide_env_values = true)]
-  username: String,
+  #[structopt(long, short = "u", env = "SF_USERNAME", hide_env_values = true, global = true)]
+  username: Option<String>,
+
+  /// Password (same deal as the secret, `SF_PASSWORD` is the safer way)
+  #[structopt(long, short = "p", env = "SF_PASSWORD", hide_env_values = true, global = true)]
+  password: Option<String>,
+
+  /// Refresh token to log in with instead of a username and password (`secret://` references work here too)
+  #[structopt(long, env = "SF_REFRESH_TOKEN", hide_env_values = true, global = true)]
+  refresh_token: Option<String>,
+
+  /// Where `login` keeps refresh tokens, used when there's no username and password (`~/.config/sf-etl/tokens.json` by default)
+  #[structopt(long, env = "SF_TOKEN_STORE", global = true)]
+  token_store: Option<PathBuf>,
+
+  /// SObject name; can be repeated (or comma separated) to generate one script for several objects
+  #[structopt(long = "name", short, use_delimiter = true, global = true)]
+  names: Vec<String>,
+
+  /// Generates every queryable object in the org (see describeGlobal)
+  #[structopt(long, conflicts_with = "names", global = true)]
+  all_objects: bool,
+
+  /// Only custom objects (with --all-objects)
+  #[structopt(long, requires = "all-objects", global = true)]
+  custom_objects: bool,
+
+  /// Only generates fields matching this pattern, e.g. `Billing*` (`*` is anything, `?` one character; can be repeated)
+  #[structopt(long = "include-field", global = true)]
+  include_fields: Vec<String>,
+
+  /// Leaves out fields matching this pattern, even included ones (can be repeated)
+  #[structopt(long = "exclude-field", global = true)]
+  exclude_fields: Vec<String>,
+
+  /// Leaves out formula and roll-up summary fields
+  #[structopt(long, global = true)]
+  skip_calculated: bool,
+
+  /// Leaves out encrypted fields
+  #[structopt(long, global = true)]
+  skip_encrypted: bool,
+
+  /// Leaves out custom fields
+  #[structopt(long, conflicts_with = "custom-only", global = true)]
+  skip_custom: bool,
+
+  /// Only custom fields (and the record Id)
+  #[structopt(long, global = true)]
+  custom_only: bool,
+
+  /// Output file path (standard output if there isn't one); `extract` also takes `s3://bucket/prefix/`, `gs://...` or `az://account/container/...`
+  #[structopt(long, short, global = true)]
+  output: Option<PathBuf>,
+
+  /// Describes the objects and reports what would be generated where, without writing anything
+  #[structopt(long, global = true)]
+  dry_run: bool,
+
+  /// Database to generate SQL for: postgres (the default), mysql, sqlite, mssql, snowflake, redshift, duckdb or clickhouse
+  #[structopt(long, short = "d")]
+  dialect: Option<Dialect>,
+
+  /// Schema to create the tables in, e.g. `sfdc`
+  #[structopt(long)]
+  schema: Option<String>,
+
+  /// Goes in front of every table name (foreign keys included), e.g. `sf_`
+  #[structopt(long, default_value = "")]
+  table_prefix: String,
+
+  /// Goes after every table name, e.g. `_raw`
+  #[structopt(long, default_value = "")]
+  table_suffix: String,
+
+  /// Lower cases table names (`Account` -> `account`), leaving columns alone
+  #[structopt(long)]
+  lowercase_tables: bool,
+
+  /// When to quote table and column names: always, or when-needed (reserved words, mixed case and so on)
+  #[structopt(long, default_value = "always")]
+  quoting: Quoting,
+
+  /// Folds table and column names to lower or upper case (or preserves them)
+  #[structopt(long, default_value = "preserve")]
+  fold_case: CaseFolding,
+
+  /// Column naming: preserve (field names as-is), snake (`billing_city`) or lower (`billingcity`)
+  #[structopt(long, default_value = "preserve")]
+  naming: NamingCase,
+
+  /// Rewrites column names with a regex before the naming gets applied, e.g. `--replace '__c$='` (can be repeated)
+  #[structopt(long = "replace", parse(try_from_str = parse_replacement))]
+  replacements: Vec<(String, String)>,
+
+  /// Names the column for one field outright, e.g. `--rename Name=account_name` (can be repeated)
+  #[structopt(long = "rename", parse(try_from_str = parse_rename))]
+  renames: Vec<(String, String)>,
+
+  /// Also writes a JSON object mapping every field name to its column name (per table, for several objects)
+  #[structopt(long)]
+  mapping_output: Option<PathBuf>,
+
+  /// Also writes upsert statements (keyed on the record Id) for loading rows into the generated tables
+  #[structopt(long)]
+  upsert_output: Option<PathBuf>,
+
+  /// Adds a surrogate UUID primary key column (with this name) alongside the Salesforce Id
+  #[structopt(long, conflicts_with = "serial-key")]
+  uuid_key: Option<String>,
+
+  /// Adds an auto-incrementing (identity) primary key column (with this name) alongside the Salesforce Id
+  #[structopt(long)]
+  serial_key: Option<String>,
+
+  /// Maps DateTime fields to plain TIMESTAMP columns instead of TIMESTAMPTZ
+  #[structopt(long)]
+  naive_timestamps: bool,
+
+  /// Adds CHECK constraints restricting picklist columns to their active values
+  #[structopt(long)]
+  picklist_checks: bool,
+
+  /// Maps Location fields to PostGIS geography points
+  #[structopt(long)]
+  postgis: bool,
+
+  /// Maps Email, Picklist and Text fields to case-insensitive CITEXT columns
+  #[structopt(long)]
+  citext: bool,
+
+  /// Mirrors Salesforce delete behavior on foreign keys (cascade for master-detail, restrict, or set null)
+  #[structopt(long)]
+  foreign_key_actions: bool,
+
+  /// Makes foreign keys DEFERRABLE INITIALLY DEFERRED, so parents and children can be loaded in any order within a transaction
+  #[structopt(long)]
+  deferrable: bool,
+
+  /// Turns simple formula fields into generated columns instead of copying their values
+  #[structopt(long)]
+  computed_formulas: bool,
+
+  /// Collation for text columns, e.g. `C` or `en-US-x-icu`
+  #[structopt(long)]
+  collation: Option<String>,
+
+  /// Renders CREATE TABLE IF NOT EXISTS, so the script can be re-run
+  #[structopt(long, conflicts_with = "or-replace")]
+  if_not_exists: bool,
+
+  /// Replaces the table if it's already there (CREATE OR REPLACE, or a DROP first where that isn't supported)
+  #[structopt(long)]
+  or_replace: bool,
+
+  /// Creates an UNLOGGED (or Snowflake TRANSIENT) table, for staging loads that don't need to survive a crash
+  #[structopt(long, conflicts_with = "temporary")]
+  unlogged: bool,
+
+  /// Creates a TEMPORARY table
+  #[structopt(long)]
+  temporary: bool,
+
+  /// Writes a script dropping the table (if it exists) instead of creating it
+  #[structopt(long)]
+  teardown: bool,
+
+  /// Drops whatever depends on the table along with it (with --teardown)
+  #[structopt(long, requires = "teardown")]
+  cascade: bool,
+
+  /// Writes ALTER TABLE statements instead, bringing a table created from this saved describe (JSON) up to date
+  #[structopt(long, conflicts_with = "teardown")]
+  alter_from: Option<PathBuf>,
+
+  /// Also creates a `<table>_view` view with the field labels as column names and simple formulas computed live
+  #[structopt(long, conflicts_with_all = &["teardown", "alter-from"])]
+  view: bool,
+
+  /// Puts multi-select picklist values into `<table>_<field>_values` tables (a row per value) instead of array columns
+  #[structopt(long)]
+  normalize_multipicklists: bool,
+
+  /// Adds comments to the table and its columns, taken from the Salesforce labels
+  #[structopt(long)]
+  comments: bool,
+
+  /// Grants SELECT on the table to this role (can be repeated)
+  #[structopt(long = "grant")]
+  grants: Vec<String>,
+
+  /// Makes this role the owner of the table
+  #[structopt(long)]
+  owner: Option<String>,
+
+  /// Adds a (multi column) UNIQUE constraint, e.g. `--unique AccountId,ExternalKey__c` (can be repeated)
+  #[structopt(long = "unique", parse(try_from_str = parse_columns))]
+  unique_keys: Vec<Vec<String>>,
+
+  /// Sets a table option for the dialect, e.g. `ENGINE=Aria` (MySQL), `SORTKEY=CreatedDate` (Redshift),
+  /// `CLUSTER BY=AccountId` (Snowflake) or `fillfactor=70` (Postgres); can be repeated
+  #[structopt(long = "option", parse(try_from_str = parse_option))]
+  table_options: Vec<(String, String)>,
+
+  /// TOML file overriding SQL types by field type (`[types] currency = "NUMERIC(18,2)"`) or by field
+  /// (`[fields] "Account.Description" = "TEXT"`); --type still wins over it
+  #[structopt(long = "type-overrides")]
+  type_overrides_file: Option<PathBuf>,
+
+  /// Overrides the SQL type of a single column, e.g. `--type Amount=MONEY` (can be repeated)
+  #[structopt(long = "type", parse(try_from_str = parse_override))]
+  type_overrides: Vec<(String, String)>,
+
+  /// Generates something other than SQL from the describes (the object and field flags still apply)
+  #[structopt(subcommand)]
+  command: Option<Command>
+}
+
+#[derive(StructOpt, Debug)]
+enum Command {
+  /// Generates code for the objects instead of SQL
+  Codegen(Codegen),
+
+  /// Logs in through the browser and saves a refresh token in the token store, so later runs need no username or password
+  Login {
+    /// Local port Salesforce redirects the browser back to; the connected app needs `http://localhost:<port>/OauthRedirect` as a callback URL
+    #[structopt(long, default_value = "1717")]
+    port: u16,
+
+    /// Uses the device flow instead, entering a code on another device (for when there's no browser around)
+    #[structopt(long)]
+    device: bool
+  },
+
+  /// Pulls every record of an object (or just the filtered fields) with a bulk query, into a CSV (or JSON Lines) file
+  Extract {
+    /// Seconds between checks on the bulk query job
+    #[structopt(long, default_value = "5")]
+    poll_interval: u64,
+
+    /// csv, or jsonl for a JSON object per record
+    #[structopt(long, default_value = "csv")]
+    format: ExtractFormat,
+
+    /// Keys JSON Lines records by column name (after --naming, --rename and so on) instead of field name
+    #[structopt(long)]
+    column_names: bool,
+
+    /// Copies the records straight into Postgres instead, e.g. `postgres://etl@localhost/warehouse` (same as `load`)
+    #[structopt(long, env = "DATABASE_URL", hide_env_values = true)]
+    to: Option<String>,
+
+    /// Keeps track of the bulk query in this file, so an interrupted extract picks up where it left off (appending to the output)
+    #[structopt(long)]
+    checkpoint: Option<PathBuf>
+  },
+
+  /// Creates the table if it isn't there yet and copies every record of the object into it, straight from a bulk query
+  Load {
+    /// Postgres database URL, e.g. `postgres://etl@localhost/warehouse` (put the password in `DATABASE_URL` rather than here)
+    #[structopt(long, env = "DATABASE_URL", hide_env_values = true)]
+    to: String,
+
+    /// Seconds between checks on the bulk query job
+    #[structopt(long, default_value = "5")]
+    poll_interval: u64,
+
+    /// Keeps track of the bulk query in this file, so an interrupted load picks up where it left off
+    #[structopt(long)]
+    checkpoint: Option<PathBuf>
+  },
+
+  /// Publishes every record of each object to Kafka through a Kafka REST Proxy, a topic per object with messages keyed by record Id
+  Publish {
+    /// Kafka REST Proxy URL, e.g. `http://localhost:8082`
+    #[structopt(long, env = "KAFKA_REST_URL")]
+    kafka: String,
+
+    /// Goes in front of the object name to make the topic, e.g. `salesforce.` for `salesforce.Account`
+    #[structopt(long, default_value = "")]
+    topic_prefix: String,
+
+    /// Field whose value keys the messages
+    #[structopt(long, default_value = "Id")]
+    key: String,
+
+    /// json, or avro (the proxy registers the schema with its schema registry)
+    #[structopt(long, default_value = "json")]
+    serialization: kafka::Serialization,
+
+    /// Seconds between checks on the bulk query job
+    #[structopt(long, default_value = "5")]
+    poll_interval: u64
+  },
+
+  /// Keeps running, syncing each object on its schedule (from `[schedules]` in the config, or --schedule) until Ctrl-C or SIGTERM
+  Daemon {
+    /// Postgres database URL, e.g. `postgres://etl@localhost/warehouse`
+    #[structopt(long, env = "DATABASE_URL", hide_env_values = true)]
+    to: String,
+
+    /// For objects without a schedule of their own: `minute hour day month weekday` in UTC like cron (e.g. `*/15 * * * *`), or @hourly, @daily...
+    #[structopt(long)]
+    schedule: Option<schedule::Schedule>,
+
+    /// Only pulls records changed since the last sync, going by `SystemModstamp`
+    #[structopt(long)]
+    incremental: bool,
+
+    /// JSON file remembering where the last sync of every object left off (`sf-etl-state.json` by default)
+    #[structopt(long)]
+    state_file: Option<PathBuf>,
+
+    /// How many objects get synced at once
+    #[structopt(long, default_value = "4")]
+    parallel: usize,
+
+    /// Seconds between checks on the bulk query job
+    #[structopt(long, default_value = "5")]
+    poll_interval: u64
+  },
+
+  /// Keeps running, applying Change Data Capture events to the Postgres tables (or publishing them to Kafka) as they come in,
+  /// until Ctrl-C or SIGTERM; change data capture has to be enabled for the objects
+  Listen {
+    /// Postgres database URL, e.g. `postgres://etl@localhost/warehouse`
+    #[structopt(long, env = "DATABASE_URL", hide_env_values = true)]
+    to: Option<String>,
+
+    /// Kafka REST Proxy URL, publishing the change events (as JSON) a topic per object, keyed by record Id
+    #[structopt(long, env = "KAFKA_REST_URL")]
+    kafka: Option<String>,
 
-  /// Password
-  #[structopt(long, short = "p", env = "SF_PASSWORD", hide_env_values = true)]
-  password: String,
+    /// Goes in front of the object name to make the topic, e.g. `salesforce.` for `salesforce.Account`
+    #[structopt(long, default_value = "")]
+    topic_prefix: String,
 
-  /// SObject name
-  #[structopt(long, short)]
-  name: String,
+    /// JSON file remembering the last event applied for every object (`sf-etl-state.json` by default); give it a file
+    /// of its own when a daemon runs alongside, they'd write over each other's changes otherwise
+    #[structopt(long)]
+    state_file: Option<PathBuf>,
 
-  /// Output file path
-  #[structopt(long, short)]
-  output: PathBuf
+    /// Starts objects without a replay ID in the state file at the earliest event still retained (three days back) rather than now
+    #[structopt(long)]
+    earliest: bool
+  },
+
+  /// Brings Postgres tables up to date with the objects: creates (or alters) each table, upserts every record
+  /// (or just the changed ones) on the record Id and prints a summary
+  Sync {
+    /// Postgres database URL, e.g. `postgres://etl@localhost/warehouse`
+    #[structopt(long, env = "DATABASE_URL", hide_env_values = true)]
+    to: String,
+
+    /// Only pulls records changed since the last sync, going by `SystemModstamp`
+    #[structopt(long)]
+    incremental: bool,
+
+    /// JSON file remembering where the last sync of every object left off (`sf-etl-state.json` by default)
+    #[structopt(long)]
+    state_file: Option<PathBuf>,
+
+    /// How many objects get synced at once
+    #[structopt(long, default_value = "4")]
+    parallel: usize,
+
+    /// Seconds between checks on the bulk query job
+    #[structopt(long, default_value = "5")]
+    poll_interval: u64
+  }
+}
+
+#[derive(StructOpt, Debug)]
+enum Codegen {
+  /// Serde annotated structs, one per object, that `Client::query` can deserialize records into
+  Rust {
+    /// Uses `Decimal` for currency and percent fields (needs oxidized-force's `decimal` feature) rather than `f64`
+    #[structopt(long)]
+    decimal: bool,
+
+    /// Derives `SObject` (needs oxidized-force's `derive` feature) instead of `Serialize` and `Deserialize`
+    #[structopt(long)]
+    sobject: bool
+  },
+
+  /// A dbt `sources.yml` for the generated tables, with the labels as descriptions
+  Dbt {
+    /// Name of the dbt source
+    #[structopt(long, default_value = "salesforce")]
+    source: String,
+
+    /// Also writes a staging model (`stg_<source>__<table>.sql`) per object into this directory
+    #[structopt(long)]
+    staging: Option<PathBuf>
+  },
+
+  /// Avro or JSON Schema for the records, e.g. for publishing them to Kafka or validating exports
+  Schema {
+    /// avro or jsonschema
+    #[structopt(long, default_value = "jsonschema")]
+    format: SchemaFormat
+  }
+}
+
+/// A flag (or its environment variable) if it's there, otherwise the config file setting.
+fn required<T>(flag: Option<T>, config: Option<T>, name: &str, key: &str) -> anyhow::Result<T> {
+  flag.or(config).ok_or_else(|| anyhow::anyhow!("missing {} (or `{}` in the config file)", name, key))
+}
+
+fn parse_columns(arg: &str) -> anyhow::Result<Vec<String>> {
+  let columns = arg.split(',').map(|col| col.trim().to_string()).collect::<Vec<_>>();
+  match columns.iter().any(String::is_empty) {
+    true  => Err(anyhow::anyhow!("expected comma separated columns, got `{}`", arg)),
+    false => Ok(columns)
+  }
+}
+
+fn parse_replacement(arg: &str) -> anyhow::Result<(String, String)> {
+  match arg.rsplit_once('=') {
+    Some((pattern, replacement)) if !pattern.is_empty() => Ok((pattern.to_string(), replacement.to_string())),
+    _ => Err(anyhow::anyhow!("expected PATTERN=REPLACEMENT, got `{}`", arg))
+  }
+}
+
+fn parse_override(arg: &str) -> anyhow::Result<(String, String)> {
+  parse_pair(arg, "COLUMN=TYPE")
+}
+
+fn parse_rename(arg: &str) -> anyhow::Result<(String, String)> {
+  parse_pair(arg, "FIELD=COLUMN")
+}
+
+fn parse_option(arg: &str) -> anyhow::Result<(String, String)> {
+  parse_pair(arg, "KEY=VALUE")
+}
+
+fn parse_pair(arg: &str, expected: &str) -> anyhow::Result<(String, String)> {
+  match arg.split_once('=') {
+    Some((key, value)) if !key.is_empty() && !value.is_empty() => Ok((key.to_string(), value.to_string())),
+    _ => Err(anyhow::anyhow!("expected {}, got `{}`", expected, arg))
+  }
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
   pretty_env_logger::init();
-  let args = Opts::from_args();
+  let args   = Opts::from_args();
+  let config = Config::load(args.config.as_deref())?;
 
-  let mut client = Client::builder()
-    .client_id(args.client_id)
-    .client_secret(args.client_secret)
-    .login_endpoint(args.login_endpoint)
+  let dialect = match (args.dialect, config.dialect) {
+    (Some(dialect), _)    => dialect,
+    (None, Some(dialect)) => dialect.parse()?,
+    (None, None)          => Dialect::default()
+  };
+  if args.uuid_key.is_some() && dialect.uuid_default().is_none() {
+    anyhow::bail!("--uuid-key needs a way to generate UUIDs, which {:?} doesn't have", dialect);
+  }
+  let output         = args.output.or(config.output);
+  let mapping_output = args.mapping_output.or(config.mapping_output);
+  let upsert_output  = args.upsert_output.or(config.upsert_output);
+
+  let credentials    = config.credentials;
+  let login_endpoint = args.login_endpoint
+    .or_else(|| std::env::var("SF_LOGIN_ENDPOINT").ok())
+    .or(credentials.login_endpoint)
+    .unwrap_or_else(|| "https://login.salesforce.com".to_string());
+  // Any of the credentials can be `secret://` references instead
+  let client = Client::builder()
+    .client_id(secrets::resolve(required(args.client_id, credentials.client_id, "--client-id", "credentials.client_id")?).await?)
+    .client_secret(secrets::resolve(required(args.client_secret, credentials.client_secret, "--client-secret", "credentials.client_secret")?).await?)
+    .login_endpoint(login_endpoint.as_str())
     .create()?;
+  let token_store = args.token_store.or(config.token_store).or_else(TokenStore::default_path);
+
+  if let Some(Command::Login { port, device }) = args.command {
+    let path = token_store.ok_or_else(|| anyhow::anyhow!("nowhere to keep the token store, pass --token-store"))?;
+    match device {
+      true  => login::device_login(&client).await?,
+      false => login::browser_login(&client, port).await?
+    }
+
+    let mut store = TokenStore::load(&path)?;
+    let token     = store.insert(&login_endpoint, &client)?.clone();
+    store.save(&path)?;
+    println!("Logged into {}, the refresh token is in {}", token.instance_url, path.display());
+    return Ok(());
+  }
 
   info!("Attempting to log into Salesforce...");
-  client.login_with_credentials(args.username, args.password).await?;
+  let refresh_token = match secrets::resolve_opt(args.refresh_token.or(credentials.refresh_token)).await? {
+    Some(refresh_token) => Some(refresh_token),
+    None                => match token_store {
+      Some(ref path) => TokenStore::load(path)?.get(&login_endpoint).map(|token| token.refresh_token.clone()),
+      None           => None
+    }
+  };
+  // A username and password win over a refresh token
+  let username = secrets::resolve_opt(args.username.or(credentials.username)).await?;
+  let password = secrets::resolve_opt(args.password.or(credentials.password)).await?;
+  match (username, password, refresh_token) {
+    (None, None, Some(refresh_token)) => client.login_with_refresh_token(&refresh_token).await?,
+    (username, password, _)           => client.login_with_credentials(
+      required(username, None, "--username", "credentials.username").map_err(|err| anyhow::anyhow!("{}, or run `login` first", err))?,
+      required(password, None, "--password", "credentials.password")?
+    ).await?
+  }
 
-  info!("Describing object...");
-  let desc = client.describe(args.name.as_ref()).await?;
-  let mut table = Table::new(args.name);
+  let names = match (args.all_objects, args.names.is_empty()) {
+    (true, _)      => {
+      info!("Listing objects...");
+      queryable_objects(&client.describe_global().await?, args.custom_objects)
+    },
+    (false, false) => unique_names(&args.names),
+    (false, true)  => unique_names(&config.objects)
+  };
+  if names.is_empty() {
+    anyhow::bail!("missing --name (or `objects` in the config file)");
+  }
+  if names.len() > 1 && args.alter_from.is_some() {
+    anyhow::bail!("--alter-from only works for a single object");
+  }
 
-  // Create columns for all of the object fields
-  for field in &desc.fields {
-    let column = column_from_field(&field)
-      .nullable(field.nillable)
-      .unique(field.unique);
+  let mut descs = Vec::with_capacity(names.len());
+  for name in &names {
+    info!("Describing {}...", name);
+    descs.push(client.describe(name.as_str()).await?);
+  }
 
-    table.add_column(&field.name, column);
+  let mut naming = Naming::default().case(args.naming);
+  for (pattern, replacement) in &args.replacements {
+    naming = naming.replace(pattern, replacement.as_str())?;
   }
+  for (field, column) in &args.renames {
+    naming = naming.rename(field.as_str(), column.as_str());
+  }
+
+  // Patterns add up, flags can only be turned on
+  let mut fields = config.fields;
+  fields.include.extend(args.include_fields);
+  fields.exclude.extend(args.exclude_fields);
+  fields.skip_calculated |= args.skip_calculated;
+  fields.skip_encrypted  |= args.skip_encrypted;
+  fields.skip_custom     |= args.skip_custom;
+  fields.custom_only     |= args.custom_only;
 
-  info!("Writing SQL file...");
-  let mut output = File::create(args.output)?;
-  output.write_all(table.generate::<Pg>().as_bytes())?;
+  let type_overrides = match args.type_overrides_file.or(config.type_overrides) {
+    Some(path) => TypeOverrides::load(path)?,
+    None       => TypeOverrides::default()
+  };
 
+  let mapping = Mapping {
+    uuid_key:                 args.uuid_key,
+    serial_key:               args.serial_key,
+    naive_timestamps:         args.naive_timestamps,
+    picklist_checks:          args.picklist_checks,
+    postgis:                  args.postgis,
+    citext:                   args.citext,
+    foreign_key_actions:      args.foreign_key_actions,
+    deferrable:               args.deferrable,
+    collation:                args.collation,
+    computed_formulas:        args.computed_formulas,
+    comments:                 args.comments,
+    normalize_multipicklists: args.normalize_multipicklists,
+    dialect,
+    identifiers:              Identifiers::default().quoting(args.quoting).folding(args.fold_case),
+    naming,
+    filter:                   fields.filter(),
+    type_overrides,
+    schema:                   args.schema,
+    table_prefix:             args.table_prefix,
+    table_suffix:             args.table_suffix,
+    lowercase_tables:         args.lowercase_tables
+  };
+
+  let mut tables = names
+    .iter()
+    .zip(&descs)
+    .map(|(name, desc)| table_from_describe(mapping.table_name(name), desc, &mapping))
+    .collect::<Vec<_>>();
+
+  match args.command {
+    Some(Command::Codegen(codegen)) => return write_codegen(codegen, &descs, &tables, &mapping, output, args.dry_run),
+    Some(Command::Extract { poll_interval, format, column_names, to, checkpoint }) => {
+      let extract = Extract { poll_interval: std::time::Duration::from_secs(poll_interval), format, column_names, to, checkpoint };
+      return run_extract(&client, &descs, &tables, &mapping, output, &extract, args.dry_run).await;
+    },
+    Some(Command::Load { to, poll_interval, checkpoint }) => {
+      let extract = Extract {
+        poll_interval: std::time::Duration::from_secs(poll_interval),
+        format:        ExtractFormat::Csv,
+        column_names:  false,
+        to:            Some(to),
+        checkpoint
+      };
+      return run_extract(&client, &descs, &tables, &mapping, output, &extract, args.dry_run).await;
+    },
+    Some(Command::Publish { kafka, topic_prefix, key, serialization, poll_interval }) => {
+      let publish = Publish { kafka, topic_prefix, key, serialization, poll_interval: std::time::Duration::from_secs(poll_interval) };
+      return run_publish(&client, &descs, &mapping, &publish, args.dry_run).await;
+    },
+    Some(Command::Sync { to, incremental, state_file, parallel, poll_interval }) => {
+      let sync = SyncOptions {
+        to,
+        incremental,
+        state_file:    state_file.or(config.state_file).unwrap_or_else(|| PathBuf::from(state::DEFAULT_PATH)),
+        parallel:      parallel.max(1),
+        poll_interval: std::time::Duration::from_secs(poll_interval)
+      };
+      return run_sync(&client, &descs, &tables, &mapping, &sync, args.dry_run).await;
+    },
+    Some(Command::Daemon { to, schedule, incremental, state_file, parallel, poll_interval }) => {
+      let sync = SyncOptions {
+        to,
+        incremental,
+        state_file:    state_file.or(config.state_file).unwrap_or_else(|| PathBuf::from(state::DEFAULT_PATH)),
+        parallel:      parallel.max(1),
+        poll_interval: std::time::Duration::from_secs(poll_interval)
+      };
+      let schedules = object_schedules(&descs, &config.schedules, schedule.as_ref())?;
+      return run_daemon(&client, &descs, &tables, &mapping, &DaemonOptions { sync, schedules }, args.dry_run).await;
+    },
+    Some(Command::Listen { to, kafka, topic_prefix, state_file, earliest }) => {
+      let listen = Listen {
+        to,
+        kafka,
+        topic_prefix,
+        state_file: state_file.or(config.state_file).unwrap_or_else(|| PathBuf::from(state::DEFAULT_PATH)),
+        earliest
+      };
+      return run_listen(&client, &descs, &tables, &mapping, &listen, args.dry_run).await;
+    },
+    Some(Command::Login { .. }) | None => ()
+  }
+
+  // With several objects, column level settings go to whichever objects have the column
+  for (column, sql) in &args.type_overrides {
+    let mut overridden = false;
+    for table in &mut tables {
+      overridden |= table.override_type(column, sql);
+    }
+    if !overridden {
+      anyhow::bail!("cannot override the type of `{}`, {} has no such field", column, names.join(", "));
+    }
+  }
+  for columns in &args.unique_keys {
+    let mut keyed = tables.iter_mut().filter(|table| columns.iter().all(|col| table.has_column(col))).peekable();
+    if keyed.peek().is_none() {
+      anyhow::bail!("cannot add a unique key on `{}`, {} has no such fields", columns.join(", "), names.join(", "));
+    }
+    for table in keyed {
+      table.unique(&columns.iter().map(String::as_str).collect::<Vec<_>>());
+    }
+  }
+
+  for table in &mut tables {
+    for (key, value) in &args.table_options {
+      table.option(key.as_str(), value.as_str());
+    }
+  }
+
+  let mut junctions = tables
+    .iter()
+    .zip(&descs)
+    .flat_map(|(table, desc)| junction_tables(table.name(), desc, &mapping))
+    .collect::<Vec<_>>();
+  for table in tables.iter_mut().chain(&mut junctions) {
+    if args.if_not_exists {
+      table.if_not_exists();
+    }
+    if args.or_replace {
+      table.or_replace();
+    }
+    if args.unlogged {
+      table.unlogged();
+    }
+    if args.temporary {
+      table.temporary();
+    }
+    for role in &args.grants {
+      table.grant("SELECT", role);
+    }
+    if let Some(ref role) = args.owner {
+      table.owner(role);
+    }
+  }
+
+  // Anything that's read has to be read before the output gets truncated, in case they're the same file
+  let previous = match args.alter_from {
+    Some(path) => Some(serde_json::from_reader::<_, oxidized_force::response::DescribeResponse>(File::open(path)?)?),
+    None       => None
+  };
+
+  let mut schema = Schema::new();
+  for table in tables.iter().chain(&junctions) {
+    schema.add_table(table.clone());
+  }
+
+  if args.dry_run {
+    let action = match (args.teardown, previous.is_some()) {
+      (true, _)      => "drop",
+      (false, true)  => "alter",
+      (false, false) => "create"
+    };
+    let outputs = [("SQL", output.as_deref()), ("mapping", mapping_output.as_deref()), ("upserts", upsert_output.as_deref())];
+    print!("{}", dry_run_report(action, &schema, &outputs));
+    return Ok(());
+  }
+
+  if let Some(path) = mapping_output {
+    serde_json::to_writer_pretty(File::create(path)?, &field_mapping(&tables))?;
+  }
+
+  if let Some(path) = upsert_output {
+    // Value tables have nothing but their primary key
+    let upserts = tables
+      .iter()
+      .map(|table| table.generate_upsert_for(dialect, &["Id"]))
+      .chain(junctions.iter().map(|junction| junction.generate_upsert_for(dialect, &[])))
+      .map(|statements| statements.map(|statements| statements.join(";\n")))
+      .collect::<anyhow::Result<Vec<_>>>()?;
+    std::fs::write(path, upserts.join(";\n\n"))?;
+  }
+
+  info!("Writing SQL...");
+  let to_stdout  = output.is_none();
+  let mut output = match output {
+    Some(path) => Box::new(BufWriter::new(File::create(path)?)) as Box<dyn Write>,
+    None       => Box::new(BufWriter::new(std::io::stdout()))
+  };
+  match (args.teardown, previous) {
+    // Children go first, the other way around from creating them
+    (true, _)               => {
+      let options = DropOptions::default().if_exists(true).cascade(args.cascade);
+      let drops   = schema.order()
+        .iter()
+        .rev()
+        .filter_map(|name| schema.tables().iter().find(|table| table.name() == *name))
+        .map(|table| table.generate_drop_for(dialect, options))
+        .collect::<Vec<_>>();
+      output.write_all(drops.join(";\n").as_bytes())?
+    },
+    (false, Some(previous)) => {
+      // Overrides only apply to the columns that were there back then
+      let mut old = table_from_describe(tables[0].name(), &previous, &mapping);
+      for (column, sql) in &args.type_overrides {
+        old.override_type(column, sql);
+      }
+      output.write_all(tables[0].generate_alter_for(dialect, &old).as_bytes())?
+    },
+    (false, None)           => {
+      schema.generate_into_for(dialect, &mut output)?;
+      if args.view {
+        for (table, desc) in tables.iter().zip(&descs) {
+          write!(output, ";\n\n{}", view_from_describe(table, desc, &mapping).generate_for(dialect))?;
+        }
+      }
+    }
+  }
+  // Files end right after the last statement, the terminal gets a newline
+  if to_stdout {
+    writeln!(output)?;
+  }
+  output.flush()?;
+
+  Ok(())
+}
+
+/// Writes whatever `codegen` generates in place of the SQL (along with any extra files, like dbt models).
+fn write_codegen(codegen: Codegen, descs: &[DescribeResponse], tables: &[Table], mapping: &Mapping, output: Option<PathBuf>, dry_run: bool) -> anyhow::Result<()> {
+  let (what, code, files) = match codegen {
+    Codegen::Rust { decimal, sobject } => {
+      let options = RustOptions::new().decimal(decimal).sobject(sobject).filter(mapping.filter.clone());
+      ("Rust structs", codegen::rust::generate(descs, &options), Vec::new())
+    },
+    Codegen::Dbt { source, staging } => {
+      let models = match staging {
+        Some(dir) => tables
+          .iter()
+          .map(|table| (dir.join(format!("{}.sql", codegen::dbt::model_name(&source, table))), codegen::dbt::staging_model(&source, table, mapping)))
+          .collect(),
+        None      => Vec::new()
+      };
+      let tables = tables.iter().zip(descs).collect::<Vec<_>>();
+      ("dbt sources", codegen::dbt::sources(&source, &tables, mapping), models)
+    },
+    Codegen::Schema { format } => {
+      let schema = codegen::schema::generate(descs, format, &mapping.filter);
+      ("schemas", format!("{}\n", serde_json::to_string_pretty(&schema)?), Vec::new())
+    }
+  };
+
+  if dry_run {
+    let names = descs.iter().map(|desc| desc.name.as_str()).collect::<Vec<_>>();
+    println!("Would generate {} for {} object(s): {}", what, names.len(), names.join(", "));
+    match output {
+      Some(path) => println!("{} would be written to {}", what, path.display()),
+      None       => println!("{} would go to standard output", what)
+    }
+    for (path, _) in &files {
+      println!("{} would be written", path.display());
+    }
+    return Ok(());
+  }
+
+  info!("Writing {}...", what);
+  match output {
+    Some(path) => std::fs::write(path, code)?,
+    None       => print!("{}", code)
+  }
+  for (path, contents) in files {
+    if let Some(dir) = path.parent() {
+      std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, contents)?;
+  }
   Ok(())
 }
 
-fn column_from_field(field: &oxidized_force::response::Field) -> Type {
-  use oxidized_force::response::FieldType::*;
-
-  match &field.field_type {
-    MultiPicklist => array(&varchar(None)),
-    Reference     => foreign(field.relationship_name.as_ref().unwrap(), vec!["Id"]),
-    Id            => varchar(None).primary(true),
-    AnyType       => jsonb(),
-    Boolean       => boolean(),
-    Time          => time(),
-    Date          => date(),
-    DateTime      => datetime(),
-    Double        => double(),
-    Int           => integer(),
-    Long          => bigint(),
-    _             => varchar(Some(field.length as usize))
+/// How `extract` pulls records and writes them out.
+#[derive(Debug)]
+struct Extract {
+  poll_interval: std::time::Duration,
+  format:        ExtractFormat,
+  column_names:  bool,
+  /// Postgres database to copy the records into, instead of `output`
+  to:            Option<String>,
+  /// Where to keep the `Checkpoint`, if the extract should be resumable
+  checkpoint:    Option<PathBuf>
+}
+
+/// Bulk queries the (single) object and streams the results to `output` (or standard output), or a Postgres table.
+async fn run_extract(client: &Client, descs: &[DescribeResponse], tables: &[Table], mapping: &Mapping, output: Option<PathBuf>, options: &Extract, dry_run: bool) -> anyhow::Result<()> {
+  let (desc, table) = match (descs, tables) {
+    ([desc], [table]) => (desc, table),
+    _                 => anyhow::bail!("extract only works for a single object")
+  };
+  let fields = extract::bulk_fields(desc, &mapping.filter);
+
+  if options.to.is_some() && mapping.dialect != Dialect::Pg {
+    anyhow::bail!("records can only be loaded into Postgres, not {:?}", mapping.dialect);
+  }
+
+  let file_name = format!("{}.{}", desc.name, match options.format {
+    ExtractFormat::Csv       => "csv",
+    ExtractFormat::JsonLines => "jsonl"
+  });
+  let target = match output {
+    Some(ref path) => storage::Target::from_output(path, &file_name)?,
+    None           => None
+  };
+  if target.is_some() && options.checkpoint.is_some() {
+    anyhow::bail!("--checkpoint needs a local output to append to, uploads can't be resumed");
+  }
+
+  if dry_run {
+    println!("Would extract {} field(s) of {}: {}", fields.len(), desc.name, fields.join(", "));
+    match (&options.to, &target, output) {
+      (Some(_), _, _)          => println!("records would be copied into {} (created if it isn't there)", table.name()),
+      (None, Some(target), _)  => println!("records would be uploaded to {}", target),
+      (None, None, Some(path)) => println!("records would be written to {}", path.display()),
+      (None, None, None)       => println!("records would go to standard output")
+    }
+    return Ok(());
+  }
+
+  let resume = match options.checkpoint {
+    Some(ref path) => match extract::Checkpoint::load(path)? {
+      Some(checkpoint) if checkpoint.matches(&desc.name, &fields, None) && checkpoint.resumable(client).await => Some(checkpoint),
+      _                                                                                                       => None
+    },
+    None           => None
+  };
+  if let Some(ref checkpoint) = resume {
+    info!("Resuming bulk query {} after {} page(s), {} record(s)", checkpoint.job_id, checkpoint.pages, checkpoint.records);
+  }
+  let save = |checkpoint: &extract::Checkpoint| match options.checkpoint {
+    Some(ref path) => checkpoint.save(path),
+    None           => Ok(())
+  };
+
+  if let Some(ref url) = options.to {
+    info!("Connecting to Postgres...");
+    let db = load::connect(url).await?;
+    load::create_table(&db, table).await?;
+
+    info!("Loading {} into {}...", desc.name, table.name());
+    let mut sink = load::PostgresSink::start(&db, table).await?;
+    let records  = extract::extract_resumable(client, &desc.name, &fields, None, options.poll_interval, &mut sink, resume, |checkpoint, _| save(checkpoint)).await?;
+    remove_checkpoint(options.checkpoint.as_ref())?;
+
+    info!("Loaded {} record(s) of {}", records, desc.name);
+    return Ok(());
+  }
+
+  info!("Extracting {}...", desc.name);
+  let mut upload = None;
+  let output = match (target, output) {
+    (Some(target), _) => {
+      info!("Uploading to {}...", target);
+      let (started, stdin) = target.upload()?;
+      upload = Some(started);
+      Box::new(BufWriter::new(stdin)) as Box<dyn Write + Send>
+    },
+    (None, Some(path)) if resume.is_some() => Box::new(BufWriter::new(File::options().append(true).open(path)?)),
+    (None, Some(path))                     => Box::new(BufWriter::new(File::create(path)?)),
+    (None, None)                           => Box::new(BufWriter::new(std::io::stdout()))
+  };
+  let mut sink = match options.format {
+    ExtractFormat::Csv if resume.is_some() => Box::new(extract::CsvSink::resume(output)) as Box<dyn Sink>,
+    ExtractFormat::Csv                     => Box::new(extract::CsvSink::new(output)),
+    ExtractFormat::JsonLines => {
+      let sink = extract::JsonLinesSink::new(output).typed(desc);
+      match options.column_names {
+        true  => Box::new(sink.columns(table.field_columns())),
+        false => Box::new(sink)
+      }
+    }
+  };
+  let records = extract::extract_resumable(client, &desc.name, &fields, None, options.poll_interval, sink.as_mut(), resume, |checkpoint, _| save(checkpoint)).await;
+  // The upload only finishes once its input is closed
+  drop(sink);
+  let records = match (records, upload) {
+    (Ok(records), Some(upload)) => {
+      upload.finish()?;
+      records
+    },
+    (Err(err), Some(upload))    => {
+      upload.abort();
+      return Err(err);
+    },
+    (records, None)             => records?
+  };
+  remove_checkpoint(options.checkpoint.as_ref())?;
+
+  info!("Extracted {} record(s) of {}", records, desc.name);
+  Ok(())
+}
+
+/// Done with the checkpoint file, if there was one.
+fn remove_checkpoint(path: Option<&PathBuf>) -> anyhow::Result<()> {
+  match path.map(std::fs::remove_file) {
+    Some(Err(err)) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+    _                                                            => Ok(())
+  }
+}
+
+/// Where and how `publish` sends the records.
+#[derive(Debug)]
+struct Publish {
+  kafka:         String,
+  topic_prefix:  String,
+  key:           String,
+  serialization: kafka::Serialization,
+  poll_interval: std::time::Duration
+}
+
+/// Bulk queries each object in turn and publishes the records to its topic as the pages come in.
+async fn run_publish(client: &Client, descs: &[DescribeResponse], mapping: &Mapping, options: &Publish, dry_run: bool) -> anyhow::Result<()> {
+  let objects = descs.iter().map(|desc| (desc, extract::bulk_fields(desc, &mapping.filter), format!("{}{}", options.topic_prefix, desc.name))).collect::<Vec<_>>();
+  for (desc, fields, _) in &objects {
+    if !fields.contains(&options.key) {
+      anyhow::bail!("cannot key messages by `{}`, {} has no such field (or it's filtered out)", options.key, desc.name);
+    }
+  }
+
+  if dry_run {
+    for (desc, fields, topic) in &objects {
+      println!("Would publish {} field(s) of {} to {} as {:?}, keyed by {}: {}", fields.len(), desc.name, topic, options.serialization, options.key, fields.join(", "));
+    }
+    return Ok(());
+  }
+
+  for (desc, fields, topic) in &objects {
+    info!("Publishing {} to {}...", desc.name, topic);
+    let mut sink = kafka::KafkaSink::new(&options.kafka, topic, desc, fields, options.serialization).key(&options.key);
+    let records  = extract::extract(client, &desc.name, fields, options.poll_interval, &mut sink).await?;
+    info!("Published {} record(s) of {} to {}", records, desc.name, topic);
+  }
+  Ok(())
+}
+
+/// How `sync` brings tables up to date.
+#[derive(Debug)]
+struct SyncOptions {
+  to:            String,
+  incremental:   bool,
+  state_file:    PathBuf,
+  parallel:      usize,
+  poll_interval: std::time::Duration
+}
+
+/// Syncs every object (`parallel` at a time): creating its Postgres table (or bringing it up to date with the describe),
+/// upserting the records and remembering where it left off. One object failing doesn't stop the rest; the summary says what happened.
+async fn run_sync(client: &Client, descs: &[DescribeResponse], tables: &[Table], mapping: &Mapping, options: &SyncOptions, dry_run: bool) -> anyhow::Result<()> {
+  check_syncable(descs, mapping)?;
+  let state = SyncState::load(&options.state_file)?;
+
+  if dry_run {
+    for (desc, table) in descs.iter().zip(tables) {
+      let plan = SyncPlan::new(desc, table, mapping, &state, options.incremental);
+      println!("Would sync {} field(s) of {} into {}: {}", plan.fields.len(), desc.name, table.name(), plan.fields.join(", "));
+      if let Some(alter) = plan.alter.as_ref().filter(|alter| !alter.is_empty()) {
+        println!("after altering {}:\n{}", table.name(), alter);
+      }
+      match plan.condition {
+        Some(ref condition) => println!("only records where {}", condition),
+        None                => println!("every record")
+      }
+    }
+    return Ok(());
+  }
+
+  // Plans come from the state as it was at the start, the state itself gets updated (and saved) as objects finish
+  let plans    = descs.iter().zip(tables).map(|(desc, table)| SyncPlan::new(desc, table, mapping, &state, options.incremental)).collect::<Vec<_>>();
+  let state    = std::sync::Mutex::new(state);
+  let progress = MultiProgress::new();
+  let mut runs = stream::iter(descs.iter().zip(tables).zip(&plans).enumerate())
+    .map(|(index, ((desc, table), plan))| {
+      let bar   = progress.add(ProgressBar::new_spinner().with_style(progress_style()).with_prefix(desc.name.clone()));
+      let state = &state;
+      bar.enable_steady_tick(std::time::Duration::from_millis(200));
+
+      async move {
+        let started = std::time::Instant::now();
+        let result  = sync_object(client, options, desc, table, plan, state, &bar).await;
+        match result {
+          Ok((records, _)) => bar.finish_with_message(format!("done, {} record(s)", records)),
+          Err(ref err)     => bar.abandon_with_message(format!("failed: {}", err))
+        }
+        (index, started.elapsed(), result)
+      }
+    })
+    .buffer_unordered(options.parallel);
+
+  let mut summaries = Vec::with_capacity(descs.len());
+  while let Some((index, elapsed, result)) = runs.next().await {
+    let (desc, table, plan) = (&descs[index], &tables[index], &plans[index]);
+    let outcome             = record_sync(&state, &options.state_file, desc, result)?;
+    summaries.push((index, SyncSummary {
+      object:      desc.name.clone(),
+      table:       table.name(),
+      incremental: plan.condition.is_some(),
+      altered:     plan.alter.as_ref().is_some_and(|alter| !alter.is_empty()),
+      elapsed,
+      outcome
+    }));
+  }
+  drop(runs);
+
+  // Back in the order the objects were given
+  summaries.sort_by_key(|(index, _)| *index);
+  let summaries = summaries.into_iter().map(|(_, summary)| summary).collect::<Vec<_>>();
+
+  print!("{}", sync_report(&summaries));
+  match summaries.iter().filter(|summary| summary.outcome.is_err()).count() {
+    0      => Ok(()),
+    failed => Err(anyhow::anyhow!("{} of {} object(s) failed to sync", failed, summaries.len()))
+  }
+}
+
+/// Each object's schedule from the config (by object name, any case), or `fallback` for the ones it doesn't have.
+fn object_schedules(descs: &[DescribeResponse], configured: &std::collections::BTreeMap<String, String>, fallback: Option<&schedule::Schedule>) -> anyhow::Result<Vec<schedule::Schedule>> {
+  if let Some(object) = configured.keys().find(|object| !descs.iter().any(|desc| desc.name.eq_ignore_ascii_case(object))) {
+    warn!("There's a schedule for {}, but it isn't one of the objects", object);
+  }
+
+  descs
+    .iter()
+    .map(|desc| match configured.iter().find(|(object, _)| desc.name.eq_ignore_ascii_case(object)) {
+      Some((_, schedule)) => schedule.parse().map_err(|err| anyhow::anyhow!("{} (for {})", err, desc.name)),
+      None                => fallback.cloned().ok_or_else(|| anyhow::anyhow!("{} has no schedule, add it to [schedules] in the config or pass --schedule", desc.name))
+    })
+    .collect()
+}
+
+/// Objects can only be synced into Postgres, and only if they have a `SystemModstamp`.
+fn check_syncable(descs: &[DescribeResponse], mapping: &Mapping) -> anyhow::Result<()> {
+  if mapping.dialect != Dialect::Pg {
+    anyhow::bail!("records can only be synced into Postgres, not {:?}", mapping.dialect);
+  }
+  if let Some(desc) = descs.iter().find(|desc| desc.field(MODSTAMP).is_none()) {
+    anyhow::bail!("{} has no {} field to sync by", desc.name, MODSTAMP);
+  }
+  Ok(())
+}
+
+/// Remembers where a successful sync left off (and saves the state); failures are logged and come back as the message.
+fn record_sync(state: &std::sync::Mutex<SyncState>, state_file: &Path, desc: &DescribeResponse, result: anyhow::Result<(usize, Option<String>)>) -> anyhow::Result<Result<usize, String>> {
+  match result {
+    Ok((records, latest)) => {
+      info!("Synced {} record(s) of {}", records, desc.name);
+      let mut state = state.lock().unwrap();
+      if let Some(latest) = latest {
+        state.set_watermark(&desc.name, latest);
+      }
+      state.set_describe(desc).clear_checkpoint(&desc.name);
+      state.save(state_file)?;
+      Ok(Ok(records))
+    },
+    Err(err)              => {
+      error!("Syncing {} failed: {}", desc.name, err);
+      Ok(Err(err.to_string()))
+    }
+  }
+}
+
+/// What syncing an object is going to do, worked out up front so `--dry-run` can show it.
+#[derive(Debug)]
+struct SyncPlan {
+  fields:    Vec<String>,
+  /// `ALTER TABLE` statements catching the table up with the describe, when there's a previous describe to go by
+  alter:     Option<String>,
+  condition: Option<String>
+}
+
+impl SyncPlan {
+  fn new(desc: &DescribeResponse, table: &Table, mapping: &Mapping, state: &SyncState, incremental: bool) -> Self {
+    // The watermark comes out of the records, so the field gets queried even if it's filtered out of the table
+    let mut fields = extract::bulk_fields(desc, &mapping.filter);
+    if !fields.iter().any(|field| field == MODSTAMP) {
+      fields.push(MODSTAMP.to_string());
+    }
+
+    let alter = state
+      .describe(&desc.name)
+      .map(|previous| table.generate_alter_for(Dialect::Pg, &table_from_describe(table.name(), previous, mapping)));
+    // Records modified in the same millisecond as the watermark may not have made it yet; upserting them again is harmless
+    let condition = match (incremental, state.watermark(&desc.name)) {
+      (true, Some(watermark)) => Some(format!("{} >= {}", MODSTAMP, watermark)),
+      _                       => None
+    };
+
+    SyncPlan { fields, alter, condition }
+  }
+}
+
+/// Syncs an object as planned over a connection of its own, checkpointing as pages make it in so an interrupted sync
+/// picks up where it left off; returns the number of records and the latest `SystemModstamp`.
+async fn sync_object(
+  client:  &Client,
+  options: &SyncOptions,
+  desc:    &DescribeResponse,
+  table:   &Table,
+  plan:    &SyncPlan,
+  state:   &std::sync::Mutex<SyncState>,
+  bar:     &ProgressBar
+) -> anyhow::Result<(usize, Option<String>)> {
+  let resume = state.lock().unwrap().checkpoint(&desc.name).map(|(checkpoint, latest)| (checkpoint.clone(), latest.map(String::from)));
+  let resume = match resume {
+    Some((checkpoint, latest)) if checkpoint.matches(&desc.name, &plan.fields, plan.condition.as_deref()) && checkpoint.resumable(client).await => {
+      bar.inc(checkpoint.records as u64);
+      Some((checkpoint, latest))
+    },
+    _ => None
+  };
+  let (checkpoint, latest) = resume.map_or((None, None), |(checkpoint, latest)| (Some(checkpoint), latest));
+
+  bar.set_message("connecting");
+  let db = load::connect(&options.to).await?;
+  // Held until the connection goes away, so two syncs of the same table (say a daemon and somebody's cron job) can't overlap
+  let lock = format!("sf-etl sync {}", table.name());
+  if !db.query_one("SELECT pg_try_advisory_lock(hashtext($1))", &[&lock]).await?.get::<_, bool>(0) {
+    anyhow::bail!("{} is already being synced into {}", desc.name, table.name());
+  }
+  load::create_table(&db, table).await?;
+  if let Some(alter) = plan.alter.as_ref().filter(|alter| !alter.is_empty()) {
+    bar.set_message(format!("altering {}", table.name()));
+    db.batch_execute(alter).await?;
+  }
+
+  bar.set_message("waiting for the bulk query");
+  let mut bytes = 0;
+  let progress  = |page: &oxidized_force::response::BulkQueryResults| {
+    bytes += page.body.len() as u64;
+    bar.inc(page.number_of_records.unwrap_or(0) as u64);
+    bar.set_message(format!("downloading, {}", HumanBytes(bytes)));
+  };
+  let sink     = extract::Inspect::new(load::PostgresSink::upsert(&db, table).await?, progress);
+  let mut sink = extract::Watermark::new(sink, MODSTAMP).since(latest);
+  let records  = extract::extract_resumable(client, &desc.name, &plan.fields, plan.condition.as_deref(), options.poll_interval, &mut sink, checkpoint, |checkpoint, sink| {
+    let mut state = state.lock().unwrap();
+    state.set_checkpoint(&desc.name, checkpoint, sink.latest());
+    state.save(&options.state_file)
+  })
+  .await?;
+  Ok((records, sink.latest().map(String::from)))
+}
+
+/// Object name, spinner, records so far and what it's up to.
+fn progress_style() -> ProgressStyle {
+  ProgressStyle::with_template("{prefix:>20} {spinner} {pos} record(s) {elapsed:>4} {msg}").expect("valid progress template")
+}
+
+/// How syncing an object went, for the summary at the end.
+#[derive(Debug)]
+struct SyncSummary {
+  object:      String,
+  table:       String,
+  incremental: bool,
+  altered:     bool,
+  elapsed:     std::time::Duration,
+  /// Records synced, or why it failed
+  outcome:     Result<usize, String>
+}
+
+/// A line per object saying what `sync` did, e.g. `Account -> Account: 12 record(s), incremental, altered (1.2s)`.
+fn sync_report(summaries: &[SyncSummary]) -> String {
+  let width      = summaries.iter().map(|summary| summary.object.len() + summary.table.len() + 4).max().unwrap_or(0);
+  let mut report = String::new();
+  for summary in summaries {
+    let name = format!("{} -> {}", summary.object, summary.table);
+    let what = match summary.outcome {
+      Ok(records)   => {
+        let mut what = format!("{} record(s), {}", records, if summary.incremental { "incremental" } else { "full" });
+        if summary.altered {
+          what.push_str(", altered");
+        }
+        what
+      },
+      Err(ref err) => format!("failed: {}", err)
+    };
+    report.push_str(&format!("{:<width$}  {} ({:.1}s)\n", name, what, summary.elapsed.as_secs_f64(), width = width));
+  }
+  report
+}
+
+/// How `daemon` runs syncs, on top of what `sync` does.
+#[derive(Debug)]
+struct DaemonOptions {
+  sync:      SyncOptions,
+  /// One per object, in the same order
+  schedules: Vec<schedule::Schedule>
+}
+
+/// Keeps syncing each object on its schedule until Ctrl-C (or SIGTERM): runs that are due start if there's room (`parallel` at a time),
+/// an object still syncing from last time gets skipped. Shutting down waits for running syncs to finish; a second signal doesn't,
+/// and they pick up from their checkpoint next time.
+async fn run_daemon(client: &Client, descs: &[DescribeResponse], tables: &[Table], mapping: &Mapping, options: &DaemonOptions, dry_run: bool) -> anyhow::Result<()> {
+  check_syncable(descs, mapping)?;
+
+  let now      = chrono::Utc::now();
+  let mut next = descs
+    .iter()
+    .zip(&options.schedules)
+    .map(|(desc, schedule)| schedule.next_after(now).map(Some).ok_or_else(|| anyhow::anyhow!("the schedule for {} never comes around", desc.name)))
+    .collect::<anyhow::Result<Vec<_>>>()?;
+
+  if dry_run {
+    for ((desc, table), next) in descs.iter().zip(tables).zip(next.iter().flatten()) {
+      println!("Would sync {} into {} next at {}", desc.name, table.name(), next.to_rfc3339());
+    }
+    return Ok(());
+  }
+
+  let sync     = &options.sync;
+  let state    = std::sync::Mutex::new(SyncState::load(&sync.state_file)?);
+  let mut busy = vec![false; descs.len()];
+  let mut runs = futures::stream::FuturesUnordered::new();
+  let start    = |index: usize| {
+    let (desc, table, state) = (&descs[index], &tables[index], &state);
+    // Plans go by the state as it is now, with whatever earlier runs left in it
+    let plan = SyncPlan::new(desc, table, mapping, &state.lock().unwrap(), sync.incremental);
+    async move { (index, sync_object(client, sync, desc, table, &plan, state, &ProgressBar::hidden()).await) }
+  };
+
+  info!("Syncing {} object(s) on schedule, Ctrl-C to stop", descs.len());
+  let shutdown = shutdown_signal();
+  tokio::pin!(shutdown);
+  loop {
+    let now = chrono::Utc::now();
+    for index in 0..descs.len() {
+      if next[index].is_none_or(|next| next > now) {
+        continue;
+      }
+      if busy[index] {
+        warn!("{} is still syncing, skipping this run", descs[index].name);
+      } else if runs.len() >= sync.parallel {
+        // Stays due until there's room
+        continue;
+      } else {
+        info!("Syncing {}...", descs[index].name);
+        busy[index] = true;
+        runs.push(start(index));
+      }
+      next[index] = options.schedules[index].next_after(now);
+    }
+
+    // Runs still waiting for room start once another one finishes, which wakes this up anyway
+    let wake = next
+      .iter()
+      .flatten()
+      .filter(|next| **next > now)
+      .min()
+      .map_or(std::time::Duration::from_secs(60), |next| (*next - now).to_std().unwrap_or_default());
+    tokio::select! {
+      Some((index, result)) = runs.next(), if !runs.is_empty() => {
+        // Failures get logged, the next run has another go
+        busy[index] = false;
+        let _ = record_sync(&state, &sync.state_file, &descs[index], result)?;
+      },
+      _ = tokio::time::delay_for(wake) => (),
+      signal = &mut shutdown => {
+        signal?;
+        break;
+      }
+    }
+  }
+
+  if !runs.is_empty() {
+    info!("Waiting for {} sync(s) to finish, Ctrl-C again to stop right away", runs.len());
+  }
+  let again = shutdown_signal();
+  tokio::pin!(again);
+  loop {
+    tokio::select! {
+      run = runs.next() => match run {
+        Some((index, result)) => {
+          let _ = record_sync(&state, &sync.state_file, &descs[index], result)?;
+        },
+        None                  => break
+      },
+      signal = &mut again => {
+        signal?;
+        warn!("Stopping {} sync(s) halfway, they'll resume from their checkpoint", runs.len());
+        break;
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Where `listen` applies the changes.
+#[derive(Debug)]
+struct Listen {
+  to:           Option<String>,
+  kafka:        Option<String>,
+  topic_prefix: String,
+  state_file:   PathBuf,
+  earliest:     bool
+}
+
+/// Subscribes to the change events of every object, applying each one (in a transaction of its own) and publishing it,
+/// then saving its replay ID so a restart picks up right after it. Gaps get the records fetched again; overflows get
+/// skipped with a warning, a `sync` has to catch those up.
+async fn run_listen(client: &Client, descs: &[DescribeResponse], tables: &[Table], mapping: &Mapping, options: &Listen, dry_run: bool) -> anyhow::Result<()> {
+  if options.to.is_none() && options.kafka.is_none() {
+    anyhow::bail!("nowhere to apply the changes to, give --to (or DATABASE_URL) and/or --kafka (or KAFKA_REST_URL)");
+  }
+  if options.to.is_some() && mapping.dialect != Dialect::Pg {
+    anyhow::bail!("changes can only be applied to Postgres, not {:?}", mapping.dialect);
+  }
+
+  let mut state = SyncState::load(&options.state_file)?;
+  let channels  = descs
+    .iter()
+    .map(|desc| {
+      let from = match (state.replay_id(&desc.name), options.earliest) {
+        (Some(replay_id), _) => ReplayFrom::After(replay_id),
+        (None, true)         => ReplayFrom::Earliest,
+        (None, false)        => ReplayFrom::Tip
+      };
+      (listen::channel(&desc.name), from)
+    })
+    .collect::<Vec<_>>();
+
+  if dry_run {
+    for ((desc, table), (channel, from)) in descs.iter().zip(tables).zip(&channels) {
+      let to = match (&options.to, &options.kafka) {
+        (Some(_), Some(_)) => format!("{} and {}{}", table.name(), options.topic_prefix, desc.name),
+        (Some(_), None)    => table.name(),
+        _                  => format!("{}{}", options.topic_prefix, desc.name)
+      };
+      println!("Would apply {} to {}, starting from {:?}", channel, to, from);
+    }
+    return Ok(());
+  }
+
+  let db = match options.to {
+    Some(ref url) => {
+      let db = load::connect(url).await?;
+      for table in tables {
+        load::create_table(&db, table).await?;
+      }
+      Some(db)
+    },
+    None          => None
+  };
+  let mut sinks = match options.kafka {
+    Some(ref url) => descs
+      .iter()
+      .map(|desc| Some(kafka::KafkaSink::new(url, &format!("{}{}", options.topic_prefix, desc.name), desc, &[], kafka::Serialization::Json)))
+      .collect(),
+    None          => descs.iter().map(|_| None).collect::<Vec<_>>()
+  };
+
+  let mut subscriber = client.subscriber();
+  for (channel, from) in &channels {
+    subscriber.subscribe(channel.as_str(), *from);
+  }
+
+  info!("Listening for changes to {} object(s), Ctrl-C to stop", descs.len());
+  let shutdown = shutdown_signal();
+  tokio::pin!(shutdown);
+  loop {
+    let event = tokio::select! {
+      event = subscriber.next_event() => event?,
+      signal = &mut shutdown => {
+        signal?;
+        break;
+      }
+    };
+    let index = match channels.iter().position(|(channel, _)| *channel == event.channel) {
+      Some(index) => index,
+      None        => continue
+    };
+    let desc = &descs[index];
+
+    let applied = apply_event(client, db.as_ref(), sinks[index].as_mut(), desc, &tables[index], mapping, &event).await;
+    settle_event(&mut state, &options.state_file, &desc.name, &event, applied, db.as_ref())?;
+  }
+
+  subscriber.disconnect().await?;
+  Ok(())
+}
+
+/// Applies a change event to the table (in one transaction) and/or publishes it, whichever `listen` is doing.
+async fn apply_event(
+  client:  &Client,
+  db:      Option<&tokio_postgres::Client>,
+  sink:    Option<&mut kafka::KafkaSink>,
+  desc:    &DescribeResponse,
+  table:   &Table,
+  mapping: &Mapping,
+  event:   &StreamingEvent
+) -> anyhow::Result<listen::Change> {
+  let change = listen::Change::from_event(event)?;
+
+  if change.change_type == listen::ChangeType::GapOverflow {
+    warn!("Too many changes to {} at once to get events for, run a sync to catch up", desc.name);
+  } else if let Some(db) = db {
+    let refetched = match change.needs_refetch() {
+      true  => listen::refetch(client, &desc.name, &extract::bulk_fields(desc, &mapping.filter), &change.record_ids).await?,
+      false => Vec::new()
+    };
+    let statements = change.statements(table, &refetched)?;
+    if !statements.is_empty() {
+      let applied = db.batch_execute(&format!("BEGIN;\n{};\nCOMMIT;", statements.join(";\n"))).await;
+      // A failed statement leaves the transaction open (and aborted), which would take every change after it down too
+      if applied.is_err() {
+        db.batch_execute("ROLLBACK").await.ok();
+      }
+      applied?;
+    }
+  }
+  if let Some(sink) = sink {
+    sink.publish(change.messages(event)).await?;
+  }
+  Ok(change)
+}
+
+/// Moves past `event` once it's been applied, or skipped because Postgres rejected it (an error with a SQLSTATE), which
+/// replaying it would only do again. Anything else (the connection going away, Salesforce or the Kafka proxy not answering)
+/// is returned without saving the replay ID, so the event comes around again next time.
+fn settle_event(
+  state:      &mut SyncState,
+  state_file: &Path,
+  object:     &str,
+  event:      &StreamingEvent,
+  applied:    anyhow::Result<listen::Change>,
+  db:         Option<&tokio_postgres::Client>
+) -> anyhow::Result<()> {
+  match applied {
+    Ok(change)                     => debug!("Applied {:?} of {} record(s) of {} ({})", change.change_type, change.record_ids.len(), object, change.replay_id),
+    Err(err) if rejected(&err, db) => error!("Cannot apply change event {} on {}, run a sync to catch up: {}", event.replay_id, event.channel, err),
+    Err(err)                       => return Err(err.context(format!("cannot apply change event {} on {}", event.replay_id, event.channel)))
+  }
+
+  state.set_replay_id(object, event.replay_id);
+  state.save(state_file)
+}
+
+/// Whether Postgres turned the statements down itself, rather than the connection failing.
+fn rejected(err: &anyhow::Error, db: Option<&tokio_postgres::Client>) -> bool {
+  let rejection = err.chain().any(|cause| cause.downcast_ref::<tokio_postgres::Error>().is_some_and(|err| err.code().is_some()));
+  rejection && !db.is_some_and(|db| db.is_closed())
+}
+
+/// Ctrl-C, or SIGTERM (what container runtimes and systemd stop things with).
+async fn shutdown_signal() -> anyhow::Result<()> {
+  #[cfg(unix)]
+  {
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+    tokio::select! {
+      signal = tokio::signal::ctrl_c() => signal?,
+      _      = terminate.recv()        => ()
+    }
+  }
+  #[cfg(not(unix))]
+  tokio::signal::ctrl_c().await?;
+
+  info!("Shutting down...");
+  Ok(())
+}
+
+/// What incremental syncs go by; every queryable object has it.
+const MODSTAMP: &str = "SystemModstamp";
+
+/// What `--dry-run` prints: the tables that'd be created (or dropped or altered) in order, and where everything would go.
+fn dry_run_report(action: &str, schema: &Schema, outputs: &[(&str, Option<&std::path::Path>)]) -> String {
+  let mut order = schema.order();
+  if action == "drop" {
+    order.reverse();
+  }
+
+  let mut report = format!("Would {} {} table(s): {}\n", action, order.len(), order.join(", "));
+  for (what, path) in outputs {
+    match (what, path) {
+      (&"SQL", None)  => report.push_str("SQL would go to standard output\n"),
+      (_, None)       => (),
+      (_, Some(path)) => report.push_str(&format!("{} would be written to {}\n", what, path.display()))
+    }
+  }
+  report
+}
+
+/// Names of the objects `--all-objects` picks up, in the order Salesforce lists them.
+fn queryable_objects(global: &oxidized_force::response::DescribeGlobalResponse, custom_only: bool) -> Vec<String> {
+  global.sobjects
+    .iter()
+    .filter(|sobject| sobject.queryable && (sobject.custom || !custom_only))
+    .map(|sobject| sobject.name.clone())
+    .collect()
+}
+
+/// Drops repeats (so `--name Account --name Account,Contact` is just the two), keeping the first of each.
+fn unique_names(names: &[String]) -> Vec<String> {
+  let mut unique = Vec::<String>::new();
+  for name in names.iter().map(|name| name.trim()).filter(|name| !name.is_empty()) {
+    if !unique.iter().any(|other| other.eq_ignore_ascii_case(name)) {
+      unique.push(name.to_string());
+    }
+  }
+  unique
+}
+
+/// Field to column names; a single table keeps the flat `{ field: column }` layout, several get nested by table name.
+fn field_mapping(tables: &[Table]) -> serde_json::Value {
+  let columns = |table: &Table| {
+    table.field_columns()
+      .iter()
+      .map(|(field, column)| (field.clone(), serde_json::Value::from(column.as_str())))
+      .collect::<serde_json::Map<_, _>>()
+  };
+
+  match tables {
+    [table] => columns(table).into(),
+    tables  => tables.iter().map(|table| (table.name(), columns(table).into())).collect::<serde_json::Map<_, _>>().into()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use oxidized_force::response::FieldType;
+  use oxidized_force::test_util::fixtures;
+
+  #[test]
+  fn arguments() {
+    assert_eq!(parse_columns("AccountId, ExternalKey__c").unwrap(), vec!["AccountId", "ExternalKey__c"]);
+    assert!(parse_columns("AccountId,").is_err());
+    assert_eq!(parse_override("Amount=positive_money").unwrap(), ("Amount".to_string(), "positive_money".to_string()));
+    assert!(parse_override("Amount").is_err());
+    assert_eq!(parse_option("ENGINE=Aria").unwrap(), ("ENGINE".to_string(), "Aria".to_string()));
+    assert!(parse_option("ENGINE").is_err());
+    assert_eq!(parse_replacement("__c$=").unwrap(), ("__c$".to_string(), String::new()));
+
+    // Object flags go after the subcommand just as well
+    let opts = Opts::from_iter_safe(&["sf-sql", "codegen", "rust", "--name", "Account,Contact", "--sobject"]).unwrap();
+    assert_eq!(opts.names, ["Account", "Contact"]);
+    assert!(matches!(opts.command, Some(Command::Codegen(Codegen::Rust { sobject: true, decimal: false }))));
+  }
+
+  #[test]
+  fn schedules() -> anyhow::Result<()> {
+    use schedule::Schedule;
+
+    let descs      = [fixtures::describe("Account").build(), fixtures::describe("Contact").build()];
+    let configured = vec![("account".to_string(), "@daily".to_string())].into_iter().collect();
+    let fallback   = "*/5 * * * *".parse::<Schedule>()?;
+    assert_eq!(object_schedules(&descs, &configured, Some(&fallback))?, ["@daily".parse::<Schedule>()?, fallback]);
+    assert!(object_schedules(&descs, &configured, None).unwrap_err().to_string().contains("Contact has no schedule"));
+    Ok(())
+  }
+
+  #[test]
+  fn multiple_objects() {
+    use oxidized_force::response::DescribeGlobalResponse;
+
+    let sobject = |name: &str, custom: bool, queryable: bool| serde_json::json!({
+      "name": name, "label": name, "labelPlural": name, "custom": custom, "queryable": queryable, "replicateable": true, "keyPrefix": null
+    });
+    let global = serde_json::from_value::<DescribeGlobalResponse>(serde_json::json!({
+      "encoding":     "UTF-8",
+      "maxBatchSize": 200,
+      "sobjects":     [sobject("Account", false, true), sobject("AccountFeed", false, false), sobject("Invoice__c", true, true)]
+    })).unwrap();
+
+    assert_eq!(queryable_objects(&global, false), ["Account", "Invoice__c"]);
+    assert_eq!(queryable_objects(&global, true), ["Invoice__c"]);
+
+    let names = ["Account", "Contact", " account", ""].iter().map(|name| name.to_string()).collect::<Vec<_>>();
+    assert_eq!(unique_names(&names), ["Account", "Contact"]);
+
+    let account = table_from_describe("Account", &fixtures::describe("Account").field("Id", FieldType::Id).build(), &Mapping::default());
+    let contact = table_from_describe(
+      "Contact",
+      &fixtures::describe("Contact").field("Id", FieldType::Id).reference("AccountId").build(),
+      &Mapping::default()
+    );
+
+    assert_eq!(field_mapping(std::slice::from_ref(&account)), serde_json::json!({ "Id": "Id" }));
+    assert_eq!(
+      field_mapping(&[contact, account]),
+      serde_json::json!({ "Contact": { "Id": "Id", "AccountId": "AccountId" }, "Account": { "Id": "Id" } })
+    );
+  }
+
+  #[test]
+  fn dry_runs() {
+    use std::path::Path;
+
+    let mut account = Table::new("Account");
+    account.add_column("Id", varchar(None).primary(true));
+    let mut contact = Table::new("Contact");
+    contact.add_column("Id", varchar(None).primary(true)).add_column("AccountId", foreign("Account", vec!["Id"]));
+
+    let mut schema = Schema::new();
+    schema.add_table(contact).add_table(account);
+
+    let outputs = [("SQL", None), ("mapping", Some(Path::new("mapping.json"))), ("upserts", None)];
+    assert_eq!(
+      dry_run_report("create", &schema, &outputs),
+      "Would create 2 table(s): Account, Contact\nSQL would go to standard output\nmapping would be written to mapping.json\n"
+    );
+    assert!(dry_run_report("drop", &schema, &[("SQL", Some(Path::new("out.sql")))]).starts_with("Would drop 2 table(s): Contact, Account\n"));
+  }
+
+  #[test]
+  fn config_files() {
+    let config = toml::from_str::<Config>(r#"
+      objects = ["Account"]
+      dialect = "snowflake"
+      output  = "account.sql"
+
+      [credentials]
+      client_id = "abc"
+      username  = "etl@example.com"
+
+      [fields]
+      exclude     = ["Billing*"]
+      skip_custom = true
+    "#).unwrap();
+
+    assert_eq!(config.objects, ["Account"]);
+    assert_eq!(config.dialect.unwrap().parse::<Dialect>().unwrap(), Dialect::Snowflake);
+    assert_eq!(config.output.unwrap(), PathBuf::from("account.sql"));
+    assert_eq!(config.credentials.client_id.as_deref(), Some("abc"));
+    assert!(config.credentials.password.is_none());
+
+    let filter = config.fields.filter();
+    assert!(filter.allows(&fixtures::field("Name", FieldType::String)));
+    assert!(!filter.allows(&fixtures::field("BillingCity", FieldType::String)));
+
+    // Flags win, then the file
+    assert_eq!(required(Some(1), Some(2), "--output", "output").unwrap(), 1);
+    assert_eq!(required(None, Some(2), "--output", "output").unwrap(), 2);
+    assert!(required::<u8>(None, None, "--output", "output").is_err());
+
+    // Typos shouldn't quietly do nothing
+    assert!(toml::from_str::<Config>("dialekt = \"mysql\"").is_err());
+    assert!(Config::load(Some(std::path::Path::new("does-not-exist.toml"))).is_err());
+
+    let path = std::env::temp_dir().join(format!("sf-etl-{}.toml", std::process::id()));
+    std::fs::write(&path, "objects = [\"Contact\"]").unwrap();
+    let loaded = Config::load(Some(&path));
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.unwrap().objects, ["Contact"]);
+  }
+
+  #[test]
+  fn sync_plans() {
+    let old = fixtures::describe("Account")
+      .field("Id", FieldType::Id)
+      .field("SystemModstamp", FieldType::DateTime)
+      .build();
+    let desc = fixtures::describe("Account")
+      .field("Id", FieldType::Id)
+      .field("Name", FieldType::String)
+      .field("SystemModstamp", FieldType::DateTime)
+      .build();
+    let mapping = Mapping { filter: FieldFilter::new().exclude("SystemModstamp"), ..Mapping::default() };
+    let table   = table_from_describe("Account", &desc, &mapping);
+
+    // Nothing synced yet: everything, into a brand new table
+    let mut state = SyncState::default();
+    let plan      = SyncPlan::new(&desc, &table, &mapping, &state, true);
+    assert_eq!(plan.fields, ["Id", "Name", "SystemModstamp"]);
+    assert!(plan.alter.is_none());
+    assert!(plan.condition.is_none());
+
+    state.set_describe(&old).set_watermark("Account", "2021-01-05T08:30:00.000Z");
+    let plan = SyncPlan::new(&desc, &table, &mapping, &state, true);
+    assert_eq!(plan.alter.as_deref(), Some("ALTER TABLE \"Account\" ADD COLUMN \"Name\" VARCHAR(255);"));
+    assert_eq!(plan.condition.as_deref(), Some("SystemModstamp >= 2021-01-05T08:30:00.000Z"));
+    assert!(SyncPlan::new(&desc, &table, &mapping, &state, false).condition.is_none());
+
+    let summaries = [
+      SyncSummary {
+        object:      "Account".to_string(),
+        table:       "sf_account".to_string(),
+        incremental: true,
+        altered:     true,
+        elapsed:     std::time::Duration::from_millis(1250),
+        outcome:     Ok(12)
+      },
+      SyncSummary {
+        object:      "Case".to_string(),
+        table:       "sf_case".to_string(),
+        incremental: false,
+        altered:     false,
+        elapsed:     std::time::Duration::from_millis(300),
+        outcome:     Err("INVALID_FIELD".to_string())
+      }
+    ];
+    assert_eq!(
+      sync_report(&summaries),
+      concat!(
+        "Account -> sf_account  12 record(s), incremental, altered (1.2s)\n",
+        "Case -> sf_case        failed: INVALID_FIELD (0.3s)\n"
+      )
+    );
+  }
+
+  #[test]
+  fn settled_events() -> anyhow::Result<()> {
+    let path = std::env::temp_dir().join(format!("sf-etl-settle-{}.json", std::process::id()));
+    let mut state = SyncState::load(&path)?;
+    let event = StreamingEvent {
+      channel:   "/data/AccountChangeEvent".to_string(),
+      replay_id: 42,
+      payload:   serde_json::json!({ "ChangeEventHeader": { "entityName": "Account", "changeType": "DELETE", "recordIds": ["001A"] } })
+    };
+
+    // The connection going away isn't the event's fault, so it has to come around again
+    let lost = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "connection reset by peer");
+    assert!(settle_event(&mut state, &path, "Account", &event, Err(lost.into()), None).is_err());
+    assert_eq!(state.replay_id("Account"), None);
+    assert!(!path.exists());
+
+    settle_event(&mut state, &path, "Account", &event, listen::Change::from_event(&event), None)?;
+    assert_eq!(SyncState::load(&path)?.replay_id("Account"), Some(42));
+    std::fs::remove_file(&path)?;
+    Ok(())
   }
 }
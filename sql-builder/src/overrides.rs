@@ -0,0 +1,155 @@
+use std::{collections::BTreeMap, path::Path};
+
+use oxidized_force::response::{Field, FieldType};
+use serde::Deserialize;
+
+/// SQL types to use instead of the default mapping, for every field of a type or for particular fields.
+/// Field overrides win over type ones, and `Object.Field` wins over a bare `Field` (which goes for any object).
+#[derive(Debug, Clone, Default)]
+pub struct TypeOverrides {
+  types:  Vec<(FieldType, String)>,
+  fields: Vec<(Option<String>, String, String)>
+}
+
+/// What goes in an overrides file, e.g.
+///
+/// ```toml
+/// [types]
+/// currency = "NUMERIC(18,2)"
+///
+/// [fields]
+/// "Account.Description" = "TEXT"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct OverridesFile {
+  types:  BTreeMap<String, String>,
+  fields: BTreeMap<String, String>
+}
+
+impl TypeOverrides {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Every field of type `field_type` becomes `sql`.
+  pub fn field_type<S>(mut self, field_type: FieldType, sql: S) -> Self
+  where S: Into<String> {
+    self.types.push((field_type, sql.into()));
+    self
+  }
+
+  /// The field becomes `sql`; `field` is either `Object.Field` or just `Field`, for that field on any object.
+  pub fn field<S>(mut self, field: &str, sql: S) -> Self
+  where S: Into<String> {
+    let (object, field) = match field.split_once('.') {
+      Some((object, field)) => (Some(object.to_string()), field.to_string()),
+      None                  => (None, field.to_string())
+    };
+    self.fields.push((object, field, sql.into()));
+    self
+  }
+
+  pub fn from_toml(contents: &str) -> anyhow::Result<Self> {
+    let file = toml::from_str::<OverridesFile>(contents)?;
+
+    let mut overrides = TypeOverrides::new();
+    for (name, sql) in file.types {
+      overrides = overrides.field_type(parse_field_type(&name)?, sql);
+    }
+    for (field, sql) in file.fields {
+      overrides = overrides.field(&field, sql);
+    }
+    Ok(overrides)
+  }
+
+  pub fn load<P>(path: P) -> anyhow::Result<Self>
+  where P: AsRef<Path> {
+    let path     = path.as_ref();
+    let contents = std::fs::read_to_string(path).map_err(|err| anyhow::anyhow!("cannot read {}: {}", path.display(), err))?;
+    Self::from_toml(&contents).map_err(|err| anyhow::anyhow!("invalid type overrides {}: {}", path.display(), err))
+  }
+
+  /// Type for a field of `object`, if there's an override for it.
+  pub fn sql_type(&self, object: &str, field: &Field) -> Option<&str> {
+    let by_field = |qualified: bool| {
+      self.fields.iter().find(|(other_object, name, _)| {
+        let object_matches = match other_object {
+          Some(other) => qualified && other.eq_ignore_ascii_case(object),
+          None        => !qualified
+        };
+        object_matches && name.eq_ignore_ascii_case(&field.name)
+      })
+    };
+
+    by_field(true)
+      .or_else(|| by_field(false))
+      .map(|(_, _, sql)| sql.as_str())
+      .or_else(|| self.types.iter().find(|(field_type, _)| *field_type == field.field_type).map(|(_, sql)| sql.as_str()))
+  }
+}
+
+/// Type names as they show up in describes (`currency`, `textarea`...), in any case.
+fn parse_field_type(name: &str) -> anyhow::Result<FieldType> {
+  let mut first_lower = name.to_string();
+  if let Some(first) = first_lower.get_mut(0..1) {
+    first.make_ascii_lowercase();
+  }
+
+  [name.to_string(), first_lower, name.to_ascii_lowercase()]
+    .iter()
+    .filter_map(|candidate| serde_json::from_value::<FieldType>(serde_json::Value::from(candidate.as_str())).ok())
+    .find(|field_type| *field_type != FieldType::Unknown)
+    .ok_or_else(|| anyhow::anyhow!("unknown field type `{}`", name))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use oxidized_force::test_util::fixtures;
+
+  use crate::describe::{table_from_describe, Mapping};
+  use crate::sql::Pg;
+
+  #[test]
+  fn type_override_files() {
+    let overrides = TypeOverrides::from_toml(r#"
+      [types]
+      Currency = "NUMERIC(18,2)"
+      textarea = "TEXT"
+
+      [fields]
+      "Account.Description" = "VARCHAR(4000)"
+      "Notes__c"            = "JSONB"
+    "#).unwrap();
+
+    let desc = fixtures::describe("Account")
+      .field("Id", FieldType::Id)
+      .field("AnnualRevenue", FieldType::Currency)
+      .field("Description", FieldType::TextArea)
+      .field("Other__c", FieldType::TextArea)
+      .field("Notes__c", FieldType::String)
+      .build();
+    let mapping = Mapping { type_overrides: overrides, ..Default::default() };
+    let sql     = table_from_describe("Account", &desc, &mapping).generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Account\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL UNIQUE,\n\
+      \"AnnualRevenue\" NUMERIC(18,2),\n\
+      \"Description\" VARCHAR(4000),\n\
+      \"Other__c\" TEXT,\n\
+      \"Notes__c\" JSONB\n\
+      )");
+
+    // Qualified fields only go for their own object
+    let contact = fixtures::describe("Contact").field("Id", FieldType::Id).field("Description", FieldType::TextArea).build();
+    assert_eq!(table_from_describe("Contact", &contact, &mapping).generate::<Pg>(), "\
+      CREATE TABLE \"Contact\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL UNIQUE,\n\
+      \"Description\" TEXT\n\
+      )");
+
+    assert!(TypeOverrides::from_toml("[types]\nbogus = \"TEXT\"").is_err());
+    assert!(TypeOverrides::from_toml("[typos]").is_err());
+  }
+}
@@ -0,0 +1,170 @@
+//! Cron-like schedules for `daemon`: `minute hour day-of-month month day-of-week`, in UTC.
+
+use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike, Utc};
+
+use crate::error::ParseError;
+
+/// When something runs, e.g. `*/15 * * * *` (every 15 minutes) or `30 2 * * mon-fri` (weekdays at 02:30).
+/// Fields take `*`, numbers, ranges, lists and steps like cron does, months and weekdays their three letter names too;
+/// `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly` are shorthands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+  minutes:     u64,
+  hours:       u64,
+  days:        u64,
+  months:      u64,
+  weekdays:    u64,
+  /// Cron matches either day field when both are restricted, but only the restricted one otherwise
+  any_day:     bool,
+  any_weekday: bool
+}
+
+const MONTHS:   [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const WEEKDAYS: [&str; 7]  = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+impl std::str::FromStr for Schedule {
+  type Err = ParseError;
+
+  fn from_str(schedule: &str) -> Result<Self, Self::Err> {
+    let expanded = match schedule.trim().to_ascii_lowercase().as_str() {
+      "@hourly"               => "0 * * * *".to_string(),
+      "@daily" | "@midnight"  => "0 0 * * *".to_string(),
+      "@weekly"               => "0 0 * * 0".to_string(),
+      "@monthly"              => "0 0 1 * *".to_string(),
+      "@yearly" | "@annually" => "0 0 1 1 *".to_string(),
+      other                   => other.to_string()
+    };
+
+    let fields = expanded.split_whitespace().collect::<Vec<_>>();
+    let (minute, hour, day, month, weekday) = match fields.as_slice() {
+      [minute, hour, day, month, weekday] => (*minute, *hour, *day, *month, *weekday),
+      _ => return Err(ParseError::invalid("schedule", schedule, "expected `minute hour day month weekday` (e.g. `*/15 * * * *`) or @hourly, @daily..."))
+    };
+
+    let field = |value: &str, min: u32, max: u32, names: &[&str]| {
+      parse_field(value, min, max, names).map_err(|err| ParseError::invalid("schedule", schedule, err))
+    };
+    let mut weekdays = field(weekday, 0, 7, &WEEKDAYS)?;
+    // Sunday is both 0 and 7
+    if weekdays & (1 << 7) != 0 {
+      weekdays |= 1;
+    }
+
+    Ok(Schedule {
+      minutes:     field(minute, 0, 59, &[])?,
+      hours:       field(hour, 0, 23, &[])?,
+      days:        field(day, 1, 31, &[])?,
+      months:      field(month, 1, 12, &MONTHS)?,
+      weekdays,
+      any_day:     day == "*",
+      any_weekday: weekday == "*"
+    })
+  }
+}
+
+/// A bit per allowed value.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+  let value = |value: &str| -> Result<u32, String> {
+    let parsed = match names.iter().position(|name| *name == value) {
+      // Names start where the numbers do (`jan` is 1, `sun` is 0)
+      Some(index) => index as u32 + min,
+      None        => value.parse().map_err(|_| format!("`{}` is not a number", value))?
+    };
+    match parsed {
+      parsed if parsed < min || parsed > max => Err(format!("{} is out of range ({}-{})", parsed, min, max)),
+      parsed                                 => Ok(parsed)
+    }
+  };
+
+  let mut bits = 0;
+  for part in field.split(',') {
+    let (range, step) = match part.split_once('/') {
+      Some((range, step)) => (range, step.parse::<u32>().ok().filter(|step| *step > 0).ok_or_else(|| format!("invalid step `{}`", step))?),
+      None                => (part, 1)
+    };
+    let (start, end) = match range.split_once('-') {
+      _ if range == "*"  => (min, max),
+      Some((start, end)) => (value(start)?, value(end)?),
+      // `5/10` goes from 5 to the end, same as cron
+      None if step > 1   => (value(range)?, max),
+      None               => (value(range)?, value(range)?)
+    };
+    if start > end {
+      return Err(format!("`{}` goes backwards", part));
+    }
+    for value in (start..=end).step_by(step as usize) {
+      bits |= 1 << value;
+    }
+  }
+  Ok(bits)
+}
+
+impl Schedule {
+  /// The first time after `after` (to the minute) this runs; `None` if it never does, like on February 30th.
+  pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let start    = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+    let mut time = start;
+
+    // Skipping a month, day or hour at a time when it doesn't match, so this is quick even for rare schedules
+    while time.year() <= start.year() + 4 {
+      if !has(self.months, time.month()) {
+        let (year, month) = if time.month() == 12 { (time.year() + 1, 1) } else { (time.year(), time.month() + 1) };
+        time = Utc.ymd_opt(year, month, 1).single()?.and_hms_opt(0, 0, 0)?;
+      } else if !self.day_matches(time) {
+        time = time.date().and_hms_opt(0, 0, 0)? + Duration::days(1);
+      } else if !has(self.hours, time.hour()) {
+        time = time.with_minute(0)? + Duration::hours(1);
+      } else if !has(self.minutes, time.minute()) {
+        time = time + Duration::minutes(1);
+      } else {
+        return Some(time);
+      }
+    }
+    None
+  }
+
+  fn day_matches(&self, time: DateTime<Utc>) -> bool {
+    let day     = has(self.days, time.day());
+    let weekday = has(self.weekdays, time.weekday().num_days_from_sunday());
+    match (self.any_day, self.any_weekday) {
+      (false, false) => day || weekday,
+      (false, true)  => day,
+      (true, false)  => weekday,
+      (true, true)   => true
+    }
+  }
+}
+
+fn has(bits: u64, value: u32) -> bool {
+  bits & (1 << value) != 0
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn schedules() -> anyhow::Result<()> {
+    // Sunday, 10:07
+    let now  = Utc.ymd(2021, 1, 3).and_hms(10, 7, 30);
+    let next = |schedule: &str| -> anyhow::Result<Option<String>> {
+      Ok(schedule.parse::<Schedule>()?.next_after(now).map(|next| next.format("%a %F %R").to_string()))
+    };
+    assert_eq!(next("*/15 * * * *")?.as_deref(), Some("Sun 2021-01-03 10:15"));
+    assert_eq!(next("* * * * *")?.as_deref(), Some("Sun 2021-01-03 10:08"));
+    assert_eq!(next("@hourly")?.as_deref(), Some("Sun 2021-01-03 11:00"));
+    assert_eq!(next("30 2 * * mon-fri")?.as_deref(), Some("Mon 2021-01-04 02:30"));
+    assert_eq!(next("0 0 1,15 * *")?.as_deref(), Some("Fri 2021-01-15 00:00"));
+    assert_eq!(next("0 6 * feb 7")?.as_deref(), Some("Sun 2021-02-07 06:00"));
+    // Both day fields restricted means either one
+    assert_eq!(next("0 0 13 * 5")?.as_deref(), Some("Fri 2021-01-08 00:00"));
+    assert_eq!(next("0 0 29 2 *")?.as_deref(), Some("Thu 2024-02-29 00:00"));
+    assert_eq!(next("0 0 30 2 *")?, None);
+
+    for invalid in ["* * * *", "60 * * * *", "*/0 * * * *", "5-1 * * * *", "0 0 * nope *"] {
+      assert!(invalid.parse::<Schedule>().is_err(), "{} parsed", invalid);
+    }
+    assert_eq!("*/0 * * * *".parse::<Schedule>(), Err(ParseError::invalid("schedule", "*/0 * * * *", "invalid step `0`")));
+    Ok(())
+  }
+}
@@ -0,0 +1,173 @@
+//! Credentials kept in a secrets manager rather than the config file, referred to as `secret://<backend>/...`.
+//! Secrets are read with the backend's own CLI (`aws`, `gcloud` or `vault`), so they're authenticated however it usually is
+//! (instance roles, `gcloud auth`, `VAULT_TOKEN`...).
+
+use serde_json::Value;
+
+use crate::error::ParseError;
+
+/// Where the references start.
+pub const SCHEME: &str = "secret://";
+
+/// A reference to a secret; secrets holding JSON objects take the key of the value to use.
+///
+/// - `secret://aws/<secret id>[/<key>]`, e.g. `secret://aws/sf-prod/client_secret` (AWS Secrets Manager)
+/// - `secret://gcp/<project>/<secret>[/<key>]`, the latest version (GCP Secret Manager)
+/// - `secret://vault/<path>/<key>`, e.g. `secret://vault/secret/sf-prod/password` (a HashiCorp Vault KV secret)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretRef {
+  Aws   { id: String, key: Option<String> },
+  Gcp   { project: String, secret: String, key: Option<String> },
+  Vault { path: String, key: String }
+}
+
+impl std::str::FromStr for SecretRef {
+  type Err = ParseError;
+
+  fn from_str(reference: &str) -> Result<Self, Self::Err> {
+    let rest = reference.strip_prefix(SCHEME).ok_or_else(|| ParseError::invalid("secret reference", reference, format!("expected {}...", SCHEME)))?;
+    let mut segments = rest.split('/').map(String::from).collect::<Vec<_>>();
+    if segments.iter().any(String::is_empty) {
+      return Err(ParseError::invalid("secret reference", reference, "it has an empty segment"));
+    }
+
+    let backend = segments.remove(0);
+    let count   = segments.len();
+    match backend.as_str() {
+      "aws" if count >= 1               => {
+        let key = if count > 1 { segments.pop() } else { None };
+        Ok(SecretRef::Aws { id: segments.join("/"), key })
+      },
+      "gcp" if count == 2 || count == 3 => {
+        let key = if count == 3 { segments.pop() } else { None };
+        Ok(SecretRef::Gcp { secret: segments.pop().unwrap(), project: segments.pop().unwrap(), key })
+      },
+      "vault" if count >= 2             => {
+        let key = segments.pop().unwrap();
+        Ok(SecretRef::Vault { path: segments.join("/"), key })
+      },
+      "aws" | "gcp" | "vault"           => Err(ParseError::invalid("secret reference", reference, "see `SecretRef` for what each backend takes")),
+      _                                 => Err(ParseError::unknown("secrets backend", &backend, "aws, gcp or vault"))
+    }
+  }
+}
+
+impl SecretRef {
+  /// The command printing the secret, program first.
+  pub fn command(&self) -> Vec<String> {
+    let args: Vec<&str> = match self {
+      SecretRef::Aws { id, .. }              => vec!["aws", "secretsmanager", "get-secret-value", "--secret-id", id, "--query", "SecretString", "--output", "text"],
+      SecretRef::Gcp { project, secret, .. } => vec!["gcloud", "secrets", "versions", "access", "latest", "--secret", secret, "--project", project],
+      // Vault picks out the key itself
+      SecretRef::Vault { path, key }         => vec!["vault", "kv", "get", "-field", key, path]
+    };
+    args.into_iter().map(String::from).collect()
+  }
+
+  /// Reads the secret; errors never include it, only the reference.
+  pub async fn fetch(&self) -> anyhow::Result<String> {
+    let command = self.command();
+    let output  = tokio::process::Command::new(&command[0])
+      .args(&command[1..])
+      .stdin(std::process::Stdio::null())
+      .output()
+      .await
+      .map_err(|err| anyhow::anyhow!("cannot run `{}` to read {}: {}", command[0], self, err))?;
+
+    if !output.status.success() {
+      anyhow::bail!("cannot read {}: {}", self, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let value = String::from_utf8(output.stdout).map_err(|_| anyhow::anyhow!("{} is not UTF-8", self))?;
+    let value = value.trim_end_matches(&['\r', '\n'][..]);
+
+    match self {
+      SecretRef::Aws { key: Some(key), .. } | SecretRef::Gcp { key: Some(key), .. } => pick(value, key).map_err(|err| anyhow::anyhow!("cannot read {}: {}", self, err)),
+      _                                                                              => Ok(value.to_string())
+    }
+  }
+}
+
+impl std::fmt::Display for SecretRef {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let (backend, path, key) = match self {
+      SecretRef::Aws { id, key }              => ("aws", id.clone(), key.as_deref()),
+      SecretRef::Gcp { project, secret, key } => ("gcp", format!("{}/{}", project, secret), key.as_deref()),
+      SecretRef::Vault { path, key }          => ("vault", path.clone(), Some(key.as_str()))
+    };
+    write!(f, "{}{}/{}", SCHEME, backend, path)?;
+    match key {
+      Some(key) => write!(f, "/{}", key),
+      None      => Ok(())
+    }
+  }
+}
+
+/// A value out of a secret holding a JSON object, e.g. `{"client_secret": "..."}`.
+pub fn pick(secret: &str, key: &str) -> anyhow::Result<String> {
+  let object = match serde_json::from_str::<Value>(secret) {
+    Ok(Value::Object(object)) => object,
+    _                         => anyhow::bail!("the secret isn't a JSON object, so there's no `{}` in it", key)
+  };
+  match object.get(key) {
+    Some(Value::String(value)) => Ok(value.clone()),
+    Some(Value::Null) | None   => anyhow::bail!("the secret has no `{}`", key),
+    Some(value)                => Ok(value.to_string())
+  }
+}
+
+/// Reads `value` from the secrets manager if it's a reference, anything else is the value itself.
+pub async fn resolve(value: String) -> anyhow::Result<String> {
+  match value.starts_with(SCHEME) {
+    true  => value.parse::<SecretRef>()?.fetch().await,
+    false => Ok(value)
+  }
+}
+
+/// Same as `resolve`, for settings that may not be there at all.
+pub async fn resolve_opt(value: Option<String>) -> anyhow::Result<Option<String>> {
+  match value {
+    Some(value) => Ok(Some(resolve(value).await?)),
+    None        => Ok(None)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn secret_references() -> anyhow::Result<()> {
+    let aws = "secret://aws/sf-prod/client_secret".parse::<SecretRef>()?;
+    assert_eq!(aws, SecretRef::Aws { id: "sf-prod".to_string(), key: Some("client_secret".to_string()) });
+    assert_eq!(aws.command(), ["aws", "secretsmanager", "get-secret-value", "--secret-id", "sf-prod", "--query", "SecretString", "--output", "text"]);
+    assert_eq!("secret://aws/prod/sf/password".parse::<SecretRef>()?, SecretRef::Aws { id: "prod/sf".to_string(), key: Some("password".to_string()) });
+    assert_eq!("secret://aws/sf-password".parse::<SecretRef>()?, SecretRef::Aws { id: "sf-password".to_string(), key: None });
+
+    let gcp = "secret://gcp/shibe-etl/sf-prod".parse::<SecretRef>()?;
+    assert_eq!(gcp.command(), ["gcloud", "secrets", "versions", "access", "latest", "--secret", "sf-prod", "--project", "shibe-etl"]);
+    assert_eq!(
+      "secret://gcp/shibe-etl/sf-prod/password".parse::<SecretRef>()?,
+      SecretRef::Gcp { project: "shibe-etl".to_string(), secret: "sf-prod".to_string(), key: Some("password".to_string()) }
+    );
+
+    let vault = "secret://vault/secret/sf-prod/refresh_token".parse::<SecretRef>()?;
+    assert_eq!(vault.command(), ["vault", "kv", "get", "-field", "refresh_token", "secret/sf-prod"]);
+
+    for reference in ["secret://aws/sf-prod/client_secret", "secret://gcp/shibe-etl/sf-prod", "secret://vault/secret/sf-prod/password"].iter() {
+      assert_eq!(reference.parse::<SecretRef>()?.to_string(), *reference);
+    }
+    for invalid in ["secret://aws", "secret://gcp/shibe-etl", "secret://vault/sf-prod", "secret://aws//key", "secret://1password/sf"].iter() {
+      assert!(invalid.parse::<SecretRef>().is_err(), "{} parsed", invalid);
+    }
+    assert_eq!("secret://1password/sf".parse::<SecretRef>(), Err(ParseError::unknown("secrets backend", "1password", "aws, gcp or vault")));
+
+    assert_eq!(pick(r#"{"client_secret": "shh", "port": 5432}"#, "client_secret")?, "shh");
+    assert_eq!(pick(r#"{"client_secret": "shh", "port": 5432}"#, "port")?, "5432");
+    assert!(pick(r#"{"client_secret": "shh"}"#, "password").is_err());
+    assert!(pick("shh", "password").is_err());
+
+    // Plain values are just that
+    assert_eq!(resolve("hunter2".to_string()).await?, "hunter2");
+    Ok(())
+  }
+}
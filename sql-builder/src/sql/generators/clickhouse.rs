@@ -0,0 +1,244 @@
+use serde_json::Value;
+
+use crate::sql::{
+  types::{BaseType, Type, WrappedDefault},
+  Constraint,
+  CreateMode,
+  DropOptions,
+  Identifiers,
+  Persistence,
+  SqlGenerator
+};
+
+/// ClickHouse (MergeTree family tables).
+/// Only CHECK constraints exist here; keys go into `ORDER BY` (see `create_options`) and unique/foreign keys are dropped.
+pub struct ClickHouse;
+impl SqlGenerator for ClickHouse {
+  // Temporary tables don't belong to a database
+  fn create_table(name: &str, mode: CreateMode, persistence: Persistence, ids: &Identifiers) -> (String, String) {
+    let (modifier, table) = match persistence {
+      Persistence::Temporary => ("TEMPORARY ", ids.render::<ClickHouse>(name)),
+      _                      => ("", ids.table::<ClickHouse>(name))
+    };
+
+    (
+      super::create_table(&table, mode, modifier), // Prefix
+      "\n)".to_owned()                             // Affix
+    )
+  }
+
+  fn create_column(name: &str, tp: &Type, ids: &Identifiers) -> String {
+    let inner = tp.inner();
+
+    let column_type = match tp.sql_type.as_ref() {
+      Some(sql) => sql.clone(),
+      None      => ClickHouse::stringify(inner.clone())
+    };
+
+    format!(
+      "{} {}{}{}",
+      ids.render::<ClickHouse>(name),
+      // Arrays can't be wrapped, they're just empty instead
+      match tp.nullable && !matches!(inner, BaseType::Array(_)) {
+        true  => format!("Nullable({})", column_type),
+        false => column_type
+      },
+      match (tp.generated.as_ref(), tp.default.as_ref()) {
+        (Some(expr), _)       => format!(" MATERIALIZED {}", expr),
+        (None, Some(default)) => format!(" DEFAULT {}", ClickHouse::default_value(default)),
+        (None, None)          => String::new()
+      },
+      // Checks only exist at the table level, so these get tacked on right after the column
+      match tp.check.as_ref() {
+        Some(expr) => format!(",\nCONSTRAINT {} CHECK ({})", ids.render::<ClickHouse>(&format!("{}_check", name)), expr),
+        None       => String::new()
+      }
+    )
+  }
+
+  fn create_constraint(name: &str, constraint: &Constraint, _deferrable: bool, ids: &Identifiers) -> String {
+    match constraint {
+      Constraint::Check(expr) => format!("CONSTRAINT {} CHECK ({})", ids.render::<ClickHouse>(name), expr),
+      _                       => String::new()
+    }
+  }
+
+  /// `ENGINE` defaults to `MergeTree()` and `ORDER BY` to the primary key; anything else (`PARTITION BY`, `TTL`...) is passed along as-is.
+  fn create_options(options: &[(String, String)], primary_key: &[String], ids: &Identifiers) -> String {
+    let option = |key: &str| options.iter().find(|(existing, _)| existing.eq_ignore_ascii_case(key)).map(|(_, value)| value.clone());
+
+    let engine   = option("ENGINE").unwrap_or_else(|| "MergeTree()".to_string());
+    let order_by = option("ORDER BY").unwrap_or_else(|| match primary_key.len() {
+      0 => "tuple()".to_string(),
+      _ => format!("({})", ids.list::<ClickHouse>(primary_key))
+    });
+
+    let rest = options
+      .iter()
+      .filter(|(key, _)| !key.eq_ignore_ascii_case("ENGINE") && !key.eq_ignore_ascii_case("ORDER BY"))
+      .map(|(key, value)| format!("\n{} {}", key.to_ascii_uppercase(), value))
+      .collect::<String>();
+
+    format!("\nENGINE = {}\nORDER BY {}{}", engine, order_by, rest)
+  }
+
+  // Databases are the closest thing to schemas
+  fn create_schema(name: &str, ids: &Identifiers) -> String {
+    format!("CREATE DATABASE IF NOT EXISTS {}", ids.render::<ClickHouse>(name))
+  }
+
+  fn or_replace() -> bool {
+    true
+  }
+
+  // There is no CASCADE
+  fn drop_table(name: &str, options: DropOptions, ids: &Identifiers) -> String {
+    super::drop_table(&ids.table::<ClickHouse>(name), DropOptions { cascade: false, ..options })
+  }
+
+  fn column_type(tp: &Type) -> String {
+    match tp.sql_type.as_ref() {
+      Some(sql) => sql.clone(),
+      None      => ClickHouse::stringify(tp.inner())
+    }
+  }
+
+  // Checks are table level here, so they can't come along
+  fn add_column(name: &str, tp: &Type, ids: &Identifiers) -> String {
+    format!("ADD COLUMN {}", ClickHouse::create_column(name, &Type { check: None, ..tp.clone() }, ids))
+  }
+
+  // Nullability is part of the type, so MODIFY covers both
+  fn alter_column(name: &str, old: &Type, new: &Type, ids: &Identifiers) -> Vec<String> {
+    let definition = |tp: &Type| ClickHouse::create_column(name, &Type { check: None, ..tp.clone() }, ids);
+
+    match definition(old) == definition(new) {
+      true  => Vec::new(),
+      false => vec![format!("MODIFY COLUMN {}", definition(new))]
+    }
+  }
+
+  fn comment_on(table: &str, column: Option<&str>, comment: &str, ids: &Identifiers) -> String {
+    match column {
+      Some(column) => format!("ALTER TABLE {} COMMENT COLUMN {} {}", ids.table::<ClickHouse>(table), ids.render::<ClickHouse>(column), ClickHouse::text(comment)),
+      None         => format!("ALTER TABLE {} MODIFY COMMENT {}", ids.table::<ClickHouse>(table), ClickHouse::text(comment))
+    }
+  }
+
+  fn grant(table: &str, privileges: &str, role: &str, ids: &Identifiers) -> String {
+    format!("GRANT {} ON {} TO {}", privileges, ids.table::<ClickHouse>(table), role)
+  }
+
+  fn owner(_table: &str, _role: &str, _ids: &Identifiers) -> String {
+    String::new()
+  }
+
+  // There are no updates as such; a `ReplacingMergeTree` engine keyed the same way collapses the duplicates eventually
+  fn upsert(table: &str, columns: &[String], _key: &[String], ids: &Identifiers) -> Vec<String> {
+    vec![super::insert_values::<ClickHouse>(table, columns, ids)]
+  }
+
+  fn literal(value: &Value, _tp: &Type) -> String {
+    super::value_literal(value, ("true", "false"), ClickHouse::text)
+  }
+
+  fn quote(name: &str) -> String {
+    format!("`{}`", name.replace('`', "\\`"))
+  }
+
+  fn uuid_default() -> Option<&'static str> {
+    Some("generateUUIDv4()")
+  }
+}
+
+impl ClickHouse {
+  /// Backslashes are escapes in string literals too.
+  fn text(text: &str) -> String {
+    super::text_literal(text).replace('\\', "\\\\")
+  }
+
+  fn default_value(default: &WrappedDefault) -> String {
+    use self::WrappedDefault::*;
+
+    match default {
+      Integer(_) | BigInt(_) | Float(_) | Double(_) => default.to_string(),
+      Boolean(val)   => match val {
+        true  => "true".to_string(),
+        false => "false".to_string()
+      },
+      Array(values)  => format!("[{}]", values.iter().map(ClickHouse::default_value).collect::<Vec<_>>().join(", ")),
+      Custom(sql)    => sql.to_string(),
+      Expr(sql)      => sql.clone(),
+      Date(time)     => format!("toDate(toDateTime64({}, 3, 'UTC'))", super::epoch_seconds(time)),
+      DateTime(time) => format!("toDateTime64({}, 3, 'UTC')", super::epoch_seconds(time)),
+      _              => ClickHouse::text(&default.to_string())
+    }
+  }
+
+  fn stringify(tp: BaseType) -> String {
+    use self::BaseType::*;
+
+    match tp {
+      Foreign(_, _)    => "String".to_string(),
+      Custom(sql)      => sql.to_string(),
+      Array(boxed)     => format!("Array({})", ClickHouse::stringify(*boxed)),
+      Varchar(_)       => "String".to_string(),
+      Text             => "String".to_string(),
+      CiText           => "String".to_string(),
+      Boolean          => "Bool".to_string(),
+      SmallInt         => "Int16".to_string(),
+      Integer          => "Int32".to_string(),
+      BigInt           => "Int64".to_string(),
+      Float            => "Float32".to_string(),
+      Double           => "Float64".to_string(),
+      Numeric(spec)    => match spec {
+        Some((precision, scale)) => format!("Decimal({}, {})", precision, scale),
+        None                     => "Decimal(38, 18)".to_string()
+      },
+      Uuid             => "UUID".to_string(),
+      Binary           => "String".to_string(),
+      Jsonb            => "String".to_string(),
+      Geography(_)     => "Point".to_string(),
+      // There is no time-of-day type, so these stay text
+      Time             => "String".to_string(),
+      // Plain `Date` only goes back to 1970
+      Date             => "Date32".to_string(),
+      DateTime         => "DateTime64(3)".to_string(),
+      DateTimeTz       => "DateTime64(3, 'UTC')".to_string(),
+      Index(_)         => unreachable!()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::sql::*;
+  use serde_json::json;
+
+  #[test]
+  fn clickhouse_statements() {
+    let ids = Identifiers::default();
+    let columns = vec!["Id".to_string(), "Name".to_string()];
+
+    assert_eq!(ClickHouse::column_type(&varchar(Some(40))), "String");
+    assert_eq!(ClickHouse::column_type(&numeric(18, 2)), "Decimal(18, 2)");
+    assert_eq!(ClickHouse::column_type(&date()), "Date32");
+    assert_eq!(ClickHouse::column_type(&datetime_tz()), "DateTime64(3, 'UTC')");
+    assert_eq!(ClickHouse::column_type(&array(&integer())), "Array(Int32)");
+
+    assert_eq!(ClickHouse::alter_column("Name", &varchar(Some(80)), &varchar(Some(255)), &ids), Vec::<String>::new());
+    assert_eq!(ClickHouse::alter_column("Amount", &integer(), &numeric(18, 2).nullable(true).check("Amount > 0"), &ids), [
+      "MODIFY COLUMN `Amount` Nullable(Decimal(18, 2))"
+    ]);
+
+    // The key is left to the table engine
+    assert_eq!(ClickHouse::upsert("Account", &columns, &columns[..1], &ids), [
+      "INSERT INTO `Account` (`Id`, `Name`) VALUES (?, ?)"
+    ]);
+
+    assert_eq!(ClickHouse::literal(&json!("O'Brien \\ Sons"), &text()), "'O''Brien \\\\ Sons'");
+    assert_eq!(ClickHouse::literal(&json!(true), &boolean()), "true");
+    assert_eq!(ClickHouse::literal(&json!(null), &text()), "NULL");
+  }
+}
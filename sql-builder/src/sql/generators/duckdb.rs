@@ -0,0 +1,219 @@
+use crate::sql::{
+  types::{BaseType, Type, WrappedDefault},
+  Constraint,
+  CreateMode,
+  Identifiers,
+  Persistence,
+  SqlGenerator
+};
+
+/// DuckDB, for querying extracts locally.
+pub struct DuckDb;
+impl SqlGenerator for DuckDb {
+  // Temporary tables always go into the `temp` catalog
+  fn create_table(name: &str, mode: CreateMode, persistence: Persistence, ids: &Identifiers) -> (String, String) {
+    let (modifier, table) = match persistence {
+      Persistence::Temporary => ("TEMPORARY ", ids.render::<DuckDb>(name)),
+      _                      => ("", ids.table::<DuckDb>(name))
+    };
+
+    (
+      super::create_table(&table, mode, modifier), // Prefix
+      "\n)".to_owned()                             // Affix
+    )
+  }
+
+  // There are no identity columns (just sequences), so `increments` is left to whoever creates those
+  fn create_column(name: &str, tp: &Type, ids: &Identifiers) -> String {
+    let inner = tp.inner();
+
+    format!(
+      "{} {}{}{}{}{}{}{}{}{}",
+      ids.render::<DuckDb>(name),
+      match tp.sql_type.as_ref() {
+        Some(sql) => sql.clone(),
+        None      => DuckDb::stringify(inner.clone())
+      },
+      match tp.collation.as_ref() {
+        Some(collation) => format!(" COLLATE {}", collation),
+        None            => String::new()
+      },
+      // Generated columns are always virtual
+      match tp.generated.as_ref() {
+        Some(expr) => format!(" GENERATED ALWAYS AS ({}) VIRTUAL", expr),
+        None       => String::new()
+      },
+      match tp.primary {
+        true  => " PRIMARY KEY",
+        false => ""
+      },
+      match tp.default.as_ref() {
+        Some(default) => format!(" DEFAULT {}", DuckDb::default_value(default)),
+        None          => String::new()
+      },
+      match tp.nullable {
+        false => " NOT NULL",
+        true  => ""
+      },
+      match tp.unique {
+        true  => " UNIQUE",
+        false => ""
+      },
+      // Referential actions other than the default aren't supported
+      match &inner {
+        BaseType::Foreign(table, keys) => format!(" REFERENCES {} ({})", ids.table::<DuckDb>(table), ids.list::<DuckDb>(&keys.0)),
+        _                              => String::new()
+      },
+      match tp.check.as_ref() {
+        Some(expr) => format!(" CHECK ({})", expr),
+        None       => String::new()
+      }
+    )
+  }
+
+  fn create_constraint(name: &str, constraint: &Constraint, _deferrable: bool, ids: &Identifiers) -> String {
+    let definition = match constraint {
+      Constraint::Check(expr)         => format!("CHECK ({})", expr),
+      Constraint::PrimaryKey(columns) => format!("PRIMARY KEY ({})", ids.list::<DuckDb>(columns)),
+      Constraint::Unique(columns)     => format!("UNIQUE ({})", ids.list::<DuckDb>(columns)),
+      Constraint::Foreign { columns, table, references, .. } => format!(
+        "FOREIGN KEY ({}) REFERENCES {} ({})",
+        ids.list::<DuckDb>(columns),
+        ids.table::<DuckDb>(table),
+        ids.list::<DuckDb>(references)
+      )
+    };
+
+    format!("CONSTRAINT {} {}", ids.render::<DuckDb>(name), definition)
+  }
+
+  // Constraints can only be declared along with the table
+  fn add_constraint(_name: &str, _constraint: &Constraint, _deferrable: bool, _ids: &Identifiers) -> String {
+    String::new()
+  }
+
+  fn or_replace() -> bool {
+    true
+  }
+
+  fn column_type(tp: &Type) -> String {
+    match tp.sql_type.as_ref() {
+      Some(sql) => sql.clone(),
+      None      => DuckDb::stringify(tp.inner())
+    }
+  }
+
+  // There are no users, so no permissions either
+  fn grant(_table: &str, _privileges: &str, _role: &str, _ids: &Identifiers) -> String {
+    String::new()
+  }
+
+  fn owner(_table: &str, _role: &str, _ids: &Identifiers) -> String {
+    String::new()
+  }
+
+  fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+  }
+
+  fn reserved_words() -> &'static [&'static str] {
+    &[
+      "ANALYSE", "ANALYZE", "ARRAY", "ASYMMETRIC", "BOTH", "COLLATE", "DEFERRABLE", "DO", "INITIALLY", "LATERAL",
+      "LEADING", "ONLY", "PIVOT", "PLACING", "QUALIFY", "RETURNING", "SYMMETRIC", "TRAILING", "UNPIVOT", "VARIADIC",
+      "WINDOW"
+    ]
+  }
+
+  fn uuid_default() -> Option<&'static str> {
+    Some("gen_random_uuid()")
+  }
+}
+
+impl DuckDb {
+  fn default_value(default: &WrappedDefault) -> String {
+    use self::WrappedDefault::*;
+
+    match default {
+      Integer(_) | BigInt(_) | Float(_) | Double(_) => default.to_string(),
+      Boolean(val)   => match val {
+        true  => "TRUE".to_string(),
+        false => "FALSE".to_string()
+      },
+      Array(values)  => format!("[{}]", values.iter().map(DuckDb::default_value).collect::<Vec<_>>().join(", ")),
+      Custom(sql)    => sql.to_string(),
+      Expr(sql)      => sql.clone(),
+      Date(time)     => format!("to_timestamp({})::DATE", super::epoch_seconds(time)),
+      DateTime(time) => format!("to_timestamp({})", super::epoch_seconds(time)),
+      _              => super::text_literal(&default.to_string())
+    }
+  }
+
+  fn stringify(tp: BaseType) -> String {
+    use self::BaseType::*;
+
+    match tp {
+      Foreign(_, _)      => "VARCHAR".to_string(),
+      Custom(sql)        => sql.to_string(),
+      Array(boxed)       => format!("{}[]", DuckDb::stringify(*boxed)),
+      // Lengths aren't enforced anyway
+      Varchar(_)         => "VARCHAR".to_string(),
+      Text               => "VARCHAR".to_string(),
+      CiText             => "VARCHAR COLLATE NOCASE".to_string(),
+      Boolean            => "BOOLEAN".to_string(),
+      SmallInt           => "SMALLINT".to_string(),
+      Integer            => "INTEGER".to_string(),
+      BigInt             => "BIGINT".to_string(),
+      Float              => "FLOAT".to_string(),
+      Double             => "DOUBLE".to_string(),
+      // A bare DECIMAL is DECIMAL(18, 3), which would round most values
+      Numeric(spec)      => match spec {
+        Some((precision, scale)) => format!("DECIMAL({}, {})", precision, scale),
+        None                     => "DECIMAL(38, 18)".to_string()
+      },
+      Uuid               => "UUID".to_string(),
+      Binary             => "BLOB".to_string(),
+      Jsonb              => "JSON".to_string(),
+      // Needs the spatial extension
+      Geography(_)       => "GEOMETRY".to_string(),
+      Time               => "TIME".to_string(),
+      Date               => "DATE".to_string(),
+      DateTime           => "TIMESTAMP".to_string(),
+      DateTimeTz         => "TIMESTAMPTZ".to_string(),
+      Index(_)           => unreachable!()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::sql::*;
+  use serde_json::json;
+
+  #[test]
+  fn duckdb_statements() {
+    let ids = Identifiers::default();
+    let columns = vec!["Id".to_string(), "Name".to_string()];
+
+    assert_eq!(DuckDb::column_type(&varchar(Some(40))), "VARCHAR");
+    assert_eq!(DuckDb::column_type(&citext()), "VARCHAR COLLATE NOCASE");
+    assert_eq!(DuckDb::column_type(&numeric(18, 2)), "DECIMAL(18, 2)");
+    assert_eq!(DuckDb::column_type(&uuid()), "UUID");
+    assert_eq!(DuckDb::column_type(&jsonb()), "JSON");
+
+    // VARCHAR lengths aren't there to change
+    assert_eq!(DuckDb::alter_column("Name", &varchar(Some(80)), &varchar(Some(255)), &ids), Vec::<String>::new());
+    assert_eq!(DuckDb::alter_column("Amount", &integer(), &numeric(18, 2).nullable(true), &ids), [
+      "ALTER COLUMN \"Amount\" SET DATA TYPE DECIMAL(18, 2) USING CAST(\"Amount\" AS DECIMAL(18, 2))",
+      "ALTER COLUMN \"Amount\" DROP NOT NULL"
+    ]);
+
+    assert_eq!(DuckDb::upsert("Account", &columns, &columns[..1], &ids), [
+      "INSERT INTO \"Account\" (\"Id\", \"Name\") VALUES (?, ?) ON CONFLICT (\"Id\") DO UPDATE SET \"Name\" = EXCLUDED.\"Name\""
+    ]);
+
+    assert_eq!(DuckDb::literal(&json!("O'Brien \\ Sons"), &text()), "'O''Brien \\ Sons'");
+    assert_eq!(DuckDb::literal(&json!(false), &boolean()), "FALSE");
+    assert_eq!(DuckDb::literal(&json!({ "a": 1 }), &jsonb()), "'{\"a\":1}'");
+  }
+}
@@ -1,2 +1,128 @@
+mod clickhouse;
+mod duckdb;
+mod mssql;
+mod mysql;
 mod pg;
+mod redshift;
+mod snowflake;
+mod sqlite;
+
+pub use clickhouse::*;
+pub use duckdb::*;
+pub use mssql::*;
+pub use mysql::*;
 pub use pg::*;
+pub use redshift::*;
+pub use snowflake::*;
+pub use sqlite::*;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::sql::{types::ReferentialAction, CreateMode, DropOptions, Identifiers, SqlGenerator};
+
+/// Opening `CREATE TABLE` for an (already rendered) table name, `modifier` being something like `TEMPORARY `.
+pub(crate) fn create_table(table: &str, mode: CreateMode, modifier: &str) -> String {
+  match mode {
+    CreateMode::Create      => format!("CREATE {}TABLE {} (\n", modifier, table),
+    CreateMode::IfNotExists => format!("CREATE {}TABLE IF NOT EXISTS {} (\n", modifier, table),
+    CreateMode::OrReplace   => format!("CREATE OR REPLACE {}TABLE {} (\n", modifier, table)
+  }
+}
+
+/// `DROP TABLE` for an (already rendered) table name.
+pub(crate) fn drop_table(table: &str, options: DropOptions) -> String {
+  format!(
+    "DROP TABLE {}{}{}",
+    match options.if_exists {
+      true  => "IF EXISTS ",
+      false => ""
+    },
+    table,
+    match options.cascade {
+      true  => " CASCADE",
+      false => ""
+    }
+  )
+}
+
+/// Standard `ON DELETE` / `ON UPDATE` clauses.
+pub(crate) fn referential_actions(on_delete: Option<ReferentialAction>, on_update: Option<ReferentialAction>) -> String {
+  let render = |action| match action {
+    ReferentialAction::NoAction   => "NO ACTION",
+    ReferentialAction::Restrict   => "RESTRICT",
+    ReferentialAction::Cascade    => "CASCADE",
+    ReferentialAction::SetNull    => "SET NULL",
+    ReferentialAction::SetDefault => "SET DEFAULT"
+  };
+
+  let mut sql = String::new();
+  if let Some(action) = on_delete {
+    sql.push_str(&format!(" ON DELETE {}", render(action)));
+  }
+  if let Some(action) = on_update {
+    sql.push_str(&format!(" ON UPDATE {}", render(action)));
+  }
+  sql
+}
+
+/// `INSERT` of a single row of bind parameters.
+pub(crate) fn insert_values<T>(table: &str, columns: &[String], ids: &Identifiers) -> String
+where T: SqlGenerator {
+  let values = (1..=columns.len()).map(T::placeholder).collect::<Vec<_>>();
+  format!("INSERT INTO {} ({}) VALUES ({})", ids.table::<T>(table), ids.list::<T>(columns), values.join(", "))
+}
+
+/// `MERGE` of a single row into `table`, where `source` (something like `(VALUES (...)) AS source (...)`) has all the `columns`.
+pub(crate) fn merge<T>(table: &str, source: &str, columns: &[String], key: &[String], ids: &Identifiers) -> String
+where T: SqlGenerator {
+  let from_source = |col: &String| format!("source.{}", ids.render::<T>(col));
+
+  let on = key
+    .iter()
+    .map(|col| format!("target.{} = {}", ids.render::<T>(col), from_source(col)))
+    .collect::<Vec<_>>();
+  let updates = columns
+    .iter()
+    .filter(|col| !key.contains(col))
+    .map(|col| format!("{} = {}", ids.render::<T>(col), from_source(col)))
+    .collect::<Vec<_>>();
+
+  let mut sql = format!("MERGE INTO {} AS target\nUSING {}\nON {}", ids.table::<T>(table), source, on.join(" AND "));
+  if !updates.is_empty() {
+    sql.push_str(&format!("\nWHEN MATCHED THEN UPDATE SET {}", updates.join(", ")));
+  }
+  sql.push_str(&format!(
+    "\nWHEN NOT MATCHED THEN INSERT ({}) VALUES ({})",
+    ids.list::<T>(columns),
+    columns.iter().map(from_source).collect::<Vec<_>>().join(", ")
+  ));
+  sql
+}
+
+/// Literal for a JSON value, with the dialect's booleans and string literals; arrays and objects go in as JSON text.
+pub(crate) fn value_literal<F>(value: &serde_json::Value, booleans: (&str, &str), text: F) -> String
+where F: Fn(&str) -> String {
+  use serde_json::Value;
+
+  match value {
+    Value::Null         => "NULL".to_string(),
+    Value::Bool(true)   => booleans.0.to_string(),
+    Value::Bool(false)  => booleans.1.to_string(),
+    Value::Number(num)  => num.to_string(),
+    Value::String(val)  => text(val),
+    value               => text(&value.to_string())
+  }
+}
+
+/// Standard single quoted string literal.
+pub(crate) fn text_literal(text: &str) -> String {
+  format!("'{}'", text.replace('\'', "''"))
+}
+
+/// Seconds since the epoch (with fractions), for `to_timestamp` style defaults.
+pub(crate) fn epoch_seconds(time: &SystemTime) -> String {
+  match time.duration_since(UNIX_EPOCH) {
+    Ok(since)   => since.as_secs_f64().to_string(),
+    Err(before) => format!("-{}", before.duration().as_secs_f64())
+  }
+}
@@ -0,0 +1,328 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+
+use crate::sql::{
+  types::{BaseType, ReferentialAction, Type, WrappedDefault},
+  Constraint,
+  CreateMode,
+  DropOptions,
+  Identifiers,
+  Persistence,
+  SqlGenerator
+};
+
+/// SQL Server (and Azure SQL).
+pub struct Mssql;
+impl SqlGenerator for Mssql {
+  // There's no IF NOT EXISTS, so that has to check the catalog instead (which is `tempdb` for temporary `#tables`)
+  fn create_table(name: &str, mode: CreateMode, persistence: Persistence, ids: &Identifiers) -> (String, String) {
+    let (table, object) = match persistence {
+      Persistence::Temporary => {
+        let table = ids.render::<Mssql>(&format!("#{}", name));
+        (table.clone(), format!("tempdb..{}", table))
+      },
+      _ => (ids.table::<Mssql>(name), ids.table::<Mssql>(name))
+    };
+    let affix = "\n)".to_owned();
+
+    match mode {
+      CreateMode::IfNotExists => (format!("IF OBJECT_ID(N{}, N'U') IS NULL\nCREATE TABLE {} (\n", super::text_literal(&object), table), affix),
+      _                       => (super::create_table(&table, mode, ""), affix)
+    }
+  }
+
+  fn create_column(name: &str, tp: &Type, ids: &Identifiers) -> String {
+    let inner = tp.inner();
+
+    // Computed columns don't get a type (or much of anything else) at all
+    if let Some(expr) = tp.generated.as_ref() {
+      return format!("{} AS ({}) PERSISTED", ids.render::<Mssql>(name), expr);
+    }
+
+    format!(
+      "{} {}{}{}{}{}{}{}{}{}",
+      ids.render::<Mssql>(name),
+      match tp.sql_type.as_ref() {
+        Some(sql) => sql.clone(),
+        None      => Mssql::stringify(inner.clone())
+      },
+      match tp.collation.as_ref() {
+        Some(collation) => format!(" COLLATE {}", collation),
+        None            => String::new()
+      },
+      match tp.increments {
+        true  => " IDENTITY(1,1)",
+        false => ""
+      },
+      match tp.nullable {
+        false => " NOT NULL",
+        true  => " NULL"
+      },
+      match tp.default.as_ref() {
+        Some(default) => format!(" DEFAULT {}", Mssql::default_value(default)),
+        None          => String::new()
+      },
+      match tp.primary {
+        true  => " PRIMARY KEY",
+        false => ""
+      },
+      match tp.unique {
+        true  => " UNIQUE",
+        false => ""
+      },
+      match &inner {
+        BaseType::Foreign(table, keys) => format!(
+          " REFERENCES {} ({}){}",
+          ids.table::<Mssql>(table),
+          ids.list::<Mssql>(&keys.0),
+          Mssql::referential_actions(tp.on_delete, tp.on_update)
+        ),
+        _ => String::new()
+      },
+      match tp.check.as_ref() {
+        Some(expr) => format!(" CHECK ({})", expr),
+        None       => String::new()
+      }
+    )
+  }
+
+  // SQL Server can't defer constraints, so that just gets dropped
+  fn create_constraint(name: &str, constraint: &Constraint, _deferrable: bool, ids: &Identifiers) -> String {
+    let definition = match constraint {
+      Constraint::Check(expr)         => format!("CHECK ({})", expr),
+      Constraint::PrimaryKey(columns) => format!("PRIMARY KEY ({})", ids.list::<Mssql>(columns)),
+      Constraint::Unique(columns)     => format!("UNIQUE ({})", ids.list::<Mssql>(columns)),
+      Constraint::Foreign { columns, table, references, on_delete, on_update } => format!(
+        "FOREIGN KEY ({}) REFERENCES {} ({}){}",
+        ids.list::<Mssql>(columns),
+        ids.table::<Mssql>(table),
+        ids.list::<Mssql>(references),
+        Mssql::referential_actions(*on_delete, *on_update)
+      )
+    };
+
+    format!("CONSTRAINT {} {}", ids.render::<Mssql>(name), definition)
+  }
+
+  fn placeholder(index: usize) -> String {
+    format!("@P{}", index)
+  }
+
+  // MERGE is the one statement that has to end with a semicolon
+  fn upsert(table: &str, columns: &[String], key: &[String], ids: &Identifiers) -> Vec<String> {
+    let values = (1..=columns.len()).map(Mssql::placeholder).collect::<Vec<_>>();
+    let source = format!("(VALUES ({})) AS source ({})", values.join(", "), ids.list::<Mssql>(columns));
+    vec![format!("{};", super::merge::<Mssql>(table, &source, columns, key, ids))]
+  }
+
+  fn literal(value: &Value, _tp: &Type) -> String {
+    super::value_literal(value, ("1", "0"), |text| format!("N{}", super::text_literal(text)))
+  }
+
+  fn create_view(name: &str, ids: &Identifiers) -> String {
+    format!("CREATE OR ALTER VIEW {}", ids.table::<Mssql>(name))
+  }
+
+  // No IF NOT EXISTS, and CREATE SCHEMA has to be the only statement in its batch
+  fn create_schema(name: &str, ids: &Identifiers) -> String {
+    format!(
+      "IF SCHEMA_ID(N{}) IS NULL EXEC(N{})",
+      super::text_literal(name),
+      super::text_literal(&format!("CREATE SCHEMA {}", ids.render::<Mssql>(name)))
+    )
+  }
+
+  // Dependent foreign keys have to be dropped by hand, there is no CASCADE
+  fn drop_table(name: &str, options: DropOptions, ids: &Identifiers) -> String {
+    super::drop_table(&ids.table::<Mssql>(name), DropOptions { cascade: false, ..options })
+  }
+
+  fn column_type(tp: &Type) -> String {
+    match tp.sql_type.as_ref() {
+      Some(sql) => sql.clone(),
+      None      => Mssql::stringify(tp.inner())
+    }
+  }
+
+  fn add_column(name: &str, tp: &Type, ids: &Identifiers) -> String {
+    format!("ADD {}", Mssql::create_column(name, tp, ids))
+  }
+
+  // ALTER COLUMN wants the type and nullability together
+  fn alter_column(name: &str, old: &Type, new: &Type, ids: &Identifiers) -> Vec<String> {
+    match Mssql::column_type(old) == Mssql::column_type(new) && old.nullable == new.nullable {
+      true  => Vec::new(),
+      false => vec![format!(
+        "ALTER COLUMN {} {}{}",
+        ids.render::<Mssql>(name),
+        Mssql::column_type(new),
+        match new.nullable {
+          true  => " NULL",
+          false => " NOT NULL"
+        }
+      )]
+    }
+  }
+
+  // Comments are `MS_Description` extended properties, which take plain names rather than identifiers
+  fn comment_on(table: &str, column: Option<&str>, comment: &str, ids: &Identifiers) -> String {
+    let literal = |text: &str| format!("N{}", super::text_literal(text));
+
+    format!(
+      "EXEC sp_addextendedproperty @name = N'MS_Description', @value = {}, @level0type = N'SCHEMA', @level0name = {}, @level1type = N'TABLE', @level1name = {}{}",
+      literal(comment),
+      literal(&ids.fold(ids.schema().unwrap_or("dbo"))),
+      literal(&ids.fold(table)),
+      match column {
+        Some(column) => format!(", @level2type = N'COLUMN', @level2name = {}", literal(&ids.fold(column))),
+        None         => String::new()
+      }
+    )
+  }
+
+  fn grant(table: &str, privileges: &str, role: &str, ids: &Identifiers) -> String {
+    format!("GRANT {} ON {} TO {}", privileges, ids.table::<Mssql>(table), role)
+  }
+
+  fn owner(table: &str, role: &str, ids: &Identifiers) -> String {
+    format!("ALTER AUTHORIZATION ON OBJECT::{} TO {}", ids.table::<Mssql>(table), role)
+  }
+
+  fn quote(name: &str) -> String {
+    format!("[{}]", name.replace(']', "]]"))
+  }
+
+  fn reserved_words() -> &'static [&'static str] {
+    &[
+      "ADD", "BACKUP", "BREAK", "BROWSE", "BULK", "CASCADE", "CHECKPOINT", "CLOSE", "CLUSTERED", "COLLATE",
+      "COMMIT", "COMPUTE", "CONTAINS", "CONTINUE", "CONVERT", "CURSOR", "DATABASE", "DBCC", "DEALLOCATE", "DECLARE",
+      "DENY", "DISK", "DISTRIBUTED", "DOUBLE", "DUMP", "ERRLVL", "ESCAPE", "EXEC", "EXECUTE", "EXIT", "EXTERNAL",
+      "FILE", "FILLFACTOR", "FUNCTION", "GOTO", "HOLDLOCK", "IDENTITY", "IDENTITYCOL", "IF", "INDEX", "KEY", "KILL",
+      "LINENO", "LOAD", "MERGE", "NOCHECK", "NONCLUSTERED", "OF", "OFF", "OFFSETS", "OPEN", "OPTION", "OVER",
+      "PERCENT", "PIVOT", "PLAN", "PRECISION", "PRINT", "PROC", "PROCEDURE", "PUBLIC", "RAISERROR", "READ",
+      "RECONFIGURE", "REPLICATION", "RESTORE", "RESTRICT", "RETURN", "REVERT", "REVOKE", "ROLLBACK", "ROWCOUNT",
+      "ROWGUIDCOL", "RULE", "SAVE", "SCHEMA", "SHUTDOWN", "STATISTICS", "TEXTSIZE", "TOP", "TRAN", "TRANSACTION",
+      "TRIGGER", "TRUNCATE", "TSEQUAL", "UNPIVOT", "UPDATETEXT", "USE", "VARYING", "VIEW", "WAITFOR", "WHILE",
+      "WRITETEXT"
+    ]
+  }
+
+  fn uuid_default() -> Option<&'static str> {
+    Some("NEWID()")
+  }
+}
+
+impl Mssql {
+  // There is no RESTRICT, but NO ACTION does the same thing here
+  fn referential_actions(on_delete: Option<ReferentialAction>, on_update: Option<ReferentialAction>) -> String {
+    let restrict = |action| match action {
+      Some(ReferentialAction::Restrict) => Some(ReferentialAction::NoAction),
+      other                             => other
+    };
+
+    super::referential_actions(restrict(on_delete), restrict(on_update))
+  }
+
+  fn default_value(default: &WrappedDefault) -> String {
+    use self::WrappedDefault::*;
+
+    let since_epoch = |time: &SystemTime| match time.duration_since(UNIX_EPOCH) {
+      Ok(since)   => since.as_secs() as i64,
+      Err(before) => -(before.duration().as_secs() as i64)
+    };
+
+    match default {
+      Integer(_) | BigInt(_) | Float(_) | Double(_) => default.to_string(),
+      Boolean(val)   => match val {
+        true  => "1".to_string(),
+        false => "0".to_string()
+      },
+      Custom(sql)    => format!("({})", sql),
+      Expr(sql)      => format!("({})", sql),
+      Date(time)     => format!("(CAST(DATEADD(SECOND, {}, '1970-01-01') AS DATE))", since_epoch(time)),
+      DateTime(time) => format!("(DATEADD(SECOND, {}, '1970-01-01'))", since_epoch(time)),
+      // Unicode literals, to go with the NVARCHAR columns
+      _              => format!("N{}", super::text_literal(&default.to_string()))
+    }
+  }
+
+  fn stringify(tp: BaseType) -> String {
+    use self::BaseType::*;
+
+    match tp {
+      // Keys have to stay short enough to be indexed
+      Foreign(_, _)      => "NVARCHAR(255)".to_string(),
+      Custom(sql)        => sql.to_string(),
+      // No arrays, these end up as JSON text
+      Array(_)           => "NVARCHAR(MAX)".to_string(),
+      Varchar(Some(len)) => match len {
+        0        => "NVARCHAR(255)".to_string(),
+        1..=4000 => format!("NVARCHAR({})", len),
+        _        => "NVARCHAR(MAX)".to_string()
+      },
+      Varchar(None)      => "NVARCHAR(255)".to_string(),
+      Text               => "NVARCHAR(MAX)".to_string(),
+      // The default collations are case-insensitive already; MAX columns can't be unique or indexed
+      CiText             => "NVARCHAR(255)".to_string(),
+      Boolean            => "BIT".to_string(),
+      SmallInt           => "SMALLINT".to_string(),
+      Integer            => "INT".to_string(),
+      BigInt             => "BIGINT".to_string(),
+      Float              => "REAL".to_string(),
+      Double             => "FLOAT".to_string(),
+      Numeric(spec)      => match spec {
+        Some((precision, scale)) => format!("DECIMAL({}, {})", precision, scale),
+        None                     => "DECIMAL(38, 18)".to_string()
+      },
+      Uuid               => "UNIQUEIDENTIFIER".to_string(),
+      Binary             => "VARBINARY(MAX)".to_string(),
+      Jsonb              => "NVARCHAR(MAX)".to_string(),
+      Geography(_)       => "GEOGRAPHY".to_string(),
+      Time               => "TIME".to_string(),
+      Date               => "DATE".to_string(),
+      DateTime           => "DATETIME2".to_string(),
+      DateTimeTz         => "DATETIMEOFFSET".to_string(),
+      Index(_)           => unreachable!()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::sql::*;
+  use serde_json::json;
+
+  #[test]
+  fn mssql_statements() {
+    let ids = Identifiers::default();
+    let columns = vec!["Id".to_string(), "Name".to_string()];
+
+    assert_eq!(Mssql::column_type(&varchar(Some(40))), "NVARCHAR(40)");
+    assert_eq!(Mssql::column_type(&varchar(Some(32768))), "NVARCHAR(MAX)");
+    assert_eq!(Mssql::column_type(&boolean()), "BIT");
+    assert_eq!(Mssql::column_type(&datetime_tz()), "DATETIMEOFFSET");
+    assert_eq!(Mssql::column_type(&numeric(18, 2)), "DECIMAL(18, 2)");
+
+    assert_eq!(Mssql::alter_column("Name", &varchar(Some(80)), &varchar(Some(80)), &ids), Vec::<String>::new());
+    assert_eq!(Mssql::alter_column("Name", &varchar(Some(80)), &varchar(Some(255)).nullable(true), &ids), [
+      "ALTER COLUMN [Name] NVARCHAR(255) NULL"
+    ]);
+    assert_eq!(Mssql::alter_column("Name", &varchar(Some(80)).nullable(true), &varchar(Some(80)), &ids), [
+      "ALTER COLUMN [Name] NVARCHAR(80) NOT NULL"
+    ]);
+
+    assert_eq!(Mssql::upsert("Account", &columns, &columns[..1], &ids), ["\
+      MERGE INTO [Account] AS target\n\
+      USING (VALUES (@P1, @P2)) AS source ([Id], [Name])\n\
+      ON target.[Id] = source.[Id]\n\
+      WHEN MATCHED THEN UPDATE SET [Name] = source.[Name]\n\
+      WHEN NOT MATCHED THEN INSERT ([Id], [Name]) VALUES (source.[Id], source.[Name]);"
+    ]);
+
+    assert_eq!(Mssql::literal(&json!("O'Brien's"), &text()), "N'O''Brien''s'");
+    assert_eq!(Mssql::literal(&json!(true), &boolean()), "1");
+    assert_eq!(Mssql::literal(&json!(["a", "b"]), &text()), "N'[\"a\",\"b\"]'");
+  }
+}
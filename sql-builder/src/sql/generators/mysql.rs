@@ -0,0 +1,290 @@
+use serde_json::Value;
+
+use crate::sql::{
+  types::{BaseType, Type, WrappedDefault},
+  Constraint,
+  CreateMode,
+  Identifiers,
+  Persistence,
+  SqlGenerator
+};
+
+/// MySQL 8 (and Aurora MySQL).
+pub struct MySql;
+impl SqlGenerator for MySql {
+  fn create_table(name: &str, mode: CreateMode, persistence: Persistence, ids: &Identifiers) -> (String, String) {
+    let (modifier, table) = match persistence {
+      Persistence::Temporary => ("TEMPORARY ", ids.table::<MySql>(name)),
+      _                      => ("", ids.table::<MySql>(name))
+    };
+
+    (
+      super::create_table(&table, mode, modifier), // Prefix
+      "\n)".to_owned()                             // Affix
+    )
+  }
+
+  fn create_column(name: &str, tp: &Type, ids: &Identifiers) -> String {
+    format!(
+      "{} {}{}{}{}{}{}{}{}{}{}",
+      ids.render::<MySql>(name),
+      match tp.sql_type.as_ref() {
+        Some(sql) => sql.clone(),
+        None      => MySql::stringify(tp.inner())
+      },
+      match tp.collation.as_ref() {
+        Some(collation) => format!(" COLLATE {}", collation),
+        None            => String::new()
+      },
+      match tp.generated.as_ref() {
+        Some(expr) => format!(" GENERATED ALWAYS AS ({}) STORED", expr),
+        None       => String::new()
+      },
+      match tp.nullable {
+        false => " NOT NULL",
+        true  => " NULL"
+      },
+      match tp.default.as_ref() {
+        Some(default) => format!(" DEFAULT {}", MySql::default_value(default)),
+        None          => String::new()
+      },
+      match tp.increments {
+        true  => " AUTO_INCREMENT",
+        false => ""
+      },
+      match tp.primary {
+        true  => " PRIMARY KEY",
+        false => ""
+      },
+      match tp.unique {
+        true  => " UNIQUE",
+        false => ""
+      },
+      // Column comments only exist inline
+      match tp.comment.as_ref() {
+        Some(comment) => format!(" COMMENT {}", super::text_literal(comment)),
+        None          => String::new()
+      },
+      match tp.check.as_ref() {
+        Some(expr) => format!(" CHECK ({})", expr),
+        None       => String::new()
+      }
+    )
+  }
+
+  // MySQL has no deferrable constraints at all, so that just gets dropped
+  fn create_constraint(name: &str, constraint: &Constraint, _deferrable: bool, ids: &Identifiers) -> String {
+    let definition = match constraint {
+      Constraint::Check(expr)         => format!("CHECK ({})", expr),
+      Constraint::PrimaryKey(columns) => format!("PRIMARY KEY ({})", ids.list::<MySql>(columns)),
+      Constraint::Unique(columns)     => format!("UNIQUE ({})", ids.list::<MySql>(columns)),
+      Constraint::Foreign { columns, table, references, on_delete, on_update } => format!(
+        "FOREIGN KEY ({}) REFERENCES {} ({}){}",
+        ids.list::<MySql>(columns),
+        ids.table::<MySql>(table),
+        ids.list::<MySql>(references),
+        super::referential_actions(*on_delete, *on_update)
+      )
+    };
+
+    format!("CONSTRAINT {} {}", ids.render::<MySql>(name), definition)
+  }
+
+  fn create_options(options: &[(String, String)], _primary_key: &[String], _ids: &Identifiers) -> String {
+    let mut options = options.to_vec();
+    for (key, value) in &[("ENGINE", "InnoDB"), ("DEFAULT CHARSET", "utf8mb4")] {
+      if !options.iter().any(|(existing, _)| existing.eq_ignore_ascii_case(key)) {
+        options.push((key.to_string(), value.to_string()));
+      }
+    }
+
+    options.iter().map(|(key, value)| format!(" {}={}", key, value)).collect()
+  }
+
+  // MySQL parses column level `REFERENCES` and then silently ignores it
+  fn inline_foreign_keys() -> bool {
+    false
+  }
+
+  fn column_type(tp: &Type) -> String {
+    match tp.sql_type.as_ref() {
+      Some(sql) => sql.clone(),
+      None      => MySql::stringify(tp.inner())
+    }
+  }
+
+  // MODIFY takes the whole column definition, which covers nullability too; keys are already there though
+  fn alter_column(name: &str, old: &Type, new: &Type, ids: &Identifiers) -> Vec<String> {
+    let definition = |tp: &Type| MySql::create_column(name, &Type { primary: false, unique: false, ..tp.clone() }, ids);
+
+    match definition(old) == definition(new) {
+      true  => Vec::new(),
+      false => vec![format!("MODIFY COLUMN {}", definition(new))]
+    }
+  }
+
+  // Conflicts are with whichever primary or unique key they're with, so `key` doesn't get a say
+  fn upsert(table: &str, columns: &[String], key: &[String], ids: &Identifiers) -> Vec<String> {
+    let updates = columns
+      .iter()
+      .filter(|col| !key.contains(col))
+      .map(|col| format!("{0} = VALUES({0})", ids.render::<MySql>(col)))
+      .collect::<Vec<_>>();
+
+    // Nothing to update still needs an assignment to keep the duplicate from being an error
+    let updates = match updates.is_empty() {
+      true  => format!("{0} = {0}", ids.render::<MySql>(&key[0])),
+      false => updates.join(", ")
+    };
+    vec![format!("{} ON DUPLICATE KEY UPDATE {}", super::insert_values::<MySql>(table, columns, ids), updates)]
+  }
+
+  // Backslashes are escapes (unless NO_BACKSLASH_ESCAPES is on)
+  fn literal(value: &Value, _tp: &Type) -> String {
+    super::value_literal(value, ("TRUE", "FALSE"), |text| super::text_literal(&text.replace('\\', "\\\\")))
+  }
+
+  fn comment_on(table: &str, column: Option<&str>, comment: &str, ids: &Identifiers) -> String {
+    match column {
+      Some(_) => String::new(),
+      None    => format!("ALTER TABLE {} COMMENT = {}", ids.table::<MySql>(table), super::text_literal(comment))
+    }
+  }
+
+  // Privileges belong to users and roles, tables don't have owners
+  fn owner(_table: &str, _role: &str, _ids: &Identifiers) -> String {
+    String::new()
+  }
+
+  fn quote(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+  }
+
+  fn reserved_words() -> &'static [&'static str] {
+    &[
+      "ADD", "ANALYZE", "BEFORE", "BIGINT", "BINARY", "BLOB", "BOTH", "CALL", "CASCADE", "CHANGE", "CHAR",
+      "CHARACTER", "COLLATE", "CONDITION", "CONVERT", "DATABASE", "DATABASES", "DEC", "DECIMAL", "DECLARE",
+      "DELAYED", "DESCRIBE", "DIV", "DOUBLE", "DUAL", "EACH", "ELSEIF", "ENCLOSED", "ESCAPED", "EXIT", "EXPLAIN",
+      "FLOAT", "FORCE", "FULLTEXT", "GENERATED", "GROUPS", "IF", "IGNORE", "INDEX", "INFILE", "INT", "INTEGER",
+      "INTERVAL", "KEY", "KEYS", "KILL", "LEADING", "LEAVE", "LINES", "LOAD", "LOCK", "LONG", "LOOP", "MATCH",
+      "MOD", "NUMERIC", "OPTION", "OUTFILE", "OVER", "PARTITION", "PRECISION", "PROCEDURE", "PURGE", "RANGE",
+      "RANK", "READ", "REAL", "REGEXP", "RELEASE", "RENAME", "REPEAT", "REPLACE", "REQUIRE", "RESTRICT", "RETURN",
+      "REVOKE", "RLIKE", "ROW", "ROWS", "SCHEMA", "SEPARATOR", "SHOW", "SIGNAL", "SMALLINT", "SPATIAL", "SQL",
+      "STARTING", "STORED", "TERMINATED", "TRIGGER", "UNDO", "UNLOCK", "UNSIGNED", "USAGE", "VARCHAR", "VIRTUAL",
+      "WHILE", "WINDOW", "WRITE", "XOR", "ZEROFILL"
+    ]
+  }
+
+  fn uuid_default() -> Option<&'static str> {
+    Some("UUID()")
+  }
+}
+
+impl MySql {
+  fn default_value(default: &WrappedDefault) -> String {
+    use self::WrappedDefault::*;
+
+    match default {
+      Integer(_) | BigInt(_) | Float(_) | Double(_) => default.to_string(),
+      Boolean(val)   => match val {
+        true  => "TRUE".to_string(),
+        false => "FALSE".to_string()
+      },
+      // Expression defaults have to be wrapped in parens
+      Custom(sql)    => format!("({})", sql),
+      Expr(sql)      => format!("({})", sql),
+      Array(_)       => format!("(CAST({} AS JSON))", super::text_literal(&MySql::json_array(default))),
+      Date(time)     => format!("(DATE(FROM_UNIXTIME({})))", super::epoch_seconds(time)),
+      DateTime(time) => format!("(FROM_UNIXTIME({}))", super::epoch_seconds(time)),
+      _              => super::text_literal(&default.to_string())
+    }
+  }
+
+  /// Arrays are stored as JSON, so array defaults become JSON arrays.
+  fn json_array(default: &WrappedDefault) -> String {
+    use self::WrappedDefault::*;
+
+    match default {
+      Array(values) => format!("[{}]", values.iter().map(MySql::json_array).collect::<Vec<_>>().join(",")),
+      Text(text)    => serde_json::Value::from(*text).to_string(),
+      _             => default.to_string()
+    }
+  }
+
+  fn stringify(tp: BaseType) -> String {
+    use self::BaseType::*;
+
+    match tp {
+      // Keys have to match the type of the `Id` columns they point at
+      Foreign(_, _)      => "VARCHAR(255)".to_string(),
+      Custom(sql)        => sql.to_string(),
+      // No native arrays, JSON is the closest thing
+      Array(_)           => "JSON".to_string(),
+      // Varchars always need a length in MySQL
+      Varchar(Some(len)) => match len {
+        0 => "VARCHAR(255)".to_string(),
+        _ => format!("VARCHAR({})", len)
+      },
+      Varchar(None)      => "VARCHAR(255)".to_string(),
+      Boolean            => "BOOLEAN".to_string(),
+      SmallInt           => "SMALLINT".to_string(),
+      Integer            => "INT".to_string(),
+      BigInt             => "BIGINT".to_string(),
+      Text               => "LONGTEXT".to_string(),
+      // TEXT can't be unique or indexed; Salesforce strings are 255 characters at most anyway
+      CiText             => "VARCHAR(255) COLLATE utf8mb4_0900_ai_ci".to_string(),
+      Float              => "FLOAT".to_string(),
+      Double             => "DOUBLE".to_string(),
+      // A bare DECIMAL is DECIMAL(10, 0), which would drop every fraction
+      Numeric(spec)      => match spec {
+        Some((precision, scale)) => format!("DECIMAL({}, {})", precision, scale),
+        None                     => "DECIMAL(65, 30)".to_string()
+      },
+      Uuid               => "CHAR(36)".to_string(),
+      Binary             => "LONGBLOB".to_string(),
+      Jsonb              => "JSON".to_string(),
+      Geography(shape)   => format!("{} SRID 4326", shape),
+      Time               => "TIME".to_string(),
+      Date               => "DATE".to_string(),
+      // TIMESTAMP tops out in 2038, so both of these end up as (UTC) DATETIME
+      DateTime           => "DATETIME(3)".to_string(),
+      DateTimeTz         => "DATETIME(3)".to_string(),
+      Index(_)           => unreachable!()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::sql::*;
+  use serde_json::json;
+
+  #[test]
+  fn mysql_statements() {
+    let ids = Identifiers::default();
+    let columns = vec!["Id".to_string(), "Name".to_string()];
+
+    assert_eq!(MySql::column_type(&varchar(None)), "VARCHAR(255)");
+    assert_eq!(MySql::column_type(&varchar(Some(40))), "VARCHAR(40)");
+    assert_eq!(MySql::column_type(&numeric(18, 2)), "DECIMAL(18, 2)");
+    assert_eq!(MySql::column_type(&datetime_tz()), "DATETIME(3)");
+    assert_eq!(MySql::column_type(&text().sql_type("MEDIUMTEXT")), "MEDIUMTEXT");
+
+    assert_eq!(MySql::alter_column("Name", &varchar(Some(80)), &varchar(Some(80)).primary(true), &ids), Vec::<String>::new());
+    assert_eq!(MySql::alter_column("Name", &varchar(Some(80)), &varchar(Some(255)).nullable(true), &ids), [
+      "MODIFY COLUMN `Name` VARCHAR(255) NULL"
+    ]);
+
+    assert_eq!(MySql::upsert("Account", &columns, &columns[..1], &ids), [
+      "INSERT INTO `Account` (`Id`, `Name`) VALUES (?, ?) ON DUPLICATE KEY UPDATE `Name` = VALUES(`Name`)"
+    ]);
+    assert_eq!(MySql::upsert("Account", &columns[..1], &columns[..1], &ids), [
+      "INSERT INTO `Account` (`Id`) VALUES (?) ON DUPLICATE KEY UPDATE `Id` = `Id`"
+    ]);
+
+    assert_eq!(MySql::literal(&json!("O'Brien \\ Sons"), &text()), "'O''Brien \\\\ Sons'");
+    assert_eq!(MySql::literal(&json!(true), &boolean()), "TRUE");
+    assert_eq!(MySql::literal(&json!(null), &text()), "NULL");
+  }
+}
@@ -1,85 +1,255 @@
+use serde_json::Value;
+
 use crate::sql::{
-  types::{BaseType, Type},
+  types::{BaseType, Type, WrappedDefault},
+  CaseFolding,
+  Constraint,
+  CreateMode,
+  Identifiers,
+  Persistence,
   SqlGenerator
 };
 
 pub struct Pg;
 impl SqlGenerator for Pg {
-  fn create_table(name: &str) -> (String, String) {
+  // Temporary tables always live in their own schema
+  fn create_table(name: &str, mode: CreateMode, persistence: Persistence, ids: &Identifiers) -> (String, String) {
+    let (modifier, table) = match persistence {
+      Persistence::Permanent => ("", ids.table::<Pg>(name)),
+      Persistence::Unlogged  => ("UNLOGGED ", ids.table::<Pg>(name)),
+      Persistence::Temporary => ("TEMPORARY ", ids.render::<Pg>(name))
+    };
+
     (
-      format!("CREATE TABLE \"{}\" (\n", name), // Prefix
-      "\n)".to_owned()                          // Affix
+      super::create_table(&table, mode, modifier), // Prefix
+      "\n)".to_owned()                             // Affix
     )
   }
 
-  fn create_column(name: &str, tp: &Type) -> String {
+  fn create_column(name: &str, tp: &Type, ids: &Identifiers) -> String {
     use self::BaseType::*;
 
     // Get the column type
     let inner = tp.inner();
+    let name  = ids.render::<Pg>(name);
+    // Identities only come in integer flavours, and a default would fight with the sequence
+    let identity = tp.increments && tp.sql_type.is_none() && tp.default.is_none() && matches!(inner, SmallInt | Integer | BigInt);
 
     format!(
-      "{}{}{}{}{}",
-      match inner {
-        Foreign(_, _) => format!("\"{}\" {}", name, Pg::stringify(inner)),
-        Custom(_)     => format!("\"{}\" {}", name, Pg::stringify(inner)),
-        Array(it)     => format!("\"{}\" {}", name, Pg::stringify(Array(Box::new(*it)))),
-        Varchar(_)    => format!("\"{}\" {}", name, Pg::stringify(inner)),
-        Boolean       => format!("\"{}\" {}", name, Pg::stringify(inner)),
-        Integer       => format!("\"{}\" {}", name, Pg::stringify(inner)),
-        BigInt        => format!("\"{}\" {}", name, Pg::stringify(inner)),
-        Text          => format!("\"{}\" {}", name, Pg::stringify(inner)),
-        Float         => format!("\"{}\" {}", name, Pg::stringify(inner)),
-        Double        => format!("\"{}\" {}", name, Pg::stringify(inner)),
-        Jsonb         => format!("\"{}\" {}", name, Pg::stringify(inner)),
-        Date          => format!("\"{}\" {}", name, Pg::stringify(inner)),
-        Time          => format!("\"{}\" {}", name, Pg::stringify(inner)),
-        DateTime      => format!("\"{}\" {}", name, Pg::stringify(inner)),
-        Index(_)      => panic!("`create_column` should not be called for indices")
+      "{}{}{}{}{}{}{}{}{}",
+      match tp.sql_type.as_ref() {
+        // Per column overrides win over whatever the base type would have been
+        Some(sql) => format!("{} {}", name, sql),
+        None      => match inner {
+          Foreign(ref table, ref keys) => format!(
+            "{} VARCHAR REFERENCES {} ({}){}{}",
+            name,
+            ids.table::<Pg>(table),
+            ids.list::<Pg>(&keys.0),
+            super::referential_actions(tp.on_delete, tp.on_update),
+            Pg::deferral(tp.deferrable)
+          ),
+          Custom(_)     => format!("{} {}", name, Pg::stringify(inner)),
+          Array(it)     => format!("{} {}", name, Pg::stringify(Array(Box::new(*it)))),
+          Varchar(_)    => format!("{} {}", name, Pg::stringify(inner)),
+          Boolean       => format!("{} {}", name, Pg::stringify(inner)),
+          SmallInt      => format!("{} {}", name, Pg::stringify(inner)),
+          Integer       => format!("{} {}", name, Pg::stringify(inner)),
+          BigInt        => format!("{} {}", name, Pg::stringify(inner)),
+          Text          => format!("{} {}", name, Pg::stringify(inner)),
+          CiText        => format!("{} {}", name, Pg::stringify(inner)),
+          Float         => format!("{} {}", name, Pg::stringify(inner)),
+          Double        => format!("{} {}", name, Pg::stringify(inner)),
+          Numeric(_)    => format!("{} {}", name, Pg::stringify(inner)),
+          Uuid          => format!("{} {}", name, Pg::stringify(inner)),
+          Binary        => format!("{} {}", name, Pg::stringify(inner)),
+          Jsonb         => format!("{} {}", name, Pg::stringify(inner)),
+          Geography(_)  => format!("{} {}", name, Pg::stringify(inner)),
+          Date          => format!("{} {}", name, Pg::stringify(inner)),
+          Time          => format!("{} {}", name, Pg::stringify(inner)),
+          DateTime      => format!("{} {}", name, Pg::stringify(inner)),
+          DateTimeTz    => format!("{} {}", name, Pg::stringify(inner)),
+          Index(_)      => panic!("`create_column` should not be called for indices")
+        }
+      },
+      match tp.collation.as_ref() {
+        Some(collation) => format!(" COLLATE \"{}\"", collation),
+        None            => String::new()
+      },
+      match identity {
+        true  => " GENERATED BY DEFAULT AS IDENTITY",
+        false => ""
+      },
+      match tp.generated.as_ref() {
+        Some(expr) => format!(" GENERATED ALWAYS AS ({}) STORED", expr),
+        None       => String::new()
       },
       match tp.primary {
         true  => " PRIMARY KEY",
         false => ""
       },
-      match (&tp.default).as_ref() {
-        Some(ref default) => format!(" DEFAULT '{}'", default),
-        _                 => format!("")
+      match tp.default.as_ref() {
+        Some(default) => format!(" DEFAULT {}", Pg::default_value(default)),
+        None          => String::new()
       },
       match tp.nullable {
         false => " NOT NULL",
         true  => ""
       },
       match tp.unique {
-        true  => " UNIQUE",
-        false => ""
+        true  => format!(" UNIQUE{}", Pg::deferral(tp.deferrable)),
+        false => String::new()
+      },
+      match tp.check.as_ref() {
+        Some(expr) => format!(" CHECK ({})", expr),
+        None       => String::new()
       }
     )
   }
+
+  fn create_constraint(name: &str, constraint: &Constraint, deferrable: bool, ids: &Identifiers) -> String {
+    let definition = match constraint {
+      Constraint::Check(expr)         => format!("CHECK ({})", expr),
+      Constraint::PrimaryKey(columns) => format!("PRIMARY KEY ({})", ids.list::<Pg>(columns)),
+      Constraint::Unique(columns)     => format!("UNIQUE ({})", ids.list::<Pg>(columns)),
+      Constraint::Foreign { columns, table, references, on_delete, on_update } => format!(
+        "FOREIGN KEY ({}) REFERENCES {} ({}){}",
+        ids.list::<Pg>(columns),
+        ids.table::<Pg>(table),
+        ids.list::<Pg>(references),
+        super::referential_actions(*on_delete, *on_update)
+      )
+    };
+
+    format!("CONSTRAINT {} {}{}", ids.render::<Pg>(name), definition, Pg::deferral(deferrable))
+  }
+
+  /// `PARTITION BY`, `USING` (access method) and `TABLESPACE` go as they are; anything else is a storage parameter,
+  /// e.g. `table.option("fillfactor", "70")` renders `WITH (fillfactor = 70)`.
+  fn create_options(options: &[(String, String)], _primary_key: &[String], _ids: &Identifiers) -> String {
+    let clauses    = ["PARTITION BY", "USING", "TABLESPACE"];
+    let clause     = |key: &str| options.iter().find(|(existing, _)| existing.eq_ignore_ascii_case(key)).map(|(_, value)| value);
+    let parameters = options
+      .iter()
+      .filter(|(key, _)| !clauses.iter().any(|clause| key.eq_ignore_ascii_case(clause)))
+      .map(|(key, value)| format!("{} = {}", key.to_ascii_lowercase(), value))
+      .collect::<Vec<_>>();
+
+    let partition = clause("PARTITION BY").map(|value| format!(" PARTITION BY {}", value));
+    let method    = clause("USING").map(|value| format!(" USING {}", value));
+    let storage   = match parameters.is_empty() {
+      true  => None,
+      false => Some(format!(" WITH ({})", parameters.join(", ")))
+    };
+    let space     = clause("TABLESPACE").map(|value| format!(" TABLESPACE {}", value));
+
+    [partition, method, storage, space].iter().flatten().cloned().collect()
+  }
+
+  fn column_type(tp: &Type) -> String {
+    match tp.sql_type.as_ref() {
+      Some(sql) => sql.clone(),
+      None      => Pg::stringify(tp.inner())
+    }
+  }
+
+  fn placeholder(index: usize) -> String {
+    format!("${}", index)
+  }
+
+  // Arrays go in as array input, which casts to whatever the column holds
+  fn literal(value: &Value, tp: &Type) -> String {
+    match (&tp.inner, value) {
+      (BaseType::Array(_), Value::Array(values)) => super::text_literal(&crate::sql::array_literal(values)),
+      _                                          => super::value_literal(value, ("TRUE", "FALSE"), super::text_literal)
+    }
+  }
+
+  fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+  }
+
+  fn reserved_words() -> &'static [&'static str] {
+    &[
+      "ANALYSE", "ANALYZE", "ARRAY", "ASYMMETRIC", "BOTH", "COLLATE", "CONCURRENTLY", "CURRENT_CATALOG",
+      "CURRENT_ROLE", "CURRENT_SCHEMA", "DEFERRABLE", "DO", "FREEZE", "ILIKE", "INITIALLY", "ISNULL", "LATERAL",
+      "LEADING", "LOCALTIME", "LOCALTIMESTAMP", "NOTNULL", "ONLY", "OVERLAPS", "PLACING", "RETURNING", "SIMILAR",
+      "SYMMETRIC", "TABLESAMPLE", "TRAILING", "VARIADIC", "VERBOSE", "WINDOW"
+    ]
+  }
+
+  // Bare identifiers get lower cased
+  fn unquoted_case() -> CaseFolding {
+    CaseFolding::Lower
+  }
+
+  fn uuid_default() -> Option<&'static str> {
+    Some("gen_random_uuid()")
+  }
 }
 
 impl Pg {
+  fn deferral(deferrable: bool) -> &'static str {
+    match deferrable {
+      true  => " DEFERRABLE INITIALLY DEFERRED",
+      false => ""
+    }
+  }
+
+  /// Renders a default as a literal; only text (and anything without a bare form) gets quoted.
+  fn default_value(default: &WrappedDefault) -> String {
+    use self::WrappedDefault::*;
+
+    match default {
+      Integer(_) | BigInt(_) | Float(_) | Double(_) => default.to_string(),
+      Boolean(val)   => match val {
+        true  => "TRUE".to_string(),
+        false => "FALSE".to_string()
+      },
+      Array(values)  => match values.is_empty() {
+        true  => "'{}'".to_string(),
+        false => format!("ARRAY[{}]", values.iter().map(Pg::default_value).collect::<Vec<_>>().join(", "))
+      },
+      Custom(sql)    => sql.to_string(),
+      Expr(sql)      => sql.clone(),
+      Date(time)     => format!("to_timestamp({})::date", super::epoch_seconds(time)),
+      DateTime(time) => format!("to_timestamp({})", super::epoch_seconds(time)),
+      _              => super::text_literal(&default.to_string())
+    }
+  }
+
   fn stringify(tp: BaseType) -> String {
     use self::BaseType::*;
 
     match tp {
-      Foreign(tbl, refs) => format!("VARCHAR REFERENCES \"{}\" ({})", tbl, refs.0.join(",")),
-      Custom(sql)        => format!("{}", sql),
+      Foreign(_, _)      => "VARCHAR".to_string(),
+      Custom(sql)        => sql.to_string(),
       Array(boxed)       => format!("{}[]", Pg::stringify(*boxed)),
       Varchar(Some(len)) => match len {
-        0 => format!("VARCHAR"),
+        0 => "VARCHAR".to_string(),
         _ => format!("VARCHAR({})", len)
       },
-      Varchar(None)      => format!("VARCHAR"),
-      Boolean            => format!("BOOLEAN"),
-      Integer            => format!("INTEGER"),
-      BigInt             => format!("BIGINT"),
-      Text               => format!("TEXT"),
-      Float              => format!("FLOAT"),
-      Double             => format!("DOUBLE PRECISION"),
-      Jsonb              => format!("JSONB"),
-      Time               => format!("TIME"),
-      Date               => format!("DATE"),
-      DateTime           => format!("TIMESTAMP"),
+      Varchar(None)      => "VARCHAR".to_string(),
+      Boolean            => "BOOLEAN".to_string(),
+      SmallInt           => "SMALLINT".to_string(),
+      Integer            => "INTEGER".to_string(),
+      BigInt             => "BIGINT".to_string(),
+      Text               => "TEXT".to_string(),
+      CiText             => "CITEXT".to_string(),
+      Float              => "FLOAT".to_string(),
+      Double             => "DOUBLE PRECISION".to_string(),
+      Numeric(spec)      => match spec {
+        Some((precision, scale)) => format!("NUMERIC({}, {})", precision, scale),
+        None                     => "NUMERIC".to_string()
+      },
+      Uuid               => "UUID".to_string(),
+      Binary             => "BYTEA".to_string(),
+      Jsonb              => "JSONB".to_string(),
+      Geography(shape)   => format!("GEOGRAPHY({}, 4326)", shape),
+      Time               => "TIME".to_string(),
+      Date               => "DATE".to_string(),
+      DateTime           => "TIMESTAMP".to_string(),
+      DateTimeTz         => "TIMESTAMPTZ".to_string(),
       _                  => unreachable!()
     }
   }
@@ -0,0 +1,256 @@
+use crate::sql::{
+  types::{BaseType, Type, WrappedDefault},
+  CaseFolding,
+  Constraint,
+  CreateMode,
+  Identifiers,
+  Persistence,
+  SqlGenerator
+};
+
+/// Amazon Redshift; like Snowflake, keys are informational only.
+pub struct Redshift;
+impl SqlGenerator for Redshift {
+  // Temporary tables can't be put into a schema
+  fn create_table(name: &str, mode: CreateMode, persistence: Persistence, ids: &Identifiers) -> (String, String) {
+    let (modifier, table) = match persistence {
+      Persistence::Temporary => ("TEMPORARY ", ids.render::<Redshift>(name)),
+      _                      => ("", ids.table::<Redshift>(name))
+    };
+
+    (
+      super::create_table(&table, mode, modifier), // Prefix
+      "\n)".to_owned()                             // Affix
+    )
+  }
+
+  // There are no generated columns (those just get loaded like any other) and no CHECK constraints
+  fn create_column(name: &str, tp: &Type, ids: &Identifiers) -> String {
+    let inner = tp.inner();
+
+    format!(
+      "{} {}{}{}{}{}{}{}{}",
+      ids.render::<Redshift>(name),
+      match tp.sql_type.as_ref() {
+        Some(sql) => sql.clone(),
+        None      => Redshift::stringify(inner.clone())
+      },
+      match tp.increments {
+        true  => " IDENTITY(1,1)",
+        false => ""
+      },
+      match tp.default.as_ref() {
+        Some(default) => format!(" DEFAULT {}", Redshift::default_value(default)),
+        None          => String::new()
+      },
+      match tp.collation.as_ref() {
+        Some(collation) => format!(" COLLATE {}", collation),
+        None            => String::new()
+      },
+      match tp.nullable {
+        false => " NOT NULL",
+        true  => ""
+      },
+      match tp.primary {
+        true  => " PRIMARY KEY",
+        false => ""
+      },
+      match tp.unique {
+        true  => " UNIQUE",
+        false => ""
+      },
+      match &inner {
+        BaseType::Foreign(table, keys) => format!(
+          " REFERENCES {} ({})",
+          ids.table::<Redshift>(table),
+          ids.list::<Redshift>(&keys.0)
+        ),
+        _ => String::new()
+      }
+    )
+  }
+
+  fn create_constraint(name: &str, constraint: &Constraint, _deferrable: bool, ids: &Identifiers) -> String {
+    let definition = match constraint {
+      Constraint::Check(_)            => return String::new(),
+      Constraint::PrimaryKey(columns) => format!("PRIMARY KEY ({})", ids.list::<Redshift>(columns)),
+      Constraint::Unique(columns)     => format!("UNIQUE ({})", ids.list::<Redshift>(columns)),
+      Constraint::Foreign { columns, table, references, .. } => format!(
+        "FOREIGN KEY ({}) REFERENCES {} ({})",
+        ids.list::<Redshift>(columns),
+        ids.table::<Redshift>(table),
+        ids.list::<Redshift>(references)
+      )
+    };
+
+    format!("CONSTRAINT {} {}", ids.render::<Redshift>(name), definition)
+  }
+
+  /// Supports `DISTSTYLE`, `DISTKEY` and `SORTKEY` (comma separated columns), e.g. `table.option("SORTKEY", "AccountId, CreatedDate")`.
+  fn create_options(options: &[(String, String)], _primary_key: &[String], ids: &Identifiers) -> String {
+    options
+      .iter()
+      .map(|(key, value)| match key.to_ascii_uppercase().as_str() {
+        "DISTKEY" | "SORTKEY" => {
+          let columns = value.split(',').map(|col| col.trim().to_string()).collect::<Vec<_>>();
+          format!(" {}({})", key.to_ascii_uppercase(), ids.list::<Redshift>(&columns))
+        },
+        _ => format!(" {} {}", key.to_ascii_uppercase(), value)
+      })
+      .collect()
+  }
+
+  fn placeholder(index: usize) -> String {
+    format!("${}", index)
+  }
+
+  // There's no ON CONFLICT (and MERGE wants a table to merge from), so the old row goes first;
+  // the DELETE only binds the values of `key`, in that order
+  fn upsert(table: &str, columns: &[String], key: &[String], ids: &Identifiers) -> Vec<String> {
+    let matches = key
+      .iter()
+      .enumerate()
+      .map(|(index, col)| format!("{} = {}", ids.render::<Redshift>(col), Redshift::placeholder(index + 1)))
+      .collect::<Vec<_>>();
+
+    vec![
+      format!("DELETE FROM {} WHERE {}", ids.table::<Redshift>(table), matches.join(" AND ")),
+      super::insert_values::<Redshift>(table, columns, ids)
+    ]
+  }
+
+  fn column_type(tp: &Type) -> String {
+    match tp.sql_type.as_ref() {
+      Some(sql) => sql.clone(),
+      None      => Redshift::stringify(tp.inner())
+    }
+  }
+
+  // Only VARCHAR lengths can change in place, and nullability can't change at all
+  fn alter_column(name: &str, old: &Type, new: &Type, ids: &Identifiers) -> Vec<String> {
+    match (old.inner(), new.inner()) {
+      (BaseType::Varchar(_), BaseType::Varchar(_)) if Redshift::column_type(old) != Redshift::column_type(new) => vec![
+        format!("ALTER COLUMN {} TYPE {}", ids.render::<Redshift>(name), Redshift::column_type(new))
+      ],
+      _ => Vec::new()
+    }
+  }
+
+  fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+  }
+
+  fn reserved_words() -> &'static [&'static str] {
+    &[
+      "ANALYSE", "ANALYZE", "AUTHORIZATION", "BACKUP", "BINARY", "BLANKSASNULL", "BOTH", "BYTEDICT", "COLLATE",
+      "CREDENTIALS", "DEFERRABLE", "DEFRAG", "DELTA", "DISABLE", "DO", "EMPTYASNULL", "ENABLE", "ENCODE", "ENCRYPT",
+      "EXPLICIT", "FREEZE", "IDENTITY", "IGNORE", "ILIKE", "INITIALLY", "ISNULL", "LEADING", "LOCALTIME",
+      "LOCALTIMESTAMP", "MINUS", "NEW", "NOTNULL", "OFF", "OFFLINE", "OID", "OLD", "ONLY", "OPEN", "OVERLAPS",
+      "PARALLEL", "PARTITION", "PERCENT", "PERMISSIONS", "PLACING", "RECOVER", "RESORT", "RESPECT", "RESTORE",
+      "SIMILAR", "SNAPSHOT", "SYSDATE", "SYSTEM", "TAG", "TIMESTAMP", "TOP", "TRAILING", "VERBOSE", "WALLET",
+      "WITHOUT"
+    ]
+  }
+
+  // Bare identifiers get lower cased (and quoted ones too, unless `enable_case_sensitive_identifier` is on)
+  fn unquoted_case() -> CaseFolding {
+    CaseFolding::Lower
+  }
+}
+
+impl Redshift {
+  /// Longest VARCHAR there is, in bytes.
+  const MAX_VARCHAR: usize = 65535;
+
+  fn default_value(default: &WrappedDefault) -> String {
+    use self::WrappedDefault::*;
+
+    match default {
+      Integer(_) | BigInt(_) | Float(_) | Double(_) => default.to_string(),
+      Boolean(val)   => match val {
+        true  => "TRUE".to_string(),
+        false => "FALSE".to_string()
+      },
+      Custom(sql)    => sql.to_string(),
+      Expr(sql)      => sql.clone(),
+      Date(time)     => format!("(TIMESTAMP 'epoch' + {} * INTERVAL '1 second')::DATE", super::epoch_seconds(time)),
+      DateTime(time) => format!("TIMESTAMP 'epoch' + {} * INTERVAL '1 second'", super::epoch_seconds(time)),
+      _              => super::text_literal(&default.to_string())
+    }
+  }
+
+  fn stringify(tp: BaseType) -> String {
+    use self::BaseType::*;
+
+    match tp {
+      Foreign(_, _)      => "VARCHAR(256)".to_string(),
+      Custom(sql)        => sql.to_string(),
+      // No arrays, but SUPER holds (semi-)structured data just fine
+      Array(_)           => "SUPER".to_string(),
+      // Lengths are in bytes rather than characters, and a character can take up to 4 of those
+      Varchar(Some(len)) => match len {
+        0 => "VARCHAR(256)".to_string(),
+        _ => format!("VARCHAR({})", (len * 4).min(Redshift::MAX_VARCHAR))
+      },
+      // A bare VARCHAR is only 256 bytes
+      Varchar(None)      => "VARCHAR(256)".to_string(),
+      Text               => "VARCHAR(MAX)".to_string(),
+      CiText             => "VARCHAR(MAX) COLLATE CASE_INSENSITIVE".to_string(),
+      Boolean            => "BOOLEAN".to_string(),
+      SmallInt           => "SMALLINT".to_string(),
+      Integer            => "INTEGER".to_string(),
+      BigInt             => "BIGINT".to_string(),
+      Float              => "REAL".to_string(),
+      Double             => "DOUBLE PRECISION".to_string(),
+      // A bare DECIMAL is DECIMAL(18, 0), which would drop every fraction
+      Numeric(spec)      => match spec {
+        Some((precision, scale)) => format!("DECIMAL({}, {})", precision, scale),
+        None                     => "DECIMAL(38, 18)".to_string()
+      },
+      Uuid               => "CHAR(36)".to_string(),
+      Binary             => "VARBYTE".to_string(),
+      Jsonb              => "SUPER".to_string(),
+      Geography(_)       => "GEOGRAPHY".to_string(),
+      Time               => "TIME".to_string(),
+      Date               => "DATE".to_string(),
+      DateTime           => "TIMESTAMP".to_string(),
+      DateTimeTz         => "TIMESTAMPTZ".to_string(),
+      Index(_)           => unreachable!()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::sql::*;
+  use serde_json::json;
+
+  #[test]
+  fn redshift_statements() {
+    let ids = Identifiers::default();
+    let columns = vec!["Id".to_string(), "Name".to_string()];
+
+    // Lengths are in bytes
+    assert_eq!(Redshift::column_type(&varchar(Some(40))), "VARCHAR(160)");
+    assert_eq!(Redshift::column_type(&varchar(Some(32768))), "VARCHAR(65535)");
+    assert_eq!(Redshift::column_type(&varchar(None)), "VARCHAR(256)");
+    assert_eq!(Redshift::column_type(&double()), "DOUBLE PRECISION");
+    assert_eq!(Redshift::column_type(&jsonb()), "SUPER");
+
+    assert_eq!(Redshift::alter_column("Name", &varchar(Some(80)), &varchar(Some(255)), &ids), [
+      "ALTER COLUMN \"Name\" TYPE VARCHAR(1020)"
+    ]);
+    assert_eq!(Redshift::alter_column("Name", &varchar(Some(80)), &varchar(Some(80)).nullable(true), &ids), Vec::<String>::new());
+    assert_eq!(Redshift::alter_column("Amount", &integer(), &bigint(), &ids), Vec::<String>::new());
+
+    assert_eq!(Redshift::upsert("Account", &columns, &columns[..1], &ids), [
+      "DELETE FROM \"Account\" WHERE \"Id\" = $1",
+      "INSERT INTO \"Account\" (\"Id\", \"Name\") VALUES ($1, $2)"
+    ]);
+
+    assert_eq!(Redshift::literal(&json!("O'Brien's"), &text()), "'O''Brien''s'");
+    assert_eq!(Redshift::literal(&json!(true), &boolean()), "TRUE");
+    assert_eq!(Redshift::literal(&json!(42), &integer()), "42");
+  }
+}
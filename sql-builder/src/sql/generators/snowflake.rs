@@ -0,0 +1,284 @@
+use serde_json::Value;
+
+use crate::sql::{
+  types::{BaseType, Type, WrappedDefault},
+  CaseFolding,
+  Constraint,
+  CreateMode,
+  Identifiers,
+  Persistence,
+  SqlGenerator
+};
+
+/// Snowflake; keys are declared (the optimizer and BI tools read them) but never enforced.
+pub struct Snowflake;
+impl SqlGenerator for Snowflake {
+  // Transient tables skip Fail-safe, which is as close to unlogged as it gets
+  fn create_table(name: &str, mode: CreateMode, persistence: Persistence, ids: &Identifiers) -> (String, String) {
+    let (modifier, table) = match persistence {
+      Persistence::Permanent => ("", ids.table::<Snowflake>(name)),
+      Persistence::Unlogged  => ("TRANSIENT ", ids.table::<Snowflake>(name)),
+      Persistence::Temporary => ("TEMPORARY ", ids.table::<Snowflake>(name))
+    };
+
+    (
+      super::create_table(&table, mode, modifier), // Prefix
+      "\n)".to_owned()                             // Affix
+    )
+  }
+
+  fn create_column(name: &str, tp: &Type, ids: &Identifiers) -> String {
+    let inner = tp.inner();
+
+    format!(
+      "{} {}{}{}{}{}{}{}{}{}",
+      ids.render::<Snowflake>(name),
+      match tp.sql_type.as_ref() {
+        Some(sql) => sql.clone(),
+        None      => Snowflake::stringify(inner.clone())
+      },
+      match tp.collation.as_ref() {
+        Some(collation) => format!(" COLLATE {}", super::text_literal(collation)),
+        None            => String::new()
+      },
+      // Snowflake only has virtual columns, which is close enough
+      match tp.generated.as_ref() {
+        Some(expr) => format!(" AS ({})", expr),
+        None       => String::new()
+      },
+      match tp.increments {
+        true  => " IDENTITY(1,1)",
+        false => ""
+      },
+      match tp.default.as_ref() {
+        Some(default) => format!(" DEFAULT {}", Snowflake::default_value(default)),
+        None          => String::new()
+      },
+      match tp.nullable {
+        false => " NOT NULL",
+        true  => ""
+      },
+      match tp.primary {
+        true  => " PRIMARY KEY",
+        false => ""
+      },
+      match tp.unique {
+        true  => " UNIQUE",
+        false => ""
+      },
+      // CHECK constraints aren't supported at all, and referential actions would never fire anyway
+      match &inner {
+        BaseType::Foreign(table, keys) => format!(
+          " REFERENCES {} ({})",
+          ids.table::<Snowflake>(table),
+          ids.list::<Snowflake>(&keys.0)
+        ),
+        _ => String::new()
+      }
+    )
+  }
+
+  fn create_constraint(name: &str, constraint: &Constraint, deferrable: bool, ids: &Identifiers) -> String {
+    let definition = match constraint {
+      Constraint::Check(_)            => return String::new(),
+      Constraint::PrimaryKey(columns) => format!("PRIMARY KEY ({})", ids.list::<Snowflake>(columns)),
+      Constraint::Unique(columns)     => format!("UNIQUE ({})", ids.list::<Snowflake>(columns)),
+      Constraint::Foreign { columns, table, references, .. } => format!(
+        "FOREIGN KEY ({}) REFERENCES {} ({})",
+        ids.list::<Snowflake>(columns),
+        ids.table::<Snowflake>(table),
+        ids.list::<Snowflake>(references)
+      )
+    };
+
+    let deferral = match deferrable {
+      true  => " DEFERRABLE INITIALLY DEFERRED",
+      false => ""
+    };
+
+    format!("CONSTRAINT {} {}{}", ids.render::<Snowflake>(name), definition, deferral)
+  }
+
+  /// `CLUSTER BY` takes comma separated columns, e.g. `table.option("CLUSTER BY", "AccountId, CreatedDate")`;
+  /// anything else is a `KEY = value` parameter, like `DATA_RETENTION_TIME_IN_DAYS`.
+  fn create_options(options: &[(String, String)], _primary_key: &[String], ids: &Identifiers) -> String {
+    options
+      .iter()
+      .map(|(key, value)| match key.to_ascii_uppercase().as_str() {
+        "CLUSTER BY" => {
+          let columns = value.split(',').map(|col| col.trim().to_string()).collect::<Vec<_>>();
+          format!(" CLUSTER BY ({})", ids.list::<Snowflake>(&columns))
+        },
+        _ => format!(" {} = {}", key.to_ascii_uppercase(), value)
+      })
+      .collect()
+  }
+
+  fn upsert(table: &str, columns: &[String], key: &[String], ids: &Identifiers) -> Vec<String> {
+    let values = columns
+      .iter()
+      .map(|col| format!("? AS {}", ids.render::<Snowflake>(col)))
+      .collect::<Vec<_>>();
+    vec![super::merge::<Snowflake>(table, &format!("(SELECT {}) AS source", values.join(", ")), columns, key, ids)]
+  }
+
+  // Backslashes are escapes in string literals
+  fn literal(value: &Value, _tp: &Type) -> String {
+    super::value_literal(value, ("TRUE", "FALSE"), |text| super::text_literal(&text.replace('\\', "\\\\")))
+  }
+
+  fn or_replace() -> bool {
+    true
+  }
+
+  fn column_type(tp: &Type) -> String {
+    match tp.sql_type.as_ref() {
+      Some(sql) => sql.clone(),
+      None      => Snowflake::stringify(tp.inner())
+    }
+  }
+
+  // There's no USING, and the only type changes in place are VARCHAR lengths
+  fn alter_column(name: &str, old: &Type, new: &Type, ids: &Identifiers) -> Vec<String> {
+    let column      = ids.render::<Snowflake>(name);
+    let mut clauses = Vec::new();
+
+    if let (BaseType::Varchar(_), BaseType::Varchar(_)) = (old.inner(), new.inner()) {
+      if Snowflake::column_type(old) != Snowflake::column_type(new) {
+        clauses.push(format!("ALTER COLUMN {} SET DATA TYPE {}", column, Snowflake::column_type(new)));
+      }
+    }
+    if old.nullable != new.nullable {
+      clauses.push(match new.nullable {
+        true  => format!("ALTER COLUMN {} DROP NOT NULL", column),
+        false => format!("ALTER COLUMN {} SET NOT NULL", column)
+      });
+    }
+    clauses
+  }
+
+  fn grant(table: &str, privileges: &str, role: &str, ids: &Identifiers) -> String {
+    format!("GRANT {} ON TABLE {} TO ROLE {}", privileges, ids.table::<Snowflake>(table), role)
+  }
+
+  fn owner(table: &str, role: &str, ids: &Identifiers) -> String {
+    format!("GRANT OWNERSHIP ON TABLE {} TO ROLE {} COPY CURRENT GRANTS", ids.table::<Snowflake>(table), role)
+  }
+
+  fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+  }
+
+  fn reserved_words() -> &'static [&'static str] {
+    &[
+      "ACCOUNT", "CONNECT", "CONNECTION", "DATABASE", "GSCLUSTER", "ILIKE", "INCREMENT", "ISSUE", "LATERAL",
+      "LOCALTIME", "LOCALTIMESTAMP", "MINUS", "OF", "ORGANIZATION", "QUALIFY", "REGEXP", "REVOKE", "RLIKE", "ROW",
+      "ROWS", "SAMPLE", "SCHEMA", "START", "TABLESAMPLE", "TRIGGER", "TRY_CAST", "VIEW", "WHENEVER"
+    ]
+  }
+
+  // Bare identifiers get upper cased, which is why quoted mixed case names have to be quoted forever after
+  fn unquoted_case() -> CaseFolding {
+    CaseFolding::Upper
+  }
+
+  fn uuid_default() -> Option<&'static str> {
+    Some("UUID_STRING()")
+  }
+}
+
+impl Snowflake {
+  fn default_value(default: &WrappedDefault) -> String {
+    use self::WrappedDefault::*;
+
+    match default {
+      Integer(_) | BigInt(_) | Float(_) | Double(_) => default.to_string(),
+      Boolean(val)   => match val {
+        true  => "TRUE".to_string(),
+        false => "FALSE".to_string()
+      },
+      Array(values)  => format!("ARRAY_CONSTRUCT({})", values.iter().map(Snowflake::default_value).collect::<Vec<_>>().join(", ")),
+      Custom(sql)    => sql.to_string(),
+      Expr(sql)      => sql.clone(),
+      Date(time)     => format!("TO_DATE(TO_TIMESTAMP({}))", super::epoch_seconds(time)),
+      DateTime(time) => format!("TO_TIMESTAMP({})", super::epoch_seconds(time)),
+      _              => super::text_literal(&default.to_string())
+    }
+  }
+
+  fn stringify(tp: BaseType) -> String {
+    use self::BaseType::*;
+
+    match tp {
+      Foreign(_, _)      => "VARCHAR".to_string(),
+      Custom(sql)        => sql.to_string(),
+      // Arrays are untyped, they're arrays of VARIANT
+      Array(_)           => "ARRAY".to_string(),
+      Varchar(Some(len)) => match len {
+        0 => "VARCHAR".to_string(),
+        _ => format!("VARCHAR({})", len)
+      },
+      Varchar(None)      => "VARCHAR".to_string(),
+      Text               => "VARCHAR".to_string(),
+      CiText             => "VARCHAR COLLATE 'en-ci'".to_string(),
+      Boolean            => "BOOLEAN".to_string(),
+      SmallInt           => "SMALLINT".to_string(),
+      Integer            => "INTEGER".to_string(),
+      BigInt             => "BIGINT".to_string(),
+      Float              => "FLOAT".to_string(),
+      Double             => "FLOAT".to_string(),
+      // A bare NUMBER is NUMBER(38, 0), which would drop every fraction
+      Numeric(spec)      => match spec {
+        Some((precision, scale)) => format!("NUMBER({}, {})", precision, scale),
+        None                     => "NUMBER(38, 18)".to_string()
+      },
+      Uuid               => "VARCHAR(36)".to_string(),
+      Binary             => "BINARY".to_string(),
+      Jsonb              => "VARIANT".to_string(),
+      Geography(_)       => "GEOGRAPHY".to_string(),
+      Time               => "TIME".to_string(),
+      Date               => "DATE".to_string(),
+      DateTime           => "TIMESTAMP_NTZ".to_string(),
+      DateTimeTz         => "TIMESTAMP_TZ".to_string(),
+      Index(_)           => unreachable!()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::sql::*;
+  use serde_json::json;
+
+  #[test]
+  fn snowflake_statements() {
+    let ids = Identifiers::default();
+    let columns = vec!["Id".to_string(), "Name".to_string()];
+
+    assert_eq!(Snowflake::column_type(&varchar(Some(40))), "VARCHAR(40)");
+    assert_eq!(Snowflake::column_type(&citext()), "VARCHAR COLLATE 'en-ci'");
+    assert_eq!(Snowflake::column_type(&numeric(18, 2)), "NUMBER(18, 2)");
+    assert_eq!(Snowflake::column_type(&jsonb()), "VARIANT");
+    assert_eq!(Snowflake::column_type(&datetime_tz()), "TIMESTAMP_TZ");
+
+    assert_eq!(Snowflake::alter_column("Name", &varchar(Some(80)), &varchar(Some(255)).nullable(true), &ids), [
+      "ALTER COLUMN \"Name\" SET DATA TYPE VARCHAR(255)",
+      "ALTER COLUMN \"Name\" DROP NOT NULL"
+    ]);
+    // Anything but a VARCHAR length can't be changed in place
+    assert_eq!(Snowflake::alter_column("Amount", &integer(), &numeric(18, 2), &ids), Vec::<String>::new());
+
+    assert_eq!(Snowflake::upsert("Account", &columns, &columns[..1], &ids), ["\
+      MERGE INTO \"Account\" AS target\n\
+      USING (SELECT ? AS \"Id\", ? AS \"Name\") AS source\n\
+      ON target.\"Id\" = source.\"Id\"\n\
+      WHEN MATCHED THEN UPDATE SET \"Name\" = source.\"Name\"\n\
+      WHEN NOT MATCHED THEN INSERT (\"Id\", \"Name\") VALUES (source.\"Id\", source.\"Name\")"
+    ]);
+
+    assert_eq!(Snowflake::literal(&json!("O'Brien \\ Sons"), &text()), "'O''Brien \\\\ Sons'");
+    assert_eq!(Snowflake::literal(&json!(false), &boolean()), "FALSE");
+    assert_eq!(Snowflake::literal(&json!(null), &text()), "NULL");
+  }
+}
@@ -0,0 +1,270 @@
+use serde_json::Value;
+
+use crate::sql::{
+  types::{BaseType, Type, WrappedDefault},
+  Constraint,
+  CreateMode,
+  DropOptions,
+  Identifiers,
+  Persistence,
+  SqlGenerator
+};
+
+/// SQLite, mapped onto its type affinities (TEXT, INTEGER, REAL, NUMERIC, BLOB).
+pub struct Sqlite;
+impl SqlGenerator for Sqlite {
+  // Temporary tables always go into the `temp` database
+  fn create_table(name: &str, mode: CreateMode, persistence: Persistence, ids: &Identifiers) -> (String, String) {
+    let (modifier, table) = match persistence {
+      Persistence::Temporary => ("TEMP ", ids.render::<Sqlite>(name)),
+      _                      => ("", ids.table::<Sqlite>(name))
+    };
+
+    (
+      super::create_table(&table, mode, modifier), // Prefix
+      "\n)".to_owned()                             // Affix
+    )
+  }
+
+  fn create_column(name: &str, tp: &Type, ids: &Identifiers) -> String {
+    let inner = tp.inner();
+
+    format!(
+      "{} {}{}{}{}{}{}{}{}{}",
+      ids.render::<Sqlite>(name),
+      match tp.sql_type.as_ref() {
+        Some(sql) => sql.clone(),
+        None      => Sqlite::stringify(inner.clone())
+      },
+      match tp.primary {
+        // Only `INTEGER PRIMARY KEY` aliases the rowid, which is how SQLite does auto increments
+        true  => match tp.increments && matches!(inner, BaseType::SmallInt | BaseType::Integer | BaseType::BigInt) {
+          true  => " PRIMARY KEY AUTOINCREMENT",
+          false => " PRIMARY KEY"
+        },
+        false => ""
+      },
+      match tp.generated.as_ref() {
+        Some(expr) => format!(" GENERATED ALWAYS AS ({}) STORED", expr),
+        None       => String::new()
+      },
+      match tp.default.as_ref() {
+        Some(default) => format!(" DEFAULT {}", Sqlite::default_value(default)),
+        None          => String::new()
+      },
+      match tp.nullable {
+        false => " NOT NULL",
+        true  => ""
+      },
+      match tp.unique {
+        true  => " UNIQUE",
+        false => ""
+      },
+      match tp.collation.as_ref() {
+        Some(collation) => format!(" COLLATE {}", collation),
+        None            => String::new()
+      },
+      // Parents have to live in the same database, so references never get a schema
+      match &inner {
+        BaseType::Foreign(table, keys) => format!(
+          " REFERENCES {} ({}){}{}",
+          ids.render::<Sqlite>(table),
+          ids.list::<Sqlite>(&keys.0),
+          super::referential_actions(tp.on_delete, tp.on_update),
+          Sqlite::deferral(tp.deferrable)
+        ),
+        _ => String::new()
+      },
+      match tp.check.as_ref() {
+        Some(expr) => format!(" CHECK ({})", expr),
+        None       => String::new()
+      }
+    )
+  }
+
+  fn create_constraint(name: &str, constraint: &Constraint, deferrable: bool, ids: &Identifiers) -> String {
+    let definition = match constraint {
+      Constraint::Check(expr)         => format!("CHECK ({})", expr),
+      Constraint::PrimaryKey(columns) => format!("PRIMARY KEY ({})", ids.list::<Sqlite>(columns)),
+      Constraint::Unique(columns)     => format!("UNIQUE ({})", ids.list::<Sqlite>(columns)),
+      Constraint::Foreign { columns, table, references, on_delete, on_update } => format!(
+        "FOREIGN KEY ({}) REFERENCES {} ({}){}{}",
+        ids.list::<Sqlite>(columns),
+        ids.render::<Sqlite>(table),
+        ids.list::<Sqlite>(references),
+        super::referential_actions(*on_delete, *on_update),
+        Sqlite::deferral(deferrable)
+      )
+    };
+
+    format!("CONSTRAINT {} {}", ids.render::<Sqlite>(name), definition)
+  }
+
+  // Constraints only come with the table, but references to tables that don't exist yet are fine anyway
+  fn add_constraint(_name: &str, _constraint: &Constraint, _deferrable: bool, _ids: &Identifiers) -> String {
+    String::new()
+  }
+
+  // Views can't be replaced, only dropped and created again
+  fn create_view(name: &str, ids: &Identifiers) -> String {
+    format!("DROP VIEW IF EXISTS {0};\n\nCREATE VIEW {0}", ids.table::<Sqlite>(name))
+  }
+
+  // Schemas are attached database files, which can't be created from SQL
+  fn create_schema(_name: &str, _ids: &Identifiers) -> String {
+    String::new()
+  }
+
+  // There is no CASCADE, but nothing stops a parent table from being dropped anyway
+  fn drop_table(name: &str, options: DropOptions, ids: &Identifiers) -> String {
+    super::drop_table(&ids.table::<Sqlite>(name), DropOptions { cascade: false, ..options })
+  }
+
+  fn column_type(tp: &Type) -> String {
+    match tp.sql_type.as_ref() {
+      Some(sql) => sql.clone(),
+      None      => Sqlite::stringify(tp.inner())
+    }
+  }
+
+  // Columns can only be added, dropped or renamed; anything else means rebuilding the table, which is left to whoever runs this
+  fn alter_column(name: &str, old: &Type, new: &Type, ids: &Identifiers) -> Vec<String> {
+    let definition = |tp: &Type| format!("{}{}", Sqlite::column_type(tp), if tp.nullable { "" } else { " NOT NULL" });
+
+    match definition(old) == definition(new) {
+      true  => Vec::new(),
+      false => vec![format!(
+        "-- {} can't change from {} to {} in place, the table has to be rebuilt",
+        ids.render::<Sqlite>(name),
+        definition(old),
+        definition(new)
+      )]
+    }
+  }
+
+  // No comments, and no users (so no permissions) either
+  fn comment_on(_table: &str, _column: Option<&str>, _comment: &str, _ids: &Identifiers) -> String {
+    String::new()
+  }
+
+  fn grant(_table: &str, _privileges: &str, _role: &str, _ids: &Identifiers) -> String {
+    String::new()
+  }
+
+  fn owner(_table: &str, _role: &str, _ids: &Identifiers) -> String {
+    String::new()
+  }
+
+  fn literal(value: &Value, _tp: &Type) -> String {
+    super::value_literal(value, ("1", "0"), super::text_literal)
+  }
+
+  fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+  }
+
+  fn reserved_words() -> &'static [&'static str] {
+    &[
+      "ABORT", "ACTION", "ADD", "AFTER", "ANALYZE", "ATTACH", "AUTOINCREMENT", "BEFORE", "BEGIN", "CASCADE",
+      "COLLATE", "COMMIT", "CONFLICT", "DATABASE", "DEFERRABLE", "DEFERRED", "DETACH", "EACH", "ESCAPE",
+      "EXCLUSIVE", "EXPLAIN", "FAIL", "GLOB", "IF", "IGNORE", "IMMEDIATE", "INDEX", "INDEXED", "INITIALLY",
+      "INSTEAD", "ISNULL", "KEY", "MATCH", "NO", "NOTNULL", "OF", "PLAN", "PRAGMA", "QUERY", "RAISE", "RECURSIVE",
+      "REGEXP", "REINDEX", "RELEASE", "RENAME", "REPLACE", "RESTRICT", "ROLLBACK", "ROW", "SAVEPOINT", "TEMP",
+      "TEMPORARY", "TRANSACTION", "TRIGGER", "VACUUM", "VIEW", "VIRTUAL", "WITHOUT"
+    ]
+  }
+}
+
+impl Sqlite {
+  // Only foreign keys can be deferred in SQLite
+  fn deferral(deferrable: bool) -> &'static str {
+    match deferrable {
+      true  => " DEFERRABLE INITIALLY DEFERRED",
+      false => ""
+    }
+  }
+
+  fn default_value(default: &WrappedDefault) -> String {
+    use self::WrappedDefault::*;
+
+    match default {
+      Integer(_) | BigInt(_) | Float(_) | Double(_) => default.to_string(),
+      Boolean(val)   => match val {
+        true  => "1".to_string(),
+        false => "0".to_string()
+      },
+      // Expression defaults have to be wrapped in parens
+      Custom(sql)    => format!("({})", sql),
+      Expr(sql)      => format!("({})", sql),
+      Date(time)     => format!("(date({}, 'unixepoch'))", super::epoch_seconds(time)),
+      DateTime(time) => format!("(strftime('%Y-%m-%dT%H:%M:%fZ', {}, 'unixepoch'))", super::epoch_seconds(time)),
+      _              => super::text_literal(&default.to_string())
+    }
+  }
+
+  fn stringify(tp: BaseType) -> String {
+    use self::BaseType::*;
+
+    match tp {
+      Foreign(_, _)  => "TEXT".to_string(),
+      Custom(sql)    => sql.to_string(),
+      // No arrays, these end up as JSON text
+      Array(_)       => "TEXT".to_string(),
+      Varchar(_)     => "TEXT".to_string(),
+      Text           => "TEXT".to_string(),
+      CiText         => "TEXT COLLATE NOCASE".to_string(),
+      Boolean        => "INTEGER".to_string(),
+      SmallInt       => "INTEGER".to_string(),
+      Integer        => "INTEGER".to_string(),
+      BigInt         => "INTEGER".to_string(),
+      Float          => "REAL".to_string(),
+      Double         => "REAL".to_string(),
+      Numeric(_)     => "NUMERIC".to_string(),
+      Uuid           => "TEXT".to_string(),
+      Binary         => "BLOB".to_string(),
+      Jsonb          => "TEXT".to_string(),
+      Geography(_)   => "TEXT".to_string(),
+      // Date and time values are stored as ISO-8601 text, which sorts and works with the date functions
+      Time           => "TEXT".to_string(),
+      Date           => "TEXT".to_string(),
+      DateTime       => "TEXT".to_string(),
+      DateTimeTz     => "TEXT".to_string(),
+      Index(_)       => unreachable!()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::sql::*;
+  use serde_json::json;
+
+  #[test]
+  fn sqlite_statements() {
+    let ids = Identifiers::default();
+    let columns = vec!["Id".to_string(), "Name".to_string()];
+
+    assert_eq!(Sqlite::column_type(&varchar(Some(40))), "TEXT");
+    assert_eq!(Sqlite::column_type(&citext()), "TEXT COLLATE NOCASE");
+    assert_eq!(Sqlite::column_type(&boolean()), "INTEGER");
+    assert_eq!(Sqlite::column_type(&numeric(18, 2)), "NUMERIC");
+    assert_eq!(Sqlite::column_type(&binary()), "BLOB");
+
+    assert_eq!(Sqlite::alter_column("Name", &varchar(Some(80)), &varchar(Some(255)), &ids), Vec::<String>::new());
+    assert_eq!(Sqlite::alter_column("Name", &varchar(Some(80)), &integer().nullable(true), &ids), [
+      "-- \"Name\" can't change from TEXT NOT NULL to INTEGER in place, the table has to be rebuilt"
+    ]);
+
+    assert_eq!(Sqlite::upsert("Account", &columns, &columns[..1], &ids), [
+      "INSERT INTO \"Account\" (\"Id\", \"Name\") VALUES (?, ?) ON CONFLICT (\"Id\") DO UPDATE SET \"Name\" = EXCLUDED.\"Name\""
+    ]);
+    assert_eq!(Sqlite::upsert("Account", &columns[..1], &columns[..1], &ids), [
+      "INSERT INTO \"Account\" (\"Id\") VALUES (?) ON CONFLICT (\"Id\") DO NOTHING"
+    ]);
+
+    assert_eq!(Sqlite::literal(&json!("O'Brien \\ Sons"), &text()), "'O''Brien \\ Sons'");
+    assert_eq!(Sqlite::literal(&json!(false), &boolean()), "0");
+    assert_eq!(Sqlite::literal(&json!(12.5), &double()), "12.5");
+  }
+}
@@ -0,0 +1,205 @@
+use crate::error::ParseError;
+
+use super::{Dialect, SqlGenerator};
+
+/// Words (nearly) every dialect reserves; `SqlGenerator::reserved_words` adds the dialect specific ones.
+pub const RESERVED: &[&str] = &[
+  "ALL", "ALTER", "AND", "ANY", "AS", "ASC", "BETWEEN", "BY", "CASE", "CAST", "CHECK", "COLUMN", "CONSTRAINT",
+  "CREATE", "CROSS", "CURRENT_DATE", "CURRENT_TIME", "CURRENT_TIMESTAMP", "CURRENT_USER", "DEFAULT", "DELETE",
+  "DESC", "DISTINCT", "DROP", "ELSE", "END", "EXCEPT", "EXISTS", "FALSE", "FETCH", "FOR", "FOREIGN", "FROM", "FULL",
+  "GRANT", "GROUP", "HAVING", "IN", "INNER", "INSERT", "INTERSECT", "INTO", "IS", "JOIN", "LEFT", "LIKE", "LIMIT",
+  "NATURAL", "NOT", "NULL", "OFFSET", "ON", "OR", "ORDER", "OUTER", "PRIMARY", "REFERENCES", "RIGHT", "SELECT",
+  "SESSION_USER", "SET", "SOME", "TABLE", "THEN", "TO", "TRUE", "UNION", "UNIQUE", "UPDATE", "USER", "USING",
+  "VALUES", "WHEN", "WHERE", "WITH"
+];
+
+/// When identifiers get quoted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quoting {
+  /// Every identifier, which keeps names exactly as written (the default)
+  Always,
+  /// Only reserved words and names that wouldn't survive being left bare
+  WhenNeeded
+}
+
+impl std::str::FromStr for Quoting {
+  type Err = ParseError;
+
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    match name.to_ascii_lowercase().as_str() {
+      "always"      => Ok(Quoting::Always),
+      "when-needed" => Ok(Quoting::WhenNeeded),
+      _ => Err(ParseError::unknown("quoting", name, "always or when-needed"))
+    }
+  }
+}
+
+/// What happens to the case of identifiers before they're rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseFolding {
+  Preserve,
+  Lower,
+  Upper
+}
+
+impl std::str::FromStr for CaseFolding {
+  type Err = ParseError;
+
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    match name.to_ascii_lowercase().as_str() {
+      "preserve" => Ok(CaseFolding::Preserve),
+      "lower"    => Ok(CaseFolding::Lower),
+      "upper"    => Ok(CaseFolding::Upper),
+      _ => Err(ParseError::unknown("case folding", name, "preserve, lower or upper"))
+    }
+  }
+}
+
+impl CaseFolding {
+  fn apply(self, name: &str) -> String {
+    match self {
+      CaseFolding::Preserve => name.to_string(),
+      CaseFolding::Lower    => name.to_lowercase(),
+      CaseFolding::Upper    => name.to_uppercase()
+    }
+  }
+}
+
+/// How table, column and constraint names get rendered, see `Table::identifiers`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identifiers {
+  pub quoting: Quoting,
+  pub folding: CaseFolding,
+  /// Filled in by `Table` while generating, see `Table::new_in`
+  schema: Option<String>
+}
+
+impl Default for Identifiers {
+  fn default() -> Self {
+    Identifiers { quoting: Quoting::Always, folding: CaseFolding::Preserve, schema: None }
+  }
+}
+
+impl Identifiers {
+  pub fn quoting(self, quoting: Quoting) -> Self {
+    Self { quoting, ..self }
+  }
+
+  pub fn folding(self, folding: CaseFolding) -> Self {
+    Self { folding, ..self }
+  }
+
+  pub(crate) fn in_schema(self, schema: Option<String>) -> Self {
+    Self { schema, ..self }
+  }
+
+  /// Just the case folding, for places that take names as plain strings.
+  pub(crate) fn fold(&self, name: &str) -> String {
+    self.folding.apply(name)
+  }
+
+  pub(crate) fn schema(&self) -> Option<&str> {
+    self.schema.as_deref()
+  }
+
+  /// Renders a table name for `T`, qualified with the schema (if there is one).
+  pub fn table<T>(&self, name: &str) -> String
+  where T: SqlGenerator {
+    match self.schema.as_ref() {
+      Some(schema) => format!("{}.{}", self.render::<T>(schema), self.render::<T>(name)),
+      None         => self.render::<T>(name)
+    }
+  }
+
+  /// Renders a single identifier for `T`.
+  pub fn render<T>(&self, name: &str) -> String
+  where T: SqlGenerator {
+    let name = self.folding.apply(name);
+    match self.quoting == Quoting::Always || needs_quotes::<T>(&name) {
+      true  => T::quote(&name),
+      false => name
+    }
+  }
+
+  /// Same as `render`, with the dialect picked at runtime.
+  pub fn render_for(&self, dialect: Dialect, name: &str) -> String {
+    dispatch!(dialect, T => self.render::<T>(name))
+  }
+
+  /// Comma separated column names.
+  pub fn list<T>(&self, columns: &[String]) -> String
+  where T: SqlGenerator {
+    columns.iter().map(|col| self.render::<T>(col)).collect::<Vec<_>>().join(", ")
+  }
+}
+
+fn needs_quotes<T>(name: &str) -> bool
+where T: SqlGenerator {
+  let mut chars = name.chars();
+  let plain     = chars.next().is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_')
+    && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+
+  // Bare names get folded by some dialects, so anything in the other case only survives quoted
+  let folded = match T::unquoted_case() {
+    CaseFolding::Preserve => false,
+    CaseFolding::Lower    => name.chars().any(|ch| ch.is_ascii_uppercase()),
+    CaseFolding::Upper    => name.chars().any(|ch| ch.is_ascii_lowercase())
+  };
+
+  let upper = name.to_ascii_uppercase();
+  !plain || folded || RESERVED.contains(&upper.as_str()) || T::reserved_words().contains(&upper.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::sql::*;
+
+  #[test]
+  fn identifier_quoting() {
+    let when_needed = Identifiers::default().quoting(Quoting::WhenNeeded);
+    assert_eq!(when_needed.render::<Pg>("status"), "status");
+    assert_eq!(when_needed.render::<Pg>("Order"), "\"Order\"");
+    assert_eq!(when_needed.render::<Pg>("User__c"), "\"User__c\"");
+    assert_eq!(when_needed.render::<Pg>("2nd"), "\"2nd\"");
+    assert_eq!(when_needed.render_for(Dialect::MySql, "Group"), "`Group`");
+    assert_eq!(when_needed.render::<MySql>("User__c"), "User__c");
+
+    let mut table = Table::new("Order");
+    table
+      .add_column("Id", varchar(None).primary(true))
+      .add_column("status", varchar(None).nullable(true))
+      .add_column("User__c", varchar(None).nullable(true))
+      .add_column("Group", varchar(None).nullable(true));
+
+    assert_eq!(table.identifiers(when_needed.clone()).generate::<Pg>(), "\
+      CREATE TABLE \"Order\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL,\n\
+      status VARCHAR,\n\
+      \"User__c\" VARCHAR,\n\
+      \"Group\" VARCHAR\n\
+      )");
+
+    // Folded to lower case, only the reserved words still need quotes
+    assert_eq!(table.identifiers(when_needed.clone().folding(CaseFolding::Lower)).generate::<Pg>(), "\
+      CREATE TABLE \"order\" (\n\
+      id VARCHAR PRIMARY KEY NOT NULL,\n\
+      status VARCHAR,\n\
+      user__c VARCHAR,\n\
+      \"group\" VARCHAR\n\
+      )");
+
+    // Snowflake folds the other way
+    assert_eq!(table.identifiers(when_needed.folding(CaseFolding::Upper)).generate::<Snowflake>(), "\
+      CREATE TABLE \"ORDER\" (\n\
+      ID VARCHAR NOT NULL PRIMARY KEY,\n\
+      STATUS VARCHAR,\n\
+      USER__C VARCHAR,\n\
+      \"GROUP\" VARCHAR\n\
+      )");
+
+    assert_eq!("when-needed".parse::<Quoting>().unwrap(), Quoting::WhenNeeded);
+    assert_eq!("UPPER".parse::<CaseFolding>().unwrap(), CaseFolding::Upper);
+    assert_eq!("sometimes".parse::<Quoting>().unwrap_err().to_string(), "unknown quoting `sometimes`, expected always or when-needed");
+  }
+}
@@ -1,12 +1,254 @@
+/// Runs `$body` with `$T` standing in for the generator of a `Dialect` picked at runtime.
+macro_rules! dispatch {
+  ($dialect:expr, $T:ident => $body:expr) => {
+    match $dialect {
+      $crate::sql::Dialect::Pg         => { type $T = $crate::sql::Pg; $body },
+      $crate::sql::Dialect::MySql      => { type $T = $crate::sql::MySql; $body },
+      $crate::sql::Dialect::Sqlite     => { type $T = $crate::sql::Sqlite; $body },
+      $crate::sql::Dialect::Mssql      => { type $T = $crate::sql::Mssql; $body },
+      $crate::sql::Dialect::Snowflake  => { type $T = $crate::sql::Snowflake; $body },
+      $crate::sql::Dialect::Redshift   => { type $T = $crate::sql::Redshift; $body },
+      $crate::sql::Dialect::DuckDb     => { type $T = $crate::sql::DuckDb; $body },
+      $crate::sql::Dialect::ClickHouse => { type $T = $crate::sql::ClickHouse; $body }
+    }
+  };
+}
+
 mod generators;
+mod identifiers;
+mod naming;
+mod rows;
+mod schema;
 mod table;
 mod types;
+mod view;
+
+use crate::error::ParseError;
 
 pub use generators::*;
+pub use identifiers::*;
+pub use naming::*;
+pub use rows::*;
+pub use schema::*;
 pub use table::*;
 pub use types::*;
+pub use view::*;
 
 pub trait SqlGenerator {
-  fn create_table(name: &str) -> (String, String);
-  fn create_column(name: &str, tp: &Type) -> String;
+  fn create_table(name: &str, mode: CreateMode, persistence: Persistence, ids: &Identifiers) -> (String, String);
+  fn create_column(name: &str, tp: &Type, ids: &Identifiers) -> String;
+  /// Returns an empty string for constraints the dialect doesn't support, which then get left out.
+  fn create_constraint(name: &str, constraint: &Constraint, deferrable: bool, ids: &Identifiers) -> String;
+
+  /// Renders table options (see `Table::option`) after the closing paren; `primary_key` is there for dialects that key tables (or sort them) out here.
+  fn create_options(_options: &[(String, String)], _primary_key: &[String], _ids: &Identifiers) -> String {
+    String::new()
+  }
+
+  /// `DROP TABLE`, see `Table::generate_drop`.
+  fn drop_table(name: &str, options: DropOptions, ids: &Identifiers) -> String
+  where Self: Sized {
+    generators::drop_table(&ids.table::<Self>(name), options)
+  }
+
+  /// Just the type of a column, as it'd show up in `create_column`.
+  fn column_type(tp: &Type) -> String;
+
+  /// `ALTER TABLE` clause adding a column, see `Table::generate_alter`.
+  fn add_column(name: &str, tp: &Type, ids: &Identifiers) -> String
+  where Self: Sized {
+    format!("ADD COLUMN {}", Self::create_column(name, tp, ids))
+  }
+
+  /// `ALTER TABLE` clause adding a table constraint after the fact, see `Schema`; empty when that can't be done.
+  fn add_constraint(name: &str, constraint: &Constraint, deferrable: bool, ids: &Identifiers) -> String
+  where Self: Sized {
+    match Self::create_constraint(name, constraint, deferrable, ids) {
+      sql if sql.is_empty() => sql,
+      sql                   => format!("ADD {}", sql)
+    }
+  }
+
+  /// `ALTER TABLE` clause dropping a column.
+  fn drop_column(name: &str, ids: &Identifiers) -> String
+  where Self: Sized {
+    format!("DROP COLUMN {}", ids.render::<Self>(name))
+  }
+
+  /// `ALTER TABLE` clauses changing the type and nullability of a column (each one gets its own statement).
+  /// Dialects that can't change columns in place return nothing, or a `--` comment saying what has to be done instead.
+  fn alter_column(name: &str, old: &Type, new: &Type, ids: &Identifiers) -> Vec<String>
+  where Self: Sized {
+    let column      = ids.render::<Self>(name);
+    let mut clauses = Vec::new();
+
+    // Without a cast only the conversions that happen implicitly (e.g. widening a VARCHAR) would work
+    let new_type = Self::column_type(new);
+    if Self::column_type(old) != new_type {
+      clauses.push(format!("ALTER COLUMN {0} SET DATA TYPE {1} USING CAST({0} AS {1})", column, new_type));
+    }
+    if old.nullable != new.nullable {
+      clauses.push(match new.nullable {
+        true  => format!("ALTER COLUMN {} DROP NOT NULL", column),
+        false => format!("ALTER COLUMN {} SET NOT NULL", column)
+      });
+    }
+    clauses
+  }
+
+  /// Comment on the table (or one of its columns), rendered after it; empty where comments go inline or don't exist.
+  fn comment_on(table: &str, column: Option<&str>, comment: &str, ids: &Identifiers) -> String
+  where Self: Sized {
+    match column {
+      Some(column) => format!("COMMENT ON COLUMN {}.{} IS {}", ids.table::<Self>(table), ids.render::<Self>(column), generators::text_literal(comment)),
+      None         => format!("COMMENT ON TABLE {} IS {}", ids.table::<Self>(table), generators::text_literal(comment))
+    }
+  }
+
+  /// `GRANT` on the table, see `Table::grant`; empty where there are no permissions.
+  fn grant(table: &str, privileges: &str, role: &str, ids: &Identifiers) -> String
+  where Self: Sized {
+    format!("GRANT {} ON TABLE {} TO {}", privileges, ids.table::<Self>(table), role)
+  }
+
+  /// Hands the table over to `role`, see `Table::owner`; empty where tables don't have owners.
+  fn owner(table: &str, role: &str, ids: &Identifiers) -> String
+  where Self: Sized {
+    format!("ALTER TABLE {} OWNER TO {}", ids.table::<Self>(table), role)
+  }
+
+  /// Bind parameter number `index` (counting from 1).
+  fn placeholder(_index: usize) -> String {
+    "?".to_string()
+  }
+
+  /// Statements writing a row, updating the one with the same `key` if it's there already; see `Table::generate_upsert`.
+  /// The values of `columns` (key included) are bound in that order, to each statement unless the dialect says otherwise.
+  fn upsert(table: &str, columns: &[String], key: &[String], ids: &Identifiers) -> Vec<String>
+  where Self: Sized {
+    let updates = columns
+      .iter()
+      .filter(|col| !key.contains(col))
+      .map(|col| format!("{0} = EXCLUDED.{0}", ids.render::<Self>(col)))
+      .collect::<Vec<_>>();
+
+    vec![format!(
+      "{} ON CONFLICT ({}) {}",
+      generators::insert_values::<Self>(table, columns, ids),
+      ids.list::<Self>(key),
+      match updates.is_empty() {
+        true  => "DO NOTHING".to_string(),
+        false => format!("DO UPDATE SET {}", updates.join(", "))
+      }
+    )]
+  }
+
+  /// Literal for a value out of a record, going into a column of type `tp`; see `Rows`.
+  fn literal(value: &serde_json::Value, _tp: &Type) -> String
+  where Self: Sized {
+    generators::value_literal(value, ("TRUE", "FALSE"), generators::text_literal)
+  }
+
+  /// Whether there's a `CREATE OR REPLACE TABLE`; when there isn't, `Table` drops the table first instead.
+  fn or_replace() -> bool {
+    false
+  }
+
+  /// Start of a `CREATE VIEW` statement (everything up to `AS`), replacing the view if it's there already; see `View`.
+  fn create_view(name: &str, ids: &Identifiers) -> String
+  where Self: Sized {
+    format!("CREATE OR REPLACE VIEW {}", ids.table::<Self>(name))
+  }
+
+  /// Statement creating a schema unless it's already there, see `Table::create_schema`.
+  fn create_schema(name: &str, ids: &Identifiers) -> String
+  where Self: Sized {
+    format!("CREATE SCHEMA IF NOT EXISTS {}", ids.render::<Self>(name))
+  }
+
+  /// Quotes an identifier, whether it needs it or not; everything else should go through `Identifiers::render`.
+  fn quote(name: &str) -> String;
+
+  /// Reserved words on top of the common `RESERVED` ones, in upper case.
+  fn reserved_words() -> &'static [&'static str] {
+    &[]
+  }
+
+  /// What bare (unquoted) identifiers get folded to.
+  fn unquoted_case() -> CaseFolding {
+    CaseFolding::Preserve
+  }
+
+  /// Whether column level `REFERENCES` actually work; when they don't, `Table` hoists those into table constraints.
+  fn inline_foreign_keys() -> bool {
+    true
+  }
+
+  /// Expression generating a random UUID, for column defaults; `None` when there's no such function.
+  fn uuid_default() -> Option<&'static str> {
+    None
+  }
+}
+
+/// Output dialect, for when it's only known at runtime (e.g. from the command line).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+  #[default]
+  Pg,
+  MySql,
+  Sqlite,
+  Mssql,
+  Snowflake,
+  Redshift,
+  DuckDb,
+  ClickHouse
+}
+
+impl Dialect {
+  /// Names accepted by `from_str`.
+  pub const NAMES: &'static [&'static str] = &["postgres", "mysql", "sqlite", "mssql", "snowflake", "redshift", "duckdb", "clickhouse"];
+
+  /// Same as `SqlGenerator::uuid_default`, for this dialect.
+  pub fn uuid_default(self) -> Option<&'static str> {
+    dispatch!(self, T => T::uuid_default())
+  }
+}
+
+impl std::str::FromStr for Dialect {
+  type Err = ParseError;
+
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    match name.to_ascii_lowercase().as_str() {
+      "postgres" | "postgresql" | "pg" => Ok(Dialect::Pg),
+      "mysql" | "mariadb"              => Ok(Dialect::MySql),
+      "sqlite"                         => Ok(Dialect::Sqlite),
+      "mssql" | "sqlserver"            => Ok(Dialect::Mssql),
+      "snowflake"                      => Ok(Dialect::Snowflake),
+      "redshift"                       => Ok(Dialect::Redshift),
+      "duckdb"                         => Ok(Dialect::DuckDb),
+      "clickhouse"                     => Ok(Dialect::ClickHouse),
+      _ => Err(ParseError::unknown("dialect", name, format!("one of {}", Dialect::NAMES.join(", "))))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn runtime_dialects() {
+    let mut table = Table::new("Account");
+    table.add_column("Id", varchar(None).primary(true));
+
+    assert_eq!(table.generate_for("postgres".parse().unwrap()), table.generate::<Pg>());
+    assert_eq!(table.generate_for("MySQL".parse().unwrap()), table.generate::<MySql>());
+    assert_eq!(table.generate_for("clickhouse".parse().unwrap()), table.generate::<ClickHouse>());
+    assert!(Dialect::NAMES.iter().all(|name| name.parse::<Dialect>().is_ok()));
+    assert_eq!("oracle".parse::<Dialect>(), Err(ParseError::Unknown {
+      what:     "dialect",
+      value:    "oracle".to_string(),
+      expected: "one of postgres, mysql, sqlite, mssql, snowflake, redshift, duckdb, clickhouse".to_string()
+    }));
+  }
 }
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::error::ParseError;
+
+/// Case style for column names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamingCase {
+  /// Field names as they are (the default)
+  Preserve,
+  /// `BillingCity` -> `billing_city`, `SLA_Expiration__c` -> `sla_expiration__c`
+  Snake,
+  /// `BillingCity` -> `billingcity`
+  Lower
+}
+
+impl std::str::FromStr for NamingCase {
+  type Err = ParseError;
+
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    match name.to_ascii_lowercase().as_str() {
+      "preserve"             => Ok(NamingCase::Preserve),
+      "snake" | "snake_case" => Ok(NamingCase::Snake),
+      "lower" | "lowercase"  => Ok(NamingCase::Lower),
+      _ => Err(ParseError::unknown("naming", name, "preserve, snake or lower"))
+    }
+  }
+}
+
+/// Turns Salesforce field names into column names, see `Table::naming`.
+/// Renames win outright; otherwise the replacements run in order, and then the case gets changed.
+#[derive(Debug, Clone)]
+pub struct Naming {
+  case:         NamingCase,
+  replacements: Vec<(Regex, String)>,
+  renames:      HashMap<String, String>
+}
+
+impl Default for Naming {
+  fn default() -> Self {
+    Naming { case: NamingCase::Preserve, replacements: Vec::new(), renames: HashMap::new() }
+  }
+}
+
+impl Naming {
+  pub fn case(self, case: NamingCase) -> Self {
+    Self { case, ..self }
+  }
+
+  /// Replaces every match of `pattern` (with `$1` style references to its groups), e.g. `__c$` with nothing.
+  pub fn replace<R>(mut self, pattern: &str, replacement: R) -> anyhow::Result<Self>
+  where R: Into<String> {
+    self.replacements.push((Regex::new(pattern)?, replacement.into()));
+    Ok(self)
+  }
+
+  /// Names the column for `field` exactly `column`.
+  pub fn rename<F, C>(mut self, field: F, column: C) -> Self
+  where F: Into<String>, C: Into<String> {
+    self.renames.insert(field.into(), column.into());
+    self
+  }
+
+  /// Column name for a field.
+  pub fn apply(&self, field: &str) -> String {
+    if let Some(column) = self.renames.get(field) {
+      return column.clone();
+    }
+
+    let name = self.replacements.iter().fold(field.to_string(), |name, (pattern, replacement)| {
+      pattern.replace_all(&name, replacement.as_str()).into_owned()
+    });
+
+    match self.case {
+      NamingCase::Preserve => name,
+      NamingCase::Snake    => snake_case(&name),
+      NamingCase::Lower    => name.to_lowercase()
+    }
+  }
+}
+
+/// Breaks words at lower to upper case changes, and before the last capital of a run followed by a lower case letter (`SLAExpiration` -> `sla_expiration`).
+fn snake_case(name: &str) -> String {
+  let chars   = name.chars().collect::<Vec<_>>();
+  let mut out = String::with_capacity(name.len() + 4);
+
+  for (index, &ch) in chars.iter().enumerate() {
+    if ch.is_uppercase() && index > 0 {
+      let previous = chars[index - 1];
+      let next     = chars.get(index + 1).copied();
+
+      let boundary = previous.is_lowercase() || previous.is_ascii_digit()
+        || (previous.is_uppercase() && next.is_some_and(char::is_lowercase));
+      if boundary && previous != '_' {
+        out.push('_');
+      }
+    }
+    out.extend(ch.to_lowercase());
+  }
+  out
+}
@@ -0,0 +1,349 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::error::ParseError;
+
+use super::{generators::*, BaseType, Dialect, Identifiers, SqlGenerator, Type};
+
+/// Postgres `COPY` input formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyFormat {
+  Text,
+  Csv
+}
+
+impl std::str::FromStr for CopyFormat {
+  type Err = ParseError;
+
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    match name.to_ascii_lowercase().as_str() {
+      "text" => Ok(CopyFormat::Text),
+      "csv"  => Ok(CopyFormat::Csv),
+      _ => Err(ParseError::unknown("copy format", name, "text or csv"))
+    }
+  }
+}
+
+/// What `Rows::staged_upsert` runs, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StagedUpsert {
+  pub create: String,
+  pub copy:   String,
+  pub merge:  String
+}
+
+/// Records to load into a table, see `Table::rows`.
+/// Values get matched to columns by field name (or column name), and anything missing is `NULL`.
+#[derive(Debug, Clone)]
+pub struct Rows {
+  table:       String,
+  schema:      Option<String>,
+  identifiers: Identifiers,
+  /// Field, column and type of everything that gets loaded
+  columns:     Vec<(String, String, Type)>,
+  rows:        Vec<Vec<Value>>
+}
+
+impl Rows {
+  pub(crate) fn new(table: String, schema: Option<String>, identifiers: Identifiers, columns: Vec<(String, String, Type)>) -> Self {
+    Rows { table, schema, identifiers, columns, rows: Vec::new() }
+  }
+
+  /// Names of the columns that get values, in order.
+  pub fn columns(&self) -> Vec<String> {
+    self.columns.iter().map(|(_, column, _)| column.clone()).collect()
+  }
+
+  pub fn len(&self) -> usize {
+    self.rows.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.rows.is_empty()
+  }
+
+  /// Adds a record, e.g. straight out of a query response; fields that don't have a column are ignored.
+  pub fn push(&mut self, record: &Map<String, Value>) -> &mut Self {
+    let row = self.columns
+      .iter()
+      .map(|(field, column, tp)| {
+        let value = record.get(field).or_else(|| record.get(column)).cloned().unwrap_or(Value::Null);
+        match (&tp.inner, value) {
+          // Multi-select picklists come back as `a;b;c`
+          (BaseType::Array(_), Value::String(values)) => Value::Array(values.split(';').map(Value::from).collect()),
+          (_, value)                                  => value
+        }
+      })
+      .collect();
+
+    self.rows.push(row);
+    self
+  }
+
+  /// Keeps just the columns of these fields (or columns), e.g. the ones a change touched.
+  pub fn only(mut self, fields: &[String]) -> Self {
+    let keep = self.columns.iter().map(|(field, column, _)| fields.contains(field) || fields.contains(column)).collect::<Vec<_>>();
+    for row in &mut self.rows {
+      *row = row.drain(..).zip(&keep).filter(|(_, keep)| **keep).map(|(value, _)| value).collect();
+    }
+    self.columns = self.columns.drain(..).zip(&keep).filter(|(_, keep)| **keep).map(|(column, _)| column).collect();
+    self
+  }
+
+  /// Same as `push`, for anything that serializes to a JSON object.
+  pub fn push_record<R>(&mut self, record: &R) -> serde_json::Result<&mut Self>
+  where R: Serialize {
+    match serde_json::to_value(record)? {
+      Value::Object(record) => Ok(self.push(&record)),
+      _                     => Err(serde::ser::Error::custom("records have to serialize to a JSON object"))
+    }
+  }
+
+  /// `INSERT` statements with up to `batch_size` rows each (SQL Server takes 1000 at most).
+  pub fn generate_inserts<T>(&self, batch_size: usize) -> Vec<String>
+  where T: SqlGenerator {
+    let ids   = self.identifiers.clone().in_schema(self.schema.clone());
+    let start = format!("INSERT INTO {} ({}) VALUES\n", ids.table::<T>(&self.table), ids.list::<T>(&self.columns()));
+
+    self.rows
+      .chunks(batch_size.max(1))
+      .map(|batch| {
+        let values = batch
+          .iter()
+          .map(|row| {
+            let literals = row.iter().zip(&self.columns).map(|(value, (_, _, tp))| T::literal(value, tp));
+            format!("({})", literals.collect::<Vec<_>>().join(", "))
+          })
+          .collect::<Vec<_>>();
+        format!("{}{}", start, values.join(",\n"))
+      })
+      .collect()
+  }
+
+  /// Same as `generate_inserts`, with the dialect picked at runtime.
+  pub fn generate_inserts_for(&self, dialect: Dialect, batch_size: usize) -> Vec<String> {
+    dispatch!(dialect, T => self.generate_inserts::<T>(batch_size))
+  }
+
+  /// The Postgres `COPY ... FROM STDIN` statement that takes `generate_copy` as its input.
+  pub fn copy_statement(&self, format: CopyFormat) -> String {
+    let ids = self.identifiers.clone().in_schema(self.schema.clone());
+    self.copy_into(&ids.table::<Pg>(&self.table), format)
+  }
+
+  /// Postgres statements upserting the rows on `key` (column names) by way of a temporary `stage` table, since `COPY`
+  /// can only append: creating the stage, copying into it (`generate_copy` is the input) and merging it into the table.
+  pub fn staged_upsert(&self, stage: &str, key: &[String], format: CopyFormat) -> StagedUpsert {
+    let ids     = self.identifiers.clone().in_schema(self.schema.clone());
+    let table   = ids.table::<Pg>(&self.table);
+    let stage   = self.identifiers.render::<Pg>(stage);
+    let columns = ids.list::<Pg>(&self.columns());
+
+    StagedUpsert {
+      create: format!("CREATE TEMPORARY TABLE {} (LIKE {} INCLUDING DEFAULTS)", stage, table),
+      copy:   self.copy_into(&stage, format),
+      merge:  format!("INSERT INTO {} ({1}) SELECT {1} FROM {2} {3};\nDROP TABLE {2}", table, columns, stage, self.on_conflict(key))
+    }
+  }
+
+  /// Postgres `INSERT` statements (see `generate_inserts`) updating the rows that have the same `key` (column names) instead.
+  pub fn generate_upserts(&self, key: &[String], batch_size: usize) -> Vec<String> {
+    let on_conflict = self.on_conflict(key);
+    self.generate_inserts::<Pg>(batch_size).into_iter().map(|insert| format!("{}\n{}", insert, on_conflict)).collect()
+  }
+
+  /// Postgres `UPDATE` setting every column but `key` to the values of the first row, on the rows with the same `key` as
+  /// any of these, e.g. records that got the same change; rows that aren't there are left alone. `None` without any rows
+  /// (or anything to set).
+  pub fn generate_update(&self, key: &str) -> Option<String> {
+    let index = self.columns.iter().position(|(_, column, _)| column == key)?;
+    let first = self.rows.first()?;
+
+    let ids         = self.identifiers.clone().in_schema(self.schema.clone());
+    let assignments = self.columns
+      .iter()
+      .zip(first)
+      .filter(|((_, column, _), _)| column != key)
+      .map(|((_, column, tp), value)| format!("{} = {}", ids.render::<Pg>(column), Pg::literal(value, tp)))
+      .collect::<Vec<_>>();
+    if assignments.is_empty() {
+      return None;
+    }
+
+    let values = self.rows.iter().map(|row| Pg::literal(&row[index], &self.columns[index].2)).collect::<Vec<_>>();
+    Some(format!(
+      "UPDATE {} SET {} WHERE {} IN ({})",
+      ids.table::<Pg>(&self.table),
+      assignments.join(", "),
+      ids.render::<Pg>(key),
+      values.join(", ")
+    ))
+  }
+
+  /// Postgres `DELETE` for the rows with the same `key` (a column) as these, e.g. deleted records; `None` without any rows.
+  pub fn generate_delete(&self, key: &str) -> Option<String> {
+    let index = self.columns.iter().position(|(_, column, _)| column == key)?;
+    if self.rows.is_empty() {
+      return None;
+    }
+
+    let ids    = self.identifiers.clone().in_schema(self.schema.clone());
+    let values = self.rows.iter().map(|row| Pg::literal(&row[index], &self.columns[index].2)).collect::<Vec<_>>();
+    Some(format!("DELETE FROM {} WHERE {} IN ({})", ids.table::<Pg>(&self.table), ids.render::<Pg>(key), values.join(", ")))
+  }
+
+  fn on_conflict(&self, key: &[String]) -> String {
+    let ids     = self.identifiers.clone().in_schema(self.schema.clone());
+    let updates = self.columns()
+      .iter()
+      .filter(|col| !key.contains(col))
+      .map(|col| format!("{0} = EXCLUDED.{0}", ids.render::<Pg>(col)))
+      .collect::<Vec<_>>();
+
+    match updates.is_empty() {
+      true  => format!("ON CONFLICT ({}) DO NOTHING", ids.list::<Pg>(key)),
+      false => format!("ON CONFLICT ({}) DO UPDATE SET {}", ids.list::<Pg>(key), updates.join(", "))
+    }
+  }
+
+  fn copy_into(&self, table: &str, format: CopyFormat) -> String {
+    format!(
+      "COPY {} ({}) FROM STDIN WITH (FORMAT {})",
+      table,
+      self.identifiers.list::<Pg>(&self.columns()),
+      match format {
+        CopyFormat::Text => "text",
+        CopyFormat::Csv  => "csv"
+      }
+    )
+  }
+
+  pub fn generate_copy(&self, format: CopyFormat) -> String {
+    let mut data = Vec::new();
+    self.write_copy(format, &mut data).expect("writing to a Vec can't fail");
+    String::from_utf8(data).expect("records are always UTF-8")
+  }
+
+  /// Same as `generate_copy`, but streams the rows into `w`, one line each.
+  pub fn write_copy<W>(&self, format: CopyFormat, w: &mut W) -> io::Result<()>
+  where W: Write {
+    let separator = match format {
+      CopyFormat::Text => "\t",
+      CopyFormat::Csv  => ","
+    };
+
+    for row in &self.rows {
+      let fields = row.iter().zip(&self.columns).map(|(value, (_, _, tp))| copy_field(value, tp, format));
+      writeln!(w, "{}", fields.collect::<Vec<_>>().join(separator))?;
+    }
+    Ok(())
+  }
+}
+
+fn copy_field(value: &Value, tp: &Type, format: CopyFormat) -> String {
+  let text = match (&tp.inner, value) {
+    (_, Value::Null)                           => None,
+    (_, Value::Bool(val))                      => Some(if *val { "t" } else { "f" }.to_string()),
+    (_, Value::String(text))                   => Some(text.clone()),
+    (BaseType::Array(_), Value::Array(values)) => Some(array_literal(values)),
+    (_, value)                                 => Some(value.to_string())
+  };
+
+  match (format, text) {
+    (CopyFormat::Text, None)       => "\\N".to_string(),
+    (CopyFormat::Text, Some(text)) => text.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r"),
+    (CopyFormat::Csv, None)        => String::new(),
+    // Empty strings have to be quoted to tell them apart from NULL
+    (CopyFormat::Csv, Some(text))  => match text.is_empty() || text.contains(&[',', '"', '\n', '\r'][..]) {
+      true  => format!("\"{}\"", text.replace('"', "\"\"")),
+      false => text
+    }
+  }
+}
+
+/// Postgres array input, e.g. `{"a","b"}`.
+pub(crate) fn array_literal(values: &[Value]) -> String {
+  let elements = values
+    .iter()
+    .map(|value| match value {
+      Value::Null         => "NULL".to_string(),
+      Value::String(text) => format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"")),
+      value               => value.to_string()
+    })
+    .collect::<Vec<_>>();
+  format!("{{{}}}", elements.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::sql::*;
+
+  #[test]
+  fn record_loading() {
+    #[derive(serde::Serialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Account {
+      id:     &'static str,
+      name:   &'static str,
+      active: bool
+    }
+
+    let mut table = Table::new("Account");
+    table
+      .naming(Naming::default().case(NamingCase::Snake))
+      .add_column("Id", varchar(Some(18)).primary(true))
+      .add_column("Name", varchar(None).nullable(true))
+      .add_column("Active", boolean())
+      .add_column("Tags__c", array(&varchar(None)).nullable(true))
+      .add_column("Score", integer().generated("1"));
+
+    let mut rows = table.rows();
+    rows.push(serde_json::json!({
+      "attributes": { "type": "Account" },
+      "Id":         "001A",
+      "Name":       "O'Brien, \"Bob\"",
+      "Active":     true,
+      "Tags__c":    "a;b"
+    }).as_object().unwrap());
+    rows.push_record(&Account { id: "001B", name: "", active: false }).unwrap();
+    assert!(rows.push_record(&"nope").is_err());
+
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows.columns(), ["id", "name", "active", "tags__c"]);
+    assert_eq!(rows.generate_inserts::<Pg>(100), [
+      "INSERT INTO \"Account\" (\"id\", \"name\", \"active\", \"tags__c\") VALUES\n\
+       ('001A', 'O''Brien, \"Bob\"', TRUE, '{\"a\",\"b\"}'),\n\
+       ('001B', '', FALSE, NULL)"
+    ]);
+    assert_eq!(rows.generate_inserts::<Mssql>(1)[1], "INSERT INTO [Account] ([id], [name], [active], [tags__c]) VALUES\n(N'001B', N'', 0, NULL)");
+    assert_eq!(rows.generate_inserts_for(Dialect::MySql, 1).len(), 2);
+
+    assert_eq!(rows.copy_statement(CopyFormat::Csv), "COPY \"Account\" (\"id\", \"name\", \"active\", \"tags__c\") FROM STDIN WITH (FORMAT csv)");
+    assert_eq!(rows.generate_copy(CopyFormat::Csv), "001A,\"O'Brien, \"\"Bob\"\"\",t,\"{\"\"a\"\",\"\"b\"\"}\"\n001B,\"\",f,\n");
+    assert_eq!(rows.generate_copy(CopyFormat::Text), "001A\tO'Brien, \"Bob\"\tt\t{\"a\",\"b\"}\n001B\t\tf\t\\N\n");
+  }
+
+  #[test]
+  fn staged_upserts() {
+    let mut table = Table::new_in("sfdc", "Account");
+    table
+      .add_column("Id", varchar(Some(18)).primary(true))
+      .add_column("Name", varchar(None).nullable(true))
+      .add_column("SystemModstamp", datetime_tz().nullable(true));
+
+    let staged = table.rows().staged_upsert("Account_stage", &["Id".to_string()], CopyFormat::Csv);
+    assert_eq!(staged.create, "CREATE TEMPORARY TABLE \"Account_stage\" (LIKE \"sfdc\".\"Account\" INCLUDING DEFAULTS)");
+    assert_eq!(staged.copy, "COPY \"Account_stage\" (\"Id\", \"Name\", \"SystemModstamp\") FROM STDIN WITH (FORMAT csv)");
+    assert_eq!(
+      staged.merge,
+      concat!(
+        "INSERT INTO \"sfdc\".\"Account\" (\"Id\", \"Name\", \"SystemModstamp\") SELECT \"Id\", \"Name\", \"SystemModstamp\" FROM \"Account_stage\" ",
+        "ON CONFLICT (\"Id\") DO UPDATE SET \"Name\" = EXCLUDED.\"Name\", \"SystemModstamp\" = EXCLUDED.\"SystemModstamp\";\n",
+        "DROP TABLE \"Account_stage\""
+      )
+    );
+  }
+}
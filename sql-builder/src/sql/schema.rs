@@ -0,0 +1,292 @@
+use std::io::{self, Write};
+
+use super::{Dialect, SqlGenerator, Table};
+
+/// A bunch of tables, created parents first so every foreign key has something to point at.
+/// Keys that can't be satisfied that way (tables referencing each other) get added with `ALTER TABLE` once everything exists,
+/// and keys to tables that aren't in the schema at all get left out.
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+  tables: Vec<Table>
+}
+
+impl Schema {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn add_table(&mut self, table: Table) -> &mut Self {
+    self.tables.push(table);
+    self
+  }
+
+  pub fn tables(&self) -> &[Table] {
+    &self.tables
+  }
+
+  /// Names of the tables in the order they get created; ties are broken by the order they were added in.
+  pub fn order(&self) -> Vec<String> {
+    self.plan().into_iter().map(|(index, _)| self.tables[index].name()).collect()
+  }
+
+  /// Indices of the tables in creation order, along with the tables each one still has to wait for.
+  fn plan(&self) -> Vec<(usize, Vec<String>)> {
+    let names       = self.tables.iter().map(Table::name).collect::<Vec<_>>();
+    let mut pending = (0..self.tables.len()).collect::<Vec<_>>();
+    let mut plan    = Vec::new();
+
+    while !pending.is_empty() {
+      let waiting_on = |index: usize| {
+        self.tables[index]
+          .references()
+          .into_iter()
+          .filter(|parent| pending.iter().any(|&other| other != index && names[other] == *parent))
+          .collect::<Vec<_>>()
+      };
+
+      let next = match pending.iter().position(|&index| waiting_on(index).is_empty()) {
+        Some(next) => next,
+        // Everything left is waiting on something, so there's a cycle; following the references from the first
+        // table leads into it, and whichever table in there was added first goes anyway
+        None => {
+          let mut seen = vec![pending[0]];
+          loop {
+            let parent = waiting_on(seen[seen.len() - 1]).remove(0);
+            let index  = pending.iter().copied().find(|&other| names[other] == parent).expect("pending parent");
+            if let Some(start) = seen.iter().position(|&other| other == index) {
+              let first = seen[start..].iter().min().copied().expect("non-empty cycle");
+              break pending.iter().position(|&other| other == first).expect("pending table");
+            }
+            seen.push(index);
+          }
+        }
+      };
+      let forward = waiting_on(pending[next]);
+      plan.push((pending.remove(next), forward));
+    }
+    plan
+  }
+
+  pub fn generate<T>(&self) -> String
+  where T: SqlGenerator {
+    let mut sql = Vec::new();
+    self.generate_into::<T, _>(&mut sql).expect("writing to a Vec can't fail");
+    String::from_utf8(sql).expect("generated SQL is always UTF-8")
+  }
+
+  /// Same as `generate`, but streams the SQL into `w` a table at a time.
+  pub fn generate_into<T, W>(&self, w: &mut W) -> io::Result<()>
+  where T: SqlGenerator, W: Write {
+    let names        = self.tables.iter().map(Table::name).collect::<Vec<_>>();
+    let mut deferred = Vec::new();
+    for (position, (index, forward)) in self.plan().into_iter().enumerate() {
+      if position > 0 {
+        w.write_all(b";\n\n")?;
+      }
+
+      let outside = self.tables[index].references().into_iter().filter(|parent| !names.contains(parent)).collect::<Vec<_>>();
+
+      // Only tables that lose some of their keys need a copy
+      match forward.is_empty() && outside.is_empty() {
+        true  => self.tables[index].generate_into::<T, W>(w)?,
+        false => {
+          let mut table = self.tables[index].clone();
+          table.drop_foreign_keys::<T>(&outside);
+          deferred.extend(table.defer_foreign_keys::<T>(&forward));
+          table.generate_into::<T, W>(w)?
+        }
+      }
+    }
+
+    if !deferred.is_empty() {
+      write!(w, ";\n\n{}", deferred.join(";\n"))?;
+    }
+    Ok(())
+  }
+
+  /// Same as `generate`, with the dialect picked at runtime.
+  pub fn generate_for(&self, dialect: Dialect) -> String {
+    dispatch!(dialect, T => self.generate::<T>())
+  }
+
+  /// Same as `generate_into`, with the dialect picked at runtime.
+  pub fn generate_into_for<W>(&self, dialect: Dialect, w: &mut W) -> io::Result<()>
+  where W: Write {
+    dispatch!(dialect, T => self.generate_into::<T, W>(w))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::sql::*;
+
+  #[test]
+  fn schema_order() {
+    let mut account = Table::new("Account");
+    account.add_column("Id", varchar(None).primary(true));
+    account.add_column("ParentId", foreign("Account", vec!["Id"]).nullable(true));
+    account.add_column("PrimaryContactId", foreign("Contact", vec!["Id"]).nullable(true));
+
+    let mut contact = Table::new("Contact");
+    contact.add_column("Id", varchar(None).primary(true));
+    contact.add_column("AccountId", foreign("Account", vec!["Id"]));
+
+    let mut opportunity = Table::new("Opportunity");
+    opportunity.add_column("Id", varchar(None).primary(true));
+    opportunity.add_column("AccountId", varchar(None));
+    opportunity.foreign_key(&["AccountId"], "Account", &["Id"]);
+
+    let mut case = Table::new("Case");
+    case.add_column("Id", varchar(None).primary(true));
+    case.add_column("ContactId", foreign("Contact", vec!["Id"]));
+
+    // Parents come first, no matter what order tables get added in
+    let mut schema = Schema::new();
+    schema.add_table(case.clone()).add_table(opportunity.clone()).add_table(contact.clone());
+    assert_eq!(schema.order(), vec!["Opportunity", "Contact", "Case"]);
+
+    // Account and Contact point at each other, so one of those keys waits until both exist
+    schema.add_table(account.clone());
+    assert_eq!(schema.order(), vec!["Contact", "Case", "Account", "Opportunity"]);
+
+    let mut schema = Schema::new();
+    schema.add_table(account).add_table(contact).add_table(case).add_table(opportunity);
+    assert_eq!(schema.order(), vec!["Account", "Contact", "Case", "Opportunity"]);
+
+    let sql = schema.generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Account\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL,\n\
+      \"ParentId\" VARCHAR REFERENCES \"Account\" (\"Id\"),\n\
+      \"PrimaryContactId\" VARCHAR\n\
+      );\n\
+      \n\
+      CREATE TABLE \"Contact\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL,\n\
+      \"AccountId\" VARCHAR REFERENCES \"Account\" (\"Id\") NOT NULL\n\
+      );\n\
+      \n\
+      CREATE TABLE \"Case\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL,\n\
+      \"ContactId\" VARCHAR REFERENCES \"Contact\" (\"Id\") NOT NULL\n\
+      );\n\
+      \n\
+      CREATE TABLE \"Opportunity\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL,\n\
+      \"AccountId\" VARCHAR NOT NULL,\n\
+      CONSTRAINT \"Opportunity_AccountId_fkey\" FOREIGN KEY (\"AccountId\") REFERENCES \"Account\" (\"Id\")\n\
+      );\n\
+      \n\
+      ALTER TABLE \"Account\" ADD CONSTRAINT \"Account_PrimaryContactId_fkey\" FOREIGN KEY (\"PrimaryContactId\") REFERENCES \"Contact\" (\"Id\")");
+
+    let sql = schema.generate::<MySql>();
+    assert_eq!(sql, "\
+      CREATE TABLE `Account` (\n\
+      `Id` VARCHAR(255) NOT NULL PRIMARY KEY,\n\
+      `ParentId` VARCHAR(255) NULL,\n\
+      `PrimaryContactId` VARCHAR(255) NULL,\n\
+      CONSTRAINT `Account_ParentId_fkey` FOREIGN KEY (`ParentId`) REFERENCES `Account` (`Id`)\n\
+      ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;\n\
+      \n\
+      CREATE TABLE `Contact` (\n\
+      `Id` VARCHAR(255) NOT NULL PRIMARY KEY,\n\
+      `AccountId` VARCHAR(255) NOT NULL,\n\
+      CONSTRAINT `Contact_AccountId_fkey` FOREIGN KEY (`AccountId`) REFERENCES `Account` (`Id`)\n\
+      ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;\n\
+      \n\
+      CREATE TABLE `Case` (\n\
+      `Id` VARCHAR(255) NOT NULL PRIMARY KEY,\n\
+      `ContactId` VARCHAR(255) NOT NULL,\n\
+      CONSTRAINT `Case_ContactId_fkey` FOREIGN KEY (`ContactId`) REFERENCES `Contact` (`Id`)\n\
+      ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;\n\
+      \n\
+      CREATE TABLE `Opportunity` (\n\
+      `Id` VARCHAR(255) NOT NULL PRIMARY KEY,\n\
+      `AccountId` VARCHAR(255) NOT NULL,\n\
+      CONSTRAINT `Opportunity_AccountId_fkey` FOREIGN KEY (`AccountId`) REFERENCES `Account` (`Id`)\n\
+      ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;\n\
+      \n\
+      ALTER TABLE `Account` ADD CONSTRAINT `Account_PrimaryContactId_fkey` FOREIGN KEY (`PrimaryContactId`) REFERENCES `Contact` (`Id`)");
+
+    // SQLite doesn't mind references to tables that aren't there yet
+    let sql = schema.generate::<Sqlite>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Account\" (\n\
+      \"Id\" TEXT PRIMARY KEY NOT NULL,\n\
+      \"ParentId\" TEXT REFERENCES \"Account\" (\"Id\"),\n\
+      \"PrimaryContactId\" TEXT REFERENCES \"Contact\" (\"Id\")\n\
+      );\n\
+      \n\
+      CREATE TABLE \"Contact\" (\n\
+      \"Id\" TEXT PRIMARY KEY NOT NULL,\n\
+      \"AccountId\" TEXT NOT NULL REFERENCES \"Account\" (\"Id\")\n\
+      );\n\
+      \n\
+      CREATE TABLE \"Case\" (\n\
+      \"Id\" TEXT PRIMARY KEY NOT NULL,\n\
+      \"ContactId\" TEXT NOT NULL REFERENCES \"Contact\" (\"Id\")\n\
+      );\n\
+      \n\
+      CREATE TABLE \"Opportunity\" (\n\
+      \"Id\" TEXT PRIMARY KEY NOT NULL,\n\
+      \"AccountId\" TEXT NOT NULL,\n\
+      CONSTRAINT \"Opportunity_AccountId_fkey\" FOREIGN KEY (\"AccountId\") REFERENCES \"Account\" (\"Id\")\n\
+      )");
+
+    // Keys to tables that aren't in the schema get left out, column level or not
+    let mut parent = Table::new("Account");
+    parent.add_column("Id", varchar(None).primary(true));
+    let mut task = Table::new("Task");
+    task.add_column("Id", varchar(None).primary(true));
+    task.add_column("AccountId", foreign("Account", vec!["Id"]));
+    task.add_column("OwnerId", foreign("User", vec!["Id"]));
+    task.add_column("WhatId", varchar(None));
+    task.foreign_key(&["WhatId"], "Opportunity", &["Id"]);
+
+    let mut schema = Schema::new();
+    schema.add_table(task).add_table(parent);
+    assert_eq!(schema.order(), vec!["Account", "Task"]);
+
+    let sql = schema.generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Account\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL\n\
+      );\n\
+      \n\
+      CREATE TABLE \"Task\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL,\n\
+      \"AccountId\" VARCHAR REFERENCES \"Account\" (\"Id\") NOT NULL,\n\
+      \"OwnerId\" VARCHAR NOT NULL,\n\
+      \"WhatId\" VARCHAR NOT NULL\n\
+      )");
+  }
+
+  #[test]
+  fn streaming_output() {
+    let mut table = Table::new("Account");
+    table.add_column("Id", varchar(None).primary(true)).add_column("Name", varchar(Some(255)).nullable(true));
+
+    let mut streamed = Vec::new();
+    table.generate_into::<Pg, _>(&mut streamed).unwrap();
+    assert_eq!(String::from_utf8(streamed).unwrap(), table.generate::<Pg>());
+
+    let mut streamed = Vec::new();
+    table.generate_into_for(Dialect::MySql, &mut streamed).unwrap();
+    assert_eq!(String::from_utf8(streamed).unwrap(), table.generate::<MySql>());
+
+    let mut schema = Schema::new();
+    schema.add_table(table.clone()).add_table(Table::new("Contact"));
+    let mut streamed = Vec::new();
+    schema.generate_into::<Pg, _>(&mut streamed).unwrap();
+    assert_eq!(String::from_utf8(streamed).unwrap(), schema.generate::<Pg>());
+
+    let mut streamed = Vec::new();
+    schema.generate_into_for(Dialect::Sqlite, &mut streamed).unwrap();
+    assert_eq!(String::from_utf8(streamed).unwrap(), schema.generate_for(Dialect::Sqlite));
+
+    // Write errors make it back out
+    let mut full = [0u8; 16];
+    assert!(table.generate_into::<Pg, _>(&mut &mut full[..]).is_err());
+  }
+}
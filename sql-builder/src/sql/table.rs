@@ -1,54 +1,1022 @@
-use std::collections::HashMap;
+use std::{
+  collections::HashMap,
+  io::{self, Write}
+};
+
+use indexmap::IndexMap;
 
 use super::{
+  Dialect,
+  Identifiers,
+  Naming,
+  Rows,
   SqlGenerator,
-  types::Type
+  View,
+  types::{BaseType, ReferentialAction, Type}
 };
 
 #[derive(Debug, Clone)]
 pub struct Table {
   name: String,
-  columns: HashMap<String, Type>
+  schema: Option<String>,
+  create_schema: bool,
+  mode: CreateMode,
+  persistence: Persistence,
+  /// Kept in the order they were added, which is the order they get created in
+  columns: IndexMap<String, Type>,
+  constraints: Vec<TableConstraint>,
+  options: Vec<(String, String)>,
+  identifiers: Identifiers,
+  comment: Option<String>,
+  grants: Vec<(String, String)>,
+  owner: Option<String>,
+  naming: Naming,
+  /// Field names and the columns they ended up as, see `naming`
+  fields: Vec<(String, String)>
+}
+
+#[derive(Debug, Clone)]
+struct TableConstraint {
+  name:       Option<String>,
+  deferrable: bool,
+  constraint: Constraint
+}
+
+impl From<Constraint> for TableConstraint {
+  fn from(constraint: Constraint) -> Self {
+    TableConstraint { name: None, deferrable: false, constraint }
+  }
+}
+
+/// What `CREATE TABLE` does about a table that's already there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreateMode {
+  /// Fails (the default)
+  Create,
+  /// Leaves it alone
+  IfNotExists,
+  /// Replaces it, by dropping it first on dialects without `OR REPLACE`
+  OrReplace
+}
+
+/// How long a table (and its data) sticks around, for staging tables that don't need to survive a crash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Persistence {
+  Permanent,
+  /// Skips the write-ahead log (Postgres), or Fail-safe (Snowflake `TRANSIENT`); permanent everywhere else
+  Unlogged,
+  /// Goes away with the session
+  Temporary
+}
+
+/// Knobs for `Table::generate_drop`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DropOptions {
+  pub if_exists: bool,
+  /// Takes dependent objects (views, foreign keys) along, where the dialect can
+  pub cascade: bool
+}
+
+impl DropOptions {
+  pub fn if_exists(self, if_exists: bool) -> Self {
+    Self { if_exists, ..self }
+  }
+
+  pub fn cascade(self, cascade: bool) -> Self {
+    Self { cascade, ..self }
+  }
+}
+
+/// A column that differs between two versions of a table, see `Table::diff`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnChange {
+  Add(String),
+  Drop(String),
+  Alter(String)
+}
+
+/// Table level constraints, rendered after the columns.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constraint {
+  /// `CHECK (<expr>)`
+  Check(String),
+  /// `PRIMARY KEY (<columns>)`
+  PrimaryKey(Vec<String>),
+  /// `UNIQUE (<columns>)`
+  Unique(Vec<String>),
+  /// `FOREIGN KEY (<columns>) REFERENCES <table> (<references>)`
+  Foreign {
+    columns:    Vec<String>,
+    table:      String,
+    references: Vec<String>,
+    on_delete:  Option<ReferentialAction>,
+    on_update:  Option<ReferentialAction>
+  }
 }
 
 impl Table {
   pub fn new<N>(name: N) -> Self
   where N: Into<String> {
     Table {
-      name:    name.into(),
-      columns: HashMap::new()
+      name:          name.into(),
+      schema:        None,
+      create_schema: false,
+      mode:          CreateMode::Create,
+      persistence:   Persistence::Permanent,
+      columns:       IndexMap::new(),
+      constraints:   Vec::new(),
+      options:       Vec::new(),
+      identifiers:   Identifiers::default(),
+      comment:       None,
+      grants:        Vec::new(),
+      owner:         None,
+      naming:        Naming::default(),
+      fields:        Vec::new()
     }
   }
 
+  /// A table living in `schema`, which then qualifies its name (and the tables its foreign keys point at).
+  pub fn new_in<S, N>(schema: S, name: N) -> Self
+  where S: Into<String>, N: Into<String> {
+    Table { schema: Some(schema.into()), ..Table::new(name) }
+  }
+
   pub fn name(&self) -> String {
     self.name.clone()
   }
 
-  pub fn add_column<N>(&mut self, name: N, tp: Type) -> &mut Self
+  pub fn schema(&self) -> Option<String> {
+    self.schema.clone()
+  }
+
+  /// Starts the output with a `CREATE SCHEMA IF NOT EXISTS` for the table's schema (when it has one).
+  pub fn create_schema(&mut self) -> &mut Self {
+    self.create_schema = true;
+    self
+  }
+
+  /// Column names get run through `naming` first (see `Table::naming`), keys referenced by foreign keys included.
+  pub fn add_column<N>(&mut self, name: N, mut tp: Type) -> &mut Self
   where N: Into<String> {
-    self.columns.insert(name.into(), tp);
+    let field  = name.into();
+    let column = self.naming.apply(&field);
+
+    if let BaseType::Foreign(_, ref mut keys) = tp.inner {
+      keys.0 = keys.0.iter().map(|key| self.naming.apply(key)).collect();
+    }
+
+    self.fields.retain(|(existing, _)| *existing != field);
+    self.fields.push((field, column.clone()));
+    self.columns.insert(column, tp);
     self
   }
 
-  pub fn generate<T>(&mut self) -> String
-  where T: SqlGenerator {
+  /// Whether there's a column for the field `name`.
+  pub fn has_column<N>(&self, name: N) -> bool
+  where N: AsRef<str> {
+    self.columns.contains_key(&self.naming.apply(name.as_ref()))
+  }
+
+  /// Swaps the SQL type of an existing column for `sql` (see `Type::sql_type`); returns false when there is no such column.
+  pub fn override_type<N, S>(&mut self, column: N, sql: S) -> bool
+  where N: AsRef<str>, S: Into<String> {
+    match self.columns.get_mut(&self.naming.apply(column.as_ref())) {
+      Some(col_type) => {
+        col_type.sql_type = Some(sql.into());
+        true
+      },
+      None => false
+    }
+  }
+
+  /// Adds a table level `CHECK` constraint.
+  pub fn check<E>(&mut self, expr: E) -> &mut Self
+  where E: Into<String> {
+    self.constraints.push(Constraint::Check(expr.into()).into());
+    self
+  }
+
+  /// Sets a (composite) table level primary key; any column level primary keys are dropped since a table can only have one.
+  pub fn primary_key(&mut self, columns: &[&str]) -> &mut Self {
+    for col_type in self.columns.values_mut() {
+      col_type.primary = false;
+    }
+
+    self.constraints.retain(|entry| !matches!(entry.constraint, Constraint::PrimaryKey(_)));
+    self.constraints.push(Constraint::PrimaryKey(self.column_names(columns)).into());
+    self
+  }
+
+  /// Adds a table level (possibly multi column) `UNIQUE` constraint.
+  pub fn unique(&mut self, columns: &[&str]) -> &mut Self {
+    self.constraints.push(Constraint::Unique(self.column_names(columns)).into());
+    self
+  }
+
+  /// Adds a table level foreign key from `columns` to `references` on another table.
+  pub fn foreign_key<T>(&mut self, columns: &[&str], table: T, references: &[&str]) -> &mut Self
+  where T: Into<String> {
+    self.constraints.push(TableConstraint::from(Constraint::Foreign {
+      columns:    self.column_names(columns),
+      table:      table.into(),
+      references: self.column_names(references),
+      on_delete:  None,
+      on_update:  None
+    }));
+    self
+  }
+
+  /// Sets the `ON DELETE` action of the most recently added foreign key.
+  pub fn on_delete(&mut self, action: ReferentialAction) -> &mut Self {
+    if let Some(Constraint::Foreign { on_delete, .. }) = self.constraints.last_mut().map(|entry| &mut entry.constraint) {
+      *on_delete = Some(action);
+    }
+    self
+  }
+
+  /// Sets the `ON UPDATE` action of the most recently added foreign key.
+  pub fn on_update(&mut self, action: ReferentialAction) -> &mut Self {
+    if let Some(Constraint::Foreign { on_update, .. }) = self.constraints.last_mut().map(|entry| &mut entry.constraint) {
+      *on_update = Some(action);
+    }
+    self
+  }
+
+  /// Names the most recently added constraint (instead of using `constraint_name`).
+  pub fn named<N>(&mut self, name: N) -> &mut Self
+  where N: Into<String> {
+    if let Some(entry) = self.constraints.last_mut() {
+      entry.name = Some(name.into());
+    }
+    self
+  }
+
+  /// Makes the most recently added constraint `DEFERRABLE INITIALLY DEFERRED`; checks can't be deferred, so those are left alone.
+  pub fn deferrable(&mut self) -> &mut Self {
+    if let Some(entry) = self.constraints.last_mut() {
+      entry.deferrable = !matches!(entry.constraint, Constraint::Check(_));
+    }
+    self
+  }
+
+  /// Sets a dialect specific table option, such as `ENGINE` for MySQL; dialects without options ignore these.
+  pub fn option<K, V>(&mut self, key: K, value: V) -> &mut Self
+  where K: Into<String>, V: Into<String> {
+    let key = key.into();
+    self.options.retain(|(existing, _)| !existing.eq_ignore_ascii_case(&key));
+    self.options.push((key, value.into()));
+    self
+  }
+
+  /// Renders `CREATE TABLE IF NOT EXISTS`, so the script can be re-run.
+  pub fn if_not_exists(&mut self) -> &mut Self {
+    self.mode = CreateMode::IfNotExists;
+    self
+  }
+
+  /// Renders `CREATE OR REPLACE TABLE`, or a `DROP TABLE IF EXISTS` first where that doesn't exist.
+  pub fn or_replace(&mut self) -> &mut Self {
+    self.mode = CreateMode::OrReplace;
+    self
+  }
+
+  /// Renders an `UNLOGGED` table, where the dialect has something like it.
+  pub fn unlogged(&mut self) -> &mut Self {
+    self.persistence = Persistence::Unlogged;
+    self
+  }
+
+  /// Renders a `TEMPORARY` table; these generally can't be put into a schema.
+  pub fn temporary(&mut self) -> &mut Self {
+    self.persistence = Persistence::Temporary;
+    self
+  }
+
+  /// Describes the table; these (and column comments) get rendered after the table, see `SqlGenerator::comment_on`.
+  pub fn comment<C>(&mut self, comment: C) -> &mut Self
+  where C: Into<String> {
+    self.comment = Some(comment.into());
+    self
+  }
+
+  /// Grants `privileges` (e.g. `SELECT` or `SELECT, INSERT`) on the table to `role`, which is used as-is.
+  pub fn grant<P, R>(&mut self, privileges: P, role: R) -> &mut Self
+  where P: Into<String>, R: Into<String> {
+    self.grants.push((privileges.into(), role.into()));
+    self
+  }
+
+  /// Hands the table over to `role` once it's created.
+  pub fn owner<R>(&mut self, role: R) -> &mut Self
+  where R: Into<String> {
+    self.owner = Some(role.into());
+    self
+  }
+
+  /// Sets how field names turn into column names; has to come before any columns get added.
+  /// Everything else keeps taking field names, which get translated along the way.
+  pub fn naming(&mut self, naming: Naming) -> &mut Self {
+    self.naming = naming;
+    self
+  }
+
+  /// Starts a view over this table (in the same schema, with the same identifier rules).
+  pub fn view<N>(&self, name: N) -> View
+  where N: Into<String> {
+    View::new(name.into(), self.name.clone(), self.schema.clone(), self.identifiers.clone())
+  }
+
+  /// Starts a batch of records to load into this table, taking values for the same columns an upsert would.
+  pub fn rows(&self) -> Rows {
+    let fields  = self.fields.iter().map(|(field, column)| (column, field)).collect::<HashMap<_, _>>();
+    let columns = self.upsert_columns(&[])
+      .into_iter()
+      .map(|column| {
+        let field = fields.get(&column).map_or_else(|| column.clone(), |field| (*field).clone());
+        let tp    = self.columns[&column].clone();
+        (field, column, tp)
+      })
+      .collect();
+
+    Rows::new(self.name.clone(), self.schema.clone(), self.identifiers.clone(), columns)
+  }
+
+  /// Field names along with the columns they map to, in column order.
+  pub fn field_columns(&self) -> &[(String, String)] {
+    &self.fields
+  }
+
+  /// Sets how table, column and constraint names get quoted (and cased); everything is quoted as-is by default.
+  pub fn identifiers(&mut self, identifiers: Identifiers) -> &mut Self {
+    self.identifiers = identifiers;
+    self
+  }
+
+  fn column_names(&self, fields: &[&str]) -> Vec<String> {
+    fields.iter().map(|field| self.naming.apply(field)).collect()
+  }
+
+  /// Default constraint names follow the Postgres scheme, e.g. `Account_pkey` or `Contact_AccountId_fkey`.
+  /// Checks don't have any columns to go by, so those get numbered instead.
+  fn constraint_name(&self, constraint: &Constraint, check_number: usize) -> String {
+    match constraint {
+      Constraint::PrimaryKey(_)           => format!("{}_pkey", self.name),
+      Constraint::Unique(columns)         => format!("{}_{}_key", self.name, columns.join("_")),
+      Constraint::Foreign { columns, .. } => format!("{}_{}_fkey", self.name, columns.join("_")),
+      Constraint::Check(_)                => format!("{}_check{}", self.name, check_number)
+    }
+  }
+
+  /// Columns making up the primary key, whether that's set per column or on the table.
+  fn primary_key_columns(&self) -> Vec<String> {
+    let composite = self.constraints.iter().find_map(|entry| match &entry.constraint {
+      Constraint::PrimaryKey(columns) => Some(columns.clone()),
+      _                               => None
+    });
+
+    composite.unwrap_or_else(|| {
+      self.columns
+        .iter()
+        .filter(|(_, col_type)| col_type.primary)
+        .map(|(name, _)| name.clone())
+        .collect()
+    })
+  }
+
+  /// Column level foreign keys as table constraints, for dialects that can't do them inline.
+  fn hoisted_foreign_keys(&self) -> Vec<TableConstraint> {
+    self.columns
+      .iter()
+      .filter_map(|(name, col_type)| match &col_type.inner {
+        BaseType::Foreign(table, keys) => Some(TableConstraint {
+          name:       None,
+          deferrable: col_type.deferrable,
+          constraint: Constraint::Foreign {
+            columns:    vec![name.clone()],
+            table:      table.clone(),
+            references: keys.0.clone(),
+            on_delete:  col_type.on_delete,
+            on_update:  col_type.on_update
+          }
+        }),
+        _ => None
+      })
+      .collect()
+  }
 
-    let (prefix, affix) = T::create_table(&self.name);
-    let col_count    = self.columns.len();
+  /// Foreign keys as table constraints, whether they're set per column or on the table.
+  fn foreign_keys(&self) -> Vec<TableConstraint> {
+    self.hoisted_foreign_keys()
+      .into_iter()
+      .chain(self.constraints.iter().filter(|entry| matches!(entry.constraint, Constraint::Foreign { .. })).cloned())
+      .collect()
+  }
+
+  /// Tables this one has foreign keys to.
+  pub(crate) fn references(&self) -> Vec<String> {
+    self.foreign_keys()
+      .into_iter()
+      .filter_map(|entry| match entry.constraint {
+        Constraint::Foreign { table, .. } => Some(table),
+        _                                 => None
+      })
+      .collect()
+  }
+
+  /// Takes the foreign keys to any of `tables` out of the table (columns lose their `REFERENCES`, but stay put),
+  /// returning `ALTER TABLE` statements adding them back. Keys the dialect can't add later on are left alone.
+  pub(crate) fn defer_foreign_keys<T>(&mut self, tables: &[String]) -> Vec<String>
+  where T: SqlGenerator {
+    let ids   = self.identifiers.clone().in_schema(self.schema.clone());
+    let table = ids.table::<T>(&self.name);
 
-    let mut sql = self.columns
-      .iter_mut()
-      .enumerate()
-      .fold(prefix, |mut sql, (idx, (ref name, ref col_type))| {
-        sql.push_str(&T::create_column(name, &col_type));
+    let mut deferred = Vec::new();
+    let mut defer    = |entry: &TableConstraint| {
+      if !matches!(&entry.constraint, Constraint::Foreign { table, .. } if tables.contains(table)) {
+        return false;
+      }
 
-        if idx < col_count - 1 {
-          sql.push_str(",\n");
+      let name = entry.name.clone().unwrap_or_else(|| self.constraint_name(&entry.constraint, 0));
+      match T::add_constraint(&name, &entry.constraint, entry.deferrable, &ids) {
+        sql if sql.is_empty() => false,
+        sql                   => {
+          deferred.push(format!("ALTER TABLE {} {}", table, sql));
+          true
         }
-        sql
-      });
+      }
+    };
+
+    let columns = self.hoisted_foreign_keys()
+      .into_iter()
+      .filter(|entry| defer(entry))
+      .filter_map(|entry| match entry.constraint {
+        Constraint::Foreign { columns, .. } => columns.into_iter().next(),
+        _                                   => None
+      })
+      .collect::<Vec<_>>();
+    let constraints = self.constraints.iter().filter(|entry| !defer(entry)).cloned().collect();
+
+    self.constraints = constraints;
+    self.unreference::<T>(columns);
+    deferred
+  }
+
+  /// Takes the foreign keys to any of `tables` out of the table for good (columns lose their `REFERENCES`, but stay put).
+  pub(crate) fn drop_foreign_keys<T>(&mut self, tables: &[String])
+  where T: SqlGenerator {
+    let dropped = |entry: &TableConstraint| matches!(&entry.constraint, Constraint::Foreign { table, .. } if tables.contains(table));
+
+    let columns = self.hoisted_foreign_keys()
+      .into_iter()
+      .filter(|entry| dropped(entry))
+      .filter_map(|entry| match entry.constraint {
+        Constraint::Foreign { columns, .. } => columns.into_iter().next(),
+        _                                   => None
+      })
+      .collect::<Vec<_>>();
+
+    self.constraints.retain(|entry| !dropped(entry));
+    self.unreference::<T>(columns);
+  }
+
+  // Foreign key columns turn into whatever type they'd have been rendered as
+  fn unreference<T>(&mut self, columns: Vec<String>)
+  where T: SqlGenerator {
+    for column in columns {
+      let col_type      = &mut self.columns[&column];
+      col_type.sql_type = Some(T::column_type(col_type));
+      col_type.inner    = BaseType::Varchar(None);
+    }
+  }
+
+  pub fn generate<T>(&self) -> String
+  where T: SqlGenerator {
+    let mut sql = Vec::new();
+    self.generate_into::<T, _>(&mut sql).expect("writing to a Vec can't fail");
+    String::from_utf8(sql).expect("generated SQL is always UTF-8")
+  }
+
+  /// Same as `generate`, but streams the SQL straight into `w` a column at a time.
+  pub fn generate_into<T, W>(&self, w: &mut W) -> io::Result<()>
+  where T: SqlGenerator, W: Write {
+    let ids = self.identifiers.clone().in_schema(self.schema.clone());
+
+    let (replace, mode) = match (self.mode, T::or_replace()) {
+      (CreateMode::OrReplace, false) => (true, CreateMode::Create),
+      (mode, _)                      => (false, mode)
+    };
+    let (prefix, affix) = T::create_table(&self.name, mode, self.persistence, &ids);
+
+    let hoisted = match T::inline_foreign_keys() {
+      true  => Vec::new(),
+      false => self.hoisted_foreign_keys()
+    };
+
+    let mut checks  = 0;
+    let columns     = self.columns.iter().map(|(name, col_type)| T::create_column(name, col_type, &ids));
+    let constraints = hoisted.iter().chain(&self.constraints).map(|entry| {
+      if let Constraint::Check(_) = entry.constraint {
+        checks += 1;
+      }
+
+      let name = entry.name.clone().unwrap_or_else(|| self.constraint_name(&entry.constraint, checks));
+      T::create_constraint(&name, &entry.constraint, entry.deferrable, &ids)
+    });
+    let definitions = columns.chain(constraints).filter(|sql| !sql.is_empty());
+
+    if let (true, Some(schema)) = (self.create_schema, self.schema.as_ref()) {
+      match T::create_schema(schema, &ids) {
+        preamble if preamble.is_empty() => {},
+        preamble                        => write!(w, "{};\n\n", preamble)?
+      }
+    }
+    if replace {
+      write!(w, "{};\n\n", T::drop_table(&self.name, DropOptions::default().if_exists(true), &ids))?;
+    }
+
+    w.write_all(prefix.as_bytes())?;
+    for (index, definition) in definitions.enumerate() {
+      if index > 0 {
+        w.write_all(b",\n")?;
+      }
+      w.write_all(definition.as_bytes())?;
+    }
+    w.write_all(affix.as_bytes())?;
+    w.write_all(T::create_options(&self.options, &self.primary_key_columns(), &ids).as_bytes())?;
+
+    let epilogue = self.epilogue::<T>(&ids);
+    if !epilogue.is_empty() {
+      write!(w, ";\n\n{}", epilogue.join(";\n"))?;
+    }
+    Ok(())
+  }
+
+  /// Comments, grants and the owner, which all come after the table.
+  fn epilogue<T>(&self, ids: &Identifiers) -> Vec<String>
+  where T: SqlGenerator {
+    let commented = self.columns
+      .iter()
+      .filter_map(|(name, col_type)| col_type.comment.as_ref().map(|comment| (name, comment)));
+
+    let table_comment   = self.comment.iter().map(|comment| T::comment_on(&self.name, None, comment, ids));
+    let column_comments = commented.map(|(name, comment)| T::comment_on(&self.name, Some(name), comment, ids));
+    let grants          = self.grants.iter().map(|(privileges, role)| T::grant(&self.name, privileges, role, ids));
+    let owner           = self.owner.iter().map(|role| T::owner(&self.name, role, ids));
+
+    table_comment
+      .chain(column_comments)
+      .chain(grants)
+      .chain(owner)
+      .filter(|sql| !sql.is_empty())
+      .collect()
+  }
+
+  /// Same as `generate`, with the dialect picked at runtime.
+  pub fn generate_for(&self, dialect: Dialect) -> String {
+    dispatch!(dialect, T => self.generate::<T>())
+  }
+
+  /// Same as `generate_into`, with the dialect picked at runtime.
+  pub fn generate_into_for<W>(&self, dialect: Dialect, w: &mut W) -> io::Result<()>
+  where W: Write {
+    dispatch!(dialect, T => self.generate_into::<T, W>(w))
+  }
+}
+
+impl Table {
+  /// Columns that were added, dropped or changed since `old` (by name), added ones first and otherwise in column order.
+  /// Changes only count when they're to the type or nullability, which is all `alter_column` can do.
+  pub fn diff(&self, old: &Table) -> Vec<ColumnChange> {
+    let alterable = |tp: &Type| (tp.inner(), tp.sql_type.clone(), tp.nullable);
+
+    let mut added   = Vec::new();
+    let mut altered = Vec::new();
+    for (name, col_type) in &self.columns {
+      match old.columns.get(name) {
+        None                                                         => added.push(name.clone()),
+        Some(previous) if alterable(previous) != alterable(col_type) => altered.push(name.clone()),
+        Some(_)                                                      => {}
+      }
+    }
+
+    let dropped = old.columns.keys().filter(|name| !self.columns.contains_key(*name)).cloned().collect::<Vec<_>>();
+
+    added.into_iter().map(ColumnChange::Add)
+      .chain(dropped.into_iter().map(ColumnChange::Drop))
+      .chain(altered.into_iter().map(ColumnChange::Alter))
+      .collect()
+  }
+
+  /// `ALTER TABLE` statements turning `old` into this table, one per change and each ending in `;`.
+  /// Only columns get compared (not constraints or options); changes the dialect can't make in place are left out,
+  /// or show up as `--` comments saying what has to be done by hand.
+  /// Added columns are nullable unless they've got a default, since the rows already there have nothing to put in them.
+  pub fn generate_alter<T>(&self, old: &Table) -> String
+  where T: SqlGenerator {
+    let ids   = self.identifiers.clone().in_schema(self.schema.clone());
+    let table = ids.table::<T>(&self.name);
+    let added = |tp: &Type| Type { nullable: tp.nullable || (tp.default.is_none() && !tp.increments), ..tp.clone() };
+
+    self.diff(old)
+      .into_iter()
+      .flat_map(|change| match change {
+        ColumnChange::Add(name)   => vec![T::add_column(&name, &added(&self.columns[&name]), &ids)],
+        ColumnChange::Drop(name)  => vec![T::drop_column(&name, &ids)],
+        ColumnChange::Alter(name) => T::alter_column(&name, &old.columns[&name], &self.columns[&name], &ids)
+      })
+      .map(|clause| match clause.starts_with("--") {
+        true  => clause,
+        false => format!("ALTER TABLE {} {};", table, clause)
+      })
+      .collect::<Vec<_>>()
+      .join("\n")
+  }
+
+  /// Same as `generate_alter`, with the dialect picked at runtime.
+  pub fn generate_alter_for(&self, dialect: Dialect, old: &Table) -> String {
+    dispatch!(dialect, T => self.generate_alter::<T>(old))
+  }
+
+  /// Parameterized statements (one, except on Redshift) inserting a row, or updating the existing one with the same `key`
+  /// (field names; the primary key when empty). Computed columns and surrogate keys are left to the database, everything
+  /// else gets bound in column order (see `upsert_columns`). Errors out when there's no key, or the database fills it in
+  /// (like a serial surrogate key), since there'd be nothing to match on.
+  pub fn generate_upsert<T>(&self, key: &[&str]) -> anyhow::Result<Vec<String>>
+  where T: SqlGenerator {
+    let ids = self.identifiers.clone().in_schema(self.schema.clone());
+    let key = match key.is_empty() {
+      true  => self.primary_key_columns(),
+      false => self.column_names(key)
+    };
+
+    let columns = self.upsert_columns(key.as_slice());
+    if key.is_empty() {
+      anyhow::bail!("cannot upsert into `{}`, it has no primary key", self.name);
+    }
+    if let Some(missing) = key.iter().find(|col| !columns.contains(col)) {
+      anyhow::bail!("cannot upsert into `{}` on `{}`, that's not a column that gets written", self.name, missing);
+    }
+    Ok(T::upsert(&self.name, &columns, &key, &ids))
+  }
+
+  /// Same as `generate_upsert`, with the dialect picked at runtime.
+  pub fn generate_upsert_for(&self, dialect: Dialect, key: &[&str]) -> anyhow::Result<Vec<String>> {
+    dispatch!(dialect, T => self.generate_upsert::<T>(key))
+  }
+
+  /// Columns an upsert on `key` (column names) binds values for, in order.
+  pub fn upsert_columns(&self, key: &[String]) -> Vec<String> {
+    self.columns
+      .iter()
+      .filter(|(name, tp)| {
+        let surrogate = tp.increments || (tp.primary && tp.default.is_some() && !key.contains(name));
+        tp.generated.is_none() && !surrogate
+      })
+      .map(|(name, _)| name.clone())
+      .collect()
+  }
+
+  /// `DROP TABLE` for this table, e.g. for tearing things down before a rebuild.
+  pub fn generate_drop<T>(&self, options: DropOptions) -> String
+  where T: SqlGenerator {
+    let ids = self.identifiers.clone().in_schema(self.schema.clone());
+    T::drop_table(&self.name, options, &ids)
+  }
+
+  /// Same as `generate_drop`, with the dialect picked at runtime.
+  pub fn generate_drop_for(&self, dialect: Dialect, options: DropOptions) -> String {
+    dispatch!(dialect, T => self.generate_drop::<T>(options))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::sql::*;
+
+  #[test]
+  fn named_constraints() {
+    let mut table = Table::new("Contact");
+    table
+      .add_column("Email", varchar(None))
+      .add_column("AccountId", varchar(None))
+      .unique(&["Email"])
+      .foreign_key(&["AccountId"], "Account", &["Id"])
+      .check("\"Email\" LIKE '%@%'")
+      .named("contact_email_format");
+
+    let sql = table.generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Contact\" (\n\
+      \"Email\" VARCHAR NOT NULL,\n\
+      \"AccountId\" VARCHAR NOT NULL,\n\
+      CONSTRAINT \"Contact_Email_key\" UNIQUE (\"Email\"),\n\
+      CONSTRAINT \"Contact_AccountId_fkey\" FOREIGN KEY (\"AccountId\") REFERENCES \"Account\" (\"Id\"),\n\
+      CONSTRAINT \"contact_email_format\" CHECK (\"Email\" LIKE '%@%')\n\
+      )");
+  }
+
+  #[test]
+  fn default_values() {
+    let mut table = Table::new("Widget");
+    table
+      .add_column("Name", varchar(None).default("O'Brien"))
+      .add_column("Active", boolean().default(true))
+      .add_column("Quantity", integer().default(5))
+      .add_column("Ratio", double().default(0.5))
+      .add_column("LoadedAt", datetime_tz().default(WrappedDefault::Expr("now()".to_string())));
+
+    let sql = table.generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Widget\" (\n\
+      \"Name\" VARCHAR DEFAULT 'O''Brien' NOT NULL,\n\
+      \"Active\" BOOLEAN DEFAULT TRUE NOT NULL,\n\
+      \"Quantity\" INTEGER DEFAULT 5 NOT NULL,\n\
+      \"Ratio\" DOUBLE PRECISION DEFAULT 0.5 NOT NULL,\n\
+      \"LoadedAt\" TIMESTAMPTZ DEFAULT now() NOT NULL\n\
+      )");
+  }
+
+  #[test]
+  fn array_columns() {
+    let mut table = Table::new("Widget");
+    table
+      .add_column("Tags", array(&varchar(None)).default(vec!["new", "it's"]))
+      .add_column("Grid", array_dims(&integer(), 2).default(vec![vec![1i64, 2], vec![3, 4]]))
+      .add_column("Empty", array(&integer()).default(Vec::<i64>::new()));
+
+    let sql = table.generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"Widget\" (\n\
+      \"Tags\" VARCHAR[] DEFAULT ARRAY['new', 'it''s'] NOT NULL,\n\
+      \"Grid\" INTEGER[][] DEFAULT ARRAY[ARRAY[1, 2], ARRAY[3, 4]] NOT NULL,\n\
+      \"Empty\" INTEGER[] DEFAULT '{}' NOT NULL\n\
+      )");
+  }
+
+  #[test]
+  fn schema_qualified_tables() {
+    let mut table = Table::new_in("salesforce", "Contact");
+    table.add_column("Id", varchar(None).primary(true));
+    table.add_column("AccountId", foreign("Account", vec!["Id"]).nullable(true));
+
+    let sql = table.generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"salesforce\".\"Contact\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL,\n\
+      \"AccountId\" VARCHAR REFERENCES \"salesforce\".\"Account\" (\"Id\")\n\
+      )");
+
+    let sql = table.create_schema().generate::<Pg>();
+    assert_eq!(sql, "\
+      CREATE SCHEMA IF NOT EXISTS \"salesforce\";\n\
+      \n\
+      CREATE TABLE \"salesforce\".\"Contact\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL,\n\
+      \"AccountId\" VARCHAR REFERENCES \"salesforce\".\"Account\" (\"Id\")\n\
+      )");
+
+    let sql = table.generate::<Mssql>();
+    assert_eq!(sql, "\
+      IF SCHEMA_ID(N'salesforce') IS NULL EXEC(N'CREATE SCHEMA [salesforce]');\n\
+      \n\
+      CREATE TABLE [salesforce].[Contact] (\n\
+      [Id] NVARCHAR(255) NOT NULL PRIMARY KEY,\n\
+      [AccountId] NVARCHAR(255) NULL REFERENCES [salesforce].[Account] ([Id])\n\
+      )");
+
+    // SQLite can't create them, and references have to stay unqualified
+    let sql = table.generate::<Sqlite>();
+    assert_eq!(sql, "\
+      CREATE TABLE \"salesforce\".\"Contact\" (\n\
+      \"Id\" TEXT PRIMARY KEY NOT NULL,\n\
+      \"AccountId\" TEXT REFERENCES \"Account\" (\"Id\")\n\
+      )");
+
+    // Without a schema there's nothing to create
+    assert_eq!(Table::new("Contact").create_schema().generate::<Pg>(), "CREATE TABLE \"Contact\" (\n\n)");
+  }
+
+  #[test]
+  fn create_modes() {
+    let mut table = Table::new("Account");
+    table.add_column("Id", varchar(None).primary(true));
+
+    assert_eq!(table.if_not_exists().generate::<Pg>(), "\
+      CREATE TABLE IF NOT EXISTS \"Account\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL\n\
+      )");
+    assert_eq!(table.generate::<Mssql>(), "\
+      IF OBJECT_ID(N'[Account]', N'U') IS NULL\n\
+      CREATE TABLE [Account] (\n\
+      [Id] NVARCHAR(255) NOT NULL PRIMARY KEY\n\
+      )");
+
+    assert_eq!(table.or_replace().generate::<Snowflake>(), "\
+      CREATE OR REPLACE TABLE \"Account\" (\n\
+      \"Id\" VARCHAR NOT NULL PRIMARY KEY\n\
+      )");
+    assert_eq!(table.generate::<Pg>(), "\
+      DROP TABLE IF EXISTS \"Account\";\n\
+      \n\
+      CREATE TABLE \"Account\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL\n\
+      )");
+  }
+
+  #[test]
+  fn table_persistence() {
+    let mut table = Table::new_in("staging", "Account");
+    table.add_column("Id", varchar(None).primary(true));
+
+    assert_eq!(table.unlogged().generate::<Pg>(), "\
+      CREATE UNLOGGED TABLE \"staging\".\"Account\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL\n\
+      )");
+    assert_eq!(table.generate::<Snowflake>(), "\
+      CREATE TRANSIENT TABLE \"staging\".\"Account\" (\n\
+      \"Id\" VARCHAR NOT NULL PRIMARY KEY\n\
+      )");
+    assert_eq!(table.generate::<MySql>(), "\
+      CREATE TABLE `staging`.`Account` (\n\
+      `Id` VARCHAR(255) NOT NULL PRIMARY KEY\n\
+      ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4");
+
+    assert_eq!(table.temporary().generate::<Pg>(), "\
+      CREATE TEMPORARY TABLE \"Account\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL\n\
+      )");
+    assert_eq!(table.generate::<Sqlite>(), "CREATE TEMP TABLE \"Account\" (\n\"Id\" TEXT PRIMARY KEY NOT NULL\n)");
+    assert_eq!(table.if_not_exists().generate::<Mssql>(), "\
+      IF OBJECT_ID(N'tempdb..[#Account]', N'U') IS NULL\n\
+      CREATE TABLE [#Account] (\n\
+      [Id] NVARCHAR(255) NOT NULL PRIMARY KEY\n\
+      )");
+  }
+
+  #[test]
+  fn drop_tables() {
+    let table   = Table::new_in("salesforce", "Account");
+    let options = DropOptions::default().if_exists(true).cascade(true);
+
+    assert_eq!(table.generate_drop::<Pg>(DropOptions::default()), "DROP TABLE \"salesforce\".\"Account\"");
+    assert_eq!(table.generate_drop::<Pg>(options), "DROP TABLE IF EXISTS \"salesforce\".\"Account\" CASCADE");
+    assert_eq!(table.generate_drop::<Mssql>(options), "DROP TABLE IF EXISTS [salesforce].[Account]");
+    assert_eq!(table.generate_drop_for(Dialect::Sqlite, options), "DROP TABLE IF EXISTS \"salesforce\".\"Account\"");
+  }
+
+  #[test]
+  fn alter_tables() {
+    let mut old = Table::new("Account");
+    old.add_column("Id", varchar(None).primary(true));
+    old.add_column("Name", varchar(Some(80)));
+    old.add_column("Fax", varchar(Some(40)).nullable(true));
+
+    let mut new = Table::new("Account");
+    new.add_column("Id", varchar(None).primary(true));
+    new.add_column("Name", varchar(Some(255)).nullable(true));
+    new.add_column("Rating", varchar(Some(40)));
+
+    assert_eq!(new.diff(&old), vec![
+      ColumnChange::Add("Rating".to_string()),
+      ColumnChange::Drop("Fax".to_string()),
+      ColumnChange::Alter("Name".to_string())
+    ]);
+
+    // Rating isn't nullable, but it comes in that way for the rows already there
+    assert_eq!(new.generate_alter::<Pg>(&old), "\
+      ALTER TABLE \"Account\" ADD COLUMN \"Rating\" VARCHAR(40);\n\
+      ALTER TABLE \"Account\" DROP COLUMN \"Fax\";\n\
+      ALTER TABLE \"Account\" ALTER COLUMN \"Name\" SET DATA TYPE VARCHAR(255) USING CAST(\"Name\" AS VARCHAR(255));\n\
+      ALTER TABLE \"Account\" ALTER COLUMN \"Name\" DROP NOT NULL;");
+
+    let sql = new.generate_alter::<Mssql>(&old);
+    assert_eq!(sql, "\
+      ALTER TABLE [Account] ADD [Rating] NVARCHAR(40) NULL;\n\
+      ALTER TABLE [Account] DROP COLUMN [Fax];\n\
+      ALTER TABLE [Account] ALTER COLUMN [Name] NVARCHAR(255) NULL;");
+
+    let sql = new.generate_alter::<MySql>(&old);
+    assert_eq!(sql, "\
+      ALTER TABLE `Account` ADD COLUMN `Rating` VARCHAR(40) NULL;\n\
+      ALTER TABLE `Account` DROP COLUMN `Fax`;\n\
+      ALTER TABLE `Account` MODIFY COLUMN `Name` VARCHAR(255) NULL;");
+
+    // SQLite can add and drop, but anything else needs the table rebuilt
+    assert_eq!(new.generate_alter::<Sqlite>(&old), "\
+      ALTER TABLE \"Account\" ADD COLUMN \"Rating\" TEXT;\n\
+      ALTER TABLE \"Account\" DROP COLUMN \"Fax\";\n\
+      -- \"Name\" can't change from TEXT NOT NULL to TEXT in place, the table has to be rebuilt");
+    assert_eq!(new.generate_alter::<Pg>(&new), "");
+
+    // Only type and nullability changes count, since those are the only ones that get made
+    let mut old = Table::new("Opportunity");
+    old.add_column("Amount", varchar(None));
+    old.add_column("Stage", varchar(Some(40)));
+    old.add_column("Probability", integer().default(WrappedDefault::Integer(0)));
+
+    let mut new = Table::new("Opportunity");
+    new.add_column("Amount", numeric(18, 2));
+    new.add_column("Stage", varchar(Some(40)).unique(true).check("\"Stage\" <> ''"));
+    new.add_column("Probability", integer().default(WrappedDefault::Integer(10)));
+    new.add_column("Score", integer().default(WrappedDefault::Integer(0)));
+
+    assert_eq!(new.diff(&old), vec![ColumnChange::Add("Score".to_string()), ColumnChange::Alter("Amount".to_string())]);
+    assert_eq!(new.generate_alter::<Pg>(&old), "\
+      ALTER TABLE \"Opportunity\" ADD COLUMN \"Score\" INTEGER DEFAULT 0 NOT NULL;\n\
+      ALTER TABLE \"Opportunity\" ALTER COLUMN \"Amount\" SET DATA TYPE NUMERIC(18, 2) USING CAST(\"Amount\" AS NUMERIC(18, 2));");
+    assert_eq!(new.generate_alter::<Snowflake>(&old), "ALTER TABLE \"Opportunity\" ADD COLUMN \"Score\" INTEGER DEFAULT 0 NOT NULL;");
+  }
+
+  #[test]
+  fn table_options() {
+    let mut table = Table::new("Event");
+    table
+      .add_column("Id", varchar(None).primary(true))
+      .add_column("AccountId", varchar(None))
+      .add_column("CreatedDate", datetime_tz())
+      .option("TABLESPACE", "fast")
+      .option("fillfactor", "70")
+      .option("PARTITION BY", "RANGE (\"CreatedDate\")")
+      .option("autovacuum_enabled", "false");
+
+    // Clauses go where Postgres wants them, whatever order they were set in
+    assert_eq!(table.generate::<Pg>(), "\
+      CREATE TABLE \"Event\" (\n\
+      \"Id\" VARCHAR PRIMARY KEY NOT NULL,\n\
+      \"AccountId\" VARCHAR NOT NULL,\n\
+      \"CreatedDate\" TIMESTAMPTZ NOT NULL\n\
+      ) PARTITION BY RANGE (\"CreatedDate\") WITH (fillfactor = 70, autovacuum_enabled = false) TABLESPACE fast");
+
+    let mut table = Table::new("Event");
+    table
+      .add_column("Id", varchar(None).primary(true))
+      .option("CLUSTER BY", "AccountId, CreatedDate")
+      .option("DATA_RETENTION_TIME_IN_DAYS", "1");
+    assert_eq!(table.generate::<Snowflake>(), "\
+      CREATE TABLE \"Event\" (\n\
+      \"Id\" VARCHAR NOT NULL PRIMARY KEY\n\
+      ) CLUSTER BY (\"AccountId\", \"CreatedDate\") DATA_RETENTION_TIME_IN_DAYS = 1");
+    assert_eq!(table.generate::<Sqlite>(), "CREATE TABLE \"Event\" (\n\"Id\" TEXT PRIMARY KEY NOT NULL\n)");
+  }
+
+  #[test]
+  fn upserts() {
+    let mut table = Table::new("Account");
+    table
+      .add_column("Key", integer().increments(true).primary(true))
+      .add_column("Id", varchar(Some(18)).unique(true))
+      .add_column("Name", varchar(None))
+      .add_column("Total", numeric(18, 2).generated("1"));
+
+    // The identity and computed columns are the database's problem
+    assert_eq!(table.upsert_columns(&["Id".to_string()]), ["Id", "Name"]);
+    assert_eq!(
+      table.generate_upsert::<Pg>(&["Id"]).unwrap()[0],
+      "INSERT INTO \"Account\" (\"Id\", \"Name\") VALUES ($1, $2) ON CONFLICT (\"Id\") DO UPDATE SET \"Name\" = EXCLUDED.\"Name\""
+    );
+    assert_eq!(
+      table.generate_upsert::<MySql>(&["Id"]).unwrap()[0],
+      "INSERT INTO `Account` (`Id`, `Name`) VALUES (?, ?) ON DUPLICATE KEY UPDATE `Name` = VALUES(`Name`)"
+    );
+    assert_eq!(
+      table.generate_upsert::<Mssql>(&["Id"]).unwrap()[0],
+      "MERGE INTO [Account] AS target\nUSING (VALUES (@P1, @P2)) AS source ([Id], [Name])\nON target.[Id] = source.[Id]\n\
+       WHEN MATCHED THEN UPDATE SET [Name] = source.[Name]\nWHEN NOT MATCHED THEN INSERT ([Id], [Name]) VALUES (source.[Id], source.[Name]);"
+    );
+    assert_eq!(
+      table.generate_upsert::<Snowflake>(&["Id"]).unwrap()[0],
+      "MERGE INTO \"Account\" AS target\nUSING (SELECT ? AS \"Id\", ? AS \"Name\") AS source\nON target.\"Id\" = source.\"Id\"\n\
+       WHEN MATCHED THEN UPDATE SET \"Name\" = source.\"Name\"\nWHEN NOT MATCHED THEN INSERT (\"Id\", \"Name\") VALUES (source.\"Id\", source.\"Name\")"
+    );
+    assert_eq!(
+      table.generate_upsert::<Redshift>(&["Id"]).unwrap(),
+      ["DELETE FROM \"Account\" WHERE \"Id\" = $1", "INSERT INTO \"Account\" (\"Id\", \"Name\") VALUES ($1, $2)"]
+    );
+    assert_eq!(table.generate_upsert::<ClickHouse>(&["Id"]).unwrap()[0], "INSERT INTO `Account` (`Id`, `Name`) VALUES (?, ?)");
+
+    // Defaults to the primary key, and tables that are all key have nothing to update
+    let mut values = Table::new("Account_Tags__c_values");
+    values
+      .add_column("ParentId", varchar(None))
+      .add_column("Value", varchar(Some(255)))
+      .primary_key(&["ParentId", "Value"]);
+    assert_eq!(
+      values.generate_upsert::<Sqlite>(&[]).unwrap()[0],
+      "INSERT INTO \"Account_Tags__c_values\" (\"ParentId\", \"Value\") VALUES (?, ?) ON CONFLICT (\"ParentId\", \"Value\") DO NOTHING"
+    );
+    assert_eq!(values.generate_upsert::<Mssql>(&[]).unwrap()[0], "\
+      MERGE INTO [Account_Tags__c_values] AS target\n\
+      USING (VALUES (@P1, @P2)) AS source ([ParentId], [Value])\n\
+      ON target.[ParentId] = source.[ParentId] AND target.[Value] = source.[Value]\n\
+      WHEN NOT MATCHED THEN INSERT ([ParentId], [Value]) VALUES (source.[ParentId], source.[Value]);");
+    assert_eq!(values.generate_upsert_for(Dialect::Pg, &[]).unwrap(), values.generate_upsert::<Pg>(&[]).unwrap());
 
-      sql.push_str(&affix);
-      sql
+    // Keys the database fills in can't be matched on, and neither can nothing at all
+    let mut serial = Table::new("Account");
+    serial.add_column("row_id", bigint().increments(true).primary(true)).add_column("Id", varchar(Some(18)));
+    assert!(serial.generate_upsert::<Redshift>(&[]).unwrap_err().to_string().contains("on `row_id`"));
+    assert!(serial.generate_upsert::<Redshift>(&["Id"]).is_ok());
+    assert!(Table::new("Empty").generate_upsert::<Pg>(&[]).is_err());
   }
 }
@@ -33,20 +33,40 @@ pub enum BaseType {
   Index(Vec<String>),
   Varchar(Option<usize>),
   Boolean,
+  SmallInt,
   Integer,
   BigInt,
   Text,
+  /// Case-insensitive text (Postgres `citext` extension).
+  CiText,
   Float,
   Double,
+  /// Exact numbers, as `(precision, scale)`; `None` leaves both up to the database.
+  Numeric(Option<(u8, u8)>),
+  Uuid,
+  Binary,
   Jsonb,
+  /// PostGIS geography of the given shape (`POINT`, `POLYGON`...), always in WGS 84.
+  Geography(&'static str),
   DateTime,
+  DateTimeTz,
   Time,
   Date
 }
 
+/// What happens to referencing rows when the referenced row is deleted/updated.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ReferentialAction {
+  NoAction,
+  Restrict,
+  Cascade,
+  SetNull,
+  SetDefault
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum WrappedDefault<'a> {
-  Array(Vec<Type>),
+  Array(Vec<WrappedDefault<'a>>),
   Text(&'a str),
   Integer(i64),
   BigInt(i128),
@@ -56,14 +76,16 @@ pub enum WrappedDefault<'a> {
   Date(SystemTime),
   DateTime(SystemTime),
   Foreign(Box<Type>),
-  Custom(&'static str)
+  Custom(&'static str),
+  /// A raw SQL expression (such as `now()`), rendered as-is.
+  Expr(String)
 }
 
 impl<'outer> Display for WrappedDefault<'outer> {
   fn fmt(&self, f: &mut Formatter) -> fmt::Result {
     use self::WrappedDefault::*;
     write!(f, "{}", &match *self {
-      Text(ref val)     => format!("{}", val),
+      Text(ref val)     => val.to_string(),
       BigInt(ref val)   => format!("{}", val),
       Integer(ref val)  => format!("{}", val),
       Float(ref val)    => format!("{}", val),
@@ -72,8 +94,9 @@ impl<'outer> Display for WrappedDefault<'outer> {
       Date(ref val)     => format!("{:?}", val),
       DateTime(ref val) => format!("{:?}", val),
       Foreign(ref val)  => format!("{:?}", val),
-      Custom(ref val)   => format!("{}", val),
-      Array(ref val)    => format!("{:?}", val)
+      Custom(ref val)   => val.to_string(),
+      Expr(ref val)     => val.to_string(),
+      Array(ref val)    => format!("[{}]", val.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "))
     })
   }
 }
@@ -108,6 +131,13 @@ impl From<f64> for WrappedDefault<'static> {
   }
 }
 
+impl<T> From<Vec<T>> for WrappedDefault<'static>
+where T: Into<WrappedDefault<'static>> {
+  fn from(val: Vec<T>) -> Self {
+    WrappedDefault::Array(val.into_iter().map(Into::into).collect())
+  }
+}
+
 impl From<SystemTime> for WrappedDefault<'static> {
   fn from(time: SystemTime) -> Self {
     WrappedDefault::Date(time)
@@ -117,6 +147,14 @@ impl From<SystemTime> for WrappedDefault<'static> {
 #[derive(Debug, PartialEq, Clone)]
 pub struct Type {
   pub default:    Option<WrappedDefault<'static>>,
+  pub check:      Option<String>,
+  pub generated:  Option<String>,
+  pub on_delete:  Option<ReferentialAction>,
+  pub on_update:  Option<ReferentialAction>,
+  pub deferrable: bool,
+  pub sql_type:   Option<String>,
+  pub collation:  Option<String>,
+  pub comment:    Option<String>,
   pub size:       Option<usize>,
   pub inner:      BaseType,
   pub nullable:   bool,
@@ -135,6 +173,14 @@ impl Default for Type {
       indexed:    false,
       primary:    false,
       default:    None,
+      check:      None,
+      generated:  None,
+      on_delete:  None,
+      on_update:  None,
+      deferrable: false,
+      sql_type:   None,
+      collation:  None,
+      comment:    None,
       size:       None,
       inner:      BaseType::Integer
     }
@@ -174,11 +220,71 @@ impl Type {
     Self { default: Some(arg.into()), ..self }
   }
 
+  /// Adds a column level `CHECK` constraint.
+  pub fn check(self, expr: impl Into<String>) -> Self {
+    Self { check: Some(expr.into()), ..self }
+  }
+
+  /// Makes this a (stored) generated column, computed from `expr`.
+  pub fn generated(self, expr: impl Into<String>) -> Self {
+    Self { generated: Some(expr.into()), ..self }
+  }
+
+  /// Sets the `ON DELETE` action of a foreign key.
+  pub fn on_delete(self, action: ReferentialAction) -> Self {
+    Self { on_delete: Some(action), ..self }
+  }
+
+  /// Sets the `ON UPDATE` action of a foreign key.
+  pub fn on_update(self, action: ReferentialAction) -> Self {
+    Self { on_update: Some(action), ..self }
+  }
+
+  /// Defers the foreign key/unique checks of this column until the end of the transaction.
+  pub fn deferrable(self, val: bool) -> Self {
+    Self { deferrable: val, ..self }
+  }
+
+  /// Renders this column as the given (dialect specific) SQL type, e.g. `MONEY` or a custom `DOMAIN`, keeping everything else.
+  pub fn sql_type(self, sql: impl Into<String>) -> Self {
+    Self { sql_type: Some(sql.into()), ..self }
+  }
+
+  /// Sets the collation of a text column (`C`, `en-US-x-icu`...).
+  pub fn collate(self, collation: impl Into<String>) -> Self {
+    Self { collation: Some(collation.into()), ..self }
+  }
+
+  /// Describes the column, see `Table::comment`.
+  pub fn comment(self, comment: impl Into<String>) -> Self {
+    Self { comment: Some(comment.into()), ..self }
+  }
+
+  /// Whether the column holds text, which is what collations apply to.
+  pub fn is_text(&self) -> bool {
+    use self::BaseType::*;
+
+    match &self.inner {
+      Varchar(_) | Text | CiText => true,
+      Array(inner)               => matches!(**inner, Varchar(_) | Text | CiText),
+      _                          => false
+    }
+  }
+
+  /// Whether the column is case-insensitive text, which comes with a collation of its own.
+  pub fn is_citext(&self) -> bool {
+    matches!(self.inner, BaseType::CiText)
+  }
+
   pub(crate) fn inner(&self) -> BaseType {
     self.inner.clone()
   }
 }
 
+pub fn smallint() -> Type {
+  Type::new(BaseType::SmallInt)
+}
+
 pub fn integer() -> Type {
   Type::new(BaseType::Integer)
 }
@@ -195,6 +301,14 @@ pub fn double() -> Type {
   Type::new(BaseType::Double)
 }
 
+/// Exact decimal with `precision` total digits, `scale` of which are after the decimal point.
+pub fn numeric(precision: u8, scale: u8) -> Type {
+  match precision {
+    0 => Type::new(BaseType::Numeric(None)),
+    _ => Type::new(BaseType::Numeric(Some((precision, scale.min(precision)))))
+  }
+}
+
 pub fn boolean() -> Type {
   Type::new(BaseType::Boolean)
 }
@@ -207,6 +321,11 @@ pub fn text() -> Type {
   Type::new(BaseType::Text)
 }
 
+/// Case-insensitive text, compared the way Salesforce compares strings (requires the `citext` extension).
+pub fn citext() -> Type {
+  Type::new(BaseType::CiText)
+}
+
 pub fn time() -> Type {
   Type::new(BaseType::Time)
 }
@@ -219,10 +338,39 @@ pub fn datetime() -> Type {
   Type::new(BaseType::DateTime)
 }
 
+pub fn uuid() -> Type {
+  Type::new(BaseType::Uuid)
+}
+
+/// Timestamp that keeps track of its time zone (Salesforce datetimes are always UTC).
+pub fn datetime_tz() -> Type {
+  Type::new(BaseType::DateTimeTz)
+}
+
+/// Raw bytes, such as the (decoded) body of Base64 fields.
+pub fn binary() -> Type {
+  Type::new(BaseType::Binary)
+}
+
+/// Same as `binary`, under its Postgres name.
+pub fn bytea() -> Type {
+  binary()
+}
+
 pub fn jsonb() -> Type {
   Type::new(BaseType::Jsonb)
 }
 
+/// Spatial column (requires PostGIS).
+pub fn geography(shape: &'static str) -> Type {
+  Type::new(BaseType::Geography(shape))
+}
+
+/// A single lat/long location.
+pub fn point() -> Type {
+  geography("POINT")
+}
+
 pub fn custom(sql: &'static str) -> Type {
   Type::new(BaseType::Custom(sql))
 }
@@ -236,6 +384,12 @@ pub fn array(inner: &Type) -> Type {
   Type::new(BaseType::Array(Box::new(inner.inner())))
 }
 
+/// Multidimensional array, `array_dims(&integer(), 2)` is an `INTEGER[][]`.
+pub fn array_dims(inner: &Type, dimensions: usize) -> Type {
+  let base = (0..dimensions.max(1)).fold(inner.inner(), |base, _| BaseType::Array(Box::new(base)));
+  Type::new(base)
+}
+
 pub fn index<S>(columns: Vec<S>) -> Type
 where S: Into<String> {
   let vec: Vec<String> = columns.into_iter().map(|s| s.into()).collect();
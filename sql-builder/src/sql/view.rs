@@ -0,0 +1,61 @@
+use super::{Dialect, Identifiers, SqlGenerator};
+
+#[derive(Debug, Clone)]
+enum Source {
+  Column(String),
+  Expr(String)
+}
+
+/// A view over a single table, renaming and/or computing columns; see `Table::view`.
+#[derive(Debug, Clone)]
+pub struct View {
+  name:        String,
+  table:       String,
+  schema:      Option<String>,
+  identifiers: Identifiers,
+  columns:     Vec<(Source, String)>
+}
+
+impl View {
+  pub(crate) fn new(name: String, table: String, schema: Option<String>, identifiers: Identifiers) -> Self {
+    View { name, table, schema, identifiers, columns: Vec::new() }
+  }
+
+  pub fn name(&self) -> String {
+    self.name.clone()
+  }
+
+  /// Exposes a column of the table as `alias`.
+  pub fn column<C, A>(&mut self, column: C, alias: A) -> &mut Self
+  where C: Into<String>, A: Into<String> {
+    self.columns.push((Source::Column(column.into()), alias.into()));
+    self
+  }
+
+  /// Exposes a SQL expression (over columns of the table) as `alias`.
+  pub fn expression<E, A>(&mut self, expr: E, alias: A) -> &mut Self
+  where E: Into<String>, A: Into<String> {
+    self.columns.push((Source::Expr(expr.into()), alias.into()));
+    self
+  }
+
+  pub fn generate<T>(&self) -> String
+  where T: SqlGenerator {
+    let ids = self.identifiers.clone().in_schema(self.schema.clone());
+
+    let columns = self.columns
+      .iter()
+      .map(|(source, alias)| match source {
+        Source::Column(column) => format!("{} AS {}", ids.render::<T>(column), ids.render::<T>(alias)),
+        Source::Expr(expr)     => format!("({}) AS {}", expr, ids.render::<T>(alias))
+      })
+      .collect::<Vec<_>>();
+
+    format!("{} AS\nSELECT\n{}\nFROM {}", T::create_view(&self.name, &ids), columns.join(",\n"), ids.table::<T>(&self.table))
+  }
+
+  /// Same as `generate`, with the dialect picked at runtime.
+  pub fn generate_for(&self, dialect: Dialect) -> String {
+    dispatch!(dialect, T => self.generate::<T>())
+  }
+}
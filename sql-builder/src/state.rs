@@ -0,0 +1,160 @@
+//! What `sync` remembers between runs, kept in a JSON file.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use oxidized_force::response::DescribeResponse;
+use serde::{Deserialize, Serialize};
+
+use crate::extract::Checkpoint;
+
+/// Used when there's no `--state-file` (or `state_file` in the config).
+pub const DEFAULT_PATH: &str = "sf-etl-state.json";
+
+/// Sync progress for every object, by object name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncState {
+  pub objects: BTreeMap<String, ObjectState>
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObjectState {
+  /// Latest `SystemModstamp` of the records synced so far
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub watermark:            Option<String>,
+  /// The describe the table was last created (or altered) from, to tell what changed since
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub describe:             Option<DescribeResponse>,
+  /// Where an interrupted sync left off, until it finishes
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub checkpoint:           Option<Checkpoint>,
+  /// Latest `SystemModstamp` of the records that made it in before the interruption
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub checkpoint_watermark: Option<String>,
+  /// Replay ID of the last change event `listen` handled
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub replay_id:            Option<i64>
+}
+
+impl SyncState {
+  /// Reads the state file; a missing file means nothing was synced yet.
+  pub fn load<P>(path: P) -> anyhow::Result<Self>
+  where P: AsRef<Path> {
+    let path = path.as_ref();
+    match std::fs::read(path) {
+      Ok(contents)                                            => serde_json::from_slice(&contents).map_err(|err| anyhow::anyhow!("invalid state file {}: {}", path.display(), err)),
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(SyncState::default()),
+      Err(err)                                                => Err(anyhow::anyhow!("cannot read {}: {}", path.display(), err))
+    }
+  }
+
+  /// Writes the state file, by way of a temporary file so a crash halfway through can't leave a broken one behind.
+  pub fn save<P>(&self, path: P) -> anyhow::Result<()>
+  where P: AsRef<Path> {
+    let path = path.as_ref();
+    let mut temp = PathBuf::from(path);
+    temp.set_extension("tmp");
+
+    std::fs::write(&temp, serde_json::to_vec_pretty(self)?)?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+  }
+
+  pub fn watermark(&self, object: &str) -> Option<&str> {
+    self.objects.get(object).and_then(|state| state.watermark.as_deref())
+  }
+
+  pub fn set_watermark<W>(&mut self, object: &str, watermark: W) -> &mut Self
+  where W: Into<String> {
+    self.objects.entry(object.to_string()).or_default().watermark = Some(watermark.into());
+    self
+  }
+
+  pub fn describe(&self, object: &str) -> Option<&DescribeResponse> {
+    self.objects.get(object).and_then(|state| state.describe.as_ref())
+  }
+
+  pub fn set_describe(&mut self, desc: &DescribeResponse) -> &mut Self {
+    self.objects.entry(desc.name.clone()).or_default().describe = Some(desc.clone());
+    self
+  }
+
+  /// The checkpoint of an interrupted sync, along with the latest `SystemModstamp` it got to.
+  pub fn checkpoint(&self, object: &str) -> Option<(&Checkpoint, Option<&str>)> {
+    self
+      .objects
+      .get(object)
+      .and_then(|state| state.checkpoint.as_ref().map(|checkpoint| (checkpoint, state.checkpoint_watermark.as_deref())))
+  }
+
+  pub fn set_checkpoint(&mut self, object: &str, checkpoint: &Checkpoint, watermark: Option<&str>) -> &mut Self {
+    let state = self.objects.entry(object.to_string()).or_default();
+    state.checkpoint           = Some(checkpoint.clone());
+    state.checkpoint_watermark = watermark.map(String::from);
+    self
+  }
+
+  pub fn replay_id(&self, object: &str) -> Option<i64> {
+    self.objects.get(object).and_then(|state| state.replay_id)
+  }
+
+  pub fn set_replay_id(&mut self, object: &str, replay_id: i64) -> &mut Self {
+    self.objects.entry(object.to_string()).or_default().replay_id = Some(replay_id);
+    self
+  }
+
+  /// Forgets the checkpoint, once the sync made it to the end.
+  pub fn clear_checkpoint(&mut self, object: &str) -> &mut Self {
+    if let Some(state) = self.objects.get_mut(object) {
+      state.checkpoint           = None;
+      state.checkpoint_watermark = None;
+    }
+    self
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use oxidized_force::response::FieldType;
+  use oxidized_force::test_util::fixtures;
+
+  #[test]
+  fn sync_state() -> anyhow::Result<()> {
+    let desc = fixtures::describe("Account").field("Id", FieldType::Id).field("SystemModstamp", FieldType::DateTime).build();
+    let dir  = std::env::temp_dir().join(format!("sf-etl-state-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("state.json");
+    assert!(SyncState::load(&path)?.objects.is_empty());
+
+    let mut state = SyncState::default();
+    state.set_watermark("Account", "2021-01-05T08:30:00.000Z").set_describe(&desc).set_replay_id("Account", 42);
+    state.save(&path)?;
+    assert_eq!(SyncState::load(&path)?.watermark("Account"), Some("2021-01-05T08:30:00.000Z"));
+    assert_eq!(SyncState::load(&path)?.describe("Account").map(|desc| desc.field_names()), Some(desc.field_names()));
+    assert_eq!(SyncState::load(&path)?.replay_id("Account"), Some(42));
+    assert_eq!(SyncState::load(&path)?.watermark("Contact"), None);
+    assert_eq!(SyncState::load(&path)?.replay_id("Contact"), None);
+
+    // Interrupted syncs keep their checkpoint (and how far the watermark got) until they finish
+    let checkpoint = Checkpoint {
+      job_id:    "750R0000000zlh9IAA".to_string(),
+      object:    "Account".to_string(),
+      fields:    vec!["Id".to_string(), "SystemModstamp".to_string()],
+      condition: None,
+      locator:   Some("page2".to_string()),
+      pages:     1,
+      records:   3
+    };
+    state.set_checkpoint("Account", &checkpoint, Some("2021-01-06T00:00:00.000Z")).save(&path)?;
+    let loaded = SyncState::load(&path)?;
+    assert_eq!(loaded.checkpoint("Account"), Some((&checkpoint, Some("2021-01-06T00:00:00.000Z"))));
+    assert_eq!(loaded.watermark("Account"), Some("2021-01-05T08:30:00.000Z"));
+    state.clear_checkpoint("Account").save(&path)?;
+    assert_eq!(SyncState::load(&path)?.checkpoint("Account"), None);
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+  }
+}
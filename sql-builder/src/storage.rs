@@ -0,0 +1,139 @@
+//! Extract outputs in object storage (`s3://`, `gs://` and `az://`), streamed to the provider's CLI as they're written
+//! so nothing has to fit on local disk; the CLIs take care of multipart uploads and authenticating.
+
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+/// Somewhere in object storage; a path ending in `/` is a prefix, getting a file name of its own.
+///
+/// - `s3://<bucket>/<key>`, uploaded with `aws s3 cp`
+/// - `gs://<bucket>/<object>`, uploaded with `gcloud storage cp`
+/// - `az://<account>/<container>/<blob>`, uploaded with `azcopy` (log in with `azcopy login`, or set `AZCOPY_AUTO_LOGIN_TYPE`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+  S3    { bucket: String, key: String },
+  Gcs   { bucket: String, object: String },
+  Azure { account: String, container: String, blob: String }
+}
+
+impl Target {
+  /// The target `output` refers to, if it's in object storage at all; prefixes get `file_name` tacked on.
+  pub fn from_output(output: &Path, file_name: &str) -> anyhow::Result<Option<Self>> {
+    let output = output.to_string_lossy();
+    let (scheme, rest) = match output.split_once("://") {
+      Some((scheme, rest)) if ["s3", "gs", "az"].contains(&scheme) => (scheme, rest),
+      _                                                           => return Ok(None)
+    };
+
+    let parts        = if scheme == "az" { 3 } else { 2 };
+    let mut segments = rest.splitn(parts, '/').map(String::from).collect::<Vec<_>>();
+    let complete     = segments.len() == parts;
+    match segments.last_mut() {
+      Some(path) if complete && (path.is_empty() || path.ends_with('/')) => path.push_str(file_name),
+      _                                                                 => ()
+    }
+    if !complete || segments.iter().any(String::is_empty) {
+      anyhow::bail!("invalid output `{}`, expected {}", output, match scheme {
+        "az" => "az://<account>/<container>/<blob or prefix/>",
+        _    => "<scheme>://<bucket>/<key or prefix/>"
+      });
+    }
+
+    let mut segments = segments.into_iter();
+    let mut next     = || segments.next().unwrap_or_default();
+    Ok(Some(match scheme {
+      "s3" => Target::S3 { bucket: next(), key: next() },
+      "gs" => Target::Gcs { bucket: next(), object: next() },
+      _    => Target::Azure { account: next(), container: next(), blob: next() }
+    }))
+  }
+
+  /// The command uploading whatever comes in on its standard input, program first.
+  pub fn command(&self) -> Vec<String> {
+    let url = self.to_string();
+    let args: Vec<&str> = match self {
+      Target::S3 { .. }    => vec!["aws", "s3", "cp", "-", &url],
+      Target::Gcs { .. }   => vec!["gcloud", "storage", "cp", "-", &url],
+      Target::Azure { .. } => vec!["azcopy", "copy", &url, "--from-to", "PipeBlob"]
+    };
+    args.into_iter().map(String::from).collect()
+  }
+
+  /// Starts uploading; write the output to the `ChildStdin`, then drop it and `Upload::finish`.
+  pub fn upload(&self) -> anyhow::Result<(Upload, ChildStdin)> {
+    let command = self.command();
+    let mut child = Command::new(&command[0])
+      .args(&command[1..])
+      .stdin(Stdio::piped())
+      .stdout(Stdio::null())
+      .spawn()
+      .map_err(|err| anyhow::anyhow!("cannot run `{}` to upload to {}: {}", command[0], self, err))?;
+
+    let stdin = child.stdin.take().expect("piped stdin");
+    Ok((Upload { child, target: self.clone() }, stdin))
+  }
+}
+
+impl std::fmt::Display for Target {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Target::S3 { bucket, key }                 => write!(f, "s3://{}/{}", bucket, key),
+      Target::Gcs { bucket, object }             => write!(f, "gs://{}/{}", bucket, object),
+      Target::Azure { account, container, blob } => write!(f, "https://{}.blob.core.windows.net/{}/{}", account, container, blob)
+    }
+  }
+}
+
+/// An upload in progress.
+#[derive(Debug)]
+pub struct Upload {
+  child:  Child,
+  target: Target
+}
+
+impl Upload {
+  /// Waits for the upload to finish, once its input has been dropped.
+  pub fn finish(mut self) -> anyhow::Result<()> {
+    let status = self.child.wait()?;
+    if !status.success() {
+      anyhow::bail!("uploading to {} failed ({})", self.target, status);
+    }
+    Ok(())
+  }
+
+  /// Stops the upload before its input is closed, so a failed extract doesn't leave half a file behind.
+  pub fn abort(mut self) {
+    let _ = self.child.kill();
+    let _ = self.child.wait();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn object_storage_outputs() -> anyhow::Result<()> {
+    let target = |output: &str| Target::from_output(Path::new(output), "Account.csv");
+    assert_eq!(target("s3://shibe-etl/exports/")?, Some(Target::S3 { bucket: "shibe-etl".to_string(), key: "exports/Account.csv".to_string() }));
+    assert_eq!(target("s3://shibe-etl/exports/accounts.csv")?, Some(Target::S3 { bucket: "shibe-etl".to_string(), key: "exports/accounts.csv".to_string() }));
+    assert_eq!(target("gs://shibe-etl/")?, Some(Target::Gcs { bucket: "shibe-etl".to_string(), object: "Account.csv".to_string() }));
+    assert_eq!(
+      target("az://shibe/exports/daily/")?,
+      Some(Target::Azure { account: "shibe".to_string(), container: "exports".to_string(), blob: "daily/Account.csv".to_string() })
+    );
+    assert_eq!(target("exports/accounts.csv")?, None);
+    assert_eq!(target("ftp://shibe-etl/accounts.csv")?, None);
+    for invalid in ["s3://shibe-etl", "s3:///accounts.csv", "gs://", "az://shibe/exports"].iter() {
+      assert!(target(invalid).is_err(), "{} parsed", invalid);
+    }
+
+    assert_eq!(target("s3://shibe-etl/exports/")?.unwrap().command(), ["aws", "s3", "cp", "-", "s3://shibe-etl/exports/Account.csv"]);
+    assert_eq!(target("gs://shibe-etl/")?.unwrap().command(), ["gcloud", "storage", "cp", "-", "gs://shibe-etl/Account.csv"]);
+    assert_eq!(
+      target("az://shibe/exports/")?.unwrap().command(),
+      ["azcopy", "copy", "https://shibe.blob.core.windows.net/exports/Account.csv", "--from-to", "PipeBlob"]
+    );
+    Ok(())
+  }
+}